@@ -0,0 +1,19 @@
+extern crate krill;
+
+use krill::daemon::config::Config;
+use krill::daemon::http::server;
+
+fn main() {
+    match Config::create_pubd() {
+        Ok(config) => {
+            if let Err(e) = server::start(&config) {
+                eprintln!("Krill Publication Server failed to start: {}", e);
+                ::std::process::exit(1);
+            }
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            ::std::process::exit(1);
+        }
+    }
+}
@@ -10,8 +10,10 @@ use crate::cli::options::{
 };
 use crate::cli::report::{ApiResponse, ReportError};
 use crate::commons::api::{
-    AllCertAuthIssues, CaRepoDetails, CertAuthIssues, ChildCaInfo, CurrentRepoState,
-    ParentCaContact, PublisherDetails, PublisherList, Token,
+    AllCertAuthIssues, CaRepoDetails, CertAuthIssues, ChildCaInfo, ChildInvitation,
+    CurrentRepoState, NewChildInvitation, NewPublisherInvitation, ParentCaContact,
+    ParentMigrationStatus, PublishedObject, PublishedObjectDetails, PublisherDetails,
+    PublisherInvitation, PublisherList, Token,
 };
 use crate::commons::remote::rfc8183;
 use crate::commons::util::httpclient;
@@ -97,12 +99,18 @@ impl KrillClient {
                 Ok(ApiResponse::Empty)
             }
 
-            CaCommand::UpdateId(handle) => {
+            CaCommand::IdRollInit(handle) => {
                 let uri = format!("api/v1/cas/{}/id", handle);
                 self.post_empty(&uri)?;
                 Ok(ApiResponse::Empty)
             }
 
+            CaCommand::IdRollActivate(handle) => {
+                let uri = format!("api/v1/cas/{}/id/activate", handle);
+                self.post_empty(&uri)?;
+                Ok(ApiResponse::Empty)
+            }
+
             CaCommand::ParentResponse(handle, child) => {
                 let uri = format!("api/v1/cas/{}/children/{}/contact", handle, child);
                 let info: ParentCaContact = self.get_json(&uri)?;
@@ -157,6 +165,24 @@ impl KrillClient {
                 Ok(ApiResponse::Empty)
             }
 
+            CaCommand::ParentMigrationStart(handle, req) => {
+                let uri = format!("api/v1/cas/{}/parent_migration", handle);
+                self.post_json(&uri, req)?;
+                Ok(ApiResponse::Empty)
+            }
+
+            CaCommand::ParentMigrationStatus(handle) => {
+                let uri = format!("api/v1/cas/{}/parent_migration", handle);
+                let status: ParentMigrationStatus = self.get_json(&uri)?;
+                Ok(ApiResponse::ParentMigrationStatus(status))
+            }
+
+            CaCommand::ParentMigrationCancel(handle) => {
+                let uri = format!("api/v1/cas/{}/parent_migration", handle);
+                self.delete(&uri)?;
+                Ok(ApiResponse::Empty)
+            }
+
             CaCommand::MyParentCaContact(handle, parent) => {
                 let uri = format!("api/v1/cas/{}/parents/{}", handle, parent);
                 let parent: ParentCaContact = self.get_json(&uri)?;
@@ -184,6 +210,31 @@ impl KrillClient {
                 self.delete(&uri)?;
                 Ok(ApiResponse::Empty)
             }
+            CaCommand::ChildInvitationAdd(handle, init) => {
+                let uri = format!("api/v1/cas/{}/invitations", handle);
+                let invitation: NewChildInvitation = self.post_json_with_response(&uri, init)?;
+                Ok(ApiResponse::ChildInvitation(invitation))
+            }
+            CaCommand::ChildInvitations(handle) => {
+                let uri = format!("api/v1/cas/{}/invitations", handle);
+                let invitations: Vec<ChildInvitation> = self.get_json(&uri)?;
+                Ok(ApiResponse::ChildInvitations(invitations))
+            }
+            CaCommand::ChildInvitationUpdate(handle, id, update) => {
+                let uri = format!("api/v1/cas/{}/invitations/{}", handle, id);
+                self.post_json(&uri, update)?;
+                Ok(ApiResponse::Empty)
+            }
+            CaCommand::ChildInvitationRevoke(handle, id) => {
+                let uri = format!("api/v1/cas/{}/invitations/{}", handle, id);
+                self.delete(&uri)?;
+                Ok(ApiResponse::Empty)
+            }
+            CaCommand::ChildTransfer(handle, transfer) => {
+                let uri = format!("api/v1/cas/{}/child_transfer", handle);
+                self.post_json(&uri, transfer)?;
+                Ok(ApiResponse::Empty)
+            }
 
             CaCommand::KeyRollInit(handle) => {
                 let uri = format!("api/v1/cas/{}/keys/roll_init", handle);
@@ -202,12 +253,74 @@ impl KrillClient {
                 Ok(ApiResponse::RouteAuthorizations(roas))
             }
 
-            CaCommand::RouteAuthorizationsUpdate(handle, updates) => {
-                let uri = format!("api/v1/cas/{}/routes", handle);
-                self.post_json(&uri, updates)?;
+            CaCommand::RouteAuthorizationsUpdate(handle, updates, strict) => {
+                let uri = if strict {
+                    format!("api/v1/cas/{}/routes?strict=true", handle)
+                } else {
+                    format!("api/v1/cas/{}/routes", handle)
+                };
+                let report = self.post_json_with_response(&uri, updates)?;
+                Ok(ApiResponse::RouteAuthorizationsLint(report))
+            }
+
+            CaCommand::RouteAuthorizationsImport(handle, roas) => {
+                let uri = format!("api/v1/cas/{}/routes/import/json", handle);
+                self.post_json(&uri, roas)?;
+                Ok(ApiResponse::Empty)
+            }
+
+            CaCommand::RouteAuthorizationsMetaUpdate(handle, roas) => {
+                let uri = format!("api/v1/cas/{}/routes/meta", handle);
+                self.post_json(&uri, roas)?;
+                Ok(ApiResponse::Empty)
+            }
+
+            CaCommand::RouteAuthorizationsSchedule(handle, roas) => {
+                let uri = format!("api/v1/cas/{}/routes/schedule", handle);
+                self.post_json(&uri, roas)?;
+                Ok(ApiResponse::Empty)
+            }
+
+            CaCommand::RouteAuthorizationsAs0Show(handle) => {
+                let uri = format!("api/v1/cas/{}/routes/as0", handle);
+                let info = self.get_json(&uri)?;
+                Ok(ApiResponse::As0RoasInfo(info))
+            }
+
+            CaCommand::RouteAuthorizationsAs0Update(handle, enabled) => {
+                let action = if enabled { "enable" } else { "disable" };
+                let uri = format!("api/v1/cas/{}/routes/as0/{}", handle, action);
+                self.post_empty(&uri)?;
+                Ok(ApiResponse::Empty)
+            }
+
+            CaCommand::RouteAuthorizationsDormantShow(handle) => {
+                let uri = format!("api/v1/cas/{}/routes/dormant", handle);
+                let info = self.get_json(&uri)?;
+                Ok(ApiResponse::DormantRoasInfo(info))
+            }
+
+            CaCommand::RouteAuthorizationsDormantPolicy(handle, policy) => {
+                let uri = format!("api/v1/cas/{}/routes/dormant/{}", handle, policy);
+                self.post_empty(&uri)?;
                 Ok(ApiResponse::Empty)
             }
 
+            CaCommand::RouteAuthorizationsLint(handle) => {
+                let uri = format!("api/v1/cas/{}/routes/lint", handle);
+                let report = self.get_json(&uri)?;
+                Ok(ApiResponse::RouteAuthorizationsLint(report))
+            }
+
+            CaCommand::RouteAuthorizationsExport(handle_opt, format) => {
+                let uri = match handle_opt {
+                    Some(handle) => format!("api/v1/cas/{}/routes/export/{}", handle, format),
+                    None => format!("api/v1/cas/routes/export/{}", format),
+                };
+                let export = self.get_text(&uri)?;
+                Ok(ApiResponse::GenericBody(export))
+            }
+
             CaCommand::Show(handle) => {
                 let uri = format!("api/v1/cas/{}", handle);
                 let ca_info = self.get_json(&uri)?;
@@ -256,6 +369,18 @@ impl KrillClient {
                 let stats = self.get_json("stats/repo")?;
                 Ok(ApiResponse::RepoStats(stats))
             }
+            PublishersCommand::ResetSession => {
+                self.post_empty("api/v1/pubd/reset_session")?;
+                Ok(ApiResponse::Empty)
+            }
+            PublishersCommand::IdRollInit => {
+                self.post_empty("api/v1/pubd/id_roll_init")?;
+                Ok(ApiResponse::Empty)
+            }
+            PublishersCommand::IdRollActivate => {
+                self.post_empty("api/v1/pubd/id_roll_activate")?;
+                Ok(ApiResponse::Empty)
+            }
             PublishersCommand::AddPublisher(req) => {
                 let res = self.post_json_with_response("api/v1/publishers", req)?;
                 Ok(ApiResponse::Rfc8183RepositoryResponse(res))
@@ -270,11 +395,46 @@ impl KrillClient {
                 let details: PublisherDetails = self.get_json(&uri)?;
                 Ok(ApiResponse::PublisherDetails(details))
             }
+            PublishersCommand::PublisherObjects(handle) => {
+                let uri = format!("api/v1/publishers/{}/objects", handle);
+                let objects: Vec<PublishedObject> = self.get_json(&uri)?;
+                Ok(ApiResponse::PublishedObjects(objects))
+            }
+            PublishersCommand::PublisherObject(handle, hash) => {
+                let uri = format!("api/v1/publishers/{}/objects/{}", handle, hash);
+                let details: PublishedObjectDetails = self.get_json(&uri)?;
+                Ok(ApiResponse::PublishedObjectDetails(details))
+            }
             PublishersCommand::RepositoryResponse(handle) => {
                 let uri = format!("api/v1/publishers/{}/response.json", handle);
                 let res = self.get_json(&uri)?;
                 Ok(ApiResponse::Rfc8183RepositoryResponse(res))
             }
+            PublishersCommand::UpdateQuota(handle, quota) => {
+                let uri = format!("api/v1/publishers/{}/quota", handle);
+                self.post_json(&uri, quota)?;
+                Ok(ApiResponse::Empty)
+            }
+            PublishersCommand::InvitationAdd(init) => {
+                let uri = "api/v1/publishers/invitations";
+                let invitation: NewPublisherInvitation = self.post_json_with_response(uri, init)?;
+                Ok(ApiResponse::PublisherInvitation(invitation))
+            }
+            PublishersCommand::Invitations => {
+                let invitations: Vec<PublisherInvitation> =
+                    self.get_json("api/v1/publishers/invitations")?;
+                Ok(ApiResponse::PublisherInvitations(invitations))
+            }
+            PublishersCommand::InvitationUpdate(id, update) => {
+                let uri = format!("api/v1/publishers/invitations/{}", id);
+                self.post_json(&uri, update)?;
+                Ok(ApiResponse::Empty)
+            }
+            PublishersCommand::InvitationRevoke(id) => {
+                let uri = format!("api/v1/publishers/invitations/{}", id);
+                self.delete(&uri)?;
+                Ok(ApiResponse::Empty)
+            }
         }
     }
 
@@ -337,6 +497,11 @@ impl KrillClient {
         httpclient::get_json(&uri, Some(&self.token)).map_err(Error::HttpClientError)
     }
 
+    fn get_text(&self, uri: &str) -> Result<String, Error> {
+        let uri = self.resolve_uri(uri);
+        httpclient::get_text(&uri, Some(&self.token)).map_err(Error::HttpClientError)
+    }
+
     fn post_empty(&self, uri: &str) -> Result<(), Error> {
         let uri = self.resolve_uri(uri);
         httpclient::post_empty(&uri, Some(&self.token)).map_err(Error::HttpClientError)
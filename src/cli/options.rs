@@ -5,16 +5,22 @@ use std::path::PathBuf;
 use std::str::{from_utf8_unchecked, FromStr};
 
 use bytes::Bytes;
+use chrono::{DateTime, Utc};
 use clap::{App, Arg, ArgMatches, SubCommand};
 
 use rpki::uri;
+use rpki::x509::Time;
 
 use crate::cli::report::{ReportError, ReportFormat};
 use crate::commons::api::RepositoryUpdate;
 use crate::commons::api::{
-    AddChildRequest, AuthorizationFmtError, CertAuthInit, ChildAuthRequest, ChildHandle, Handle,
-    ParentCaContact, ParentCaReq, ParentHandle, PublisherHandle, ResourceSet, ResourceSetError,
-    RoaDefinitionUpdates, Token, UpdateChildRequest,
+    AddChildRequest, AuthorizationFmtError, CertAuthInit, ChildAuthRequest, ChildCertValidity,
+    ChildHandle, ChildInvitationInit, ChildInvitationUpdate, ChildResourceTransfer, ConfiguredRoa,
+    Handle, HexEncodedHash, ParentCaContact, ParentCaReq, ParentHandle, ParentMigrationReq,
+    PublisherHandle, PublisherInvitationInit, PublisherInvitationUpdate, PublisherQuota,
+    ResourceSet, ResourceSetError, RoaDefinition, RoaDefinitionMeta, RoaDefinitionUpdates,
+    RoaDefinitionsFormat, RoaDormantPolicy, RoaSchedule, ScheduledRoa, Token, TokenHash,
+    UpdateChildRequest,
 };
 use crate::commons::remote::id::IdCert;
 use crate::commons::remote::rfc8183;
@@ -198,6 +204,25 @@ impl Options {
         )
     }
 
+    fn add_child_validity_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        app.arg(
+            Arg::with_name("validity_weeks")
+                .long("validity-weeks")
+                .value_name("weeks")
+                .help("The validity time for certificates issued to the child")
+                .requires("reissue_weeks")
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("reissue_weeks")
+                .long("reissue-weeks")
+                .value_name("weeks")
+                .help("The time before expiry when certificates are re-issued")
+                .requires("validity_weeks")
+                .required(false),
+        )
+    }
+
     fn add_parent_arg<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         app.arg(
             Arg::with_name("parent")
@@ -321,6 +346,7 @@ impl Options {
         embedded = Self::add_my_ca_arg(embedded);
         embedded = Self::add_child_arg(embedded);
         embedded = Self::add_child_resource_args(embedded);
+        embedded = Self::add_child_validity_args(embedded);
 
         let mut remote = SubCommand::with_name("remote")
             .about("Add a remote child, and return the parent response");
@@ -328,6 +354,7 @@ impl Options {
         remote = Self::add_my_ca_arg(remote);
         remote = Self::add_child_arg(remote);
         remote = Self::add_child_resource_args(remote);
+        remote = Self::add_child_validity_args(remote);
         let remote = remote.arg(
             Arg::with_name("rfc8183")
                 .long("rfc8183")
@@ -346,6 +373,7 @@ impl Options {
         sub = Self::add_my_ca_arg(sub);
         sub = Self::add_child_arg(sub);
         sub = Self::add_child_resource_args(sub);
+        sub = Self::add_child_validity_args(sub);
         sub = sub.arg(
             Arg::with_name("idcert")
                 .long("idcert")
@@ -369,8 +397,8 @@ impl Options {
     }
 
     fn make_cas_children_info_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
-        let mut sub =
-            SubCommand::with_name("info").about("Show info for a child (id and resources).");
+        let mut sub = SubCommand::with_name("info")
+            .about("Show info for a child (id, resources and recent activity).");
 
         sub = Self::add_general_args(sub);
         sub = Self::add_my_ca_arg(sub);
@@ -389,6 +417,114 @@ impl Options {
         app.subcommand(sub)
     }
 
+    fn make_cas_children_suspend_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("suspend")
+            .about("Suspend a child, revoking its certificates but keeping its entitlements.");
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+        sub = Self::add_child_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_children_resume_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("resume").about("Resume a suspended child.");
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+        sub = Self::add_child_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_children_transfer_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("transfer")
+            .about("Move prefixes, and their ROAs, to another child in *this* Krill server.");
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+        sub = sub
+            .arg(
+                Arg::with_name("from")
+                    .long("from")
+                    .value_name("name")
+                    .help("The name of the child giving up the prefixes.")
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("to")
+                    .long("to")
+                    .value_name("name")
+                    .help("The name of the child receiving the prefixes.")
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("ipv4")
+                    .short("4")
+                    .long("ipv4")
+                    .value_name("IPv4 resources")
+                    .help("The IPv4 resources to move: e.g. 192.168.0.0/16")
+                    .required(false),
+            )
+            .arg(
+                Arg::with_name("ipv6")
+                    .short("6")
+                    .long("ipv6")
+                    .value_name("IPv6 resources")
+                    .help("The IPv6 resources to move: e.g. 2001:db8::/32")
+                    .required(false),
+            );
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_children_invitations_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("invitations")
+            .about("Manage invitations for children to add themselves.");
+
+        let id_arg = Arg::with_name("id")
+            .long("id")
+            .value_name("hex encoded SHA-256 hash")
+            .help("The id of the invitation, as shown in the list of invitations.")
+            .required(true);
+        let expires_arg = Arg::with_name("expires")
+            .long("expires")
+            .value_name("RFC 3339 time")
+            .help("The invitation can not be used after this time, e.g.: 2020-01-01T00:00:00Z")
+            .required(true);
+
+        let mut add = SubCommand::with_name("add")
+            .about("Invite a child, and show the token it should use to add itself.");
+        add = Self::add_general_args(add);
+        add = Self::add_my_ca_arg(add);
+        add = Self::add_child_resource_args(add);
+        add = add.arg(expires_arg.clone());
+
+        let mut list = SubCommand::with_name("list").about("Show the current invitations.");
+        list = Self::add_general_args(list);
+        list = Self::add_my_ca_arg(list);
+
+        let mut update =
+            SubCommand::with_name("update").about("Change the expiry time of an invitation.");
+        update = Self::add_general_args(update);
+        update = Self::add_my_ca_arg(update);
+        update = update.arg(id_arg.clone()).arg(expires_arg);
+
+        let mut revoke = SubCommand::with_name("revoke").about("Revoke an invitation.");
+        revoke = Self::add_general_args(revoke);
+        revoke = Self::add_my_ca_arg(revoke);
+        revoke = revoke.arg(id_arg);
+
+        sub = sub
+            .subcommand(add)
+            .subcommand(list)
+            .subcommand(update)
+            .subcommand(revoke);
+
+        app.subcommand(sub)
+    }
+
     fn make_cas_children_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         let mut sub = SubCommand::with_name("children").about("Manage children for a CA in Krill.");
 
@@ -396,6 +532,10 @@ impl Options {
         sub = Self::make_cas_children_update_sc(sub);
         sub = Self::make_cas_children_info_sc(sub);
         sub = Self::make_cas_children_remove_sc(sub);
+        sub = Self::make_cas_children_suspend_sc(sub);
+        sub = Self::make_cas_children_resume_sc(sub);
+        sub = Self::make_cas_children_transfer_sc(sub);
+        sub = Self::make_cas_children_invitations_sc(sub);
         sub = Self::make_cas_children_response_sc(sub);
 
         app.subcommand(sub)
@@ -474,6 +614,44 @@ impl Options {
         app.subcommand(sub)
     }
 
+    fn make_cas_parents_migrate_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let sub = SubCommand::with_name("migrate")
+            .about("Move this CA to a new parent, without breaking its ROAs.");
+
+        let mut start = SubCommand::with_name("start").about(
+            "Add the new parent, and remove the old parent after all ROAs are re-issued under it.",
+        );
+        start = Self::add_general_args(start);
+        start = Self::add_my_ca_arg(start);
+        start = start.arg(
+            Arg::with_name("from")
+                .long("from")
+                .value_name("name")
+                .help("The local name of the parent to move away from.")
+                .required(true),
+        );
+        start = Self::add_parent_arg(start);
+        start = start.arg(
+            Arg::with_name("rfc8183")
+                .long("rfc8183")
+                .help("The RFC8183 Parent Response XML, leave out for an embedded parent.")
+                .value_name("<XML file>")
+                .required(false),
+        );
+
+        let mut status =
+            SubCommand::with_name("status").about("Show the progress of moving to a new parent.");
+        status = Self::add_general_args(status);
+        status = Self::add_my_ca_arg(status);
+
+        let mut cancel = SubCommand::with_name("cancel")
+            .about("Cancel moving to a new parent, and remove the new parent again.");
+        cancel = Self::add_general_args(cancel);
+        cancel = Self::add_my_ca_arg(cancel);
+
+        app.subcommand(sub.subcommand(start).subcommand(status).subcommand(cancel))
+    }
+
     fn make_cas_parents_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         let mut sub = SubCommand::with_name("parents").about("Manage parents for this CA.");
 
@@ -482,6 +660,7 @@ impl Options {
         sub = Self::make_cas_parents_update_sc(sub);
         sub = Self::make_cas_parents_contact_sc(sub);
         sub = Self::make_cas_parents_remove_sc(sub);
+        sub = Self::make_cas_parents_migrate_sc(sub);
 
         app.subcommand(sub)
     }
@@ -515,6 +694,36 @@ impl Options {
         app.subcommand(sub)
     }
 
+    fn make_cas_idroll_init_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("init")
+            .about("Generate a new ID key, and show it to children in parent responses.");
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_idroll_activate_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("activate")
+            .about("Start using the new ID key, and remove the old key.");
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_idroll_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("idroll")
+            .about("Perform a roll of the RFC 8183 ID key of this CA.");
+
+        sub = Self::make_cas_idroll_init_sc(sub);
+        sub = Self::make_cas_idroll_activate_sc(sub);
+
+        app.subcommand(sub)
+    }
+
     fn make_cas_routes_list_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         let mut sub = SubCommand::with_name("list").about("Show current authorizations.");
 
@@ -546,6 +755,196 @@ impl Options {
                 .required(true),
         );
 
+        sub = sub.arg(
+            Arg::with_name("strict")
+                .long("strict")
+                .help("Reject the update on new lint warnings, except for loose max lengths."),
+        );
+
+        app.subcommand(sub)
+    }
+
+    fn add_roas_type_arg<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        app.arg(
+            Arg::with_name("type")
+                .long("type")
+                .value_name("csv|json|slurm")
+                .help(concat!(
+                    "The format of the ROA definitions:\n",
+                    "csv:   lines of 'prefix,max_length,asn,comment'\n",
+                    "json:  a list of ROA definitions as used in the API\n",
+                    "slurm: RFC 8416 SLURM 'prefixAssertions'\n",
+                ))
+                .required(true),
+        )
+    }
+
+    fn make_cas_routes_import_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("import")
+            .about("Import authorizations, existing authorizations are kept.");
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+        sub = Self::add_roas_type_arg(sub);
+
+        sub = sub.arg(
+            Arg::with_name("file")
+                .long("file")
+                .help("The file containing the authorizations to import.")
+                .value_name("<file>")
+                .required(true),
+        );
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_routes_export_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("export").about("Export authorizations.");
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+        sub = Self::add_roas_type_arg(sub);
+
+        sub = sub.arg(
+            Arg::with_name("all")
+                .long("all")
+                .help("Export the authorizations of all CAs.")
+                .required(false),
+        );
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_routes_meta_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("meta").about(
+            "Update the comment, tags and/or ticket for an existing authorization. \
+            Fields which are not given are kept, use an empty value to clear a field.",
+        );
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        sub = sub
+            .arg(
+                Arg::with_name("roa")
+                    .long("roa")
+                    .value_name("definition")
+                    .help("The authorization, e.g.: \"192.168.0.0/16-24 => 64496\"")
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("comment")
+                    .long("comment")
+                    .value_name("text")
+                    .help("A free text comment.")
+                    .required(false),
+            )
+            .arg(
+                Arg::with_name("tag")
+                    .long("tag")
+                    .value_name("tag")
+                    .help("A tag, may be repeated.")
+                    .multiple(true)
+                    .number_of_values(1)
+                    .required(false),
+            )
+            .arg(
+                Arg::with_name("ticket")
+                    .long("ticket")
+                    .value_name("reference")
+                    .help("A ticket reference.")
+                    .required(false),
+            );
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_routes_schedule_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("schedule").about(
+            "Set the time window for an authorization, omit both times to cancel a schedule.",
+        );
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
+        sub = sub
+            .arg(
+                Arg::with_name("roa")
+                    .long("roa")
+                    .value_name("definition")
+                    .help("The authorization, e.g.: \"192.168.0.0/16-24 => 64496\"")
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("not_before")
+                    .long("not-before")
+                    .value_name("RFC 3339 time")
+                    .help("Add the authorization at this time, e.g.: 2020-01-01T00:00:00Z")
+                    .required(false),
+            )
+            .arg(
+                Arg::with_name("not_after")
+                    .long("not-after")
+                    .value_name("RFC 3339 time")
+                    .help("Remove the authorization at this time, e.g.: 2020-01-02T00:00:00Z")
+                    .required(false),
+            );
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_routes_as0_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("as0")
+            .about("Manage AS0 ROAs for space not covered by authorizations or children.");
+
+        let mut show = SubCommand::with_name("show").about("Show AS0 ROAs for unused space.");
+        show = Self::add_general_args(show);
+        show = Self::add_my_ca_arg(show);
+
+        let mut enable = SubCommand::with_name("enable").about("Issue AS0 ROAs for unused space.");
+        enable = Self::add_general_args(enable);
+        enable = Self::add_my_ca_arg(enable);
+
+        let mut disable =
+            SubCommand::with_name("disable").about("Stop issuing AS0 ROAs for unused space.");
+        disable = Self::add_general_args(disable);
+        disable = Self::add_my_ca_arg(disable);
+
+        sub = sub.subcommand(show).subcommand(enable).subcommand(disable);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_routes_dormant_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("dormant")
+            .about("Manage ROAs no longer covered by the resources of your CA.");
+
+        let mut show = SubCommand::with_name("show").about("Show the policy and dormant ROAs.");
+        show = Self::add_general_args(show);
+        show = Self::add_my_ca_arg(show);
+
+        let mut keep =
+            SubCommand::with_name("keep").about("Keep ROAs dormant until resources return.");
+        keep = Self::add_general_args(keep);
+        keep = Self::add_my_ca_arg(keep);
+
+        let mut remove =
+            SubCommand::with_name("remove").about("Remove ROAs no longer covered by resources.");
+        remove = Self::add_general_args(remove);
+        remove = Self::add_my_ca_arg(remove);
+
+        sub = sub.subcommand(show).subcommand(keep).subcommand(remove);
+
+        app.subcommand(sub)
+    }
+
+    fn make_cas_routes_lint_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("lint")
+            .about("Show best practice warnings for current authorizations.");
+
+        sub = Self::add_general_args(sub);
+        sub = Self::add_my_ca_arg(sub);
+
         app.subcommand(sub)
     }
 
@@ -554,6 +953,13 @@ impl Options {
 
         sub = Self::make_cas_routes_list_sc(sub);
         sub = Self::make_cas_routes_update_sc(sub);
+        sub = Self::make_cas_routes_import_sc(sub);
+        sub = Self::make_cas_routes_export_sc(sub);
+        sub = Self::make_cas_routes_meta_sc(sub);
+        sub = Self::make_cas_routes_lint_sc(sub);
+        sub = Self::make_cas_routes_schedule_sc(sub);
+        sub = Self::make_cas_routes_as0_sc(sub);
+        sub = Self::make_cas_routes_dormant_sc(sub);
 
         app.subcommand(sub)
     }
@@ -651,6 +1057,30 @@ impl Options {
         app.subcommand(sub)
     }
 
+    fn make_publishers_reset_session_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("reset_session")
+            .about("Start a new RRDP session with a fresh snapshot of all current objects.");
+        sub = Self::add_general_args(sub);
+        app.subcommand(sub)
+    }
+
+    fn make_publishers_idroll_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("idroll")
+            .about("Perform a roll of the RFC 8183 ID key of the publication server.");
+
+        let mut init = SubCommand::with_name("init")
+            .about("Generate a new ID key, and show it to publishers in repository responses.");
+        init = Self::add_general_args(init);
+
+        let mut activate = SubCommand::with_name("activate")
+            .about("Start using the new ID key, and remove the old key.");
+        activate = Self::add_general_args(activate);
+
+        sub = sub.subcommand(init).subcommand(activate);
+
+        app.subcommand(sub)
+    }
+
     fn make_publishers_stats_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         let mut sub = SubCommand::with_name("stats").about("Show publication server stats.");
         sub = Self::add_general_args(sub);
@@ -704,6 +1134,21 @@ impl Options {
         app.subcommand(sub)
     }
 
+    fn make_publishers_objects_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("objects")
+            .about("List the current objects of a publisher, or show one object decoded.");
+        sub = Self::add_general_args(sub);
+        sub = Self::add_publisher_arg(sub);
+        sub = sub.arg(
+            Arg::with_name("hash")
+                .long("hash")
+                .value_name("hex")
+                .help("The hash of the object to show. Lists all objects if not set.")
+                .required(false),
+        );
+        app.subcommand(sub)
+    }
+
     fn make_publishers_response_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         let mut sub = SubCommand::with_name("response")
             .about("Show RFC8183 Repository Response for a publisher.");
@@ -712,16 +1157,104 @@ impl Options {
         app.subcommand(sub)
     }
 
+    fn add_publisher_quota_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        app.arg(
+            Arg::with_name("max_objects")
+                .long("max-objects")
+                .value_name("number")
+                .help("The maximum number of objects. Unlimited if not set.")
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("max_size")
+                .long("max-size")
+                .value_name("bytes")
+                .help("The maximum combined size of all objects. Unlimited if not set.")
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("max_object_size")
+                .long("max-object-size")
+                .value_name("bytes")
+                .help("The maximum size of a single object. Unlimited if not set.")
+                .required(false),
+        )
+    }
+
+    fn make_publishers_quota_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("quota")
+            .about("Replace the quota for a publisher. Limits which are not given are removed.");
+        sub = Self::add_general_args(sub);
+        sub = Self::add_publisher_arg(sub);
+        sub = Self::add_publisher_quota_args(sub);
+        app.subcommand(sub)
+    }
+
+    fn make_publishers_invitations_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        let mut sub = SubCommand::with_name("invitations")
+            .about("Manage invitations for publishers to add themselves.");
+
+        let id_arg = Arg::with_name("id")
+            .long("id")
+            .value_name("hex encoded SHA-256 hash")
+            .help("The id of the invitation, as shown in the list of invitations.")
+            .required(true);
+        let expires_arg = Arg::with_name("expires")
+            .long("expires")
+            .value_name("RFC 3339 time")
+            .help("The invitation can not be used after this time, e.g.: 2020-01-01T00:00:00Z")
+            .required(true);
+
+        let mut add = SubCommand::with_name("add")
+            .about("Invite a publisher, and show the token it should use to add itself.");
+        add = Self::add_general_args(add);
+        add = Self::add_publisher_quota_args(add);
+        add = add
+            .arg(
+                Arg::with_name("handle_prefix")
+                    .long("handle-prefix")
+                    .value_name("prefix")
+                    .help("Prefix for the handle of the publisher, without '/', e.g.: member-")
+                    .required(false),
+            )
+            .arg(expires_arg.clone());
+
+        let mut list = SubCommand::with_name("list").about("Show the current invitations.");
+        list = Self::add_general_args(list);
+
+        let mut update =
+            SubCommand::with_name("update").about("Change the expiry time of an invitation.");
+        update = Self::add_general_args(update);
+        update = update.arg(id_arg.clone()).arg(expires_arg);
+
+        let mut revoke = SubCommand::with_name("revoke").about("Revoke an invitation.");
+        revoke = Self::add_general_args(revoke);
+        revoke = revoke.arg(id_arg);
+
+        sub = sub
+            .subcommand(add)
+            .subcommand(list)
+            .subcommand(update)
+            .subcommand(revoke);
+
+        app.subcommand(sub)
+    }
+
     fn make_publishers_sc<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
         let mut sub = SubCommand::with_name("publishers").about("Manage publishers in Krill.");
 
         sub = Self::make_publishers_list_sc(sub);
         sub = Self::make_publishers_stale_sc(sub);
         sub = Self::make_publishers_stats_sc(sub);
+        sub = Self::make_publishers_reset_session_sc(sub);
+        sub = Self::make_publishers_idroll_sc(sub);
         sub = Self::make_publishers_add_sc(sub);
         sub = Self::make_publishers_remove_sc(sub);
         sub = Self::make_publishers_show_sc(sub);
+        sub = Self::make_publishers_objects_sc(sub);
         sub = Self::make_publishers_response_sc(sub);
+        sub = Self::make_publishers_quota_sc(sub);
+        sub = Self::make_publishers_invitations_sc(sub);
 
         app.subcommand(sub)
     }
@@ -774,6 +1307,7 @@ impl Options {
         app = Self::make_cas_children_sc(app);
         app = Self::make_cas_parents_sc(app);
         app = Self::make_cas_keyroll_sc(app);
+        app = Self::make_cas_idroll_sc(app);
         app = Self::make_cas_routes_sc(app);
         app = Self::make_cas_repo_sc(app);
         app = Self::make_cas_issues_sc(app);
@@ -949,11 +1483,33 @@ impl Options {
             None => ChildAuthRequest::Embedded,
         };
 
-        let child_request = AddChildRequest::new(child, resources, auth_request);
+        let mut child_request = AddChildRequest::new(child, resources, auth_request);
+        if let Some(validity) = Self::parse_child_validity_args(matches)? {
+            child_request = child_request.with_validity(validity);
+        }
         let command = Command::CertAuth(CaCommand::ChildAdd(my_ca, child_request));
         Ok(Options::make(general_args, command))
     }
 
+    fn parse_child_validity_args(matches: &ArgMatches) -> Result<Option<ChildCertValidity>, Error> {
+        match (
+            matches.value_of("validity_weeks"),
+            matches.value_of("reissue_weeks"),
+        ) {
+            (Some(validity), Some(reissue)) => {
+                let validity = i64::from_str(validity).map_err(|_| Error::InvalidWeeks)?;
+                let reissue = i64::from_str(reissue).map_err(|_| Error::InvalidWeeks)?;
+                let validity = ChildCertValidity::new(validity, reissue);
+                if validity.is_valid() {
+                    Ok(Some(validity))
+                } else {
+                    Err(Error::InvalidWeeks)
+                }
+            }
+            _ => Ok(None),
+        }
+    }
+
     fn parse_matches_cas_children_add(matches: &ArgMatches) -> Result<Options, Error> {
         if let Some(m) = matches.subcommand_matches("embedded") {
             Self::parse_matches_cas_children_add_general(m, None)
@@ -985,7 +1541,9 @@ impl Options {
         };
         let resources = Self::parse_resource_args(matches)?;
 
-        let update = UpdateChildRequest::new(id_cert, resources);
+        let validity = Self::parse_child_validity_args(matches)?;
+
+        let update = UpdateChildRequest::new(id_cert, resources).with_validity(validity);
 
         let command = Command::CertAuth(CaCommand::ChildUpdate(my_ca, child, update));
         Ok(Options::make(general_args, command))
@@ -1024,6 +1582,79 @@ impl Options {
         Ok(Options::make(general_args, command))
     }
 
+    fn parse_matches_cas_children_suspended(
+        matches: &ArgMatches,
+        suspended: bool,
+    ) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let child = matches
+            .value_of("child")
+            .ok_or_else(|| Error::missing_arg("child"))?;
+        let child = Handle::from_str(child).map_err(|_| Error::InvalidHandle)?;
+
+        let update = if suspended {
+            UpdateChildRequest::suspend()
+        } else {
+            UpdateChildRequest::resume()
+        };
+
+        let command = Command::CertAuth(CaCommand::ChildUpdate(my_ca, child, update));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_children_transfer(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let from = matches
+            .value_of("from")
+            .ok_or_else(|| Error::missing_arg("from"))?;
+        let from = Handle::from_str(from).map_err(|_| Error::InvalidHandle)?;
+        let to = matches
+            .value_of("to")
+            .ok_or_else(|| Error::missing_arg("to"))?;
+        let to = Handle::from_str(to).map_err(|_| Error::InvalidHandle)?;
+
+        let resources =
+            Self::parse_resource_args(matches)?.ok_or_else(|| Error::MissingResources)?;
+
+        let transfer = ChildResourceTransfer::new(from, to, resources);
+        let command = Command::CertAuth(CaCommand::ChildTransfer(my_ca, transfer));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_children_invitations(matches: &ArgMatches) -> Result<Options, Error> {
+        let (m, command) = if let Some(m) = matches.subcommand_matches("add") {
+            let my_ca = Self::parse_my_ca(m)?;
+            let resources = Self::parse_resource_args(m)?.ok_or_else(|| Error::MissingResources)?;
+            let expires =
+                Self::parse_time_arg(m, "expires")?.ok_or_else(|| Error::missing_arg("expires"))?;
+            let init = ChildInvitationInit::new(resources, expires);
+            (m, CaCommand::ChildInvitationAdd(my_ca, init))
+        } else if let Some(m) = matches.subcommand_matches("list") {
+            let my_ca = Self::parse_my_ca(m)?;
+            (m, CaCommand::ChildInvitations(my_ca))
+        } else if let Some(m) = matches.subcommand_matches("update") {
+            let my_ca = Self::parse_my_ca(m)?;
+            let id = TokenHash::from(m.value_of("id").ok_or_else(|| Error::missing_arg("id"))?);
+            let expires =
+                Self::parse_time_arg(m, "expires")?.ok_or_else(|| Error::missing_arg("expires"))?;
+            let update = ChildInvitationUpdate::new(expires);
+            (m, CaCommand::ChildInvitationUpdate(my_ca, id, update))
+        } else if let Some(m) = matches.subcommand_matches("revoke") {
+            let my_ca = Self::parse_my_ca(m)?;
+            let id = TokenHash::from(m.value_of("id").ok_or_else(|| Error::missing_arg("id"))?);
+            (m, CaCommand::ChildInvitationRevoke(my_ca, id))
+        } else {
+            return Err(Error::UnrecognisedSubCommand);
+        };
+
+        let general_args = GeneralArgs::from_matches(m)?;
+        Ok(Options::make(general_args, Command::CertAuth(command)))
+    }
+
     fn parse_matches_cas_children(matches: &ArgMatches) -> Result<Options, Error> {
         if let Some(m) = matches.subcommand_matches("add") {
             Self::parse_matches_cas_children_add(m)
@@ -1035,6 +1666,14 @@ impl Options {
             Self::parse_matches_cas_children_update(m)
         } else if let Some(m) = matches.subcommand_matches("remove") {
             Self::parse_matches_cas_children_remove(m)
+        } else if let Some(m) = matches.subcommand_matches("suspend") {
+            Self::parse_matches_cas_children_suspended(m, true)
+        } else if let Some(m) = matches.subcommand_matches("resume") {
+            Self::parse_matches_cas_children_suspended(m, false)
+        } else if let Some(m) = matches.subcommand_matches("transfer") {
+            Self::parse_matches_cas_children_transfer(m)
+        } else if let Some(m) = matches.subcommand_matches("invitations") {
+            Self::parse_matches_cas_children_invitations(m)
         } else {
             Err(Error::UnrecognisedSubCommand)
         }
@@ -1118,6 +1757,49 @@ impl Options {
         Ok(Options::make(general_args, command))
     }
 
+    fn parse_matches_cas_parents_migrate(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("start") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let my_ca = Self::parse_my_ca(m)?;
+
+            let from = m
+                .value_of("from")
+                .ok_or_else(|| Error::missing_arg("from"))?;
+            let from = Handle::from_str(from).map_err(|_| Error::InvalidHandle)?;
+            let parent = m
+                .value_of("parent")
+                .ok_or_else(|| Error::missing_arg("parent"))?;
+            let parent = Handle::from_str(parent).map_err(|_| Error::InvalidHandle)?;
+
+            let contact = match m.value_of("rfc8183") {
+                Some(path) => {
+                    let bytes = Self::read_file_arg(path)?;
+                    let response = rfc8183::ParentResponse::validate(bytes.as_ref())?;
+                    ParentCaContact::for_rfc6492(response)
+                }
+                None => ParentCaContact::Embedded,
+            };
+            let req = ParentMigrationReq::new(from, ParentCaReq::new(parent, contact));
+
+            let command = Command::CertAuth(CaCommand::ParentMigrationStart(my_ca, req));
+            Ok(Options::make(general_args, command))
+        } else if let Some(m) = matches.subcommand_matches("status") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let my_ca = Self::parse_my_ca(m)?;
+
+            let command = Command::CertAuth(CaCommand::ParentMigrationStatus(my_ca));
+            Ok(Options::make(general_args, command))
+        } else if let Some(m) = matches.subcommand_matches("cancel") {
+            let general_args = GeneralArgs::from_matches(m)?;
+            let my_ca = Self::parse_my_ca(m)?;
+
+            let command = Command::CertAuth(CaCommand::ParentMigrationCancel(my_ca));
+            Ok(Options::make(general_args, command))
+        } else {
+            Err(Error::UnrecognisedSubCommand)
+        }
+    }
+
     fn parse_matches_cas_parents(matches: &ArgMatches) -> Result<Options, Error> {
         if let Some(m) = matches.subcommand_matches("request") {
             Self::parse_matches_cas_parents_request(m)
@@ -1129,6 +1811,8 @@ impl Options {
             Self::parse_matches_cas_parents_info(m)
         } else if let Some(m) = matches.subcommand_matches("remove") {
             Self::parse_matches_cas_parents_remove(m)
+        } else if let Some(m) = matches.subcommand_matches("migrate") {
+            Self::parse_matches_cas_parents_migrate(m)
         } else {
             Err(Error::UnrecognisedSubCommand)
         }
@@ -1162,6 +1846,34 @@ impl Options {
         }
     }
 
+    fn parse_matches_cas_idroll_init(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let command = Command::CertAuth(CaCommand::IdRollInit(my_ca));
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_idroll_activate(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let command = Command::CertAuth(CaCommand::IdRollActivate(my_ca));
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_idroll(matches: &ArgMatches) -> Result<Options, Error> {
+        if let Some(m) = matches.subcommand_matches("init") {
+            Self::parse_matches_cas_idroll_init(m)
+        } else if let Some(m) = matches.subcommand_matches("activate") {
+            Self::parse_matches_cas_idroll_activate(m)
+        } else {
+            Err(Error::UnrecognisedSubCommand)
+        }
+    }
+
     fn parse_matches_cas_routes_list(matches: &ArgMatches) -> Result<Options, Error> {
         let general_args = GeneralArgs::from_matches(matches)?;
         let my_ca = Self::parse_my_ca(matches)?;
@@ -1171,6 +1883,15 @@ impl Options {
         Ok(Options::make(general_args, command))
     }
 
+    fn parse_matches_cas_routes_lint(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let command = Command::CertAuth(CaCommand::RouteAuthorizationsLint(my_ca));
+
+        Ok(Options::make(general_args, command))
+    }
+
     fn parse_matches_cas_routes_update(matches: &ArgMatches) -> Result<Options, Error> {
         let general_args = GeneralArgs::from_matches(matches)?;
         let my_ca = Self::parse_my_ca(matches)?;
@@ -1182,16 +1903,168 @@ impl Options {
             RoaDefinitionUpdates::from_str(updates_str)?
         };
 
-        let command = Command::CertAuth(CaCommand::RouteAuthorizationsUpdate(my_ca, updates));
+        let strict = matches.is_present("strict");
+
+        let command = Command::CertAuth(CaCommand::RouteAuthorizationsUpdate(
+            my_ca, updates, strict,
+        ));
 
         Ok(Options::make(general_args, command))
     }
 
+    fn parse_roas_type_arg(matches: &ArgMatches) -> Result<RoaDefinitionsFormat, Error> {
+        let format = matches
+            .value_of("type")
+            .ok_or_else(|| Error::missing_arg("type"))?;
+        Ok(RoaDefinitionsFormat::from_str(format)?)
+    }
+
+    fn parse_matches_cas_routes_import(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+        let format = Self::parse_roas_type_arg(matches)?;
+
+        let roas = {
+            let path = matches
+                .value_of("file")
+                .ok_or_else(|| Error::missing_arg("file"))?;
+            let bytes = Self::read_file_arg(path)?;
+            let roas_str = unsafe { from_utf8_unchecked(&bytes) };
+            format.parse(&my_ca, roas_str)?
+        };
+
+        let command = Command::CertAuth(CaCommand::RouteAuthorizationsImport(my_ca, roas));
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_routes_export(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let format = Self::parse_roas_type_arg(matches)?;
+
+        let my_ca = if matches.is_present("all") {
+            None
+        } else {
+            Some(Self::parse_my_ca(matches)?)
+        };
+
+        let command = Command::CertAuth(CaCommand::RouteAuthorizationsExport(my_ca, format));
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_routes_meta(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let roa_str = matches
+            .value_of("roa")
+            .ok_or_else(|| Error::missing_arg("roa"))?;
+        let definition = RoaDefinition::from_str(roa_str)?;
+        let comment = matches.value_of("comment").map(|s| s.to_string());
+        let tags = match matches.values_of("tag") {
+            Some(tags) => tags.map(|s| s.to_string()).collect(),
+            None => vec![],
+        };
+        let ticket = matches.value_of("ticket").map(|s| s.to_string());
+
+        let roa = ConfiguredRoa::new(definition, RoaDefinitionMeta::new(comment, tags, ticket));
+
+        let command = Command::CertAuth(CaCommand::RouteAuthorizationsMetaUpdate(my_ca, vec![roa]));
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_time_arg(matches: &ArgMatches, name: &str) -> Result<Option<Time>, Error> {
+        match matches.value_of(name) {
+            None => Ok(None),
+            Some(time_str) => {
+                let time = DateTime::<Utc>::from_str(time_str)
+                    .map_err(|_| Error::InvalidTime(time_str.to_string()))?;
+                Ok(Some(Time::new(time)))
+            }
+        }
+    }
+
+    fn parse_matches_cas_routes_schedule(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let my_ca = Self::parse_my_ca(matches)?;
+
+        let roa_str = matches
+            .value_of("roa")
+            .ok_or_else(|| Error::missing_arg("roa"))?;
+        let definition = RoaDefinition::from_str(roa_str)?;
+        let not_before = Self::parse_time_arg(matches, "not_before")?;
+        let not_after = Self::parse_time_arg(matches, "not_after")?;
+
+        let roa = ScheduledRoa::new(definition, RoaSchedule::new(not_before, not_after));
+
+        let command = Command::CertAuth(CaCommand::RouteAuthorizationsSchedule(my_ca, vec![roa]));
+
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_cas_routes_as0(matches: &ArgMatches) -> Result<Options, Error> {
+        let (m, command) = if let Some(m) = matches.subcommand_matches("show") {
+            let my_ca = Self::parse_my_ca(m)?;
+            (m, CaCommand::RouteAuthorizationsAs0Show(my_ca))
+        } else if let Some(m) = matches.subcommand_matches("enable") {
+            let my_ca = Self::parse_my_ca(m)?;
+            (m, CaCommand::RouteAuthorizationsAs0Update(my_ca, true))
+        } else if let Some(m) = matches.subcommand_matches("disable") {
+            let my_ca = Self::parse_my_ca(m)?;
+            (m, CaCommand::RouteAuthorizationsAs0Update(my_ca, false))
+        } else {
+            return Err(Error::UnrecognisedSubCommand);
+        };
+
+        let general_args = GeneralArgs::from_matches(m)?;
+        Ok(Options::make(general_args, Command::CertAuth(command)))
+    }
+
+    fn parse_matches_cas_routes_dormant(matches: &ArgMatches) -> Result<Options, Error> {
+        let (m, command) = if let Some(m) = matches.subcommand_matches("show") {
+            let my_ca = Self::parse_my_ca(m)?;
+            (m, CaCommand::RouteAuthorizationsDormantShow(my_ca))
+        } else if let Some(m) = matches.subcommand_matches("keep") {
+            let my_ca = Self::parse_my_ca(m)?;
+            (
+                m,
+                CaCommand::RouteAuthorizationsDormantPolicy(my_ca, RoaDormantPolicy::Keep),
+            )
+        } else if let Some(m) = matches.subcommand_matches("remove") {
+            let my_ca = Self::parse_my_ca(m)?;
+            (
+                m,
+                CaCommand::RouteAuthorizationsDormantPolicy(my_ca, RoaDormantPolicy::Remove),
+            )
+        } else {
+            return Err(Error::UnrecognisedSubCommand);
+        };
+
+        let general_args = GeneralArgs::from_matches(m)?;
+        Ok(Options::make(general_args, Command::CertAuth(command)))
+    }
+
     fn parse_matches_cas_routes(matches: &ArgMatches) -> Result<Options, Error> {
         if let Some(m) = matches.subcommand_matches("list") {
             Self::parse_matches_cas_routes_list(m)
         } else if let Some(m) = matches.subcommand_matches("update") {
             Self::parse_matches_cas_routes_update(m)
+        } else if let Some(m) = matches.subcommand_matches("import") {
+            Self::parse_matches_cas_routes_import(m)
+        } else if let Some(m) = matches.subcommand_matches("export") {
+            Self::parse_matches_cas_routes_export(m)
+        } else if let Some(m) = matches.subcommand_matches("meta") {
+            Self::parse_matches_cas_routes_meta(m)
+        } else if let Some(m) = matches.subcommand_matches("lint") {
+            Self::parse_matches_cas_routes_lint(m)
+        } else if let Some(m) = matches.subcommand_matches("schedule") {
+            Self::parse_matches_cas_routes_schedule(m)
+        } else if let Some(m) = matches.subcommand_matches("as0") {
+            Self::parse_matches_cas_routes_as0(m)
+        } else if let Some(m) = matches.subcommand_matches("dormant") {
+            Self::parse_matches_cas_routes_dormant(m)
         } else {
             Err(Error::UnrecognisedSubCommand)
         }
@@ -1296,6 +2169,25 @@ impl Options {
         Ok(Options::make(general_args, command))
     }
 
+    fn parse_matches_publishers_reset_session(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let command = Command::Publishers(PublishersCommand::ResetSession);
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_publishers_idroll(matches: &ArgMatches) -> Result<Options, Error> {
+        let (command, m) = if let Some(m) = matches.subcommand_matches("init") {
+            (PublishersCommand::IdRollInit, m)
+        } else if let Some(m) = matches.subcommand_matches("activate") {
+            (PublishersCommand::IdRollActivate, m)
+        } else {
+            return Err(Error::UnrecognisedSubCommand);
+        };
+
+        let general_args = GeneralArgs::from_matches(m)?;
+        Ok(Options::make(general_args, Command::Publishers(command)))
+    }
+
     fn parse_matches_publishers_add(matches: &ArgMatches) -> Result<Options, Error> {
         let general_args = GeneralArgs::from_matches(matches)?;
 
@@ -1329,6 +2221,19 @@ impl Options {
         Ok(Options::make(general_args, command))
     }
 
+    fn parse_matches_publishers_objects(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let publisher = Self::parse_publisher_arg(matches)?;
+        let command = match matches.value_of("hash") {
+            None => PublishersCommand::PublisherObjects(publisher),
+            Some(hash) => {
+                let hash = HexEncodedHash::from(hash.to_string());
+                PublishersCommand::PublisherObject(publisher, hash)
+            }
+        };
+        Ok(Options::make(general_args, Command::Publishers(command)))
+    }
+
     fn parse_matches_publishers_repo_response(matches: &ArgMatches) -> Result<Options, Error> {
         let general_args = GeneralArgs::from_matches(matches)?;
         let publisher = Self::parse_publisher_arg(matches)?;
@@ -1336,6 +2241,61 @@ impl Options {
         Ok(Options::make(general_args, command))
     }
 
+    fn parse_publisher_quota_args(matches: &ArgMatches) -> Result<PublisherQuota, Error> {
+        let limit = |name: &str| -> Result<Option<usize>, Error> {
+            match matches.value_of(name) {
+                None => Ok(None),
+                Some(s) => usize::from_str(s)
+                    .map(Some)
+                    .map_err(|_| Error::InvalidQuota(s.to_string())),
+            }
+        };
+
+        Ok(PublisherQuota::new(
+            limit("max_objects")?,
+            limit("max_size")?,
+            limit("max_object_size")?,
+        ))
+    }
+
+    fn parse_matches_publishers_quota(matches: &ArgMatches) -> Result<Options, Error> {
+        let general_args = GeneralArgs::from_matches(matches)?;
+        let publisher = Self::parse_publisher_arg(matches)?;
+        let quota = Self::parse_publisher_quota_args(matches)?;
+        let command = Command::Publishers(PublishersCommand::UpdateQuota(publisher, quota));
+        Ok(Options::make(general_args, command))
+    }
+
+    fn parse_matches_publishers_invitations(matches: &ArgMatches) -> Result<Options, Error> {
+        let (m, command) = if let Some(m) = matches.subcommand_matches("add") {
+            let handle_prefix = match m.value_of("handle_prefix") {
+                None => None,
+                Some(s) => Some(Handle::from_str(s).map_err(|_| Error::InvalidHandle)?),
+            };
+            let quota = Self::parse_publisher_quota_args(m)?;
+            let expires =
+                Self::parse_time_arg(m, "expires")?.ok_or_else(|| Error::missing_arg("expires"))?;
+            let init = PublisherInvitationInit::new(handle_prefix, quota, expires);
+            (m, PublishersCommand::InvitationAdd(init))
+        } else if let Some(m) = matches.subcommand_matches("list") {
+            (m, PublishersCommand::Invitations)
+        } else if let Some(m) = matches.subcommand_matches("update") {
+            let id = TokenHash::from(m.value_of("id").ok_or_else(|| Error::missing_arg("id"))?);
+            let expires =
+                Self::parse_time_arg(m, "expires")?.ok_or_else(|| Error::missing_arg("expires"))?;
+            let update = PublisherInvitationUpdate::new(expires);
+            (m, PublishersCommand::InvitationUpdate(id, update))
+        } else if let Some(m) = matches.subcommand_matches("revoke") {
+            let id = TokenHash::from(m.value_of("id").ok_or_else(|| Error::missing_arg("id"))?);
+            (m, PublishersCommand::InvitationRevoke(id))
+        } else {
+            return Err(Error::UnrecognisedSubCommand);
+        };
+
+        let general_args = GeneralArgs::from_matches(m)?;
+        Ok(Options::make(general_args, Command::Publishers(command)))
+    }
+
     fn parse_matches_publishers(matches: &ArgMatches) -> Result<Options, Error> {
         if let Some(m) = matches.subcommand_matches("list") {
             Self::parse_matches_publishers_list(m)
@@ -1343,14 +2303,24 @@ impl Options {
             Self::parse_matches_publishers_stale(m)
         } else if let Some(m) = matches.subcommand_matches("stats") {
             Self::parse_matches_publishers_stats(m)
+        } else if let Some(m) = matches.subcommand_matches("reset_session") {
+            Self::parse_matches_publishers_reset_session(m)
+        } else if let Some(m) = matches.subcommand_matches("idroll") {
+            Self::parse_matches_publishers_idroll(m)
         } else if let Some(m) = matches.subcommand_matches("add") {
             Self::parse_matches_publishers_add(m)
         } else if let Some(m) = matches.subcommand_matches("remove") {
             Self::parse_matches_publishers_remove(m)
         } else if let Some(m) = matches.subcommand_matches("show") {
             Self::parse_matches_publishers_show(m)
+        } else if let Some(m) = matches.subcommand_matches("objects") {
+            Self::parse_matches_publishers_objects(m)
         } else if let Some(m) = matches.subcommand_matches("response") {
             Self::parse_matches_publishers_repo_response(m)
+        } else if let Some(m) = matches.subcommand_matches("quota") {
+            Self::parse_matches_publishers_quota(m)
+        } else if let Some(m) = matches.subcommand_matches("invitations") {
+            Self::parse_matches_publishers_invitations(m)
         } else {
             Err(Error::UnrecognisedSubCommand)
         }
@@ -1403,6 +2373,8 @@ impl Options {
             Self::parse_matches_cas_parents(m)
         } else if let Some(m) = matches.subcommand_matches("keyroll") {
             Self::parse_matches_cas_keyroll(m)
+        } else if let Some(m) = matches.subcommand_matches("idroll") {
+            Self::parse_matches_cas_idroll(m)
         } else if let Some(m) = matches.subcommand_matches("roas") {
             Self::parse_matches_cas_routes(m)
         } else if let Some(m) = matches.subcommand_matches("repo") {
@@ -1446,8 +2418,11 @@ pub enum CaCommand {
     // Initialise a CA
     Init(CertAuthInit),
 
-    // Update CA id
-    UpdateId(Handle),
+    // Start a roll of the CA id
+    IdRollInit(Handle),
+
+    // Activate the new CA id
+    IdRollActivate(Handle),
 
     // Get an RFC8183 parent response for a child
     ParentResponse(Handle, ChildHandle),
@@ -1472,11 +2447,23 @@ pub enum CaCommand {
     // Remove a parent
     RemoveParent(Handle, ParentHandle),
 
+    // Move to a new parent, show the progress of doing so, or cancel it
+    ParentMigrationStart(Handle, ParentMigrationReq),
+    ParentMigrationStatus(Handle),
+    ParentMigrationCancel(Handle),
+
     // Children
     ChildInfo(Handle, ChildHandle),
     ChildAdd(Handle, AddChildRequest),
     ChildUpdate(Handle, ChildHandle, UpdateChildRequest),
     ChildDelete(Handle, ChildHandle),
+    ChildTransfer(Handle, ChildResourceTransfer),
+
+    // Invitations for children to add themselves
+    ChildInvitationAdd(Handle, ChildInvitationInit),
+    ChildInvitations(Handle),
+    ChildInvitationUpdate(Handle, TokenHash, ChildInvitationUpdate),
+    ChildInvitationRevoke(Handle, TokenHash),
 
     // Initialise a manual key-roll now
     KeyRollInit(Handle),
@@ -1488,7 +2475,34 @@ pub enum CaCommand {
     RouteAuthorizationsList(Handle),
 
     // Update the Route Authorizations for this CA
-    RouteAuthorizationsUpdate(Handle, RoaDefinitionUpdates),
+    RouteAuthorizationsUpdate(Handle, RoaDefinitionUpdates, bool),
+
+    // Import Route Authorizations for this CA, existing ones are kept
+    RouteAuthorizationsImport(Handle, Vec<ConfiguredRoa>),
+
+    // Update the comment, tags and ticket for existing Route Authorizations
+    RouteAuthorizationsMetaUpdate(Handle, Vec<ConfiguredRoa>),
+
+    // Export the Route Authorizations for this CA, or all CAs if None
+    RouteAuthorizationsExport(Option<Handle>, RoaDefinitionsFormat),
+
+    // Show best practice warnings for the Route Authorizations of this CA
+    RouteAuthorizationsLint(Handle),
+
+    // Set the time window in which Route Authorizations should be present
+    RouteAuthorizationsSchedule(Handle, Vec<ScheduledRoa>),
+
+    // Show the AS0 ROAs for space not covered by authorizations or children
+    RouteAuthorizationsAs0Show(Handle),
+
+    // Enable or disable AS0 ROAs for space not covered by authorizations or children
+    RouteAuthorizationsAs0Update(Handle, bool),
+
+    // Show the policy for, and the Route Authorizations not covered by resources
+    RouteAuthorizationsDormantShow(Handle),
+
+    // Keep Route Authorizations not covered by resources dormant, or remove them
+    RouteAuthorizationsDormantPolicy(Handle, RoaDormantPolicy),
 
     // Show details for this CA
     Show(Handle),
@@ -1515,11 +2529,21 @@ pub enum BulkCaCommand {
 pub enum PublishersCommand {
     AddPublisher(rfc8183::PublisherRequest),
     ShowPublisher(PublisherHandle),
+    PublisherObjects(PublisherHandle),
+    PublisherObject(PublisherHandle, HexEncodedHash),
     RemovePublisher(PublisherHandle),
     RepositoryResponse(PublisherHandle),
+    UpdateQuota(PublisherHandle, PublisherQuota),
     StalePublishers(i64),
     Stats,
+    ResetSession,
+    IdRollInit,
+    IdRollActivate,
     PublisherList,
+    InvitationAdd(PublisherInvitationInit),
+    Invitations,
+    InvitationUpdate(TokenHash, PublisherInvitationUpdate),
+    InvitationRevoke(TokenHash),
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -1603,6 +2627,17 @@ pub enum Error {
     #[display(fmt = "Use a number of 0 or more seconds.")]
     InvalidSeconds,
 
+    #[display(fmt = "Invalid time: {}, use RFC 3339, e.g.: 2020-01-01T00:00:00Z", _0)]
+    InvalidTime(String),
+
+    #[display(fmt = "Invalid quota: {}, use a number of 0 or more.", _0)]
+    InvalidQuota(String),
+
+    #[display(
+        fmt = "Use a number of 1 or more weeks, with re-issue weeks less than validity weeks."
+    )]
+    InvalidWeeks,
+
     #[display(
         fmt = "Missing argument: --{}, alternatively you may use env var: {}",
         _0,
@@ -1610,6 +2645,9 @@ pub enum Error {
     )]
     MissingArgWithEnv(String, String),
 
+    #[display(fmt = "Missing argument: --{}", _0)]
+    MissingArgument(String),
+
     #[display(fmt = "You must specify resources when adding a CA (--asn, --ipv4, --ipv6)")]
     MissingResources,
 
@@ -1631,6 +2669,10 @@ impl Error {
         Error::MissingArgWithEnv(arg.to_string(), env_var.to_string())
     }
 
+    fn missing_arg(arg: &str) -> Self {
+        Error::MissingArgument(arg.to_string())
+    }
+
     fn general(msg: &str) -> Self {
         Error::GeneralArgumentError(msg.to_string())
     }
@@ -6,9 +6,12 @@ use serde::Serialize;
 use rpki::x509::Time;
 
 use crate::commons::api::{
-    AllCertAuthIssues, CaRepoDetails, CertAuthHistory, CertAuthInfo, CertAuthIssues, CertAuthList,
-    ChildCaInfo, CurrentObjects, CurrentRepoState, ParentCaContact, PublisherDetails,
-    PublisherList, RepositoryContact, RoaDefinition, ServerInfo,
+    AllCertAuthIssues, As0RoasInfo, CaRepoDetails, CertAuthHistory, CertAuthInfo, CertAuthIssues,
+    CertAuthList, ChildCaInfo, ChildInvitation, ConfiguredRoa, CurrentObjects, CurrentRepoState,
+    DormantRoasInfo, NewChildInvitation, NewPublisherInvitation, ParentCaContact,
+    ParentMigrationStatus, PublishedObject, PublishedObjectContent, PublishedObjectDetails,
+    PublisherDetails, PublisherInvitation, PublisherList, RepositoryContact, RoaLintReport,
+    ServerInfo,
 };
 use crate::commons::remote::api::ClientInfo;
 use crate::commons::remote::rfc8183;
@@ -26,14 +29,24 @@ pub enum ApiResponse {
     CertAuthInfo(CertAuthInfo),
     CertAuthHistory(CertAuthHistory),
     CertAuths(CertAuthList),
-    RouteAuthorizations(Vec<RoaDefinition>),
+    RouteAuthorizations(Vec<ConfiguredRoa>),
+    RouteAuthorizationsLint(RoaLintReport),
+    As0RoasInfo(As0RoasInfo),
+    DormantRoasInfo(DormantRoasInfo),
 
     ParentCaContact(ParentCaContact),
+    ParentMigrationStatus(ParentMigrationStatus),
 
     ChildInfo(ChildCaInfo),
+    ChildInvitation(NewChildInvitation),
+    ChildInvitations(Vec<ChildInvitation>),
 
     PublisherDetails(PublisherDetails),
+    PublishedObjects(Vec<PublishedObject>),
+    PublishedObjectDetails(PublishedObjectDetails),
     PublisherList(PublisherList),
+    PublisherInvitation(NewPublisherInvitation),
+    PublisherInvitations(Vec<PublisherInvitation>),
     RepoStats(RepoStats),
 
     Rfc8181ClientList(Vec<ClientInfo>),
@@ -65,10 +78,20 @@ impl ApiResponse {
                 ApiResponse::CertAuthIssues(issues) => Ok(Some(issues.report(fmt)?)),
                 ApiResponse::AllCertAuthIssues(issues) => Ok(Some(issues.report(fmt)?)),
                 ApiResponse::RouteAuthorizations(auths) => Ok(Some(auths.report(fmt)?)),
+                ApiResponse::RouteAuthorizationsLint(report) => Ok(Some(report.report(fmt)?)),
+                ApiResponse::As0RoasInfo(info) => Ok(Some(info.report(fmt)?)),
+                ApiResponse::DormantRoasInfo(info) => Ok(Some(info.report(fmt)?)),
                 ApiResponse::ParentCaContact(contact) => Ok(Some(contact.report(fmt)?)),
+                ApiResponse::ParentMigrationStatus(status) => Ok(Some(status.report(fmt)?)),
                 ApiResponse::ChildInfo(info) => Ok(Some(info.report(fmt)?)),
+                ApiResponse::ChildInvitation(invitation) => Ok(Some(invitation.report(fmt)?)),
+                ApiResponse::ChildInvitations(list) => Ok(Some(list.report(fmt)?)),
                 ApiResponse::PublisherList(list) => Ok(Some(list.report(fmt)?)),
                 ApiResponse::PublisherDetails(details) => Ok(Some(details.report(fmt)?)),
+                ApiResponse::PublishedObjects(objects) => Ok(Some(objects.report(fmt)?)),
+                ApiResponse::PublishedObjectDetails(details) => Ok(Some(details.report(fmt)?)),
+                ApiResponse::PublisherInvitation(invitation) => Ok(Some(invitation.report(fmt)?)),
+                ApiResponse::PublisherInvitations(list) => Ok(Some(list.report(fmt)?)),
                 ApiResponse::RepoStats(stats) => Ok(Some(stats.report(fmt)?)),
                 ApiResponse::Rfc8181ClientList(list) => Ok(Some(list.report(fmt)?)),
                 ApiResponse::Rfc8183ChildRequest(req) => Ok(Some(req.report(fmt)?)),
@@ -252,6 +275,38 @@ impl Report for ChildCaInfo {
     }
 }
 
+impl Report for NewChildInvitation {
+    fn text(&self) -> Result<String, ReportError> {
+        Ok(format!("{}\n", self))
+    }
+}
+
+impl Report for Vec<ChildInvitation> {
+    fn text(&self) -> Result<String, ReportError> {
+        let mut res = String::new();
+        for invitation in self.iter() {
+            res.push_str(&format!("{}\n", invitation));
+        }
+        Ok(res)
+    }
+}
+
+impl Report for NewPublisherInvitation {
+    fn text(&self) -> Result<String, ReportError> {
+        Ok(format!("{}\n", self))
+    }
+}
+
+impl Report for Vec<PublisherInvitation> {
+    fn text(&self) -> Result<String, ReportError> {
+        let mut res = String::new();
+        for invitation in self.iter() {
+            res.push_str(&format!("{}\n", invitation));
+        }
+        Ok(res)
+    }
+}
+
 impl Report for PublisherList {
     fn text(&self) -> Result<String, ReportError> {
         let mut res = String::new();
@@ -265,6 +320,13 @@ impl Report for PublisherList {
                 first = false;
             }
             res.push_str(p.handle().as_str());
+            if let Some(next_update) = p.next_update() {
+                res.push_str(&format!(" (next update: {}", next_update.to_rfc3339()));
+                if let Some(not_after) = p.not_after() {
+                    res.push_str(&format!(", not after: {}", not_after.to_rfc3339()));
+                }
+                res.push_str(")");
+            }
         }
         Ok(res)
     }
@@ -280,7 +342,7 @@ impl Report for RepoStats {
         res.push_str(&format!("RRDP session: {}\n", self.session()));
         res.push_str(&format!("RRDP serial:  {}\n", self.serial()));
         res.push_str("\n");
-        res.push_str("Publisher, Objects, Size, Last Updated\n");
+        res.push_str("Publisher, Objects, Size, Last Updated, Next Update\n");
         for (publisher, stats) in self.get_publishers() {
             res.push_str(&format!(
                 "{}, {}, {}, ",
@@ -289,8 +351,28 @@ impl Report for RepoStats {
                 stats.size()
             ));
             match stats.last_update() {
-                None => res.push_str("never\n"),
-                Some(update) => res.push_str(&format!("{}\n", update.to_rfc3339())),
+                None => res.push_str("never, "),
+                Some(update) => res.push_str(&format!("{}, ", update.to_rfc3339())),
+            }
+            match stats.next_update() {
+                None => res.push_str("unknown\n"),
+                Some(next_update) => res.push_str(&format!("{}\n", next_update.to_rfc3339())),
+            }
+        }
+
+        if !self.targets().is_empty() {
+            res.push_str("\n");
+            res.push_str("Output target, Last Success, Error\n");
+            for status in self.targets() {
+                res.push_str(&format!("{}, ", status.target()));
+                match status.last_success() {
+                    None => res.push_str("never, "),
+                    Some(time) => res.push_str(&format!("{}, ", time.to_rfc3339())),
+                }
+                match status.error() {
+                    None => res.push_str("none\n"),
+                    Some(error) => res.push_str(&format!("{}\n", error)),
+                }
             }
         }
 
@@ -305,6 +387,102 @@ impl Report for PublisherDetails {
         res.push_str(&format!("handle: {}\n", self.handle()));
         res.push_str(&format!("id: {}", self.id_cert().ski_hex()));
         res.push_str(&format!("base uri: {}\n", self.base_uri().to_string()));
+        res.push_str(&format!("quota: {}\n", self.quota()));
+
+        Ok(res)
+    }
+}
+
+impl Report for Vec<PublishedObject> {
+    fn text(&self) -> Result<String, ReportError> {
+        let mut res = String::new();
+
+        res.push_str("URI, Hash, Size, Published\n");
+        for object in self.iter() {
+            res.push_str(&format!(
+                "{}, {}, {}, ",
+                object.uri(),
+                object.hash(),
+                object.size()
+            ));
+            match object.published() {
+                None => res.push_str("unknown\n"),
+                Some(time) => res.push_str(&format!("{}\n", time.to_rfc3339())),
+            }
+        }
+
+        Ok(res)
+    }
+}
+
+impl Report for PublishedObjectDetails {
+    fn text(&self) -> Result<String, ReportError> {
+        let mut res = String::new();
+
+        let object = self.object();
+        res.push_str(&format!("uri: {}\n", object.uri()));
+        res.push_str(&format!("hash: {}\n", object.hash()));
+        res.push_str(&format!("size: {}\n", object.size()));
+        match object.published() {
+            None => res.push_str("published: unknown\n"),
+            Some(time) => res.push_str(&format!("published: {}\n", time.to_rfc3339())),
+        }
+
+        match self.content() {
+            PublishedObjectContent::Cert {
+                not_before,
+                not_after,
+                resources,
+            } => {
+                res.push_str(&format!("not before: {}\n", not_before.to_rfc3339()));
+                res.push_str(&format!("not after: {}\n", not_after.to_rfc3339()));
+                match resources {
+                    None => res.push_str("resources: inherited\n"),
+                    Some(resources) => res.push_str(&format!("resources: {}\n", resources)),
+                }
+            }
+            PublishedObjectContent::Manifest {
+                number,
+                this_update,
+                next_update,
+                entries,
+            } => {
+                res.push_str(&format!("manifest number: {}\n", number));
+                res.push_str(&format!("this update: {}\n", this_update.to_rfc3339()));
+                res.push_str(&format!("next update: {}\n", next_update.to_rfc3339()));
+                res.push_str("entries:\n");
+                for entry in entries {
+                    res.push_str(&format!("  {} {}\n", entry.file(), entry.hash()));
+                }
+            }
+            PublishedObjectContent::Crl {
+                number,
+                this_update,
+                next_update,
+                revoked,
+            } => {
+                res.push_str(&format!("crl number: {}\n", number));
+                res.push_str(&format!("this update: {}\n", this_update.to_rfc3339()));
+                res.push_str(&format!("next update: {}\n", next_update.to_rfc3339()));
+                res.push_str("revoked serials:\n");
+                for serial in revoked {
+                    res.push_str(&format!("  {}\n", serial));
+                }
+            }
+            PublishedObjectContent::Roa {
+                not_after,
+                authorizations,
+            } => {
+                res.push_str(&format!("not after: {}\n", not_after.to_rfc3339()));
+                res.push_str("authorizations:\n");
+                for auth in authorizations {
+                    res.push_str(&format!("  {}\n", auth));
+                }
+            }
+            PublishedObjectContent::Other { base64 } => {
+                res.push_str(&format!("base64: {}\n", base64));
+            }
+        }
 
         Ok(res)
     }
@@ -350,7 +528,7 @@ impl Report for rfc8183::PublisherRequest {
     }
 }
 
-impl Report for Vec<RoaDefinition> {
+impl Report for Vec<ConfiguredRoa> {
     fn text(&self) -> Result<String, ReportError> {
         let mut res = String::new();
         for a in self.iter() {
@@ -360,6 +538,37 @@ impl Report for Vec<RoaDefinition> {
     }
 }
 
+impl Report for As0RoasInfo {
+    fn text(&self) -> Result<String, ReportError> {
+        Ok(self.to_string())
+    }
+}
+
+impl Report for DormantRoasInfo {
+    fn text(&self) -> Result<String, ReportError> {
+        Ok(self.to_string())
+    }
+}
+
+impl Report for ParentMigrationStatus {
+    fn text(&self) -> Result<String, ReportError> {
+        Ok(self.to_string())
+    }
+}
+
+impl Report for RoaLintReport {
+    fn text(&self) -> Result<String, ReportError> {
+        let mut res = String::new();
+        if self.is_empty() {
+            res.push_str("No warnings\n");
+        }
+        for warning in self.warnings() {
+            res.push_str(&format!("{}\n", warning));
+        }
+        Ok(res)
+    }
+}
+
 impl Report for CaRepoDetails {
     fn text(&self) -> Result<String, ReportError> {
         let mut res = String::new();
@@ -422,6 +631,9 @@ impl Report for CertAuthIssues {
                     res.push_str(&format!("Parent '{}' has issue: {}\n", parent, issue));
                 }
             }
+            for roa in self.dormant_roas() {
+                res.push_str(&format!("ROA '{}' is not covered by held resources\n", roa));
+            }
         }
         Ok(res)
     }
@@ -446,6 +658,12 @@ impl Report for AllCertAuthIssues {
                         res.push_str(&format!("   Parent '{}' has issue: {}\n", parent, issue));
                     }
                 }
+                for roa in issues.dormant_roas() {
+                    res.push_str(&format!(
+                        "   ROA '{}' is not covered by held resources\n",
+                        roa
+                    ));
+                }
             }
         }
         Ok(res)
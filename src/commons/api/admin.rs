@@ -11,13 +11,14 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use rpki::cert::Cert;
 use rpki::crypto::Signer;
 use rpki::uri;
-use rpki::x509::Time;
+use rpki::x509::{Serial, Time};
 
 use crate::commons::api::ca::{ResourceSet, TrustAnchorLocator};
 use crate::commons::api::rrdp::PublishElement;
-use crate::commons::api::{Link, RepoInfo};
+use crate::commons::api::{Base64, HexEncodedHash, Link, RepoInfo, RoaDefinition};
 use crate::commons::remote::id::IdCert;
 use crate::commons::remote::rfc8183;
+use crate::constants::{CHILD_CERTIFICATE_REISSUE_WEEKS, CHILD_CERTIFICATE_VALIDITY_WEEKS};
 
 //------------ Handle --------------------------------------------------------
 
@@ -160,6 +161,41 @@ impl fmt::Display for Token {
     }
 }
 
+//------------ TokenHash -----------------------------------------------------
+
+/// The SHA-256 hash of a [`Token`].
+///
+/// Invitations are kept by the hash of their token only, because the token
+/// is all that is needed to accept an invitation. This way the token is not
+/// kept at rest, or shown in the history of a CA or the repository. The
+/// hash is used to refer to the invitation when it is updated or revoked.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct TokenHash(HexEncodedHash);
+
+impl From<&Token> for TokenHash {
+    fn from(token: &Token) -> Self {
+        TokenHash(HexEncodedHash::from_content(token.as_ref().as_bytes()))
+    }
+}
+
+impl From<String> for TokenHash {
+    fn from(s: String) -> Self {
+        TokenHash(HexEncodedHash::from(s))
+    }
+}
+
+impl From<&str> for TokenHash {
+    fn from(s: &str) -> Self {
+        TokenHash::from(s.to_string())
+    }
+}
+
+impl fmt::Display for TokenHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
 //------------ PublisherSummaryInfo ------------------------------------------
 
 /// Defines a summary of publisher information to be used in the publisher
@@ -168,6 +204,16 @@ impl fmt::Display for Token {
 pub struct PublisherSummary {
     handle: PublisherHandle,
     links: Vec<Link>,
+
+    // Earliest nextUpdate of the manifests and CRLs of the publisher, only
+    // included in the list of stale publishers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    next_update: Option<Time>,
+
+    // Earliest notAfter of the manifest EE certificates of the publisher,
+    // only included in the list of stale publishers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    not_after: Option<Time>,
 }
 
 impl PublisherSummary {
@@ -182,12 +228,28 @@ impl PublisherSummary {
         PublisherSummary {
             handle: handle.clone(),
             links,
+            next_update: None,
+            not_after: None,
         }
     }
 
+    pub fn with_expiry(mut self, next_update: Option<Time>, not_after: Option<Time>) -> Self {
+        self.next_update = next_update;
+        self.not_after = not_after;
+        self
+    }
+
     pub fn handle(&self) -> &PublisherHandle {
         &self.handle
     }
+
+    pub fn next_update(&self) -> Option<Time> {
+        self.next_update
+    }
+
+    pub fn not_after(&self) -> Option<Time> {
+        self.not_after
+    }
 }
 
 //------------ PublisherList -------------------------------------------------
@@ -208,6 +270,10 @@ impl PublisherList {
         PublisherList { publishers }
     }
 
+    pub fn new(publishers: Vec<PublisherSummary>) -> Self {
+        PublisherList { publishers }
+    }
+
     pub fn publishers(&self) -> &Vec<PublisherSummary> {
         &self.publishers
     }
@@ -223,6 +289,8 @@ pub struct PublisherDetails {
     id_cert: IdCert,
     base_uri: uri::Rsync,
     current_files: Vec<PublishElement>,
+    #[serde(default)]
+    quota: PublisherQuota,
 }
 
 impl PublisherDetails {
@@ -231,12 +299,14 @@ impl PublisherDetails {
         id_cert: IdCert,
         base_uri: &uri::Rsync,
         current_files: Vec<PublishElement>,
+        quota: PublisherQuota,
     ) -> Self {
         PublisherDetails {
             handle: handle.clone(),
             id_cert,
             base_uri: base_uri.clone(),
             current_files,
+            quota,
         }
     }
 
@@ -252,6 +322,372 @@ impl PublisherDetails {
     pub fn current_files(&self) -> &Vec<PublishElement> {
         &self.current_files
     }
+    pub fn quota(&self) -> &PublisherQuota {
+        &self.quota
+    }
+}
+
+//------------ PublishedObject -----------------------------------------------
+
+/// This type defines an object currently published by a publisher, as
+/// listed in: /api/v1/publishers/{handle}/objects
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PublishedObject {
+    uri: uri::Rsync,
+    hash: HexEncodedHash,
+    size: usize,
+
+    /// Not known for objects published before publication times were kept.
+    published: Option<Time>,
+}
+
+impl PublishedObject {
+    pub fn new(element: &PublishElement, published: Option<Time>) -> Self {
+        PublishedObject {
+            uri: element.uri().clone(),
+            hash: element.base64().to_encoded_hash(),
+            size: element.size(),
+            published,
+        }
+    }
+
+    pub fn uri(&self) -> &uri::Rsync {
+        &self.uri
+    }
+    pub fn hash(&self) -> &HexEncodedHash {
+        &self.hash
+    }
+    pub fn size(&self) -> usize {
+        self.size
+    }
+    pub fn published(&self) -> Option<Time> {
+        self.published
+    }
+}
+
+//------------ PublishedObjectDetails ----------------------------------------
+
+/// This type defines a published object and its decoded content for:
+/// /api/v1/publishers/{handle}/objects/{hash}
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PublishedObjectDetails {
+    object: PublishedObject,
+    content: PublishedObjectContent,
+}
+
+impl PublishedObjectDetails {
+    pub fn new(object: PublishedObject, content: PublishedObjectContent) -> Self {
+        PublishedObjectDetails { object, content }
+    }
+
+    pub fn object(&self) -> &PublishedObject {
+        &self.object
+    }
+    pub fn content(&self) -> &PublishedObjectContent {
+        &self.content
+    }
+}
+
+//------------ PublishedObjectContent ----------------------------------------
+
+/// The decoded content of a published object. Objects of other types, and
+/// objects which cannot be decoded as the type indicated by their file
+/// extension, are given as is.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PublishedObjectContent {
+    Cert {
+        not_before: Time,
+        not_after: Time,
+
+        /// Not set if the certificate inherits (some of) its resources.
+        resources: Option<ResourceSet>,
+    },
+    Manifest {
+        number: Serial,
+        this_update: Time,
+        next_update: Time,
+        entries: Vec<ManifestEntry>,
+    },
+    Crl {
+        number: Serial,
+        this_update: Time,
+        next_update: Time,
+        revoked: Vec<Serial>,
+    },
+    Roa {
+        not_after: Time,
+        authorizations: Vec<RoaDefinition>,
+    },
+    Other {
+        base64: Base64,
+    },
+}
+
+/// A file listed on a manifest.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ManifestEntry {
+    file: String,
+    hash: HexEncodedHash,
+}
+
+impl ManifestEntry {
+    pub fn new(file: String, hash: HexEncodedHash) -> Self {
+        ManifestEntry { file, hash }
+    }
+
+    pub fn file(&self) -> &String {
+        &self.file
+    }
+    pub fn hash(&self) -> &HexEncodedHash {
+        &self.hash
+    }
+}
+
+//------------ PublisherQuota ------------------------------------------------
+
+/// The limits on the objects which a publisher may have in the repository.
+/// Limits which are not set are not enforced.
+///
+/// Sizes are counted for the base64 encoded objects, as they are exchanged
+/// in the publication protocol and reported in the publisher stats.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PublisherQuota {
+    max_objects: Option<usize>,
+    max_size: Option<usize>,
+    max_object_size: Option<usize>,
+}
+
+impl PublisherQuota {
+    pub fn new(
+        max_objects: Option<usize>,
+        max_size: Option<usize>,
+        max_object_size: Option<usize>,
+    ) -> Self {
+        PublisherQuota {
+            max_objects,
+            max_size,
+            max_object_size,
+        }
+    }
+
+    /// The maximum number of objects.
+    pub fn max_objects(&self) -> Option<usize> {
+        self.max_objects
+    }
+
+    /// The maximum combined size of all objects, in bytes.
+    pub fn max_size(&self) -> Option<usize> {
+        self.max_size
+    }
+
+    /// The maximum size of a single object, in bytes.
+    pub fn max_object_size(&self) -> Option<usize> {
+        self.max_object_size
+    }
+}
+
+impl fmt::Display for PublisherQuota {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn limit(value: Option<usize>) -> String {
+            value
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| "unlimited".to_string())
+        }
+
+        write!(
+            f,
+            "max objects: {} max size: {} max object size: {}",
+            limit(self.max_objects),
+            limit(self.max_size),
+            limit(self.max_object_size)
+        )
+    }
+}
+
+//------------ QuotaExceeded -------------------------------------------------
+
+/// The limit of a [`PublisherQuota`] which a publisher would exceed.
+#[derive(Clone, Debug, Display, Eq, PartialEq)]
+pub enum QuotaExceeded {
+    #[display(fmt = "more than {} objects", _0)]
+    Objects(usize),
+
+    #[display(fmt = "more than {} bytes in total", _0)]
+    Size(usize),
+
+    #[display(fmt = "object '{}' is larger than {} bytes", _0, _1)]
+    ObjectSize(uri::Rsync, usize),
+}
+
+//------------ PublisherInvitationInit ---------------------------------------
+
+/// Asks the publication server to invite a publisher. The handle of the
+/// publisher will start with the prefix, if set, and the publisher will
+/// be given the quota. The prefix may not contain '/', as the handle is
+/// used as a directory for the publisher in the repository.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PublisherInvitationInit {
+    handle_prefix: Option<Handle>,
+    #[serde(default)]
+    quota: PublisherQuota,
+    expires: Time,
+}
+
+impl PublisherInvitationInit {
+    pub fn new(handle_prefix: Option<Handle>, quota: PublisherQuota, expires: Time) -> Self {
+        PublisherInvitationInit {
+            handle_prefix,
+            quota,
+            expires,
+        }
+    }
+
+    pub fn unpack(self) -> (Option<Handle>, PublisherQuota, Time) {
+        (self.handle_prefix, self.quota, self.expires)
+    }
+}
+
+//------------ PublisherInvitation -------------------------------------------
+
+/// A single-use invitation for a publisher to add itself to the publication
+/// server. The publisher accepts the invitation by sending its RFC 8183
+/// publisher request along with the token, and gets the RFC 8183
+/// repository response back. Only the hash of the token is kept, it
+/// identifies the invitation.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PublisherInvitation {
+    id: TokenHash,
+    handle_prefix: Option<Handle>,
+    quota: PublisherQuota,
+    expires: Time,
+}
+
+impl PublisherInvitation {
+    pub fn new(
+        token: &Token,
+        handle_prefix: Option<Handle>,
+        quota: PublisherQuota,
+        expires: Time,
+    ) -> Self {
+        PublisherInvitation {
+            id: TokenHash::from(token),
+            handle_prefix,
+            quota,
+            expires,
+        }
+    }
+
+    pub fn id(&self) -> &TokenHash {
+        &self.id
+    }
+
+    pub fn handle_prefix(&self) -> Option<&Handle> {
+        self.handle_prefix.as_ref()
+    }
+
+    /// Returns false if the prefix contains '/'. Publishers use their handle
+    /// as their directory in the repository, so such a prefix would put
+    /// invited publishers in the directory of another publisher.
+    pub fn handle_prefix_valid(&self) -> bool {
+        match &self.handle_prefix {
+            Some(prefix) => !prefix.as_str().contains('/'),
+            None => true,
+        }
+    }
+
+    pub fn quota(&self) -> &PublisherQuota {
+        &self.quota
+    }
+
+    pub fn expires(&self) -> Time {
+        self.expires
+    }
+
+    pub fn set_expires(&mut self, expires: Time) {
+        self.expires = expires;
+    }
+
+    pub fn is_expired(&self, now: Time) -> bool {
+        self.expires <= now
+    }
+
+    /// Returns the handle for a publisher which asked for the given handle,
+    /// i.e. the requested handle with the prefix of this invitation. The
+    /// requested handle may not contain '/', for the same reason as the
+    /// prefix.
+    pub fn publisher_handle(&self, requested: &Handle) -> Result<PublisherHandle, InvalidHandle> {
+        if requested.as_str().contains('/') {
+            return Err(InvalidHandle);
+        }
+        match &self.handle_prefix {
+            Some(prefix) => PublisherHandle::from_str(&format!("{}{}", prefix, requested)),
+            None => Ok(requested.clone()),
+        }
+    }
+}
+
+impl fmt::Display for PublisherInvitation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "id: {}", self.id)?;
+        if let Some(prefix) = &self.handle_prefix {
+            write!(f, " handle prefix: {}", prefix)?;
+        }
+        write!(
+            f,
+            " quota: {} expires: {}",
+            self.quota,
+            self.expires.to_rfc3339()
+        )
+    }
+}
+
+//------------ NewPublisherInvitation ----------------------------------------
+
+/// A publisher invitation which was just added, with its token. This is the
+/// only time the token is shown, because only its hash is kept.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct NewPublisherInvitation {
+    token: Token,
+    invitation: PublisherInvitation,
+}
+
+impl NewPublisherInvitation {
+    pub fn new(token: Token, invitation: PublisherInvitation) -> Self {
+        NewPublisherInvitation { token, invitation }
+    }
+
+    pub fn token(&self) -> &Token {
+        &self.token
+    }
+
+    pub fn invitation(&self) -> &PublisherInvitation {
+        &self.invitation
+    }
+}
+
+impl fmt::Display for NewPublisherInvitation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "token: {} {}", self.token, self.invitation)
+    }
+}
+
+//------------ PublisherInvitationUpdate -------------------------------------
+
+/// Changes the expiry time of a publisher invitation.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PublisherInvitationUpdate {
+    expires: Time,
+}
+
+impl PublisherInvitationUpdate {
+    pub fn new(expires: Time) -> Self {
+        PublisherInvitationUpdate { expires }
+    }
+
+    pub fn expires(&self) -> Time {
+        self.expires
+    }
 }
 
 //------------ PublisherClientRequest ----------------------------------------
@@ -396,6 +832,35 @@ impl ParentCaReq {
     }
 }
 
+//------------ ParentMigrationReq --------------------------------------------
+
+/// This type defines a request to move a CA from one of its parents to a new
+/// parent. The new parent is added, and the old parent is only removed after
+/// all ROAs were issued and published under the new parent.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ParentMigrationReq {
+    from: ParentHandle, // the local name of the current parent
+    to: ParentCaReq,    // the new parent
+}
+
+impl ParentMigrationReq {
+    pub fn new(from: ParentHandle, to: ParentCaReq) -> Self {
+        ParentMigrationReq { from, to }
+    }
+
+    pub fn from(&self) -> &ParentHandle {
+        &self.from
+    }
+
+    pub fn to(&self) -> &ParentCaReq {
+        &self.to
+    }
+
+    pub fn unpack(self) -> (ParentHandle, ParentCaReq) {
+        (self.from, self.to)
+    }
+}
+
 //------------ TaCertDetails -------------------------------------------------
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -508,6 +973,9 @@ pub struct AddChildRequest {
     handle: Handle,
     resources: ResourceSet,
     auth: ChildAuthRequest,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    validity: Option<ChildCertValidity>, // server default if not set
 }
 
 impl AddChildRequest {
@@ -516,9 +984,19 @@ impl AddChildRequest {
             handle,
             resources,
             auth,
+            validity: None,
         }
     }
 
+    pub fn with_validity(mut self, validity: ChildCertValidity) -> Self {
+        self.validity = Some(validity);
+        self
+    }
+
+    pub fn validity(&self) -> Option<ChildCertValidity> {
+        self.validity
+    }
+
     pub fn unwrap(self) -> (Handle, ResourceSet, ChildAuthRequest) {
         (self.handle, self.resources, self.auth)
     }
@@ -542,16 +1020,29 @@ pub enum ChildAuthRequest {
 pub struct UpdateChildRequest {
     id_cert: Option<IdCert>,
     resources: Option<ResourceSet>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    validity: Option<ChildCertValidity>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    suspended: Option<bool>,
 }
 
 impl UpdateChildRequest {
     pub fn new(id_cert: Option<IdCert>, resources: Option<ResourceSet>) -> Self {
-        UpdateChildRequest { id_cert, resources }
+        UpdateChildRequest {
+            id_cert,
+            resources,
+            validity: None,
+            suspended: None,
+        }
     }
     pub fn id_cert(id_cert: IdCert) -> Self {
         UpdateChildRequest {
             id_cert: Some(id_cert),
             resources: None,
+            validity: None,
+            suspended: None,
         }
     }
 
@@ -559,11 +1050,61 @@ impl UpdateChildRequest {
         UpdateChildRequest {
             id_cert: None,
             resources: Some(resources),
+            validity: None,
+            suspended: None,
+        }
+    }
+
+    pub fn validity(validity: ChildCertValidity) -> Self {
+        UpdateChildRequest {
+            id_cert: None,
+            resources: None,
+            validity: Some(validity),
+            suspended: None,
+        }
+    }
+
+    /// Suspends the child: its certificates are revoked and withdrawn, but
+    /// it is kept with its entitlements, so that it can be resumed later.
+    pub fn suspend() -> Self {
+        UpdateChildRequest {
+            id_cert: None,
+            resources: None,
+            validity: None,
+            suspended: Some(true),
+        }
+    }
+
+    /// Resumes a suspended child, it will be issued certificates again on
+    /// its next request.
+    pub fn resume() -> Self {
+        UpdateChildRequest {
+            id_cert: None,
+            resources: None,
+            validity: None,
+            suspended: Some(false),
         }
     }
 
-    pub fn unpack(self) -> (Option<IdCert>, Option<ResourceSet>) {
-        (self.id_cert, self.resources)
+    pub fn with_validity(mut self, validity: Option<ChildCertValidity>) -> Self {
+        self.validity = validity;
+        self
+    }
+
+    pub fn with_suspended(mut self, suspended: Option<bool>) -> Self {
+        self.suspended = suspended;
+        self
+    }
+
+    pub fn unpack(
+        self,
+    ) -> (
+        Option<IdCert>,
+        Option<ResourceSet>,
+        Option<ChildCertValidity>,
+        Option<bool>,
+    ) {
+        (self.id_cert, self.resources, self.validity, self.suspended)
     }
 }
 
@@ -575,10 +1116,251 @@ impl fmt::Display for UpdateChildRequest {
         if let Some(resources) = &self.resources {
             write!(f, "new resources: {} ", resources)?;
         }
+        if let Some(validity) = &self.validity {
+            write!(f, "new validity: {} ", validity)?;
+        }
+        match self.suspended {
+            Some(true) => write!(f, "suspend ")?,
+            Some(false) => write!(f, "resume ")?,
+            None => {}
+        }
         Ok(())
     }
 }
 
+//------------ ChildResourceTransfer -----------------------------------------
+
+/// Moves prefixes from one child of a CA to another. Both children must be
+/// CAs in this server, so that their ROAs for the prefixes can be moved too.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ChildResourceTransfer {
+    from: ChildHandle,
+    to: ChildHandle,
+    resources: ResourceSet,
+}
+
+impl ChildResourceTransfer {
+    pub fn new(from: ChildHandle, to: ChildHandle, resources: ResourceSet) -> Self {
+        ChildResourceTransfer {
+            from,
+            to,
+            resources,
+        }
+    }
+
+    pub fn from(&self) -> &ChildHandle {
+        &self.from
+    }
+
+    pub fn to(&self) -> &ChildHandle {
+        &self.to
+    }
+
+    pub fn resources(&self) -> &ResourceSet {
+        &self.resources
+    }
+
+    pub fn unpack(self) -> (ChildHandle, ChildHandle, ResourceSet) {
+        (self.from, self.to, self.resources)
+    }
+}
+
+impl fmt::Display for ChildResourceTransfer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "transfer {} from child '{}' to child '{}'",
+            self.resources, self.from, self.to
+        )
+    }
+}
+
+//------------ ChildInvitationInit -------------------------------------------
+
+/// Asks a CA to invite a child. The child will be delegated the resources
+/// when it accepts the invitation before it expires.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ChildInvitationInit {
+    resources: ResourceSet,
+    expires: Time,
+}
+
+impl ChildInvitationInit {
+    pub fn new(resources: ResourceSet, expires: Time) -> Self {
+        ChildInvitationInit { resources, expires }
+    }
+
+    pub fn resources(&self) -> &ResourceSet {
+        &self.resources
+    }
+
+    pub fn expires(&self) -> Time {
+        self.expires
+    }
+
+    pub fn unpack(self) -> (ResourceSet, Time) {
+        (self.resources, self.expires)
+    }
+}
+
+//------------ ChildInvitation -----------------------------------------------
+
+/// A single-use invitation for a child to add itself to a CA. The child
+/// accepts the invitation by sending its RFC 8183 child request along with
+/// the token, and gets the RFC 8183 parent response back. Only the hash of
+/// the token is kept, it identifies the invitation.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ChildInvitation {
+    id: TokenHash,
+    resources: ResourceSet,
+    expires: Time,
+}
+
+impl ChildInvitation {
+    pub fn new(token: &Token, resources: ResourceSet, expires: Time) -> Self {
+        ChildInvitation {
+            id: TokenHash::from(token),
+            resources,
+            expires,
+        }
+    }
+
+    pub fn id(&self) -> &TokenHash {
+        &self.id
+    }
+
+    pub fn resources(&self) -> &ResourceSet {
+        &self.resources
+    }
+
+    pub fn expires(&self) -> Time {
+        self.expires
+    }
+
+    pub fn set_expires(&mut self, expires: Time) {
+        self.expires = expires;
+    }
+
+    pub fn is_expired(&self, now: Time) -> bool {
+        self.expires <= now
+    }
+}
+
+impl fmt::Display for ChildInvitation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "id: {} resources: {} expires: {}",
+            self.id,
+            self.resources,
+            self.expires.to_rfc3339()
+        )
+    }
+}
+
+//------------ NewChildInvitation --------------------------------------------
+
+/// A child invitation which was just added, with its token. This is the only
+/// time the token is shown, because only its hash is kept.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct NewChildInvitation {
+    token: Token,
+    invitation: ChildInvitation,
+}
+
+impl NewChildInvitation {
+    pub fn new(token: Token, invitation: ChildInvitation) -> Self {
+        NewChildInvitation { token, invitation }
+    }
+
+    pub fn token(&self) -> &Token {
+        &self.token
+    }
+
+    pub fn invitation(&self) -> &ChildInvitation {
+        &self.invitation
+    }
+}
+
+impl fmt::Display for NewChildInvitation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "token: {} {}", self.token, self.invitation)
+    }
+}
+
+//------------ ChildInvitationUpdate -----------------------------------------
+
+/// Changes the expiry time of an invitation.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ChildInvitationUpdate {
+    expires: Time,
+}
+
+impl ChildInvitationUpdate {
+    pub fn new(expires: Time) -> Self {
+        ChildInvitationUpdate { expires }
+    }
+
+    pub fn expires(&self) -> Time {
+        self.expires
+    }
+}
+
+//------------ ChildCertValidity ---------------------------------------------
+
+/// The validity time for certificates issued to a child, and the time before
+/// expiry when these certificates should be re-issued. Both are expressed in
+/// weeks.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ChildCertValidity {
+    validity_weeks: i64,
+    reissue_weeks: i64,
+}
+
+impl ChildCertValidity {
+    pub fn new(validity_weeks: i64, reissue_weeks: i64) -> Self {
+        ChildCertValidity {
+            validity_weeks,
+            reissue_weeks,
+        }
+    }
+
+    pub fn validity_weeks(&self) -> i64 {
+        self.validity_weeks
+    }
+
+    pub fn reissue_weeks(&self) -> i64 {
+        self.reissue_weeks
+    }
+
+    /// Certificates must be valid for at least one week, and must be re-issued
+    /// some time before they expire.
+    pub fn is_valid(&self) -> bool {
+        self.validity_weeks > 0
+            && self.reissue_weeks > 0
+            && self.reissue_weeks < self.validity_weeks
+    }
+}
+
+impl Default for ChildCertValidity {
+    fn default() -> Self {
+        ChildCertValidity {
+            validity_weeks: CHILD_CERTIFICATE_VALIDITY_WEEKS,
+            reissue_weeks: CHILD_CERTIFICATE_REISSUE_WEEKS,
+        }
+    }
+}
+
+impl fmt::Display for ChildCertValidity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} weeks, re-issue {} weeks before expiry",
+            self.validity_weeks, self.reissue_weeks
+        )
+    }
+}
+
 //------------ ServerInfo ----------------------------------------------------
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -639,4 +1421,53 @@ mod tests {
         let expected_handle = Handle::from_str("abcDEF012/\\-_").unwrap();
         assert_eq!(handle, expected_handle);
     }
+
+    #[test]
+    fn should_verify_child_cert_validity() {
+        assert!(ChildCertValidity::default().is_valid());
+        assert!(ChildCertValidity::new(26, 2).is_valid());
+        assert!(!ChildCertValidity::new(4, 4).is_valid());
+        assert!(!ChildCertValidity::new(4, 0).is_valid());
+        assert!(!ChildCertValidity::new(0, -1).is_valid());
+    }
+
+    #[test]
+    fn should_prefix_invited_publisher_handle() {
+        let requested = Handle::from_str_unsafe("ca");
+        let expires = Time::now();
+
+        let invitation = PublisherInvitation::new(
+            &Token::from("token"),
+            None,
+            PublisherQuota::default(),
+            expires,
+        );
+        assert_eq!(invitation.publisher_handle(&requested).unwrap(), requested);
+
+        let invitation = PublisherInvitation::new(
+            &Token::from("token"),
+            Some(Handle::from_str_unsafe("member-")),
+            PublisherQuota::default(),
+            expires,
+        );
+        assert!(invitation.handle_prefix_valid());
+        assert_eq!(
+            invitation.publisher_handle(&requested).unwrap(),
+            Handle::from_str_unsafe("member-ca")
+        );
+
+        let long = Handle::from_str(&"a".repeat(255)).unwrap();
+        assert!(invitation.publisher_handle(&long).is_err());
+
+        let nested = Handle::from_str_unsafe("ca/nested");
+        assert!(invitation.publisher_handle(&nested).is_err());
+
+        let invitation = PublisherInvitation::new(
+            &Token::from("token"),
+            Some(Handle::from_str_unsafe("member/")),
+            PublisherQuota::default(),
+            expires,
+        );
+        assert!(!invitation.handle_prefix_valid());
+    }
 }
@@ -22,8 +22,9 @@ use rpki::x509::{Serial, Time};
 use crate::commons::api::publication;
 use crate::commons::api::publication::Publish;
 use crate::commons::api::{
-    Base64, ChildHandle, ErrorResponse, Handle, HexEncodedHash, IssuanceRequest, ListReply,
-    ParentCaContact, ParentHandle, RepositoryContact, RequestResourceLimit, RoaDefinition,
+    Base64, ChildCertValidity, ChildHandle, ErrorResponse, Handle, HexEncodedHash, IssuanceRequest,
+    ListReply, ParentCaContact, ParentHandle, RepositoryContact, RequestResourceLimit,
+    RoaDefinition, TypedPrefix,
 };
 use crate::commons::eventsourcing::AggregateHistory;
 use crate::commons::remote::id::IdCert;
@@ -147,16 +148,38 @@ impl From<&IdCert> for IdCertPem {
 pub struct ChildCaInfo {
     id_cert: Option<IdCertPem>,
     entitled_resources: ResourceSet,
+
+    #[serde(default)]
+    validity: ChildCertValidity,
+
+    #[serde(default)]
+    suspended: bool,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    activity: Option<ChildActivity>,
 }
 
 impl ChildCaInfo {
-    pub fn new(id_cert: Option<&IdCert>, entitled_resources: ResourceSet) -> Self {
+    pub fn new(
+        id_cert: Option<&IdCert>,
+        entitled_resources: ResourceSet,
+        validity: ChildCertValidity,
+        suspended: bool,
+    ) -> Self {
         ChildCaInfo {
             id_cert: id_cert.map(IdCertPem::from),
             entitled_resources,
+            validity,
+            suspended,
+            activity: None,
         }
     }
 
+    pub fn with_activity(mut self, activity: Option<ChildActivity>) -> Self {
+        self.activity = activity;
+        self
+    }
+
     pub fn id_cert(&self) -> Option<&IdCertPem> {
         self.id_cert.as_ref()
     }
@@ -164,6 +187,18 @@ impl ChildCaInfo {
     pub fn entitled_resources(&self) -> &ResourceSet {
         &self.entitled_resources
     }
+
+    pub fn validity(&self) -> ChildCertValidity {
+        self.validity
+    }
+
+    pub fn suspended(&self) -> bool {
+        self.suspended
+    }
+
+    pub fn activity(&self) -> Option<&ChildActivity> {
+        self.activity.as_ref()
+    }
 }
 
 impl fmt::Display for ChildCaInfo {
@@ -172,7 +207,128 @@ impl fmt::Display for ChildCaInfo {
             writeln!(f, "{}", id.pem())?;
             writeln!(f, "SHA256 hash of PEM encoded certificate: {}", id.hash())?;
         }
-        writeln!(f, "resources: {}", self.entitled_resources)
+        writeln!(f, "resources: {}", self.entitled_resources)?;
+        writeln!(f, "certificate validity: {}", self.validity)?;
+        if self.suspended {
+            writeln!(f, "status: suspended")?;
+        }
+        if let Some(activity) = &self.activity {
+            write!(f, "{}", activity)?;
+        }
+        Ok(())
+    }
+}
+
+//------------ ChildRequestType ----------------------------------------------
+
+/// The type of an RFC 6492 request sent by a child.
+#[derive(Clone, Copy, Debug, Deserialize, Display, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChildRequestType {
+    #[display(fmt = "list")]
+    List,
+
+    #[display(fmt = "issue")]
+    Issue,
+
+    #[display(fmt = "revoke")]
+    Revoke,
+
+    /// Any other, or unknown, message type.
+    #[display(fmt = "other")]
+    Other,
+}
+
+//------------ ChildActivity -------------------------------------------------
+
+/// The most recent requests seen from a child, and the last error returned
+/// to it. Used to find children which have gone quiet, or are failing.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ChildActivity {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    last_request: Option<Time>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    last_request_type: Option<ChildRequestType>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    last_list: Option<Time>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    user_agent: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    last_error: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    last_error_time: Option<Time>,
+}
+
+impl ChildActivity {
+    /// Records a request. The user agent is kept if the new request does
+    /// not have one, and the last error is kept until another error occurs.
+    pub fn request(
+        &mut self,
+        request_type: ChildRequestType,
+        user_agent: Option<String>,
+        error: Option<String>,
+    ) {
+        let now = Time::now();
+        self.last_request = Some(now);
+        self.last_request_type = Some(request_type);
+
+        if request_type == ChildRequestType::List {
+            self.last_list = Some(now);
+        }
+        if user_agent.is_some() {
+            self.user_agent = user_agent;
+        }
+        if error.is_some() {
+            self.last_error = error;
+            self.last_error_time = Some(now);
+        }
+    }
+
+    pub fn last_request(&self) -> Option<Time> {
+        self.last_request
+    }
+
+    pub fn last_request_type(&self) -> Option<ChildRequestType> {
+        self.last_request_type
+    }
+
+    pub fn last_list(&self) -> Option<Time> {
+        self.last_list
+    }
+
+    pub fn user_agent(&self) -> Option<&String> {
+        self.user_agent.as_ref()
+    }
+
+    pub fn last_error(&self) -> Option<&String> {
+        self.last_error.as_ref()
+    }
+
+    pub fn last_error_time(&self) -> Option<Time> {
+        self.last_error_time
+    }
+}
+
+impl fmt::Display for ChildActivity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let (Some(time), Some(request_type)) = (self.last_request, self.last_request_type) {
+            writeln!(f, "last request: {} ({})", time.to_rfc3339(), request_type)?;
+        }
+        if let Some(time) = self.last_list {
+            writeln!(f, "last list request: {}", time.to_rfc3339())?;
+        }
+        if let Some(user_agent) = &self.user_agent {
+            writeln!(f, "user agent: {}", user_agent)?;
+        }
+        if let (Some(time), Some(error)) = (self.last_error_time, &self.last_error) {
+            writeln!(f, "last error: {} {}", time.to_rfc3339(), error)?;
+        }
+        Ok(())
     }
 }
 
@@ -1334,6 +1490,17 @@ impl ResourceSet {
         let v6 = self.v6.intersection(&other.v6);
         ResourceSet { asn, v4, v6 }
     }
+
+    /// Returns a new ResourceSet with the IP resources of this set which
+    /// are not found in the other. AS numbers are kept as they are.
+    pub fn without_prefixes(&self, other: &ResourceSet) -> Self {
+        let asn_only = ResourceSet::new(self.asn.clone(), IpBlocks::empty(), IpBlocks::empty());
+        TypedPrefix::unused(self, other)
+            .into_iter()
+            .fold(asn_only, |res, prefix| {
+                res.union(&ResourceSet::from(prefix))
+            })
+    }
 }
 
 impl Default for ResourceSet {
@@ -1463,6 +1630,10 @@ impl ParentInfo {
         };
         ParentInfo { handle, kind }
     }
+
+    pub fn handle(&self) -> &ParentHandle {
+        &self.handle
+    }
 }
 
 impl fmt::Display for ParentInfo {
@@ -1471,6 +1642,118 @@ impl fmt::Display for ParentInfo {
     }
 }
 
+//------------ ParentMigrationPhase ------------------------------------------
+
+/// The phases of moving a CA from one parent to another.
+#[derive(Clone, Copy, Debug, Deserialize, Display, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParentMigrationPhase {
+    #[display(fmt = "waiting for a certificate from the new parent")]
+    AwaitingCertificates,
+
+    #[display(fmt = "waiting for ROAs to be issued under the new parent")]
+    AwaitingRoas,
+
+    #[display(fmt = "waiting for ROAs under the new parent to be published")]
+    AwaitingPublication,
+
+    #[display(fmt = "completed, the old parent was removed")]
+    Completed,
+}
+
+//------------ ParentMigrationStatus -----------------------------------------
+
+/// Shows the progress of moving a CA from one parent to another. The ROAs
+/// which are issued under the old parent, but not yet under the new parent,
+/// are listed as pending. Pending ROAs for prefixes which are not held under
+/// the new parent are also listed as uncovered: the migration cannot finish
+/// unless the new parent's resources change, or the migration is cancelled.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ParentMigrationStatus {
+    from: ParentHandle,
+    to: ParentHandle,
+    started: Time,
+    completed: Option<Time>,
+    phase: ParentMigrationPhase,
+    pending_roas: Vec<RoaDefinition>,
+    #[serde(default)]
+    uncovered_roas: Vec<RoaDefinition>,
+}
+
+impl ParentMigrationStatus {
+    pub fn new(
+        from: ParentHandle,
+        to: ParentHandle,
+        started: Time,
+        completed: Option<Time>,
+        phase: ParentMigrationPhase,
+        pending_roas: Vec<RoaDefinition>,
+        uncovered_roas: Vec<RoaDefinition>,
+    ) -> Self {
+        ParentMigrationStatus {
+            from,
+            to,
+            started,
+            completed,
+            phase,
+            pending_roas,
+            uncovered_roas,
+        }
+    }
+
+    pub fn from(&self) -> &ParentHandle {
+        &self.from
+    }
+
+    pub fn to(&self) -> &ParentHandle {
+        &self.to
+    }
+
+    pub fn started(&self) -> Time {
+        self.started
+    }
+
+    pub fn completed(&self) -> Option<Time> {
+        self.completed
+    }
+
+    pub fn phase(&self) -> ParentMigrationPhase {
+        self.phase
+    }
+
+    pub fn pending_roas(&self) -> &Vec<RoaDefinition> {
+        &self.pending_roas
+    }
+
+    pub fn uncovered_roas(&self) -> &Vec<RoaDefinition> {
+        &self.uncovered_roas
+    }
+}
+
+impl fmt::Display for ParentMigrationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Migrating from parent '{}' to '{}'", self.from, self.to)?;
+        writeln!(f, "Started: {}", self.started.to_rfc3339())?;
+        if let Some(completed) = self.completed {
+            writeln!(f, "Completed: {}", completed.to_rfc3339())?;
+        }
+        writeln!(f, "Phase: {}", self.phase)?;
+        if !self.pending_roas.is_empty() {
+            writeln!(f, "ROAs not yet issued under the new parent:")?;
+            for roa in &self.pending_roas {
+                writeln!(f, "  {}", roa)?;
+            }
+        }
+        if !self.uncovered_roas.is_empty() {
+            writeln!(f, "ROAs not covered by resources from the new parent:")?;
+            for roa in &self.uncovered_roas {
+                writeln!(f, "  {}", roa)?;
+            }
+        }
+        Ok(())
+    }
+}
+
 //------------ CertAuthInfo --------------------------------------------------
 
 /// This type represents the details of a CertAuth that need
@@ -1811,6 +2094,10 @@ impl AllCertAuthIssues {
 pub struct CertAuthIssues {
     repo: Option<ErrorResponse>,
     parents: HashMap<ParentHandle, ErrorResponse>,
+
+    // authorizations no longer covered by the resources of the CA
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    dormant_roas: Vec<RoaDefinition>,
 }
 
 impl Default for CertAuthIssues {
@@ -1818,6 +2105,7 @@ impl Default for CertAuthIssues {
         CertAuthIssues {
             repo: None,
             parents: HashMap::new(),
+            dormant_roas: vec![],
         }
     }
 }
@@ -1839,8 +2127,16 @@ impl CertAuthIssues {
         &self.parents
     }
 
+    pub fn add_dormant_roas(&mut self, roas: Vec<RoaDefinition>) {
+        self.dormant_roas = roas;
+    }
+
+    pub fn dormant_roas(&self) -> &Vec<RoaDefinition> {
+        &self.dormant_roas
+    }
+
     pub fn is_empty(&self) -> bool {
-        self.repo.is_none() && self.parents.is_empty()
+        self.repo.is_none() && self.parents.is_empty() && self.dormant_roas.is_empty()
     }
 }
 
@@ -1849,13 +2145,15 @@ impl CertAuthIssues {
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct CertAuthStats {
     roa_count: usize,
+    dormant_roa_count: usize,
     child_count: usize,
 }
 
 impl CertAuthStats {
-    pub fn new(roa_count: usize, child_count: usize) -> Self {
+    pub fn new(roa_count: usize, dormant_roa_count: usize, child_count: usize) -> Self {
         CertAuthStats {
             roa_count,
+            dormant_roa_count,
             child_count,
         }
     }
@@ -1864,6 +2162,10 @@ impl CertAuthStats {
         self.roa_count
     }
 
+    pub fn dormant_roa_count(&self) -> usize {
+        self.dormant_roa_count
+    }
+
     pub fn child_count(&self) -> usize {
         self.child_count
     }
@@ -2078,4 +2380,16 @@ mod test {
         assert_eq!(intersection, child_resources);
     }
 
+    #[test]
+    fn resource_set_without_prefixes() {
+        let held = ResourceSet::from_strs("AS65000", "10.0.0.0/16", "2001:db8::/32").unwrap();
+        let other = ResourceSet::from_strs("", "10.0.1.0/24", "2001:db8::/32").unwrap();
+
+        let expected =
+            ResourceSet::from_strs("AS65000", "10.0.0.0/24, 10.0.2.0-10.0.255.255", "").unwrap();
+
+        assert_eq!(held.without_prefixes(&other), expected);
+        assert_eq!(held.without_prefixes(&ResourceSet::default()), held);
+    }
+
 }
@@ -288,6 +288,10 @@ impl ErrorResponse {
         res
     }
 
+    pub fn with_hash(self, hash: &HexEncodedHash) -> Self {
+        self.with_arg("hash", hash)
+    }
+
     pub fn with_key_identifier(self, ki: &KeyIdentifier) -> Self {
         self.with_arg("key_id", ki)
     }
@@ -296,6 +300,14 @@ impl ErrorResponse {
         self.with_arg("class_name", class_name)
     }
 
+    pub fn with_rfc6492_code(self, code: u64) -> Self {
+        self.with_arg("rfc6492_code", code)
+    }
+
+    pub fn with_line(self, line: usize, reason: impl fmt::Display) -> Self {
+        self.with_arg(&format!("line_{}", line), reason)
+    }
+
     pub fn label(&self) -> &str {
         &self.label
     }
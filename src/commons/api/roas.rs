@@ -1,14 +1,15 @@
 use std::collections::HashSet;
 use std::fmt;
-use std::net::IpAddr;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::ops::Deref;
 use std::str::FromStr;
 
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
-use rpki::resources::{AsBlocks, AsId, IpBlocks, IpBlocksBuilder, Prefix};
+use rpki::resources::{Addr, AsBlocks, AsId, IpBlocks, IpBlocksBuilder, Prefix};
+use rpki::x509::Time;
 
-use crate::commons::api::ResourceSet;
+use crate::commons::api::{Handle, ResourceSet};
 
 //------------ RoaDefinition -----------------------------------------------
 
@@ -60,6 +61,31 @@ impl RoaDefinition {
             true
         }
     }
+
+    /// Returns definitions for the parts of this prefix which are found in
+    /// the given resources, for the same ASN and max length. Parts which are
+    /// more specific than the max length are left out, because this
+    /// definition does not authorize them.
+    pub fn restrict_to(&self, resources: &ResourceSet) -> Vec<RoaDefinition> {
+        let held = resources.intersection(&ResourceSet::from(self.prefix));
+        let max_length = self.effective_max_length();
+
+        TypedPrefix::unused(&held, &ResourceSet::default())
+            .into_iter()
+            .filter(|prefix| prefix.addr_len() <= max_length)
+            .map(|prefix| {
+                if prefix == self.prefix {
+                    return *self;
+                }
+                let max_length = if max_length > prefix.addr_len() {
+                    Some(max_length)
+                } else {
+                    None
+                };
+                RoaDefinition::new(self.asn, prefix, max_length)
+            })
+            .collect()
+    }
 }
 
 impl FromStr for RoaDefinition {
@@ -107,6 +133,298 @@ impl fmt::Display for RoaDefinition {
     }
 }
 
+//------------ RoaDefinitionMeta -------------------------------------------
+
+/// Optional meta-information for a ROA definition, to help users remember
+/// why an authorization exists. This is not used in the actual ROA objects.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RoaDefinitionMeta {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    comment: Option<String>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    tags: Vec<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    ticket: Option<String>,
+}
+
+impl RoaDefinitionMeta {
+    pub fn new(comment: Option<String>, tags: Vec<String>, ticket: Option<String>) -> Self {
+        RoaDefinitionMeta {
+            comment,
+            tags,
+            ticket,
+        }
+    }
+
+    pub fn with_comment(comment: &str) -> Self {
+        RoaDefinitionMeta {
+            comment: Some(comment.to_string()),
+            tags: vec![],
+            ticket: None,
+        }
+    }
+
+    pub fn comment(&self) -> Option<&String> {
+        self.comment.as_ref()
+    }
+
+    pub fn tags(&self) -> &Vec<String> {
+        &self.tags
+    }
+
+    pub fn ticket(&self) -> Option<&String> {
+        self.ticket.as_ref()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.comment.is_none() && self.tags.is_empty() && self.ticket.is_none()
+    }
+
+    /// Returns this meta-information updated with the fields supplied in
+    /// the update. Fields which are not supplied are kept. An empty comment
+    /// or ticket, or a single empty tag, clears the field.
+    pub fn updated_with(&self, update: RoaDefinitionMeta) -> Self {
+        fn update_text(current: &Option<String>, update: Option<String>) -> Option<String> {
+            match update {
+                None => current.clone(),
+                Some(text) if text.is_empty() => None,
+                Some(text) => Some(text),
+            }
+        }
+
+        let tags = if update.tags.is_empty() {
+            self.tags.clone()
+        } else {
+            update.tags.into_iter().filter(|t| !t.is_empty()).collect()
+        };
+
+        RoaDefinitionMeta {
+            comment: update_text(&self.comment, update.comment),
+            tags,
+            ticket: update_text(&self.ticket, update.ticket),
+        }
+    }
+}
+
+impl fmt::Display for RoaDefinitionMeta {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut parts = vec![];
+        if let Some(comment) = &self.comment {
+            parts.push(format!("comment: {}", comment));
+        }
+        if !self.tags.is_empty() {
+            parts.push(format!("tags: {}", self.tags.join(", ")));
+        }
+        if let Some(ticket) = &self.ticket {
+            parts.push(format!("ticket: {}", ticket));
+        }
+        write!(f, "{}", parts.join(" | "))
+    }
+}
+
+//------------ ConfiguredRoa -----------------------------------------------
+
+/// A ROA definition as configured by a user, including its meta-information
+/// and optional schedule. The JSON representation is that of a RoaDefinition
+/// with the additional optional fields of the RoaDefinitionMeta and
+/// RoaSchedule.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ConfiguredRoa {
+    #[serde(flatten)]
+    definition: RoaDefinition,
+
+    #[serde(flatten)]
+    meta: RoaDefinitionMeta,
+
+    #[serde(flatten)]
+    schedule: RoaSchedule,
+}
+
+impl ConfiguredRoa {
+    pub fn new(definition: RoaDefinition, meta: RoaDefinitionMeta) -> Self {
+        ConfiguredRoa {
+            definition,
+            meta,
+            schedule: RoaSchedule::default(),
+        }
+    }
+
+    pub fn with_schedule(mut self, schedule: RoaSchedule) -> Self {
+        self.schedule = schedule;
+        self
+    }
+
+    pub fn definition(&self) -> &RoaDefinition {
+        &self.definition
+    }
+
+    pub fn meta(&self) -> &RoaDefinitionMeta {
+        &self.meta
+    }
+
+    pub fn schedule(&self) -> &RoaSchedule {
+        &self.schedule
+    }
+
+    pub fn unpack(self) -> (RoaDefinition, RoaDefinitionMeta) {
+        (self.definition, self.meta)
+    }
+}
+
+impl From<RoaDefinition> for ConfiguredRoa {
+    fn from(definition: RoaDefinition) -> Self {
+        ConfiguredRoa::new(definition, RoaDefinitionMeta::default())
+    }
+}
+
+impl fmt::Display for ConfiguredRoa {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut parts = vec![];
+        if !self.meta.is_empty() {
+            parts.push(self.meta.to_string());
+        }
+        if !self.schedule.is_empty() {
+            parts.push(self.schedule.to_string());
+        }
+
+        if parts.is_empty() {
+            write!(f, "{}", self.definition)
+        } else {
+            write!(f, "{} # {}", self.definition, parts.join(" | "))
+        }
+    }
+}
+
+//------------ RoaSchedule -------------------------------------------------
+
+/// An optional time window for a ROA definition. The definition is added to
+/// the authorizations of a CA at the not-before time, and removed again at
+/// the not-after time.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RoaSchedule {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    not_before: Option<Time>,
+
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    not_after: Option<Time>,
+}
+
+impl RoaSchedule {
+    pub fn new(not_before: Option<Time>, not_after: Option<Time>) -> Self {
+        RoaSchedule {
+            not_before,
+            not_after,
+        }
+    }
+
+    pub fn not_before(&self) -> Option<Time> {
+        self.not_before
+    }
+
+    pub fn not_after(&self) -> Option<Time> {
+        self.not_after
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.not_before.is_none() && self.not_after.is_none()
+    }
+
+    /// Returns true if the not-before time lies before the not-after time,
+    /// or if either is not set.
+    pub fn is_valid(&self) -> bool {
+        match (self.not_before, self.not_after) {
+            (Some(not_before), Some(not_after)) => not_before < not_after,
+            _ => true,
+        }
+    }
+
+    pub fn is_pending(&self, now: Time) -> bool {
+        match self.not_before {
+            Some(not_before) => not_before > now,
+            None => false,
+        }
+    }
+
+    pub fn is_expired(&self, now: Time) -> bool {
+        match self.not_after {
+            Some(not_after) => not_after <= now,
+            None => false,
+        }
+    }
+
+    pub fn is_active(&self, now: Time) -> bool {
+        !self.is_pending(now) && !self.is_expired(now)
+    }
+}
+
+impl fmt::Display for RoaSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let now = Time::now();
+        let status = if self.is_pending(now) {
+            "pending"
+        } else if self.is_expired(now) {
+            "expired"
+        } else {
+            "active"
+        };
+
+        let mut parts = vec![status.to_string()];
+        if let Some(not_before) = self.not_before {
+            parts.push(format!("not before: {}", not_before.to_rfc3339()));
+        }
+        if let Some(not_after) = self.not_after {
+            parts.push(format!("not after: {}", not_after.to_rfc3339()));
+        }
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+//------------ ScheduledRoa ------------------------------------------------
+
+/// A ROA definition with the time window in which it should be authorized.
+/// A ScheduledRoa with an empty schedule cancels an earlier schedule.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ScheduledRoa {
+    #[serde(flatten)]
+    definition: RoaDefinition,
+
+    #[serde(flatten)]
+    schedule: RoaSchedule,
+}
+
+impl ScheduledRoa {
+    pub fn new(definition: RoaDefinition, schedule: RoaSchedule) -> Self {
+        ScheduledRoa {
+            definition,
+            schedule,
+        }
+    }
+
+    pub fn definition(&self) -> &RoaDefinition {
+        &self.definition
+    }
+
+    pub fn schedule(&self) -> &RoaSchedule {
+        &self.schedule
+    }
+
+    pub fn unpack(self) -> (RoaDefinition, RoaSchedule) {
+        (self.definition, self.schedule)
+    }
+}
+
+impl fmt::Display for ScheduledRoa {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.schedule.is_empty() {
+            write!(f, "{} # no schedule", self.definition)
+        } else {
+            write!(f, "{} # {}", self.definition, self.schedule)
+        }
+    }
+}
+
 //------------ RouteAuthorizationUpdates -----------------------------------
 
 /// This type defines a delta of Route Authorizations, i.e. additions or removals
@@ -145,6 +463,19 @@ impl RoaDefinitionUpdates {
     pub fn remove(&mut self, rem: RoaDefinition) {
         self.removed.insert(rem);
     }
+
+    /// Returns the definitions which would result from applying these
+    /// updates to the current definitions.
+    pub fn apply_to(&self, current: &[RoaDefinition]) -> Vec<RoaDefinition> {
+        let mut res: HashSet<RoaDefinition> = current.iter().cloned().collect();
+        for rem in &self.removed {
+            res.remove(rem);
+        }
+        for add in &self.added {
+            res.insert(*add);
+        }
+        res.into_iter().collect()
+    }
 }
 
 impl Default for RoaDefinitionUpdates {
@@ -203,6 +534,670 @@ impl FromStr for RoaDefinitionUpdates {
     }
 }
 
+//------------ RoaLintWarning ----------------------------------------------
+
+/// A warning about a ROA definition which is allowed, but which does not
+/// follow best current practice. These warnings are advisory, and unless
+/// they are informational they cause strict updates to be rejected.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RoaLintWarning {
+    /// A max length longer than the prefix allows for forged-origin
+    /// sub-prefix hijacks, unless all more-specifics up to the max length
+    /// are announced, see RFC 9319. Krill cannot tell which prefixes are
+    /// announced, so this warning is informational.
+    LooseMaxLength { roa: RoaDefinition },
+
+    /// An AS0 ROA overlaps with a ROA for a real origin ASN.
+    As0Overlap {
+        as0: RoaDefinition,
+        roa: RoaDefinition,
+    },
+
+    /// A ROA covers address space which is delegated to a child CA.
+    DelegatedSpace { roa: RoaDefinition, child: Handle },
+
+    /// Two ROAs for the same prefix and ASN differ only in max length.
+    MaxLengthDuplicate {
+        roa: RoaDefinition,
+        other: RoaDefinition,
+    },
+}
+
+impl RoaLintWarning {
+    /// Returns true for warnings which may be fine, depending on things that
+    /// Krill cannot check. These do not cause strict updates to be rejected.
+    pub fn is_informational(&self) -> bool {
+        match self {
+            RoaLintWarning::LooseMaxLength { .. } => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for RoaLintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RoaLintWarning::LooseMaxLength { roa } => write!(
+                f,
+                "ROA '{}' uses a max length beyond the prefix length, make sure all more-specifics are announced, see RFC 9319",
+                roa
+            ),
+            RoaLintWarning::As0Overlap { as0, roa } => {
+                write!(f, "AS0 ROA '{}' overlaps with ROA '{}'", as0, roa)
+            }
+            RoaLintWarning::DelegatedSpace { roa, child } => write!(
+                f,
+                "ROA '{}' covers space delegated to child '{}'",
+                roa, child
+            ),
+            RoaLintWarning::MaxLengthDuplicate { roa, other } => write!(
+                f,
+                "ROA '{}' only differs in max length from ROA '{}'",
+                roa, other
+            ),
+        }
+    }
+}
+
+//------------ RoaLintReport -----------------------------------------------
+
+/// The result of linting a set of ROA definitions.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RoaLintReport {
+    warnings: Vec<RoaLintWarning>,
+}
+
+impl RoaLintReport {
+    /// Lints the given definitions. The delegated resources are the resources
+    /// issued to each child of the CA.
+    pub fn lint(roas: &[RoaDefinition], delegated: &[(Handle, ResourceSet)]) -> Self {
+        let mut roas = roas.to_vec();
+        roas.sort_by_key(RoaDefinition::to_string);
+
+        let mut warnings = vec![];
+
+        for roa in roas.iter() {
+            if roa.effective_max_length() > roa.prefix().addr_len() {
+                warnings.push(RoaLintWarning::LooseMaxLength { roa: *roa });
+            }
+        }
+
+        let as0 = AsNumber::new(0);
+        for roa in roas.iter().filter(|roa| roa.asn() == as0) {
+            for other in roas.iter().filter(|other| other.asn() != as0) {
+                if Self::overlaps(roa.prefix(), other.prefix()) {
+                    warnings.push(RoaLintWarning::As0Overlap {
+                        as0: *roa,
+                        roa: *other,
+                    });
+                }
+            }
+        }
+
+        for roa in roas.iter() {
+            let roa_resources = ResourceSet::from(roa.prefix());
+            for (child, resources) in delegated {
+                if !roa_resources.intersection(resources).is_empty() {
+                    warnings.push(RoaLintWarning::DelegatedSpace {
+                        roa: *roa,
+                        child: child.clone(),
+                    });
+                }
+            }
+        }
+
+        for (idx, roa) in roas.iter().enumerate() {
+            for other in roas[idx + 1..].iter() {
+                if roa.asn() == other.asn() && roa.prefix() == other.prefix() {
+                    warnings.push(RoaLintWarning::MaxLengthDuplicate {
+                        roa: *roa,
+                        other: *other,
+                    });
+                }
+            }
+        }
+
+        RoaLintReport { warnings }
+    }
+
+    fn overlaps(left: TypedPrefix, right: TypedPrefix) -> bool {
+        let left = ResourceSet::from(left);
+        let right = ResourceSet::from(right);
+        !left.intersection(&right).is_empty()
+    }
+
+    pub fn warnings(&self) -> &Vec<RoaLintWarning> {
+        &self.warnings
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    /// Returns a report with only the warnings which are not informational,
+    /// i.e. the warnings which cause strict updates to be rejected.
+    pub fn blocking(&self) -> Self {
+        let warnings = self
+            .warnings
+            .iter()
+            .filter(|w| !w.is_informational())
+            .cloned()
+            .collect();
+        RoaLintReport { warnings }
+    }
+
+    /// Returns a report with only the warnings which are not present in the
+    /// earlier report.
+    pub fn new_since(&self, earlier: &RoaLintReport) -> Self {
+        let warnings = self
+            .warnings
+            .iter()
+            .filter(|w| !earlier.warnings.contains(w))
+            .cloned()
+            .collect();
+        RoaLintReport { warnings }
+    }
+}
+
+impl fmt::Display for RoaLintReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let warnings: Vec<String> = self.warnings.iter().map(|w| w.to_string()).collect();
+        write!(f, "{}", warnings.join("; "))
+    }
+}
+
+//------------ As0RoasInfo -------------------------------------------------
+
+/// Shows whether a CA issues AS0 ROAs for all its address space which is
+/// neither covered by an authorization, nor delegated to a child, and the
+/// definitions used for this.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct As0RoasInfo {
+    enabled: bool,
+    roas: Vec<RoaDefinition>,
+}
+
+impl As0RoasInfo {
+    pub fn new(enabled: bool, roas: Vec<RoaDefinition>) -> Self {
+        As0RoasInfo { enabled, roas }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn roas(&self) -> &Vec<RoaDefinition> {
+        &self.roas
+    }
+}
+
+impl fmt::Display for As0RoasInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.enabled {
+            writeln!(f, "AS0 ROAs for unused space: enabled")?;
+            for roa in &self.roas {
+                writeln!(f, "{}", roa)?;
+            }
+            Ok(())
+        } else {
+            writeln!(f, "AS0 ROAs for unused space: disabled")
+        }
+    }
+}
+
+//------------ RoaDormantPolicy --------------------------------------------
+
+/// Determines what happens to authorizations which are no longer covered by
+/// the resources a CA holds, e.g. because a parent shrunk its entitlements.
+/// They can be kept dormant, so that ROAs are issued again if the resources
+/// return, or they can be removed outright.
+#[derive(Clone, Copy, Debug, Deserialize, Display, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoaDormantPolicy {
+    #[display(fmt = "keep")]
+    Keep,
+
+    #[display(fmt = "remove")]
+    Remove,
+}
+
+impl Default for RoaDormantPolicy {
+    fn default() -> Self {
+        RoaDormantPolicy::Keep
+    }
+}
+
+//------------ DormantRoasInfo ---------------------------------------------
+
+/// Shows the policy for authorizations which are no longer covered by the
+/// resources of a CA, and the authorizations which are currently dormant.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct DormantRoasInfo {
+    policy: RoaDormantPolicy,
+    roas: Vec<RoaDefinition>,
+}
+
+impl DormantRoasInfo {
+    pub fn new(policy: RoaDormantPolicy, roas: Vec<RoaDefinition>) -> Self {
+        DormantRoasInfo { policy, roas }
+    }
+
+    pub fn policy(&self) -> RoaDormantPolicy {
+        self.policy
+    }
+
+    pub fn roas(&self) -> &Vec<RoaDefinition> {
+        &self.roas
+    }
+}
+
+impl fmt::Display for DormantRoasInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Policy for ROAs outside of held resources: {}",
+            self.policy
+        )?;
+        for roa in &self.roas {
+            writeln!(f, "{}", roa)?;
+        }
+        Ok(())
+    }
+}
+
+//------------ RoaDefinitionsFormat ----------------------------------------
+
+/// The formats supported for the bulk import and export of ROA definitions.
+///
+/// - csv:   lines of 'prefix,max_length,asn,comment', max_length may be empty
+/// - json:  a JSON array of ConfiguredRoas, as used elsewhere in the API
+/// - slurm: an RFC 8416 SLURM file, using its 'prefixAssertions'
+///
+/// Comments are included in all formats. Tags and ticket references are only
+/// included in json.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RoaDefinitionsFormat {
+    Csv,
+    Json,
+    Slurm,
+}
+
+impl RoaDefinitionsFormat {
+    const CSV_HEADER: &str = "prefix,max_length,asn,comment";
+    const CSV_HEADER_ALL: &str = "ca,prefix,max_length,asn,comment";
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            RoaDefinitionsFormat::Csv => "text/csv",
+            RoaDefinitionsFormat::Json | RoaDefinitionsFormat::Slurm => "application/json",
+        }
+    }
+
+    /// Parses ROA definitions in this format for the given CA. Errors include
+    /// the line number of each offending entry, so that they can be fixed in
+    /// the source file.
+    ///
+    /// Files exported for all CAs can be imported as well, in which case only
+    /// the definitions for the given CA are used.
+    pub fn parse(self, ca: &Handle, s: &str) -> Result<Vec<ConfiguredRoa>, AuthorizationFmtError> {
+        Ok(self
+            .parse_numbered(ca, s)?
+            .into_iter()
+            .map(|(_, roa)| roa)
+            .collect())
+    }
+
+    /// Parses ROA definitions in this format for the given CA, and returns
+    /// them with their line number for csv, or their position in the list
+    /// for json and slurm. This allows errors found when validating the
+    /// definitions to be reported against the source file as well.
+    pub fn parse_numbered(
+        self,
+        ca: &Handle,
+        s: &str,
+    ) -> Result<Vec<(usize, ConfiguredRoa)>, AuthorizationFmtError> {
+        match self {
+            RoaDefinitionsFormat::Csv => Self::parse_csv(ca, s),
+            RoaDefinitionsFormat::Json => {
+                let roas: Vec<ConfiguredRoa> = match serde_json::from_str(s) {
+                    Ok(roas) => roas,
+                    Err(e) => {
+                        // Fall back to an export for all CAs, but report the
+                        // error for a single CA if that fails as well.
+                        let all: Vec<CaRoaDefinitions> =
+                            serde_json::from_str(s).map_err(|_| AuthorizationFmtError::json(e))?;
+                        all.into_iter()
+                            .filter(|ca_roas| &ca_roas.ca == ca)
+                            .flat_map(|ca_roas| ca_roas.roas)
+                            .collect()
+                    }
+                };
+                Ok(roas
+                    .into_iter()
+                    .enumerate()
+                    .map(|(idx, roa)| (idx + 1, roa))
+                    .collect())
+            }
+            RoaDefinitionsFormat::Slurm => {
+                let slurm: SlurmFile =
+                    serde_json::from_str(s).map_err(AuthorizationFmtError::json)?;
+                Ok(slurm
+                    .locally_added_assertions
+                    .prefix_assertions
+                    .into_iter()
+                    .enumerate()
+                    .filter_map(|(idx, assertion)| {
+                        assertion.into_roa(ca).map(|roa| (idx + 1, roa))
+                    })
+                    .collect())
+            }
+        }
+    }
+
+    /// Parses all lines, and reports the errors for all offending lines
+    /// rather than stopping at the first. If the file starts with the header
+    /// used in exports for all CAs, then the first column holds the CA, and
+    /// only the lines for the given CA are used.
+    fn parse_csv(
+        ca: &Handle,
+        s: &str,
+    ) -> Result<Vec<(usize, ConfiguredRoa)>, AuthorizationFmtError> {
+        let mut res = vec![];
+        let mut errors = vec![];
+        let mut with_ca = false;
+
+        for (idx, line) in s.lines().enumerate() {
+            let line_nr = idx + 1;
+            let line = line.trim();
+
+            if line.starts_with(Self::CSV_HEADER_ALL) {
+                with_ca = true;
+                continue;
+            }
+            if line.is_empty() || line.starts_with('#') || line.starts_with(Self::CSV_HEADER) {
+                continue;
+            }
+
+            let line = if with_ca {
+                let mut columns = line.splitn(2, ',');
+                let line_ca = columns.next().unwrap_or("").trim();
+                if line_ca != ca.as_str() {
+                    continue;
+                }
+                columns.next().unwrap_or("")
+            } else {
+                line
+            };
+
+            match Self::parse_csv_line(line) {
+                Ok(roa) => res.push((line_nr, roa)),
+                Err(e) => errors.push((line_nr, e.to_string())),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(res)
+        } else {
+            Err(AuthorizationFmtError::lines(errors))
+        }
+    }
+
+    fn parse_csv_line(line: &str) -> Result<ConfiguredRoa, AuthorizationFmtError> {
+        // The comment may contain commas, so only split off the first 3 columns.
+        let mut columns = line.splitn(4, ',');
+        let prefix = columns.next().unwrap_or("");
+        let max_length = columns.next().unwrap_or("").trim();
+        let asn = columns.next().unwrap_or("");
+        let comment = columns.next().unwrap_or("").trim();
+
+        let prefix = TypedPrefix::from_str(prefix)?;
+        let asn = AsNumber::from_str(asn)?;
+        let max_length = if max_length.is_empty() {
+            None
+        } else {
+            Some(u8::from_str(max_length).map_err(|_| AuthorizationFmtError::auth(line))?)
+        };
+
+        let definition = RoaDefinition::new(asn, prefix, max_length);
+        let meta = if comment.is_empty() {
+            RoaDefinitionMeta::default()
+        } else {
+            RoaDefinitionMeta::with_comment(comment)
+        };
+
+        Ok(ConfiguredRoa::new(definition, meta))
+    }
+
+    /// Exports the ROA definitions of a single CA in this format.
+    pub fn export(self, roas: &[ConfiguredRoa]) -> String {
+        match self {
+            RoaDefinitionsFormat::Csv => {
+                let mut res = format!("{}\n", Self::CSV_HEADER);
+                for roa in roas {
+                    res.push_str(&format!("{}\n", Self::csv_line(roa)));
+                }
+                res
+            }
+            RoaDefinitionsFormat::Json => serde_json::to_string_pretty(roas).unwrap(),
+            RoaDefinitionsFormat::Slurm => {
+                let assertions = roas
+                    .iter()
+                    .map(|roa| SlurmPrefixAssertion::new(roa, None))
+                    .collect();
+                SlurmFile::new(assertions).to_json()
+            }
+        }
+    }
+
+    /// Exports the ROA definitions of all CAs in this format. CSV includes an
+    /// extra first column for the CA, SLURM uses the comment for this.
+    pub fn export_all(self, all: &[CaRoaDefinitions]) -> String {
+        match self {
+            RoaDefinitionsFormat::Csv => {
+                let mut res = format!("{}\n", Self::CSV_HEADER_ALL);
+                for ca_roas in all {
+                    for roa in &ca_roas.roas {
+                        res.push_str(&format!("{},{}\n", ca_roas.ca, Self::csv_line(roa)));
+                    }
+                }
+                res
+            }
+            RoaDefinitionsFormat::Json => serde_json::to_string_pretty(all).unwrap(),
+            RoaDefinitionsFormat::Slurm => {
+                let mut assertions = vec![];
+                for ca_roas in all {
+                    for roa in &ca_roas.roas {
+                        assertions.push(SlurmPrefixAssertion::new(roa, Some(&ca_roas.ca)));
+                    }
+                }
+                SlurmFile::new(assertions).to_json()
+            }
+        }
+    }
+
+    fn csv_line(roa: &ConfiguredRoa) -> String {
+        let definition = roa.definition();
+        // Comments cannot span lines in CSV
+        let comment = roa
+            .meta()
+            .comment()
+            .map(|c| c.replace('\n', " "))
+            .unwrap_or_default();
+
+        match definition.max_length() {
+            Some(max_length) => format!(
+                "{},{},{},{}",
+                definition.prefix(),
+                max_length,
+                definition.asn(),
+                comment
+            ),
+            None => format!("{},,{},{}", definition.prefix(), definition.asn(), comment),
+        }
+    }
+}
+
+impl FromStr for RoaDefinitionsFormat {
+    type Err = AuthorizationFmtError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(RoaDefinitionsFormat::Csv),
+            "json" => Ok(RoaDefinitionsFormat::Json),
+            "slurm" => Ok(RoaDefinitionsFormat::Slurm),
+            _ => Err(AuthorizationFmtError::Format(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for RoaDefinitionsFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RoaDefinitionsFormat::Csv => write!(f, "csv"),
+            RoaDefinitionsFormat::Json => write!(f, "json"),
+            RoaDefinitionsFormat::Slurm => write!(f, "slurm"),
+        }
+    }
+}
+
+//------------ CaRoaDefinitions --------------------------------------------
+
+/// The ROA definitions of a single CA, used when exporting for all CAs.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CaRoaDefinitions {
+    ca: Handle,
+    roas: Vec<ConfiguredRoa>,
+}
+
+impl CaRoaDefinitions {
+    pub fn new(ca: Handle, roas: Vec<ConfiguredRoa>) -> Self {
+        CaRoaDefinitions { ca, roas }
+    }
+
+    pub fn ca(&self) -> &Handle {
+        &self.ca
+    }
+
+    pub fn roas(&self) -> &Vec<ConfiguredRoa> {
+        &self.roas
+    }
+}
+
+//------------ SlurmFile ---------------------------------------------------
+
+/// The subset of RFC 8416 SLURM that we need to import and export ROA
+/// definitions as 'prefixAssertions'. Filters and BGPSec assertions are
+/// preserved as-is when parsing, but otherwise ignored.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SlurmFile {
+    slurm_version: u8,
+    validation_output_filters: SlurmOutputFilters,
+    locally_added_assertions: SlurmLocallyAddedAssertions,
+}
+
+impl SlurmFile {
+    fn new(prefix_assertions: Vec<SlurmPrefixAssertion>) -> Self {
+        SlurmFile {
+            slurm_version: 1,
+            validation_output_filters: SlurmOutputFilters {
+                prefix_filters: vec![],
+                bgpsec_filters: vec![],
+            },
+            locally_added_assertions: SlurmLocallyAddedAssertions {
+                prefix_assertions,
+                bgpsec_assertions: vec![],
+            },
+        }
+    }
+
+    fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SlurmOutputFilters {
+    prefix_filters: Vec<serde_json::Value>,
+    bgpsec_filters: Vec<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SlurmLocallyAddedAssertions {
+    prefix_assertions: Vec<SlurmPrefixAssertion>,
+    bgpsec_assertions: Vec<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SlurmPrefixAssertion {
+    asn: AsNumber,
+    prefix: TypedPrefix,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    max_prefix_length: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    comment: Option<String>,
+}
+
+impl SlurmPrefixAssertion {
+    const CA_PREFIX: &'static str = "CA: ";
+    const CA_SEPARATOR: &'static str = " - ";
+
+    /// Creates an assertion for a configured ROA. When exporting for all CAs
+    /// the CA is prepended to the comment.
+    fn new(roa: &ConfiguredRoa, ca: Option<&Handle>) -> Self {
+        let definition = roa.definition();
+        let comment = match (ca, roa.meta().comment()) {
+            (Some(ca), Some(comment)) => Some(format!(
+                "{}{}{}{}",
+                Self::CA_PREFIX,
+                ca,
+                Self::CA_SEPARATOR,
+                comment
+            )),
+            (Some(ca), None) => Some(format!("{}{}", Self::CA_PREFIX, ca)),
+            (None, comment) => comment.cloned(),
+        };
+
+        SlurmPrefixAssertion {
+            asn: definition.asn(),
+            prefix: definition.prefix(),
+            max_prefix_length: definition.max_length(),
+            comment,
+        }
+    }
+
+    /// Returns the configured ROA for this assertion if it is meant for the
+    /// given CA. Assertions exported for another CA are skipped, and the CA
+    /// is removed from the comment of assertions exported for this CA.
+    fn into_roa(self, ca: &Handle) -> Option<ConfiguredRoa> {
+        let comment = match self.comment {
+            Some(comment) if comment.starts_with(Self::CA_PREFIX) => {
+                let rest = &comment[Self::CA_PREFIX.len()..];
+                let (comment_ca, comment) = match rest.find(Self::CA_SEPARATOR) {
+                    Some(pos) => (
+                        &rest[..pos],
+                        Some(rest[pos + Self::CA_SEPARATOR.len()..].to_string()),
+                    ),
+                    None => (rest, None),
+                };
+                if comment_ca != ca.as_str() {
+                    return None;
+                }
+                comment
+            }
+            comment => comment,
+        };
+
+        let definition = RoaDefinition::new(self.asn, self.prefix, self.max_prefix_length);
+        let meta = RoaDefinitionMeta::new(comment, vec![], None);
+        Some(ConfiguredRoa::new(definition, meta))
+    }
+}
+
 //------------ TypedPrefix -------------------------------------------------
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum TypedPrefix {
@@ -231,6 +1226,71 @@ impl TypedPrefix {
     pub fn v6_from_prefix(prefix: Prefix) -> TypedPrefix {
         TypedPrefix::V6(Ipv6Prefix(prefix))
     }
+
+    /// Returns the two halves of this prefix, or None if this prefix is a
+    /// single address.
+    pub fn split(&self) -> Option<(TypedPrefix, TypedPrefix)> {
+        let len = self.addr_len();
+        match self {
+            TypedPrefix::V4(v4) => {
+                if len >= 32 {
+                    return None;
+                }
+                let left = u32::from(v4.0.to_v4());
+                let right = left | (1u32 << (31 - len));
+                let half = |addr: u32| {
+                    TypedPrefix::v4_from_prefix(Prefix::new(
+                        Addr::from_v4(Ipv4Addr::from(addr)),
+                        len + 1,
+                    ))
+                };
+                Some((half(left), half(right)))
+            }
+            TypedPrefix::V6(v6) => {
+                if len >= 128 {
+                    return None;
+                }
+                let left = u128::from(v6.0.to_v6());
+                let right = left | (1u128 << (127 - len));
+                let half = |addr: u128| {
+                    TypedPrefix::v6_from_prefix(Prefix::new(
+                        Addr::from_v6(Ipv6Addr::from(addr)),
+                        len + 1,
+                    ))
+                };
+                Some((half(left), half(right)))
+            }
+        }
+    }
+
+    /// Returns the smallest list of the largest prefixes which are held,
+    /// but which do not overlap with any of the used resources.
+    pub fn unused(held: &ResourceSet, used: &ResourceSet) -> Vec<TypedPrefix> {
+        let v4_root =
+            TypedPrefix::v4_from_prefix(Prefix::new(Addr::from_v4(Ipv4Addr::new(0, 0, 0, 0)), 0));
+        let v6_root =
+            TypedPrefix::v6_from_prefix(Prefix::new(Addr::from_v6(Ipv6Addr::from(0u128)), 0));
+
+        let mut res = vec![];
+        v4_root.collect_unused(held, used, &mut res);
+        v6_root.collect_unused(held, used, &mut res);
+        res
+    }
+
+    fn collect_unused(self, held: &ResourceSet, used: &ResourceSet, res: &mut Vec<TypedPrefix>) {
+        let set = ResourceSet::from(self);
+        if held.intersection(&set).is_empty() || used.contains(&set) {
+            return;
+        }
+        if held.contains(&set) && used.intersection(&set).is_empty() {
+            res.push(self);
+            return;
+        }
+        if let Some((left, right)) = self.split() {
+            left.collect_unused(held, used, res);
+            right.collect_unused(held, used, res);
+        }
+    }
 }
 
 impl FromStr for TypedPrefix {
@@ -385,6 +1445,15 @@ pub enum AuthorizationFmtError {
 
     #[display(fmt = "Invalid authorisation delta string: {}", _0)]
     Delta(String),
+
+    #[display(fmt = "Unsupported ROA format: {}, use csv, json or slurm", _0)]
+    Format(String),
+
+    #[display(fmt = "Error on line {}: {}", _0, _1)]
+    Line(usize, String),
+
+    #[display(fmt = "{}", _0)]
+    Lines(LineErrors),
 }
 
 impl AuthorizationFmtError {
@@ -403,6 +1472,53 @@ impl AuthorizationFmtError {
     pub fn delta(s: &str) -> Self {
         AuthorizationFmtError::Delta(s.to_string())
     }
+
+    pub fn line(line: usize, e: impl fmt::Display) -> Self {
+        AuthorizationFmtError::Line(line, e.to_string())
+    }
+
+    /// Reports the errors for several lines, or for a single line if
+    /// there is only one.
+    pub fn lines(mut errors: Vec<(usize, String)>) -> Self {
+        if errors.len() == 1 {
+            let (line, e) = errors.pop().unwrap();
+            AuthorizationFmtError::Line(line, e)
+        } else {
+            AuthorizationFmtError::Lines(LineErrors(errors))
+        }
+    }
+
+    /// The line numbers and reasons of all offending lines.
+    pub fn line_errors(&self) -> Vec<(usize, String)> {
+        match self {
+            AuthorizationFmtError::Line(line, e) => vec![(*line, e.clone())],
+            AuthorizationFmtError::Lines(errors) => errors.0.clone(),
+            _ => vec![],
+        }
+    }
+
+    fn json(e: serde_json::Error) -> Self {
+        AuthorizationFmtError::Line(e.line(), e.to_string())
+    }
+}
+
+//------------ LineErrors --------------------------------------------------
+
+/// The errors for several lines of an imported file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LineErrors(Vec<(usize, String)>);
+
+impl fmt::Display for LineErrors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Errors on lines: ")?;
+        for (idx, (line, e)) in self.0.iter().enumerate() {
+            if idx > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}: {}", line, e)?;
+        }
+        Ok(())
+    }
 }
 
 //------------ Tests -------------------------------------------------------
@@ -501,4 +1617,276 @@ mod tests {
         invalid_max_length("2001:db8::/32-129 => 64496");
     }
 
+    #[test]
+    fn roa_restrict_to_resources() {
+        fn restrict(def: &str, v4: &str) -> Vec<RoaDefinition> {
+            let def = RoaDefinition::from_str(def).unwrap();
+            def.restrict_to(&ResourceSet::from_strs("", v4, "").unwrap())
+        }
+
+        fn defs(s: &[&str]) -> Vec<RoaDefinition> {
+            s.iter()
+                .map(|s| RoaDefinition::from_str(s).unwrap())
+                .collect()
+        }
+
+        // Parts more specific than the max length are not authorized.
+        assert!(restrict("10.0.0.0/16 => 64496", "10.0.1.0/24").is_empty());
+        assert!(restrict("10.0.0.0/23-23 => 64496", "10.0.1.0/24").is_empty());
+        assert_eq!(
+            defs(&["10.0.1.0/24 => 64496"]),
+            restrict("10.0.0.0/16-24 => 64496", "10.0.1.0/24")
+        );
+        assert_eq!(
+            defs(&["10.0.1.0/24-28 => 64496"]),
+            restrict("10.0.0.0/16-28 => 64496", "10.0.1.0/24")
+        );
+        assert_eq!(
+            defs(&["10.0.0.0/24 => 64496"]),
+            restrict("10.0.0.0/24 => 64496", "10.0.0.0/16")
+        );
+        assert_eq!(
+            defs(&["10.0.2.0/23-24 => 64496"]),
+            restrict("10.0.0.0/16-24 => 64496", "10.0.1.0/25, 10.0.2.0/23")
+        );
+        assert!(restrict("10.0.0.0/16 => 64496", "10.1.0.0/16").is_empty());
+        assert_eq!(
+            defs(&["10.0.0.0/24-24 => 64496"]),
+            restrict("10.0.0.0/24-24 => 64496", "10.0.0.0/16")
+        );
+    }
+
+    fn ca() -> Handle {
+        Handle::from_str_unsafe("ca")
+    }
+
+    fn configured(s: &str, comment: Option<&str>) -> ConfiguredRoa {
+        let definition = RoaDefinition::from_str(s).unwrap();
+        match comment {
+            Some(comment) => {
+                ConfiguredRoa::new(definition, RoaDefinitionMeta::with_comment(comment))
+            }
+            None => ConfiguredRoa::from(definition),
+        }
+    }
+
+    #[test]
+    fn roa_definition_meta_update_keeps_fields_not_supplied() {
+        let s = |s: &str| s.to_string();
+        let current = RoaDefinitionMeta::new(
+            Some(s("comment")),
+            vec![s("tag1"), s("tag2")],
+            Some(s("TICKET-1")),
+        );
+
+        // Only the comment is supplied
+        let updated = current.updated_with(RoaDefinitionMeta::with_comment("new comment"));
+        assert_eq!(
+            RoaDefinitionMeta::new(
+                Some(s("new comment")),
+                vec![s("tag1"), s("tag2")],
+                Some(s("TICKET-1"))
+            ),
+            updated
+        );
+
+        // Empty values clear the fields
+        let clear = RoaDefinitionMeta::new(Some(s("")), vec![s("")], Some(s("")));
+        assert!(current.updated_with(clear).is_empty());
+
+        // Nothing supplied, nothing changes
+        assert_eq!(current, current.updated_with(RoaDefinitionMeta::default()));
+    }
+
+    #[test]
+    fn configured_roa_json() {
+        // Without meta-information the JSON is the same as for a RoaDefinition
+        let roa = configured("192.168.0.0/16-24 => 64496", None);
+        let json = serde_json::to_string(&roa).unwrap();
+        let expected = "{\"asn\":64496,\"prefix\":\"192.168.0.0/16\",\"max_length\":24}";
+        assert_eq!(json, expected);
+        assert_eq!(roa, serde_json::from_str(&json).unwrap());
+
+        let meta = RoaDefinitionMeta::new(
+            Some("customer x".to_string()),
+            vec!["anycast".to_string()],
+            Some("TICKET-1".to_string()),
+        );
+        let roa = ConfiguredRoa::new(*roa.definition(), meta);
+        let json = serde_json::to_string(&roa).unwrap();
+        assert_eq!(roa, serde_json::from_str(&json).unwrap());
+        assert_eq!(
+            "192.168.0.0/16-24 => 64496 # comment: customer x | tags: anycast | ticket: TICKET-1",
+            roa.to_string()
+        );
+    }
+
+    #[test]
+    fn roa_definitions_csv_import_export() {
+        let csv = concat!(
+            "prefix,max_length,asn,comment\n",
+            "# a comment line\n",
+            "192.168.0.0/16,24,64496,some comment, with a comma\n",
+            "2001:db8::/32,,64496,\n",
+        );
+
+        let roas = RoaDefinitionsFormat::Csv.parse(&ca(), csv).unwrap();
+        assert_eq!(
+            roas,
+            vec![
+                configured(
+                    "192.168.0.0/16-24 => 64496",
+                    Some("some comment, with a comma")
+                ),
+                configured("2001:db8::/32 => 64496", None),
+            ]
+        );
+
+        let exported = RoaDefinitionsFormat::Csv.export(&roas);
+        assert_eq!(roas, RoaDefinitionsFormat::Csv.parse(&ca(), &exported).unwrap());
+    }
+
+    #[test]
+    fn roa_definitions_import_export_all() {
+        let roas = vec![
+            configured("192.168.0.0/16-24 => 64496", Some("some comment, with a comma")),
+            configured("2001:db8::/32 => 64496", None),
+        ];
+        let other = vec![configured("10.0.0.0/8 => 64497", Some("other"))];
+
+        let all = vec![
+            CaRoaDefinitions::new(Handle::from_str_unsafe("other"), other),
+            CaRoaDefinitions::new(ca(), roas.clone()),
+        ];
+
+        for format in &[
+            RoaDefinitionsFormat::Csv,
+            RoaDefinitionsFormat::Json,
+            RoaDefinitionsFormat::Slurm,
+        ] {
+            let exported = format.export_all(&all);
+            assert_eq!(roas, format.parse(&ca(), &exported).unwrap());
+        }
+    }
+
+    #[test]
+    fn roa_definitions_csv_import_reports_line() {
+        let csv = concat!(
+            "prefix,max_length,asn,comment\n",
+            "192.168.0.0/16,24,64496,\n",
+            "192.168.0.0/16,24,AS64496,\n",
+        );
+
+        match RoaDefinitionsFormat::Csv.parse(&ca(), csv) {
+            Err(AuthorizationFmtError::Line(3, _)) => {}
+            other => panic!("Expected error on line 3, got: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn roa_definitions_csv_import_reports_all_lines() {
+        let csv = concat!(
+            "prefix,max_length,asn,comment\n",
+            "192.168.0.0/16,24,AS64496,\n",
+            "192.168.0.0/16,24,64496,\n",
+            "192.168.0.0/33,24,64496,\n",
+        );
+
+        let err = RoaDefinitionsFormat::Csv.parse(&ca(), csv).unwrap_err();
+        let lines: Vec<usize> = err.line_errors().iter().map(|(line, _)| *line).collect();
+        assert_eq!(vec![2, 4], lines);
+    }
+
+    #[test]
+    fn roa_definitions_json_and_slurm_import_export() {
+        let roas = vec![
+            configured("192.168.0.0/16-24 => 64496", Some("important")),
+            configured("2001:db8::/32 => 64496", None),
+        ];
+
+        for format in &[RoaDefinitionsFormat::Json, RoaDefinitionsFormat::Slurm] {
+            let exported = format.export(&roas);
+            assert_eq!(roas, format.parse(&ca(), &exported).unwrap());
+        }
+
+        let slurm = r#"{
+            "slurmVersion": 1,
+            "validationOutputFilters": { "prefixFilters": [], "bgpsecFilters": [] },
+            "locallyAddedAssertions": {
+                "prefixAssertions": [
+                    { "asn": 64496, "prefix": "198.51.100.0/24", "comment": "My route" },
+                    { "asn": 64496, "prefix": "2001:db8::/32", "maxPrefixLength": 48 }
+                ],
+                "bgpsecAssertions": []
+            }
+        }"#;
+
+        let expected = vec![
+            configured("198.51.100.0/24 => 64496", Some("My route")),
+            configured("2001:db8::/32-48 => 64496", None),
+        ];
+        assert_eq!(expected, RoaDefinitionsFormat::Slurm.parse(&ca(), slurm).unwrap());
+    }
+
+    #[test]
+    fn roa_lint() {
+        let def = |s: &str| RoaDefinition::from_str(s).unwrap();
+
+        let loose = def("192.168.0.0/16-24 => 64496");
+        let exact = def("192.168.0.0/16 => 64496");
+        let as0 = def("192.168.0.0/24 => 0");
+        let delegated = def("10.0.0.0/8 => 64497");
+        let clean = def("2001:db8::/32 => 64496");
+
+        let child = Handle::from_str_unsafe("child");
+        let child_resources = ResourceSet::from_strs("", "10.1.0.0/16", "").unwrap();
+
+        let report = RoaLintReport::lint(
+            &[loose, exact, as0, delegated, clean],
+            &[(child.clone(), child_resources)],
+        );
+
+        let expected = vec![
+            RoaLintWarning::LooseMaxLength { roa: loose },
+            RoaLintWarning::As0Overlap { as0, roa: exact },
+            RoaLintWarning::As0Overlap { as0, roa: loose },
+            RoaLintWarning::DelegatedSpace {
+                roa: delegated,
+                child,
+            },
+            RoaLintWarning::MaxLengthDuplicate {
+                roa: exact,
+                other: loose,
+            },
+        ];
+        assert_eq!(&expected, report.warnings());
+
+        let clean_report = RoaLintReport::lint(&[exact, clean], &[]);
+        assert!(clean_report.is_empty());
+        assert_eq!(clean_report, report.new_since(&report));
+        assert_eq!(report, report.new_since(&clean_report));
+
+        let loose_report = RoaLintReport::lint(&[loose], &[]);
+        assert!(!loose_report.is_empty());
+        assert!(loose_report.blocking().is_empty());
+        assert_eq!(&expected[1..], report.blocking().warnings().as_slice());
+    }
+
+    #[test]
+    fn unused_prefixes() {
+        let held = ResourceSet::from_strs("", "10.0.0.0/8", "2001:db8::/32").unwrap();
+        let used =
+            ResourceSet::from_strs("", "10.0.0.0/9, 10.192.0.0/10", "2001:db8::/33").unwrap();
+
+        let unused: Vec<String> = TypedPrefix::unused(&held, &used)
+            .iter()
+            .map(TypedPrefix::to_string)
+            .collect();
+
+        assert_eq!(vec!["10.128.0.0/10", "2001:db8:8000::/33"], unused);
+
+        let nothing_used = ResourceSet::default();
+        let unused = TypedPrefix::unused(&held, &nothing_used);
+        assert_eq!(2, unused.len());
+    }
 }
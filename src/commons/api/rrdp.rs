@@ -3,10 +3,12 @@
 //! definitions.
 use std::collections::HashMap;
 use std::fmt;
+use std::fs;
 use std::io;
 use std::path::PathBuf;
 
 use bytes::Bytes;
+use chrono::Duration;
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use uuid::Uuid;
 
@@ -18,6 +20,10 @@ use crate::commons::api::Base64;
 use crate::commons::api::HexEncodedHash;
 use crate::commons::util::file;
 use crate::commons::util::xml::XmlWriter;
+use crate::constants::{
+    RRDP_DELTA_MAX_AGE_SECONDS, RRDP_DELTA_MAX_COUNT, RRDP_DELTA_MIN_COUNT,
+    RRDP_DELTA_REMOVAL_GRACE_SECONDS, RRDP_DELTA_SIZE_RATIO,
+};
 
 const VERSION: &str = "1";
 const NS: &str = "http://www.ripe.net/rpki/rrdp";
@@ -323,9 +329,13 @@ impl Notification {
         Notification::new(session, 0, snapshot, vec![])
     }
 
+    /// Writes the notification file to a temporary file next to it first,
+    /// and then renames it. This way relying parties never see a partially
+    /// written notification file.
     pub fn write_xml(&self, path: &PathBuf) -> Result<(), io::Error> {
         trace!("Writing notification file: {}", path.to_string_lossy());
-        let mut file = file::create_file_with_path(&path)?;
+        let tmp_path = path.with_extension("xml.tmp");
+        let mut file = file::create_file_with_path(&tmp_path)?;
 
         XmlWriter::encode_to_file(&mut file, |w| {
             let a = [
@@ -361,7 +371,8 @@ impl Notification {
             })
         })?;
 
-        Ok(())
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)
     }
 }
 
@@ -533,6 +544,10 @@ impl CurrentObjects {
         }
     }
 
+    pub fn get(&self, hash: &HexEncodedHash) -> Option<&PublishElement> {
+        self.0.get(hash)
+    }
+
     pub fn len(&self) -> usize {
         self.0.len()
     }
@@ -580,6 +595,16 @@ impl Snapshot {
         }
     }
 
+    /// Creates a snapshot of the current objects of this snapshot for a
+    /// new session, starting at the given serial.
+    pub fn for_new_session(&self, session: RrdpSession, serial: u64) -> Self {
+        Snapshot {
+            session,
+            serial,
+            current_objects: self.current_objects.clone(),
+        }
+    }
+
     pub fn elements(&self) -> Vec<&PublishElement> {
         self.current_objects.elements()
     }
@@ -693,6 +718,15 @@ impl DeltaElements {
     pub fn withdraws(&self) -> &Vec<WithdrawElement> {
         &self.withdraws
     }
+
+    /// Adds the elements of another delta. Used to combine the deltas of
+    /// several publishers into a single RRDP delta.
+    pub fn append(&mut self, other: DeltaElements) {
+        let (mut publishes, mut updates, mut withdraws) = other.unwrap();
+        self.publishes.append(&mut publishes);
+        self.updates.append(&mut updates);
+        self.withdraws.append(&mut withdraws);
+    }
 }
 
 impl From<publication::PublishDelta> for DeltaElements {
@@ -804,3 +838,117 @@ impl Delta {
         })
     }
 }
+
+//------------ RrdpRetention -------------------------------------------------
+
+/// Determines which deltas are listed in the notification file, and how long
+/// delta files are kept on disk after they are no longer listed.
+///
+/// The most recent delta is always listed. Older deltas are listed as long
+/// as there are no more than `max_count` deltas in total, none of them is
+/// older than `max_age`, and their combined size does not exceed the size of
+/// the snapshot multiplied by `size_ratio`. The size ratio does not apply to
+/// the 5 most recent deltas, so that relying parties which fetch often can
+/// keep using deltas even if the snapshot is small.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct RrdpRetention {
+    max_count: usize,
+    max_age_seconds: i64,
+    size_ratio: f64,
+    removal_grace_seconds: i64,
+}
+
+impl RrdpRetention {
+    pub fn new(
+        max_count: usize,
+        max_age: Duration,
+        size_ratio: f64,
+        removal_grace: Duration,
+    ) -> Self {
+        RrdpRetention {
+            max_count,
+            max_age_seconds: max_age.num_seconds(),
+            size_ratio,
+            removal_grace_seconds: removal_grace.num_seconds(),
+        }
+    }
+
+    pub fn max_count(&self) -> usize {
+        self.max_count
+    }
+
+    pub fn max_age(&self) -> Duration {
+        Duration::seconds(self.max_age_seconds)
+    }
+
+    pub fn size_ratio(&self) -> f64 {
+        self.size_ratio
+    }
+
+    /// The time that delta files are kept on disk after they are no longer
+    /// listed in the notification file, so that relying parties which
+    /// fetched the notification file before can still get them.
+    pub fn removal_grace(&self) -> Duration {
+        Duration::seconds(self.removal_grace_seconds)
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.max_count > 0
+            && self.max_age_seconds > 0
+            && self.size_ratio > 0.0
+            && self.removal_grace_seconds >= 0
+    }
+
+    /// Returns the deltas to list in the notification file, given the new
+    /// delta, the currently listed deltas ordered from newest to oldest, and
+    /// the size of the new snapshot.
+    pub fn retain<'a>(
+        &self,
+        new: &'a Delta,
+        current: &'a [Delta],
+        snapshot_size: usize,
+    ) -> Vec<&'a Delta> {
+        let oldest = Time::now() - self.max_age();
+        let max_size = (snapshot_size as f64 * self.size_ratio) as usize;
+
+        let mut deltas_size = new.elements().size();
+        let mut deltas = vec![new];
+
+        for delta in current {
+            if deltas.len() >= self.max_count || *delta.time() < oldest {
+                break;
+            }
+            deltas_size += delta.elements().size();
+            if deltas.len() >= RRDP_DELTA_MIN_COUNT && deltas_size > max_size {
+                break;
+            }
+            deltas.push(delta);
+        }
+
+        deltas
+    }
+}
+
+// The size ratio is validated to be a positive number, so it is never NaN.
+impl Eq for RrdpRetention {}
+
+impl fmt::Display for RrdpRetention {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "max deltas: {}, max delta age: {}s, max size ratio: {}, removal grace: {}s",
+            self.max_count, self.max_age_seconds, self.size_ratio, self.removal_grace_seconds
+        )
+    }
+}
+
+impl Default for RrdpRetention {
+    fn default() -> Self {
+        RrdpRetention {
+            max_count: RRDP_DELTA_MAX_COUNT,
+            max_age_seconds: RRDP_DELTA_MAX_AGE_SECONDS,
+            size_ratio: RRDP_DELTA_SIZE_RATIO,
+            removal_grace_seconds: RRDP_DELTA_REMOVAL_GRACE_SECONDS,
+        }
+    }
+}
@@ -11,16 +11,20 @@ use rpki::x509::ValidationError;
 
 use crate::commons::api::rrdp::PublicationDeltaError;
 use crate::commons::api::{
-    ChildHandle, ErrorResponse, Handle, ParentHandle, PublisherHandle, ResourceClassName,
-    ResourceSetError,
+    AuthorizationFmtError, ChildHandle, ChildRequestType, ErrorResponse, Handle, HexEncodedHash,
+    ParentHandle, PublisherHandle, QuotaExceeded, ResourceClassName, ResourceSetError,
+    RoaLintReport,
 };
 use crate::commons::eventsourcing::AggregateStoreError;
+use crate::commons::remote::guard::MessageRejected;
 use crate::commons::remote::rfc6492;
+use crate::commons::remote::rfc6492::NotPerformedResponse;
 use crate::commons::remote::rfc8181;
 use crate::commons::remote::rfc8181::ReportErrorCode;
 use crate::commons::util::httpclient;
 use crate::commons::util::softsigner::SignerError;
 use crate::daemon::ca::RouteAuthorization;
+use crate::pubd::ObjectValidationError;
 
 #[derive(Debug, Display)]
 pub enum Error {
@@ -78,6 +82,48 @@ pub enum Error {
     #[display(fmt = "No embedded repository configured")]
     PublisherNoEmbeddedRepo,
 
+    #[display(fmt = "Message from publisher '{}' rejected: {}", _0, _1)]
+    PublisherMessageRejected(PublisherHandle, MessageRejected),
+
+    #[display(fmt = "Invalid publisher request xml: {}", _0)]
+    PublisherRequestInvalidXml(String),
+
+    #[display(fmt = "No publisher invitation with this token")]
+    PublisherInvitationUnknown,
+
+    #[display(fmt = "Publisher invitation has expired")]
+    PublisherInvitationExpired,
+
+    #[display(
+        fmt = "Publisher handle '{}' cannot be used with the invitation, it contains '/' or is too long with the prefix",
+        _0
+    )]
+    PublisherInvitationHandle(PublisherHandle),
+
+    #[display(
+        fmt = "Handle prefix '{}' for a publisher invitation may not contain '/'",
+        _0
+    )]
+    PublisherInvitationPrefix(Handle),
+
+    #[display(fmt = "Too many attempts to use a publisher invitation")]
+    PublisherInvitationRateLimited,
+
+    #[display(fmt = "Publisher '{}' would exceed its quota: {}", _0, _1)]
+    PublisherQuotaExceeded(PublisherHandle, QuotaExceeded),
+
+    #[display(fmt = "Publisher '{}' published an invalid object: {}", _0, _1)]
+    PublisherObjectInvalid(PublisherHandle, ObjectValidationError),
+
+    #[display(fmt = "Publisher '{}' has no object with hash '{}'", _0, _1)]
+    PublisherObjectUnknown(PublisherHandle, HexEncodedHash),
+
+    #[display(fmt = "Publication server already has an ID key roll in progress")]
+    PublisherIdRollInProgress,
+
+    #[display(fmt = "Publication server has no ID key roll in progress")]
+    PublisherIdRollUnknown,
+
     //-----------------------------------------------------------------
     // RFC 8181 (publishing)
     //-----------------------------------------------------------------
@@ -96,10 +142,19 @@ pub enum Error {
     //-----------------------------------------------------------------
     // CA Issues
     //-----------------------------------------------------------------
+    #[display(fmt = "CAs are not supported by a standalone publication server")]
+    CaServerDisabled,
+
     #[display(fmt = "CA '{}' was already initialised", _0)]
     CaDuplicate(Handle),
     #[display(fmt = "CA '{}' is unknown", _0)]
     CaUnknown(Handle),
+    #[display(fmt = "CA '{}' already has an ID key roll in progress", _0)]
+    CaIdRollInProgress(Handle),
+    #[display(fmt = "CA '{}' has no ID key roll in progress", _0)]
+    CaIdRollUnknown(Handle),
+    #[display(fmt = "CA '{}' cannot activate new ID key, not accepted by {}", _0, _1)]
+    CaIdRollNotAccepted(Handle, String),
 
     // CA Repo Issues
     #[display(fmt = "CA '{}' already uses this repository", _0)]
@@ -127,6 +182,21 @@ pub enum Error {
     #[display(fmt = "CA '{}' got repository response when adding parent", _0)]
     CaParentResponseWrongXml(Handle),
 
+    #[display(fmt = "CA '{}' rejected response from parent '{}': {}", _0, _1, _2)]
+    CaParentMessageRejected(Handle, ParentHandle, MessageRejected),
+
+    #[display(fmt = "CA '{}' got error response from parent '{}': {}", _0, _1, _2)]
+    CaParentNotPerformed(Handle, ParentHandle, NotPerformedResponse),
+
+    #[display(fmt = "CA '{}' already has a parent migration in progress", _0)]
+    CaParentMigrationInProgress(Handle),
+
+    #[display(fmt = "CA '{}' has no parent migration", _0)]
+    CaParentMigrationUnknown(Handle),
+
+    #[display(fmt = "CA '{}' does not have all ROAs under the new parent yet", _0)]
+    CaParentMigrationNotReady(Handle),
+
     //-----------------------------------------------------------------
     // RFC6492 (requesting resources)
     //-----------------------------------------------------------------
@@ -155,6 +225,56 @@ pub enum Error {
     #[display(fmt = "CA '{}' does not know id certificate for child '{}'", _0, _1)]
     CaChildUnauthorised(Handle, ChildHandle),
 
+    #[display(
+        fmt = "Invalid certificate validity for child '{}' of CA '{}', re-issue time must be shorter than validity",
+        _1,
+        _0
+    )]
+    CaChildInvalidValidity(Handle, ChildHandle),
+
+    #[display(fmt = "Child '{}' of CA '{}' is suspended", _1, _0)]
+    CaChildSuspended(Handle, ChildHandle),
+
+    #[display(fmt = "Message from child '{}' of CA '{}' rejected: {}", _1, _0, _2)]
+    CaChildMessageRejected(Handle, ChildHandle, MessageRejected),
+
+    #[display(fmt = "CA '{}' got invalid child request xml: {}", _0, _1)]
+    CaChildRequestInvalidXml(Handle, String),
+
+    #[display(fmt = "CA '{}' has no invitation with this token", _0)]
+    CaChildInvitationUnknown(Handle),
+
+    #[display(fmt = "Invitation for a child of CA '{}' has expired", _0)]
+    CaChildInvitationExpired(Handle),
+
+    #[display(
+        fmt = "Invitation for a child of CA '{}' must have resources held by the CA",
+        _0
+    )]
+    CaChildInvitationResources(Handle),
+
+    #[display(
+        fmt = "Too many attempts to use an invitation for a child of CA '{}'",
+        _0
+    )]
+    CaChildInvitationRateLimited(Handle),
+
+    #[display(fmt = "Child '{}' of CA '{}' is not a CA in this server", _1, _0)]
+    CaChildNotLocal(Handle, ChildHandle),
+
+    #[display(
+        fmt = "Child '{}' of CA '{}' does not hold the resources to transfer",
+        _1,
+        _0
+    )]
+    CaChildTransferNotHeld(Handle, ChildHandle),
+
+    #[display(
+        fmt = "CA '{}' can only transfer prefixes between children, not AS numbers",
+        _0
+    )]
+    CaChildTransferAsns(Handle),
+
     // RouteAuthorizations - ROAs
     #[display(fmt = "Cannot remove unknown ROA '{}' from CA '{}'", _0, _1)]
     CaAuthorisationUnknown(Handle, RouteAuthorization),
@@ -168,6 +288,19 @@ pub enum Error {
     #[display(fmt = "Prefix in ROA '{}' not held by CA '{}'.", _1, _0)]
     CaAuthorisationNotEntitled(Handle, RouteAuthorization),
 
+    #[display(
+        fmt = "Invalid schedule for ROA '{}' for CA '{}', not before must precede not after",
+        _1,
+        _0
+    )]
+    CaAuthorisationInvalidSchedule(Handle, RouteAuthorization),
+
+    #[display(fmt = "Cannot import ROAs for CA '{}': {}", _0, _1)]
+    CaAuthorisationImport(Handle, AuthorizationFmtError),
+
+    #[display(fmt = "ROA update for CA '{}' rejected by lint: {}", _0, _1)]
+    CaAuthorisationLint(Handle, RoaLintReport),
+
     //-----------------------------------------------------------------
     // Key Usage Issues
     //-----------------------------------------------------------------
@@ -285,11 +418,21 @@ impl Error {
                 StatusCode::INTERNAL_SERVER_ERROR
             }
             Error::PublisherUnknown(_)
+            | Error::PublisherObjectUnknown(_, _)
             | Error::CaUnknown(_)
             | Error::CaChildUnknown(_, _)
             | Error::CaParentUnknown(_, _)
+            | Error::CaParentMigrationUnknown(_)
+            | Error::CaIdRollUnknown(_)
+            | Error::PublisherIdRollUnknown
+            | Error::CaChildInvitationUnknown(_)
+            | Error::PublisherInvitationUnknown
             | Error::ApiUnknownResource => StatusCode::NOT_FOUND,
 
+            Error::CaChildInvitationRateLimited(_) | Error::PublisherInvitationRateLimited => {
+                StatusCode::TOO_MANY_REQUESTS
+            }
+
             _ => StatusCode::BAD_REQUEST,
         }
     }
@@ -358,6 +501,56 @@ impl Error {
 
             Error::PublisherNoEmbeddedRepo => ErrorResponse::new("pub-no-embedded-repo", &self),
 
+            Error::PublisherMessageRejected(p, e) => {
+                ErrorResponse::new("pub-message-rejected", &self)
+                    .with_publisher(p)
+                    .with_cause(e)
+            }
+
+            Error::PublisherRequestInvalidXml(e) => {
+                ErrorResponse::new("pub-request-invalid-xml", &self).with_cause(e)
+            }
+
+            Error::PublisherInvitationUnknown => {
+                ErrorResponse::new("pub-invitation-unknown", &self)
+            }
+
+            Error::PublisherInvitationExpired => {
+                ErrorResponse::new("pub-invitation-expired", &self)
+            }
+
+            Error::PublisherInvitationHandle(p) => {
+                ErrorResponse::new("pub-invitation-handle", &self).with_publisher(p)
+            }
+
+            Error::PublisherInvitationPrefix(prefix) => {
+                ErrorResponse::new("pub-invitation-prefix", &self).with_cause(prefix)
+            }
+
+            Error::PublisherInvitationRateLimited => {
+                ErrorResponse::new("pub-invitation-rate-limited", &self)
+            }
+
+            Error::PublisherQuotaExceeded(p, e) => ErrorResponse::new("pub-quota-exceeded", &self)
+                .with_publisher(p)
+                .with_cause(e),
+
+            Error::PublisherObjectInvalid(p, e) => ErrorResponse::new("pub-object-invalid", &self)
+                .with_publisher(p)
+                .with_cause(e),
+
+            Error::PublisherObjectUnknown(p, hash) => {
+                ErrorResponse::new("pub-object-unknown", &self)
+                    .with_publisher(p)
+                    .with_hash(hash)
+            }
+
+            Error::PublisherIdRollInProgress => {
+                ErrorResponse::new("pub-id-roll-in-progress", &self)
+            }
+
+            Error::PublisherIdRollUnknown => ErrorResponse::new("pub-id-roll-unknown", &self),
+
             //-----------------------------------------------------------------
             // RFC 8181
             //-----------------------------------------------------------------
@@ -373,10 +566,26 @@ impl Error {
             //-----------------------------------------------------------------
             // CA Issues (label: ca-*)
             //-----------------------------------------------------------------
+            Error::CaServerDisabled => ErrorResponse::new("ca-server-disabled", &self),
+
             Error::CaDuplicate(ca) => ErrorResponse::new("ca-duplicate", &self).with_ca(ca),
 
             Error::CaUnknown(ca) => ErrorResponse::new("ca-unknown", &self).with_ca(ca),
 
+            Error::CaIdRollInProgress(ca) => {
+                ErrorResponse::new("ca-id-roll-in-progress", &self).with_ca(ca)
+            }
+
+            Error::CaIdRollUnknown(ca) => {
+                ErrorResponse::new("ca-id-roll-unknown", &self).with_ca(ca)
+            }
+
+            Error::CaIdRollNotAccepted(ca, cause) => {
+                ErrorResponse::new("ca-id-roll-not-accepted", &self)
+                    .with_ca(ca)
+                    .with_cause(cause)
+            }
+
             Error::CaRepoInUse(ca) => ErrorResponse::new("ca-repo-same", &self).with_ca(ca),
 
             Error::CaRepoIssue(ca, err) => ErrorResponse::new("ca-repo-issue", &self)
@@ -418,6 +627,33 @@ impl Error {
                 ErrorResponse::new("ca-parent-response-wrong-xml", &self).with_ca(ca)
             }
 
+            Error::CaParentMessageRejected(ca, parent, e) => {
+                ErrorResponse::new("ca-parent-message-rejected", &self)
+                    .with_ca(ca)
+                    .with_parent(parent)
+                    .with_cause(e)
+            }
+
+            Error::CaParentNotPerformed(ca, parent, e) => {
+                ErrorResponse::new("ca-parent-not-performed", &self)
+                    .with_ca(ca)
+                    .with_parent(parent)
+                    .with_rfc6492_code(e.status())
+                    .with_cause(e.description())
+            }
+
+            Error::CaParentMigrationInProgress(ca) => {
+                ErrorResponse::new("ca-parent-migration-in-progress", &self).with_ca(ca)
+            }
+
+            Error::CaParentMigrationUnknown(ca) => {
+                ErrorResponse::new("ca-parent-migration-unknown", &self).with_ca(ca)
+            }
+
+            Error::CaParentMigrationNotReady(ca) => {
+                ErrorResponse::new("ca-parent-migration-not-ready", &self).with_ca(ca)
+            }
+
             //-----------------------------------------------------------------
             // RFC6492 (requesting resources, not on JSON api)
             //-----------------------------------------------------------------
@@ -451,6 +687,48 @@ impl Error {
                     .with_ca(ca)
                     .with_child(child)
             }
+            Error::CaChildInvalidValidity(ca, child) => {
+                ErrorResponse::new("ca-child-invalid-validity", &self)
+                    .with_ca(ca)
+                    .with_child(child)
+            }
+            Error::CaChildSuspended(ca, child) => ErrorResponse::new("ca-child-suspended", &self)
+                .with_ca(ca)
+                .with_child(child),
+            Error::CaChildMessageRejected(ca, child, e) => {
+                ErrorResponse::new("ca-child-message-rejected", &self)
+                    .with_ca(ca)
+                    .with_child(child)
+                    .with_cause(e)
+            }
+            Error::CaChildRequestInvalidXml(ca, err) => {
+                ErrorResponse::new("ca-child-request-invalid-xml", &self)
+                    .with_ca(ca)
+                    .with_cause(err)
+            }
+            Error::CaChildInvitationUnknown(ca) => {
+                ErrorResponse::new("ca-child-invitation-unknown", &self).with_ca(ca)
+            }
+            Error::CaChildInvitationExpired(ca) => {
+                ErrorResponse::new("ca-child-invitation-expired", &self).with_ca(ca)
+            }
+            Error::CaChildInvitationResources(ca) => {
+                ErrorResponse::new("ca-child-invitation-resources", &self).with_ca(ca)
+            }
+            Error::CaChildInvitationRateLimited(ca) => {
+                ErrorResponse::new("ca-child-invitation-rate-limited", &self).with_ca(ca)
+            }
+            Error::CaChildNotLocal(ca, child) => ErrorResponse::new("ca-child-not-local", &self)
+                .with_ca(ca)
+                .with_child(child),
+            Error::CaChildTransferNotHeld(ca, child) => {
+                ErrorResponse::new("ca-child-transfer-not-held", &self)
+                    .with_ca(ca)
+                    .with_child(child)
+            }
+            Error::CaChildTransferAsns(ca) => {
+                ErrorResponse::new("ca-child-transfer-asns", &self).with_ca(ca)
+            }
 
             // RouteAuthorizations
             Error::CaAuthorisationUnknown(ca, auth) => ErrorResponse::new("ca-roa-unknown", &self)
@@ -475,6 +753,26 @@ impl Error {
                     .with_auth(auth)
             }
 
+            Error::CaAuthorisationInvalidSchedule(ca, auth) => {
+                ErrorResponse::new("ca-roa-invalid-schedule", &self)
+                    .with_ca(ca)
+                    .with_auth(auth)
+            }
+
+            Error::CaAuthorisationImport(ca, e) => {
+                let mut res = ErrorResponse::new("ca-roa-import", &self)
+                    .with_ca(ca)
+                    .with_cause(e);
+                for (line, reason) in e.line_errors() {
+                    res = res.with_line(line, reason);
+                }
+                res
+            }
+
+            Error::CaAuthorisationLint(ca, report) => ErrorResponse::new("ca-roa-lint", &self)
+                .with_ca(ca)
+                .with_cause(report),
+
             //-----------------------------------------------------------------
             // Key Usage Issues (key-*)
             //-----------------------------------------------------------------
@@ -516,6 +814,18 @@ impl Error {
                 ReportErrorCode::PermissionFailure
             }
             Error::Rfc8181MessageError(_) => ReportErrorCode::XmlError,
+            Error::PublisherMessageRejected(_, e) => match e {
+                MessageRejected::RateLimited => ReportErrorCode::OtherError,
+                MessageRejected::NoSigningTime
+                | MessageRejected::Stale
+                | MessageRejected::Replayed => ReportErrorCode::PermissionFailure,
+            },
+            Error::PublisherObjectInvalid(_, e) => match e {
+                ObjectValidationError::ManifestEntryUnknown(_, _) => {
+                    ReportErrorCode::ConsistencyProblem
+                }
+                _ => ReportErrorCode::OtherError,
+            },
             Error::Rfc8181Delta(e) => match e {
                 PublicationDeltaError::UriOutsideJail(_, _) => ReportErrorCode::PermissionFailure,
                 PublicationDeltaError::NoObjectForHashAndOrUri(_) => {
@@ -528,6 +838,45 @@ impl Error {
             _ => ReportErrorCode::OtherError,
         }
     }
+
+    /// Maps an error in processing a request from a child to one of the
+    /// error codes defined in section 3.6 of RFC 6492. Some errors map to
+    /// different codes for issuance and revocation requests.
+    pub fn to_rfc6492_error_code(
+        &self,
+        request_type: Option<ChildRequestType>,
+    ) -> NotPerformedResponse {
+        let revoke = request_type == Some(ChildRequestType::Revoke);
+
+        match self {
+            // The child may retry with a new message.
+            Error::CaChildMessageRejected(_, _, MessageRejected::RateLimited)
+            | Error::CaChildMessageRejected(_, _, MessageRejected::Stale)
+            | Error::CaChildMessageRejected(_, _, MessageRejected::Replayed) => {
+                NotPerformedResponse::_1101()
+            }
+            Error::Rfc6492(rfc6492::Error::InvalidVersion) => NotPerformedResponse::_1102(),
+            Error::Rfc6492(rfc6492::Error::UnknownMessageType)
+            | Error::Rfc6492(rfc6492::Error::WrongMessageType)
+            | Error::CaChildMessageRejected(_, _, MessageRejected::NoSigningTime) => {
+                NotPerformedResponse::_1103()
+            }
+
+            Error::ResourceClassUnknown(_) if revoke => NotPerformedResponse::_1301(),
+            Error::KeyUseNoIssuedCert | Error::KeyUseNoMatch(_) if revoke => {
+                NotPerformedResponse::_1302()
+            }
+
+            Error::ResourceClassUnknown(_) => NotPerformedResponse::_1201(),
+            Error::MissingResources
+            | Error::ResourceSetError(_)
+            | Error::CaChildSuspended(_, _) => NotPerformedResponse::_1202(),
+            Error::Rfc6492InvalidCsrSent(_) => NotPerformedResponse::_1203(),
+            Error::KeyUseAttemptReuse => NotPerformedResponse::_1204(),
+
+            _ => NotPerformedResponse::_2001(),
+        }
+    }
 }
 
 //------------ Tests ---------------------------------------------------------
@@ -539,6 +888,7 @@ mod tests {
 
     use crate::commons::api::RoaDefinition;
     use crate::commons::remote::id::tests::test_id_certificate;
+    use crate::pubd::ObjectType;
 
     use super::*;
 
@@ -551,6 +901,7 @@ mod tests {
 
         let auth =
             RouteAuthorization::new(RoaDefinition::from_str("192.168.0.0/16-24 => 64496").unwrap());
+        let as0 = RoaDefinition::from_str("192.168.0.0/24 => 0").unwrap();
 
         fn verify(expected_json: &str, e: Error) {
             let actual = e.to_error_response();
@@ -627,7 +978,7 @@ mod tests {
         );
         verify(
             include_str!("../../test-resources/errors/pub-duplicate.json"),
-            Error::PublisherDuplicate(publisher),
+            Error::PublisherDuplicate(publisher.clone()),
         );
         verify(
             include_str!("../../test-resources/errors/pub-outside-jail.json"),
@@ -644,6 +995,60 @@ mod tests {
             include_str!("../../test-resources/errors/pub-no-embedded-repo.json"),
             Error::PublisherNoEmbeddedRepo,
         );
+        verify(
+            include_str!("../../test-resources/errors/pub-message-rejected.json"),
+            Error::PublisherMessageRejected(publisher.clone(), MessageRejected::RateLimited),
+        );
+        verify(
+            include_str!("../../test-resources/errors/pub-request-invalid-xml.json"),
+            Error::PublisherRequestInvalidXml("expected some tag".to_string()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/pub-invitation-unknown.json"),
+            Error::PublisherInvitationUnknown,
+        );
+        verify(
+            include_str!("../../test-resources/errors/pub-invitation-expired.json"),
+            Error::PublisherInvitationExpired,
+        );
+        verify(
+            include_str!("../../test-resources/errors/pub-invitation-handle.json"),
+            Error::PublisherInvitationHandle(publisher.clone()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/pub-invitation-prefix.json"),
+            Error::PublisherInvitationPrefix(Handle::from_str_unsafe("member/")),
+        );
+        verify(
+            include_str!("../../test-resources/errors/pub-invitation-rate-limited.json"),
+            Error::PublisherInvitationRateLimited,
+        );
+        verify(
+            include_str!("../../test-resources/errors/pub-quota-exceeded.json"),
+            Error::PublisherQuotaExceeded(publisher.clone(), QuotaExceeded::Objects(10)),
+        );
+        verify(
+            include_str!("../../test-resources/errors/pub-object-invalid.json"),
+            Error::PublisherObjectInvalid(
+                publisher.clone(),
+                ObjectValidationError::Malformed(
+                    uri::Rsync::from_str("rsync://host/module/publisher/file.roa").unwrap(),
+                    ObjectType::Roa,
+                ),
+            ),
+        );
+        verify(
+            include_str!("../../test-resources/errors/pub-object-unknown.json"),
+            Error::PublisherObjectUnknown(publisher, HexEncodedHash::from_content(b"example")),
+        );
+        verify(
+            include_str!("../../test-resources/errors/pub-id-roll-in-progress.json"),
+            Error::PublisherIdRollInProgress,
+        );
+        verify(
+            include_str!("../../test-resources/errors/pub-id-roll-unknown.json"),
+            Error::PublisherIdRollUnknown,
+        );
 
         //-----------------------------------------------------------------
         // RFC 8181
@@ -670,6 +1075,10 @@ mod tests {
         //-----------------------------------------------------------------
         // CA Issues (label: ca-*)
         //-----------------------------------------------------------------
+        verify(
+            include_str!("../../test-resources/errors/ca-server-disabled.json"),
+            Error::CaServerDisabled,
+        );
         verify(
             include_str!("../../test-resources/errors/ca-duplicate.json"),
             Error::CaDuplicate(ca.clone()),
@@ -678,6 +1087,18 @@ mod tests {
             include_str!("../../test-resources/errors/ca-unknown.json"),
             Error::CaUnknown(ca.clone()),
         );
+        verify(
+            include_str!("../../test-resources/errors/ca-id-roll-in-progress.json"),
+            Error::CaIdRollInProgress(ca.clone()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-id-roll-unknown.json"),
+            Error::CaIdRollUnknown(ca.clone()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-id-roll-not-accepted.json"),
+            Error::CaIdRollNotAccepted(ca.clone(), "repository: rejected".to_string()),
+        );
 
         verify(
             include_str!("../../test-resources/errors/ca-repo-same.json"),
@@ -706,7 +1127,7 @@ mod tests {
         );
         verify(
             include_str!("../../test-resources/errors/ca-parent-issue.json"),
-            Error::CaParentIssue(ca.clone(), parent, "connection refused".to_string()),
+            Error::CaParentIssue(ca.clone(), parent.clone(), "connection refused".to_string()),
         );
         verify(
             include_str!("../../test-resources/errors/ca-parent-response-invalid-xml.json"),
@@ -716,6 +1137,26 @@ mod tests {
             include_str!("../../test-resources/errors/ca-parent-response-wrong-xml.json"),
             Error::CaParentResponseWrongXml(ca.clone()),
         );
+        verify(
+            include_str!("../../test-resources/errors/ca-parent-message-rejected.json"),
+            Error::CaParentMessageRejected(ca.clone(), parent.clone(), MessageRejected::Stale),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-parent-not-performed.json"),
+            Error::CaParentNotPerformed(ca.clone(), parent, NotPerformedResponse::_1201()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-parent-migration-in-progress.json"),
+            Error::CaParentMigrationInProgress(ca.clone()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-parent-migration-unknown.json"),
+            Error::CaParentMigrationUnknown(ca.clone()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-parent-migration-not-ready.json"),
+            Error::CaParentMigrationNotReady(ca.clone()),
+        );
 
         verify(
             include_str!("../../test-resources/errors/rfc6492-protocol.json"),
@@ -746,6 +1187,50 @@ mod tests {
             include_str!("../../test-resources/errors/ca-child-resources-extra.json"),
             Error::CaChildExtraResources(ca.clone(), child.clone()),
         );
+        verify(
+            include_str!("../../test-resources/errors/ca-child-invalid-validity.json"),
+            Error::CaChildInvalidValidity(ca.clone(), child.clone()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-child-suspended.json"),
+            Error::CaChildSuspended(ca.clone(), child.clone()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-child-message-rejected.json"),
+            Error::CaChildMessageRejected(ca.clone(), child.clone(), MessageRejected::Replayed),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-child-request-invalid-xml.json"),
+            Error::CaChildRequestInvalidXml(ca.clone(), "expected some tag".to_string()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-child-invitation-unknown.json"),
+            Error::CaChildInvitationUnknown(ca.clone()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-child-invitation-expired.json"),
+            Error::CaChildInvitationExpired(ca.clone()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-child-invitation-resources.json"),
+            Error::CaChildInvitationResources(ca.clone()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-child-invitation-rate-limited.json"),
+            Error::CaChildInvitationRateLimited(ca.clone()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-child-not-local.json"),
+            Error::CaChildNotLocal(ca.clone(), child.clone()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-child-transfer-not-held.json"),
+            Error::CaChildTransferNotHeld(ca.clone(), child.clone()),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-child-transfer-asns.json"),
+            Error::CaChildTransferAsns(ca.clone()),
+        );
         verify(
             include_str!("../../test-resources/errors/ca-child-unauthorised.json"),
             Error::CaChildUnauthorised(ca.clone(), child),
@@ -763,6 +1248,34 @@ mod tests {
             include_str!("../../test-resources/errors/ca-roa-invalid-max-length.json"),
             Error::CaAuthorisationInvalidMaxlength(ca.clone(), auth),
         );
+        verify(
+            include_str!("../../test-resources/errors/ca-roa-invalid-schedule.json"),
+            Error::CaAuthorisationInvalidSchedule(ca.clone(), auth),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-roa-import.json"),
+            Error::CaAuthorisationImport(
+                ca.clone(),
+                AuthorizationFmtError::line(3, "Invalid asn in string: AS64496"),
+            ),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-roa-import-lines.json"),
+            Error::CaAuthorisationImport(
+                ca.clone(),
+                AuthorizationFmtError::lines(vec![
+                    (2, "Invalid asn in string: AS64496".to_string()),
+                    (4, "Prefix 10.0.0.0/8 not held by CA".to_string()),
+                ]),
+            ),
+        );
+        verify(
+            include_str!("../../test-resources/errors/ca-roa-lint.json"),
+            Error::CaAuthorisationLint(
+                ca.clone(),
+                RoaLintReport::lint(&[as0, *auth.as_ref()], &[]).blocking(),
+            ),
+        );
         verify(
             include_str!("../../test-resources/errors/ca-roa-not-entitled.json"),
             Error::CaAuthorisationNotEntitled(ca, auth),
@@ -837,4 +1350,97 @@ mod tests {
         //            file::save_json(&error_response, &path).unwrap();
         //        }
     }
+
+    #[test]
+    fn rfc6492_error_codes() {
+        fn status(e: Error, request_type: ChildRequestType) -> u64 {
+            e.to_rfc6492_error_code(Some(request_type)).status()
+        }
+
+        let ca = Handle::from_str_unsafe("ca");
+        let child = ChildHandle::from_str_unsafe("child");
+        let rcn = ResourceClassName::from("RC0");
+
+        let rejected = |reason| Error::CaChildMessageRejected(ca.clone(), child.clone(), reason);
+
+        assert_eq!(
+            1101,
+            status(
+                rejected(MessageRejected::RateLimited),
+                ChildRequestType::List
+            )
+        );
+        assert_eq!(
+            1101,
+            status(rejected(MessageRejected::Stale), ChildRequestType::List)
+        );
+        assert_eq!(
+            1101,
+            status(rejected(MessageRejected::Replayed), ChildRequestType::Issue)
+        );
+        assert_eq!(
+            1103,
+            status(
+                rejected(MessageRejected::NoSigningTime),
+                ChildRequestType::Revoke
+            )
+        );
+        assert_eq!(
+            1102,
+            status(
+                Error::Rfc6492(rfc6492::Error::InvalidVersion),
+                ChildRequestType::List
+            )
+        );
+        assert_eq!(
+            1201,
+            status(
+                Error::ResourceClassUnknown(rcn.clone()),
+                ChildRequestType::Issue
+            )
+        );
+        assert_eq!(
+            1202,
+            status(Error::CaChildSuspended(ca, child), ChildRequestType::Issue)
+        );
+        assert_eq!(
+            1203,
+            status(
+                Error::invalid_csr("invalid signature"),
+                ChildRequestType::Issue
+            )
+        );
+        assert_eq!(
+            1204,
+            status(Error::KeyUseAttemptReuse, ChildRequestType::Issue)
+        );
+        assert_eq!(
+            1301,
+            status(Error::ResourceClassUnknown(rcn), ChildRequestType::Revoke)
+        );
+        assert_eq!(
+            1302,
+            status(Error::KeyUseNoIssuedCert, ChildRequestType::Revoke)
+        );
+        assert_eq!(
+            2001,
+            status(Error::signer("no key"), ChildRequestType::Issue)
+        );
+        assert_eq!(
+            1103,
+            Error::Rfc6492(rfc6492::Error::WrongMessageType)
+                .to_rfc6492_error_code(None)
+                .status()
+        );
+    }
+
+    #[test]
+    fn roa_import_errors_are_bad_requests() {
+        let ca = Handle::from_str_unsafe("ca");
+        let e = Error::CaAuthorisationImport(
+            ca,
+            AuthorizationFmtError::lines(vec![(2, "Invalid asn in string: AS1x".to_string())]),
+        );
+        assert_eq!(StatusCode::BAD_REQUEST, e.status());
+    }
 }
@@ -0,0 +1,392 @@
+//! Protection against protocol peers which flood us with requests, or which
+//! send us stale or replayed messages.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+use chrono::{Duration, TimeZone, Utc};
+
+use rpki::x509::Time;
+
+use crate::commons::api::HexEncodedHash;
+use crate::commons::util::file::PersistedState;
+
+//------------ MessageRejected -----------------------------------------------
+
+/// The reason why a protocol message from a peer was rejected.
+#[derive(Clone, Copy, Debug, Display, Eq, PartialEq)]
+pub enum MessageRejected {
+    #[display(fmt = "too many requests")]
+    RateLimited,
+
+    #[display(fmt = "message has no signing time")]
+    NoSigningTime,
+
+    #[display(fmt = "message signing time is too far in the past or future")]
+    Stale,
+
+    #[display(fmt = "message is older than, or the same as, a message seen before")]
+    Replayed,
+}
+
+//------------ RateLimiter ---------------------------------------------------
+
+/// Limits the number of messages accepted from each sender per minute.
+///
+/// The counts are only kept in memory, so they start afresh when the server
+/// is restarted.
+pub struct RateLimiter {
+    per_minute: u32,
+    windows: RwLock<HashMap<String, (Time, u32)>>,
+}
+
+impl RateLimiter {
+    /// Creates a new limiter. A limit of 0 disables rate limiting.
+    pub fn new(per_minute: u32) -> Self {
+        RateLimiter {
+            per_minute,
+            windows: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Counts a message from the sender, and returns an error if the sender
+    /// sent more messages than allowed in the current minute.
+    pub fn check(&self, sender: &str) -> Result<(), MessageRejected> {
+        if self.per_minute == 0 {
+            return Ok(());
+        }
+
+        if self.add(sender) > self.per_minute {
+            Err(MessageRejected::RateLimited)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns an error if the sender already sent as many messages as
+    /// allowed in the current minute, without counting a message. This is
+    /// used together with `count`, to only count some messages, e.g. failed
+    /// attempts.
+    pub fn check_uncounted(&self, sender: &str) -> Result<(), MessageRejected> {
+        if self.per_minute == 0 {
+            return Ok(());
+        }
+
+        let windows = self.windows.read().unwrap();
+        match windows.get(sender) {
+            Some((start, count))
+                if *start + Duration::minutes(1) > Time::now() && *count >= self.per_minute =>
+            {
+                Err(MessageRejected::RateLimited)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Counts a message from the sender.
+    pub fn count(&self, sender: &str) {
+        if self.per_minute != 0 {
+            self.add(sender);
+        }
+    }
+
+    /// Adds a message to the count for the sender in the current minute, and
+    /// returns the count.
+    fn add(&self, sender: &str) -> u32 {
+        let now = Time::now();
+        let mut windows = self.windows.write().unwrap();
+        let window = windows.entry(sender.to_string()).or_insert((now, 0));
+
+        if window.0 + Duration::minutes(1) <= now {
+            *window = (now, 0);
+        }
+        window.1 += 1;
+        window.1
+    }
+
+    /// Forgets the sender, e.g. because it was removed.
+    pub fn remove(&self, sender: &str) {
+        self.windows.write().unwrap().remove(sender);
+    }
+}
+
+//------------ ReplayGuard ---------------------------------------------------
+
+/// Keeps the signing time of the last accepted message for each sender, and
+/// rejects messages which are signed before it, messages which were seen
+/// before, and messages with a signing time too far from the current time.
+///
+/// Messages are checked in memory. Only the last signing time for each
+/// sender is saved, to a file in the work directory when [`flush`] is
+/// called. The schedulers do this periodically, and the server does it when
+/// it shuts down. Messages accepted after the last save are not known after
+/// a restart, so messages signed before the restart are rejected. Such
+/// messages would be too old after the maximum message age anyway.
+///
+/// [`flush`]: #method.flush
+pub struct ReplayGuard {
+    max_age: Duration,
+    started: Time,
+    last: RwLock<HashMap<String, LastAccepted>>,
+    saved: PersistedState<HashMap<String, Time>>,
+}
+
+/// The signing time of the last accepted message from a sender, and the
+/// hashes of all messages accepted with that signing time. Signing times
+/// have a resolution of a second, so several messages may share one. The
+/// hashes are not saved, so there are none for a signing time which was
+/// loaded from the file.
+#[derive(Clone, Debug)]
+struct LastAccepted {
+    signing_time: Time,
+    hashes: Vec<HexEncodedHash>,
+}
+
+impl ReplayGuard {
+    pub fn build(work_dir: &PathBuf, file_name: &str, max_age: Duration) -> Self {
+        // Signing times have a resolution of a second
+        let started = Time::new(Utc.timestamp(Time::now().timestamp(), 0));
+        Self::build_at(work_dir, file_name, max_age, started)
+    }
+
+    fn build_at(work_dir: &PathBuf, file_name: &str, max_age: Duration, started: Time) -> Self {
+        let saved: PersistedState<HashMap<String, Time>> =
+            PersistedState::load(work_dir, file_name, "message signing times");
+
+        // A signing time saved while the clock of the sender or this server
+        // was off should not keep the sender from being heard for longer
+        // than the maximum message age.
+        let latest = started + max_age;
+        let last = saved.read(|saved| {
+            saved
+                .iter()
+                .map(|(sender, signing_time)| {
+                    let signing_time = if *signing_time > latest {
+                        latest
+                    } else {
+                        *signing_time
+                    };
+                    let accepted = LastAccepted {
+                        signing_time,
+                        hashes: vec![],
+                    };
+                    (sender.clone(), accepted)
+                })
+                .collect()
+        });
+
+        ReplayGuard {
+            max_age,
+            started,
+            last: RwLock::new(last),
+            saved,
+        }
+    }
+
+    /// Checks a message from the sender, and remembers it if it is accepted.
+    ///
+    /// Messages without a signing time are rejected. The signing time is
+    /// optional in CMS in general, but it is required by the CMS profile for
+    /// RFC 6492 and RFC 8181 messages, and without it neither staleness nor
+    /// replays could be detected.
+    pub fn check(
+        &self,
+        sender: &str,
+        signing_time: Option<Time>,
+        msg: &[u8],
+    ) -> Result<(), MessageRejected> {
+        let signing_time = signing_time.ok_or(MessageRejected::NoSigningTime)?;
+
+        let now = Time::now();
+        if signing_time < now - self.max_age
+            || signing_time > now + self.max_age
+            || signing_time < self.started
+        {
+            return Err(MessageRejected::Stale);
+        }
+
+        let hash = HexEncodedHash::from_content(msg);
+
+        {
+            let mut last = self.last.write().unwrap();
+            match last.get_mut(sender) {
+                None => {
+                    let accepted = LastAccepted {
+                        signing_time,
+                        hashes: vec![hash],
+                    };
+                    last.insert(sender.to_string(), accepted);
+                }
+                Some(accepted) => {
+                    if signing_time < accepted.signing_time {
+                        return Err(MessageRejected::Replayed);
+                    } else if signing_time > accepted.signing_time {
+                        accepted.signing_time = signing_time;
+                        accepted.hashes.clear();
+                    } else if accepted.hashes.is_empty() || accepted.hashes.contains(&hash) {
+                        // No hashes means they are unknown, so any message
+                        // with this signing time may have been seen before.
+                        return Err(MessageRejected::Replayed);
+                    }
+                    accepted.hashes.push(hash);
+                }
+            }
+        }
+
+        self.saved
+            .update(|saved| saved.insert(sender.to_string(), signing_time) != Some(signing_time));
+
+        Ok(())
+    }
+
+    /// Forgets the sender, e.g. because it was removed.
+    pub fn remove(&self, sender: &str) {
+        self.last.write().unwrap().remove(sender);
+        self.saved.update(|saved| saved.remove(sender).is_some());
+    }
+
+    /// Saves the last signing times if they changed since they were saved.
+    pub fn flush(&self) {
+        self.saved.flush();
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::commons::util::test;
+
+    #[test]
+    fn limit_rate_per_sender() {
+        let limiter = RateLimiter::new(2);
+        assert!(limiter.check("child").is_ok());
+        assert!(limiter.check("child").is_ok());
+        assert_eq!(limiter.check("child"), Err(MessageRejected::RateLimited));
+        assert!(limiter.check("other").is_ok());
+
+        let unlimited = RateLimiter::new(0);
+        for _ in 0..10 {
+            assert!(unlimited.check("child").is_ok());
+        }
+    }
+
+    #[test]
+    fn reject_stale_and_replayed_messages() {
+        test::test_under_tmp(|d| {
+            // Signing times in CMS have a resolution of one second.
+            let now = Time::new(Utc.timestamp(Utc::now().timestamp(), 0));
+            let earlier = now - Duration::seconds(10);
+            let too_old = now - Duration::minutes(10);
+            let too_new = now + Duration::minutes(10);
+
+            let started = now - Duration::minutes(1);
+            let max_age = Duration::minutes(5);
+            let guard = ReplayGuard::build_at(&d, "signing_times.json", max_age, started);
+
+            assert_eq!(
+                guard.check("child", None, b"any"),
+                Err(MessageRejected::NoSigningTime)
+            );
+            assert_eq!(
+                guard.check("child", Some(too_old), b"old"),
+                Err(MessageRejected::Stale)
+            );
+            assert_eq!(
+                guard.check("child", Some(too_new), b"new"),
+                Err(MessageRejected::Stale)
+            );
+
+            assert!(guard.check("child", Some(now), b"first").is_ok());
+            assert!(guard.check("child", Some(now), b"second").is_ok());
+            assert_eq!(
+                guard.check("child", Some(now), b"first"),
+                Err(MessageRejected::Replayed)
+            );
+            assert_eq!(
+                guard.check("child", Some(earlier), b"earlier"),
+                Err(MessageRejected::Replayed)
+            );
+            assert!(guard.check("other", Some(earlier), b"earlier").is_ok());
+
+            // Signing times are only saved when flushed
+            let reloaded = ReplayGuard::build_at(&d, "signing_times.json", max_age, started);
+            assert!(reloaded.check("child", Some(earlier), b"earlier").is_ok());
+
+            // Only the last signing time is saved, so any message with that
+            // signing time is rejected after a reload.
+            guard.flush();
+            let reloaded = ReplayGuard::build_at(&d, "signing_times.json", max_age, started);
+            assert_eq!(
+                reloaded.check("child", Some(now), b"third"),
+                Err(MessageRejected::Replayed)
+            );
+            let later = now + Duration::seconds(1);
+            assert!(reloaded.check("child", Some(later), b"later").is_ok());
+
+            guard.remove("child");
+            guard.flush();
+            let reloaded = ReplayGuard::build_at(&d, "signing_times.json", max_age, started);
+            assert!(reloaded.check("child", Some(earlier), b"earlier").is_ok());
+            assert_eq!(
+                reloaded.check("other", Some(earlier), b"earlier"),
+                Err(MessageRejected::Replayed)
+            );
+        })
+    }
+
+    #[test]
+    fn reject_messages_signed_before_restart() {
+        test::test_under_tmp(|d| {
+            let max_age = Duration::minutes(5);
+            let guard = ReplayGuard::build(&d, "signing_times.json", max_age);
+
+            let now = Time::new(Utc.timestamp(Utc::now().timestamp(), 0));
+            let earlier = now - Duration::seconds(10);
+            assert_eq!(
+                guard.check("child", Some(earlier), b"earlier"),
+                Err(MessageRejected::Stale)
+            );
+            assert!(guard.check("child", Some(now), b"now").is_ok());
+
+            // Signing times saved in the future only hold for the maximum
+            // message age after a restart.
+            let ahead = now + Duration::minutes(4);
+            assert!(guard.check("child", Some(ahead), b"ahead").is_ok());
+            guard.flush();
+
+            let max_age = Duration::minutes(1);
+            let reloaded = ReplayGuard::build_at(&d, "signing_times.json", max_age, now);
+            let last = reloaded.last.read().unwrap();
+            assert_eq!(last["child"].signing_time, now + max_age);
+        })
+    }
+
+    #[test]
+    fn limit_counted_messages_only() {
+        let limiter = RateLimiter::new(2);
+        assert!(limiter.check_uncounted("peer").is_ok());
+        limiter.count("peer");
+        assert!(limiter.check_uncounted("peer").is_ok());
+        limiter.count("peer");
+        assert_eq!(
+            limiter.check_uncounted("peer"),
+            Err(MessageRejected::RateLimited)
+        );
+        assert!(limiter.check_uncounted("other").is_ok());
+    }
+
+    #[test]
+    fn forget_removed_sender() {
+        let limiter = RateLimiter::new(1);
+        assert!(limiter.check("child").is_ok());
+        assert_eq!(limiter.check("child"), Err(MessageRejected::RateLimited));
+
+        limiter.remove("child");
+        assert!(limiter.check("child").is_ok());
+    }
+}
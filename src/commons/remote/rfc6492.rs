@@ -770,7 +770,7 @@ impl Res {
 
 /// This type describes the Not-performed responses defined in section 3.6
 /// of RFC 6492.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct NotPerformedResponse {
     status: u64,
     description: String,
@@ -869,6 +869,14 @@ impl NotPerformedResponse {
     pub fn _2001() -> Self {
         Self::from_code("2001").unwrap()
     }
+
+    pub fn status(&self) -> u64 {
+        self.status
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
 }
 
 impl fmt::Display for NotPerformedResponse {
@@ -653,6 +653,13 @@ impl ReportError {
             failed_pdu,
         }
     }
+
+    /// Replaces the generic text for the error code with an explanation
+    /// of this specific error.
+    pub fn with_text(mut self, text: String) -> Self {
+        self.error_text = Some(text);
+        self
+    }
 }
 
 //------------ ReportErrorCodes ----------------------------------------------
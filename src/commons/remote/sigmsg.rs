@@ -40,6 +40,7 @@ pub struct SignedMessage {
     //--- SignedAttributes
     //
     message_digest: MessageDigest,
+    signing_time: Option<Time>,
 }
 
 /// # Decoding
@@ -56,6 +57,11 @@ impl SignedMessage {
     pub fn content(&self) -> &OctetString {
         &self.content
     }
+
+    /// The signing time attribute, if it was included.
+    pub fn signing_time(&self) -> Option<Time> {
+        self.signing_time
+    }
 }
 
 /// # Parsing
@@ -124,6 +130,7 @@ impl SignedMessage {
                 signature,
 
                 message_digest: attrs.1,
+                signing_time: attrs.3,
             })
         })
     }
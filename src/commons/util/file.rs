@@ -1,8 +1,11 @@
 use std::fs;
 use std::fs::File;
 use std::io::{self, Read, Write};
+use std::ops::{Deref, DerefMut};
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
 
 use bytes::Bytes;
 use serde::de::DeserializeOwned;
@@ -59,6 +62,26 @@ pub fn save_json<O: Serialize>(object: &O, full_path: &PathBuf) -> Result<(), io
     save(&Bytes::from(json), full_path)
 }
 
+/// Saves an object to json, by writing it to a temporary file first and then
+/// renaming that file. This ensures that a crash while saving cannot leave a
+/// truncated file behind - unwraps any json errors!
+pub fn save_json_atomic<O: Serialize>(object: &O, full_path: &PathBuf) -> Result<(), io::Error> {
+    let json = serde_json::to_string(object).unwrap();
+
+    let mut tmp_path = full_path.clone().into_os_string();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+
+    let mut f = create_file_with_path(&tmp_path)?;
+    f.write_all(json.as_bytes())?;
+    f.sync_all()?;
+
+    fs::rename(&tmp_path, full_path)?;
+
+    trace!("Saved file: {}", full_path.to_string_lossy());
+    Ok(())
+}
+
 /// Loads a files and deserialzes as json for the expected type. Maps json
 /// errors to io::Error
 pub fn load_json<O: DeserializeOwned>(full_path: &PathBuf) -> Result<O, io::Error> {
@@ -288,6 +311,78 @@ impl PartialEq for CurrentFile {
 
 impl Eq for CurrentFile {}
 
+//------------ PersistedState ------------------------------------------------
+
+/// State which is kept in memory rather than as events, and saved as JSON
+/// to a file in the work directory so that it survives a restart.
+///
+/// Changes are marked so that they are saved when [`flush`] is called. If
+/// the file cannot be read when the state is loaded, then the state starts
+/// afresh.
+///
+/// [`flush`]: #method.flush
+pub struct PersistedState<T> {
+    path: PathBuf,
+    name: &'static str,
+    state: RwLock<T>,
+    dirty: AtomicBool,
+}
+
+impl<T: Clone + Default + DeserializeOwned + Serialize> PersistedState<T> {
+    /// Loads the state from the file in the work directory, if it exists.
+    /// The name describes the state in log messages.
+    pub fn load(work_dir: &PathBuf, file_name: &str, name: &'static str) -> Self {
+        let path = file_path(work_dir, file_name);
+
+        let state = if path.exists() {
+            load_json(&path).unwrap_or_else(|e| {
+                warn!(
+                    "Could not read {} from '{}', starting afresh. Error: {}",
+                    name,
+                    path.to_string_lossy(),
+                    e
+                );
+                T::default()
+            })
+        } else {
+            T::default()
+        };
+
+        PersistedState {
+            path,
+            name,
+            state: RwLock::new(state),
+            dirty: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the result of the given function applied to the state.
+    pub fn read<R>(&self, op: impl FnOnce(&T) -> R) -> R {
+        op(self.state.read().unwrap().deref())
+    }
+
+    /// Changes the state, to be saved on the next [`flush`]. The given
+    /// function returns whether it changed anything.
+    ///
+    /// [`flush`]: #method.flush
+    pub fn update(&self, op: impl FnOnce(&mut T) -> bool) {
+        if op(self.state.write().unwrap().deref_mut()) {
+            self.dirty.store(true, Ordering::Release);
+        }
+    }
+
+    /// Saves the state if it changed since it was last saved.
+    pub fn flush(&self) {
+        if self.dirty.swap(false, Ordering::AcqRel) {
+            let state = self.state.read().unwrap();
+            if let Err(e) = save_json_atomic(state.deref(), &self.path) {
+                self.dirty.store(true, Ordering::Release);
+                error!("Could not save {}: {}", self.name, e);
+            }
+        }
+    }
+}
+
 //------------ Error ---------------------------------------------------------
 #[derive(Debug, Display)]
 pub enum Error {
@@ -358,4 +453,18 @@ mod tests {
         });
     }
 
+    #[test]
+    fn should_save_json_atomically() {
+        test::test_under_tmp(|base_dir| {
+            let path = file_path(&base_dir, "state.json");
+
+            save_json_atomic(&vec!["a", "b"], &path).unwrap();
+            save_json_atomic(&vec!["c"], &path).unwrap();
+
+            let loaded: Vec<String> = load_json(&path).unwrap();
+            assert_eq!(vec!["c".to_string()], loaded);
+            assert!(!file_path(&base_dir, "state.json.tmp").exists());
+        });
+    }
+
 }
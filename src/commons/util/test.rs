@@ -1,12 +1,22 @@
 use bytes::Bytes;
+use chrono::Duration;
 use rand::{thread_rng, Rng};
+use rpki::crl::{CrlEntry, TbsCertList};
+use rpki::crypto::{DigestAlgorithm, PublicKeyFormat, Signer};
+use rpki::manifest::{FileAndHash, ManifestContent};
+use rpki::sigobj::SignedObjectBuilder;
 use rpki::uri;
+use rpki::x509::{Serial, Time, Validity};
 use std::fs;
 use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
 use std::str::FromStr;
 
+use crate::commons::remote::builder::IdCertBuilder;
+use crate::commons::remote::id::IdCert;
+use crate::commons::util::softsigner::OpenSslSigner;
+
 /// This method sets up a test directory with a random name (a number)
 /// under 'work', relative to where cargo is running. It then runs the
 /// test provided in the closure, and finally it cleans up the test
@@ -58,3 +68,82 @@ pub fn save_file(base_dir: &PathBuf, file_name: &str, content: &[u8]) {
     let mut f = File::create(full_name).unwrap();
     f.write_all(content).unwrap();
 }
+
+/// Returns all files under the directory, including in sub-directories,
+/// which contain the text.
+pub fn files_containing(dir: &PathBuf, text: &str) -> Vec<PathBuf> {
+    let mut res = vec![];
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            res.append(&mut files_containing(&path, text));
+        } else if String::from_utf8_lossy(&fs::read(&path).unwrap()).contains(text) {
+            res.push(path);
+        }
+    }
+    res
+}
+
+/// Creates a self-signed identity certificate for a new key.
+pub fn id_cert(signer: &mut OpenSslSigner) -> IdCert {
+    let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+    IdCertBuilder::new_ta_id_cert(&key, &*signer).unwrap()
+}
+
+/// Creates a manifest, signed with a new key, which lists the given files
+/// and is due for an update at 'next_update'. The manifest can be parsed,
+/// but it does not validate under any CA certificate.
+pub fn manifest(signer: &mut OpenSslSigner, files: &[(&str, &[u8])], next_update: Time) -> Bytes {
+    let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+
+    let this_update = Time::five_minutes_ago();
+    let valid_until = next_update + Duration::days(1);
+
+    let entries = files.iter().map(|(name, content)| {
+        let hash = DigestAlgorithm::default().digest(content);
+        FileAndHash::new(Bytes::from(*name), Bytes::from(hash.as_ref()))
+    });
+
+    let content = ManifestContent::new(
+        Serial::from(1_u64),
+        this_update,
+        next_update,
+        DigestAlgorithm::default(),
+        entries,
+    );
+    let builder = SignedObjectBuilder::new(
+        Serial::from(1_u64),
+        Validity::new(this_update, valid_until),
+        rsync("rsync://localhost/repo/ca/ca.crl"),
+        rsync("rsync://localhost/repo/ta/ca.cer"),
+        rsync("rsync://localhost/repo/ca/ca.mft"),
+    );
+
+    content
+        .into_manifest(builder, signer, &key)
+        .unwrap()
+        .to_captured()
+        .into_bytes()
+}
+
+/// Creates an empty CRL, signed with a new key, which is due for an update
+/// at 'next_update'.
+pub fn crl(signer: &mut OpenSslSigner, next_update: Time) -> Bytes {
+    let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+    let public_key = signer.get_key_info(&key).unwrap();
+
+    let crl = TbsCertList::new(
+        Default::default(),
+        public_key.to_subject_name(),
+        Time::five_minutes_ago(),
+        next_update,
+        Vec::<CrlEntry>::new(),
+        key,
+        Serial::from(1_u64),
+    );
+
+    crl.into_crl(signer, &key)
+        .unwrap()
+        .to_captured()
+        .into_bytes()
+}
@@ -1,10 +1,14 @@
 pub const KRILL_VERSION: &str = env!("CARGO_PKG_VERSION");
 pub const KRILL_SERVER_APP: &str = "Krill";
+pub const KRILL_PUBD_APP: &str = "Krill Publication Server";
 pub const KRILL_CLIENT_APP: &str = "Krill Client";
 
 pub const KRILL_DEFAULT_CONFIG_FILE: &str = "./defaults/krill.conf";
+pub const KRILL_PUBD_DEFAULT_CONFIG_FILE: &str = "./defaults/krillpubd.conf";
 
 pub const CASERVER_DIR: &str = "cas";
+pub const CHILD_ACTIVITY_FILE: &str = "child_activity.json";
+pub const PARENT_NOT_PERFORMED_FILE: &str = "parent_not_performed.json";
 
 pub const PUBSERVER_DFLT: &str = "0";
 pub const PUBSERVER_DIR: &str = "pubd";
@@ -17,10 +21,23 @@ pub const REPOSITORY_DIR: &str = "repo";
 pub const REPOSITORY_RRDP_DIR: &str = "rrdp";
 pub const REPOSITORY_RSYNC_DIR: &str = "rsync";
 
+pub const RRDP_DELTA_MAX_COUNT: usize = 100;
+pub const RRDP_DELTA_MIN_COUNT: usize = 5;
+pub const RRDP_DELTA_MAX_AGE_SECONDS: i64 = 24 * 3600;
+pub const RRDP_DELTA_SIZE_RATIO: f64 = 1.0;
+pub const RRDP_DELTA_REMOVAL_GRACE_SECONDS: i64 = 600;
+pub const RRDP_BATCH_WINDOW_MILLIS: u64 = 100;
+pub const RRDP_RETIRED_MARKER_FILE: &str = "retired.json";
+pub const REPOSITORY_OUTPUT_STATUS_FILE: &str = "repo_output_status.json";
+
 pub const RFC6492_LOG_DIR: &str = "rfc6492";
 pub const RFC8181_SENT_LOG_DIR: &str = "rfc8181_sent";
 pub const RFC8181_RCVD_LOG_DIR: &str = "rfc8181_repo";
 
+pub const RFC6492_CHILD_SIGNING_TIMES_FILE: &str = "rfc6492_child_signing_times.json";
+pub const RFC6492_PARENT_SIGNING_TIMES_FILE: &str = "rfc6492_parent_signing_times.json";
+pub const RFC8181_PUBLISHER_SIGNING_TIMES_FILE: &str = "rfc8181_publisher_signing_times.json";
+
 pub const KRILL_CLI_SERVER_ARG: &str = "server";
 pub const KRILL_CLI_SERVER_ENV: &str = "KRILL_CLI_SERVER";
 pub const KRILL_CLI_SERVER_DFLT: &str = "https://localhost:3000/";
@@ -0,0 +1,265 @@
+//! Keeps track of the requests children send to their parent CA, and of the
+//! error responses CAs get from their parents.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use chrono::Duration;
+
+use rpki::x509::Time;
+
+use crate::commons::api::{ChildActivity, ChildHandle, ChildRequestType, Handle, ParentHandle};
+use crate::commons::remote::rfc6492::NotPerformedResponse;
+use crate::commons::util::file::PersistedState;
+use crate::constants::{CHILD_ACTIVITY_FILE, PARENT_NOT_PERFORMED_FILE};
+
+//------------ ChildActivityStore --------------------------------------------
+
+/// Keeps the most recent activity for the children of all CAs.
+///
+/// This is not kept in the [`CertAuth`] itself, because children ask for
+/// their entitlements frequently and we do not want to record an event for
+/// every such request. Instead the activity is kept in memory, and saved to
+/// a single file in the work directory when [`flush`] is called, which the
+/// scheduler does periodically and the server does when it shuts down.
+///
+/// [`flush`]: #method.flush
+pub struct ChildActivityStore {
+    started: Time,
+    activity: PersistedState<ChildActivityState>,
+}
+
+/// The activity saved by the [`ChildActivityStore`]. Requests which could
+/// not be attributed to a known child, e.g. because they could not be
+/// decoded, are kept as rejected requests for the CA they were sent to.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct ChildActivityState {
+    children: HashMap<Handle, HashMap<ChildHandle, ChildActivity>>,
+
+    #[serde(default)]
+    rejected: HashMap<Handle, ChildActivity>,
+}
+
+impl ChildActivityStore {
+    pub fn build(work_dir: &PathBuf) -> Self {
+        ChildActivityStore {
+            started: Time::now(),
+            activity: PersistedState::load(work_dir, CHILD_ACTIVITY_FILE, "child activity"),
+        }
+    }
+
+    /// Records a request from a child, and the error returned to it, if any.
+    pub fn request_received(
+        &self,
+        ca: &Handle,
+        child: &ChildHandle,
+        request_type: ChildRequestType,
+        user_agent: Option<String>,
+        error: Option<String>,
+    ) {
+        self.activity.update(|activity| {
+            activity
+                .children
+                .entry(ca.clone())
+                .or_insert_with(HashMap::new)
+                .entry(child.clone())
+                .or_insert_with(ChildActivity::default)
+                .request(request_type, user_agent, error);
+            true
+        })
+    }
+
+    /// Records a request sent to a CA which was rejected before it could be
+    /// attributed to one of its children.
+    pub fn request_rejected(&self, ca: &Handle, user_agent: Option<String>, error: String) {
+        self.activity.update(|activity| {
+            activity
+                .rejected
+                .entry(ca.clone())
+                .or_insert_with(ChildActivity::default)
+                .request(ChildRequestType::Other, user_agent, Some(error));
+            true
+        })
+    }
+
+    /// Saves the activity if it changed since it was last saved.
+    pub fn flush(&self) {
+        self.activity.flush()
+    }
+
+    /// Returns the activity for all children of all CAs.
+    pub fn all(&self) -> HashMap<Handle, HashMap<ChildHandle, ChildActivity>> {
+        self.activity.read(|activity| activity.children.clone())
+    }
+
+    /// Returns the last rejected request which could not be attributed to
+    /// a child, for all CAs.
+    pub fn all_rejected(&self) -> HashMap<Handle, ChildActivity> {
+        self.activity.read(|activity| activity.rejected.clone())
+    }
+
+    pub fn get(&self, ca: &Handle, child: &ChildHandle) -> Option<ChildActivity> {
+        self.activity.read(|activity| {
+            activity
+                .children
+                .get(ca)
+                .and_then(|children| children.get(child))
+                .cloned()
+        })
+    }
+
+    /// Returns whether the child has not asked for its entitlements for
+    /// longer than the given duration. Children which have not been seen
+    /// since this server started are considered active until the duration
+    /// has passed since the start.
+    pub fn is_inactive(&self, ca: &Handle, child: &ChildHandle, max: Duration) -> bool {
+        let last_seen = self
+            .get(ca, child)
+            .and_then(|activity| activity.last_list())
+            .unwrap_or(self.started);
+
+        last_seen + max < Time::now()
+    }
+}
+
+//------------ ParentResponseStore -------------------------------------------
+
+/// Keeps the error response to the last issuance or revocation request sent
+/// by each CA to each of its parents, unless a later request succeeded.
+///
+/// These responses are reported as issues for the CA, so they are saved to
+/// a file in the work directory in order to survive a restart. Like the
+/// child activity they are saved when [`flush`] is called, rather than on
+/// every response.
+///
+/// [`flush`]: #method.flush
+pub struct ParentResponseStore {
+    not_performed: PersistedState<HashMap<Handle, HashMap<ParentHandle, NotPerformedResponse>>>,
+}
+
+impl ParentResponseStore {
+    pub fn build(work_dir: &PathBuf) -> Self {
+        ParentResponseStore {
+            not_performed: PersistedState::load(
+                work_dir,
+                PARENT_NOT_PERFORMED_FILE,
+                "parent responses",
+            ),
+        }
+    }
+
+    /// Remembers the error response to a request sent to a parent, or
+    /// forgets it if the request succeeded.
+    pub fn response_received(
+        &self,
+        ca: &Handle,
+        parent: &ParentHandle,
+        not_performed: Option<NotPerformedResponse>,
+    ) {
+        self.not_performed.update(|all| match not_performed {
+            Some(not_performed) => {
+                let parents = all.entry(ca.clone()).or_insert_with(HashMap::new);
+                parents.insert(parent.clone(), not_performed.clone()) != Some(not_performed)
+            }
+            None => match all.get_mut(ca) {
+                Some(parents) => {
+                    let removed = parents.remove(parent).is_some();
+                    if parents.is_empty() {
+                        all.remove(ca);
+                    }
+                    removed
+                }
+                None => false,
+            },
+        })
+    }
+
+    pub fn get(&self, ca: &Handle, parent: &ParentHandle) -> Option<NotPerformedResponse> {
+        self.not_performed.read(|all| {
+            all.get(ca)
+                .and_then(|parents| parents.get(parent))
+                .cloned()
+        })
+    }
+
+    /// Saves the responses if they changed since they were last saved.
+    pub fn flush(&self) {
+        self.not_performed.flush()
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::commons::util::test;
+
+    #[test]
+    fn record_and_reload_child_activity() {
+        test::test_under_tmp(|d| {
+            let ca = Handle::from_str_unsafe("ca");
+            let child = Handle::from_str_unsafe("child");
+
+            let store = ChildActivityStore::build(&d);
+            assert!(store.get(&ca, &child).is_none());
+            assert!(!store.is_inactive(&ca, &child, Duration::hours(1)));
+            assert!(store.is_inactive(&ca, &child, Duration::hours(-1)));
+
+            store.request_received(&ca, &child, ChildRequestType::List, None, None);
+
+            let not_flushed = ChildActivityStore::build(&d);
+            assert!(not_flushed.get(&ca, &child).is_none());
+
+            store.flush();
+
+            let reloaded = ChildActivityStore::build(&d);
+            assert_eq!(store.get(&ca, &child), reloaded.get(&ca, &child));
+            assert!(!reloaded.is_inactive(&ca, &child, Duration::hours(1)));
+
+            let user_agent = Some("krill".to_string());
+            let error = Some("no such resource class".to_string());
+            store.request_received(&ca, &child, ChildRequestType::Issue, user_agent, error);
+            store.request_received(&ca, &child, ChildRequestType::Revoke, None, None);
+
+            let activity = store.get(&ca, &child).unwrap();
+            assert_eq!(activity.last_request_type(), Some(ChildRequestType::Revoke));
+            assert_eq!(activity.user_agent().map(String::as_str), Some("krill"));
+            assert!(activity.last_error().is_some());
+            assert!(activity.last_list().is_some());
+
+            store.request_rejected(&ca, None, "could not decode".to_string());
+            store.flush();
+
+            let reloaded = ChildActivityStore::build(&d);
+            let rejected = reloaded.all_rejected().remove(&ca).unwrap();
+            assert_eq!(rejected.last_request_type(), Some(ChildRequestType::Other));
+            assert!(rejected.last_error().is_some());
+            assert_eq!(store.get(&ca, &child), reloaded.get(&ca, &child));
+        })
+    }
+
+    #[test]
+    fn record_and_reload_parent_responses() {
+        test::test_under_tmp(|d| {
+            let ca = Handle::from_str_unsafe("ca");
+            let parent = Handle::from_str_unsafe("parent");
+            let not_performed = NotPerformedResponse::from_code("1201").unwrap();
+
+            let store = ParentResponseStore::build(&d);
+            store.response_received(&ca, &parent, Some(not_performed.clone()));
+            store.flush();
+
+            let reloaded = ParentResponseStore::build(&d);
+            assert_eq!(Some(not_performed), reloaded.get(&ca, &parent));
+
+            reloaded.response_received(&ca, &parent, None);
+            reloaded.flush();
+
+            let reloaded = ParentResponseStore::build(&d);
+            assert!(reloaded.get(&ca, &parent).is_none());
+        })
+    }
+}
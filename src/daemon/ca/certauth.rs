@@ -15,11 +15,13 @@ use rpki::x509::{Serial, Time, Validity};
 
 use crate::commons::api::rrdp::PublishElement;
 use crate::commons::api::{
-    self, CertAuthInfo, ChildHandle, EntitlementClass, Entitlements, Handle, IdCertPem,
-    IssuanceRequest, IssuedCert, ObjectsDelta, ParentCaContact, ParentHandle, RcvdCert,
-    RepositoryContact, RequestResourceLimit, ResourceClassName, ResourceSet, RevocationRequest,
-    RevocationResponse, RoaDefinition, SigningCert, TaCertDetails, TrustAnchorLocator,
-    UpdateChildRequest,
+    self, As0RoasInfo, AsNumber, CertAuthInfo, ChildCaInfo, ChildCertValidity, ChildHandle,
+    ChildInvitation, ConfiguredRoa, DormantRoasInfo, EntitlementClass, Entitlements, Handle,
+    IdCertPem, IssuanceRequest, IssuedCert, ObjectsDelta, ParentCaContact, ParentHandle,
+    ParentMigrationPhase, ParentMigrationStatus, RcvdCert, RepositoryContact, RequestResourceLimit,
+    ResourceClassName, ResourceSet, RevocationRequest, RevocationResponse, RoaDefinition,
+    RoaDormantPolicy, RoaLintReport, RoaSchedule, ScheduledRoa, SigningCert, TaCertDetails,
+    TokenHash, TrustAnchorLocator, TypedPrefix, UpdateChildRequest,
 };
 use crate::commons::error::Error;
 use crate::commons::eventsourcing::{Aggregate, StoredEvent};
@@ -29,14 +31,13 @@ use crate::commons::remote::rfc6492;
 use crate::commons::remote::rfc8183;
 use crate::commons::remote::sigmsg::SignedMessage;
 use crate::commons::KrillResult;
-use crate::constants::CHILD_CERTIFICATE_REISSUE_WEEKS;
 use crate::daemon::ca::events::ChildCertificateUpdates;
 use crate::daemon::ca::rc::PublishMode;
 use crate::daemon::ca::server::RoaPrefixGroupingStrategy;
 use crate::daemon::ca::signing::CsrInfo;
 use crate::daemon::ca::{
-    ta_handle, ChildDetails, Cmd, CmdDet, CurrentObjectSetDelta, Evt, EvtDet, Ini, ResourceClass,
-    RouteAuthorization, RouteAuthorizationUpdates, Routes, Signer,
+    ta_handle, ChildDetails, Cmd, CmdDet, CurrentObjectSetDelta, Evt, EvtDet, Ini, ParentMigration,
+    ResourceClass, RouteAuthorization, RouteAuthorizationUpdates, Routes, Signer,
 };
 
 //------------ Rfc8183Id ---------------------------------------------------
@@ -83,16 +84,42 @@ pub struct CertAuth<S: Signer> {
 
     id: Rfc8183Id, // Used for RFC 6492 (up-down) and RFC 8181 (publication)
 
+    // The new id while an ID key roll is in progress
+    #[serde(default)]
+    next_id: Option<Rfc8183Id>,
+
     repository: Option<RepositoryContact>,
     repository_pending_withdraw: Option<RepositoryContact>,
 
+    // The previous ID cert of the repository, if it was updated, so that
+    // responses are still accepted while the repository rolls its key.
+    #[serde(default)]
+    repository_previous_id: Option<IdCert>,
+
     parents: HashMap<ParentHandle, ParentCaContact>,
 
+    // The previous ID certs of parents which were updated, so that responses
+    // are still accepted while a parent rolls its key.
+    #[serde(default)]
+    parent_previous_ids: HashMap<ParentHandle, IdCert>,
+
+    #[serde(default)]
+    parent_migration: Option<ParentMigration>,
+
     next_class_name: u32,
     resources: HashMap<ResourceClassName, ResourceClass>,
 
     children: HashMap<ChildHandle, ChildDetails>,
 
+    // The validity for certificates issued to children which do not have
+    // their own validity. This follows the server configuration, and is
+    // updated through a command when that changes.
+    #[serde(default)]
+    child_cert_validity: ChildCertValidity,
+
+    #[serde(default)]
+    child_invitations: HashMap<TokenHash, ChildInvitation>,
+
     routes: Routes,
 
     phantom_signer: PhantomData<S>,
@@ -131,16 +158,22 @@ impl<S: Signer> Aggregate for CertAuth<S> {
             version: 1,
 
             id,
+            next_id: None,
 
             repository,
             repository_pending_withdraw: None,
+            repository_previous_id: None,
 
             parents,
+            parent_previous_ids: HashMap::new(),
+            parent_migration: None,
 
             next_class_name,
             resources,
 
             children,
+            child_cert_validity: ChildCertValidity::default(),
+            child_invitations: HashMap::new(),
 
             routes,
 
@@ -220,10 +253,42 @@ impl<S: Signer> Aggregate for CertAuth<S> {
                 .get_mut(&child)
                 .unwrap()
                 .set_resources(resources),
+            EvtDet::ChildUpdatedValidity(child, validity) => self
+                .children
+                .get_mut(&child)
+                .unwrap()
+                .set_validity(validity),
+            EvtDet::ChildCertValidityDefaultUpdated(validity) => {
+                self.child_cert_validity = validity;
+                for child in self.children.values_mut() {
+                    if !child.has_own_validity() {
+                        child.reissue_current();
+                    }
+                }
+            }
+            EvtDet::ChildSuspended(child) => {
+                self.children.get_mut(&child).unwrap().set_suspended(true)
+            }
+            EvtDet::ChildResumed(child) => {
+                self.children.get_mut(&child).unwrap().set_suspended(false)
+            }
 
             EvtDet::ChildRemoved(child) => {
                 self.children.remove(&child);
             }
+            EvtDet::ChildInvitationAdded(invitation) => {
+                self.child_invitations
+                    .insert(invitation.id().clone(), invitation);
+            }
+            EvtDet::ChildInvitationUpdated(id, expires) => {
+                self.child_invitations
+                    .get_mut(&id)
+                    .unwrap()
+                    .set_expires(expires);
+            }
+            EvtDet::ChildInvitationRemoved(id) => {
+                self.child_invitations.remove(&id);
+            }
 
             //-----------------------------------------------------------------------
             // Being a child
@@ -231,15 +296,52 @@ impl<S: Signer> Aggregate for CertAuth<S> {
             EvtDet::IdUpdated(id) => {
                 self.id = id;
             }
+            EvtDet::IdRollStarted(id) => {
+                self.next_id = Some(id);
+            }
+            EvtDet::IdRollActivated => {
+                if let Some(id) = self.next_id.take() {
+                    self.id = id;
+                }
+            }
             EvtDet::ParentAdded(handle, info) => {
                 self.parents.insert(handle, info);
             }
             EvtDet::ParentUpdated(handle, info) => {
+                if let Some(ParentCaContact::Rfc6492(previous)) = self.parents.get(&handle) {
+                    if let ParentCaContact::Rfc6492(updated) = &info {
+                        if previous.id_cert() != updated.id_cert() {
+                            self.parent_previous_ids
+                                .insert(handle.clone(), previous.id_cert().clone());
+                        }
+                    }
+                }
                 self.parents.insert(handle, info);
             }
+            EvtDet::ParentPreviousIdRemoved(handle) => {
+                self.parent_previous_ids.remove(&handle);
+            }
             EvtDet::ParentRemoved(handle, _deltas) => {
                 self.parents.remove(&handle);
+                self.parent_previous_ids.remove(&handle);
                 self.resources.retain(|_, rc| rc.parent_handle() != &handle);
+
+                // Removing the new parent aborts a migration in progress.
+                let aborted = match &self.parent_migration {
+                    Some(migration) => !migration.is_completed() && migration.to() == &handle,
+                    None => false,
+                };
+                if aborted {
+                    self.parent_migration = None;
+                }
+            }
+            EvtDet::ParentMigrationStarted(migration) => {
+                self.parent_migration = Some(migration);
+            }
+            EvtDet::ParentMigrationCompleted(time) => {
+                if let Some(migration) = self.parent_migration.as_mut() {
+                    migration.complete(time);
+                }
             }
 
             EvtDet::ResourceClassAdded(name, rc) => {
@@ -304,6 +406,20 @@ impl<S: Signer> Aggregate for CertAuth<S> {
             //-----------------------------------------------------------------------
             EvtDet::RouteAuthorizationAdded(update) => self.routes.add(update),
             EvtDet::RouteAuthorizationRemoved(removal) => self.routes.remove(&removal),
+            EvtDet::RouteAuthorizationMetaUpdated(auth, meta) => {
+                self.routes.update_meta(&auth, meta)
+            }
+            EvtDet::RouteAuthorizationScheduled(auth, schedule) => {
+                self.routes.set_schedule(auth, schedule)
+            }
+            EvtDet::RouteAuthorizationsAs0Updated(enabled) => self.routes.set_as0(enabled),
+            EvtDet::RouteAuthorizationsDormantPolicyUpdated(policy) => {
+                self.routes.set_dormant_policy(policy)
+            }
+            EvtDet::RouteAuthorizationsUnpublishable(_) => {
+                // no action needed, this is recorded to flag that ROAs are no
+                // longer issued for these authorizations
+            }
             EvtDet::RoasUpdated(rcn, updates) => {
                 self.resources.get_mut(&rcn).unwrap().roas_updated(updates)
             }
@@ -322,6 +438,16 @@ impl<S: Signer> Aggregate for CertAuth<S> {
                     self.repository_pending_withdraw = Some(current.clone())
                 }
                 self.repository = Some(contact);
+                self.repository_previous_id = None;
+            }
+            EvtDet::RepoIdUpdated(contact) => {
+                if let Some(RepositoryContact::Rfc8181(previous)) = &self.repository {
+                    self.repository_previous_id = Some(previous.id_cert().clone());
+                }
+                self.repository = Some(contact);
+            }
+            EvtDet::RepoPreviousIdRemoved => {
+                self.repository_previous_id = None;
             }
             EvtDet::RepoCleaned(_) => {
                 self.repository_pending_withdraw = None;
@@ -340,10 +466,10 @@ impl<S: Signer> Aggregate for CertAuth<S> {
             CmdDet::MakeTrustAnchor(uris, signer) => self.trust_anchor_make(uris, signer),
 
             // being a parent
-            CmdDet::ChildAdd(child, id_cert_opt, resources) => {
-                self.child_add(child, id_cert_opt, resources)
+            CmdDet::ChildAdd(child, id_cert_opt, resources, validity) => {
+                self.child_add(child, id_cert_opt, resources, validity)
             }
-            CmdDet::ChildUpdate(child, req) => self.child_update(&child, req),
+            CmdDet::ChildUpdate(child, req, signer) => self.child_update(&child, req, signer),
             CmdDet::ChildCertify(child, request, signer) => {
                 self.child_certify(child, request, signer)
             }
@@ -351,12 +477,28 @@ impl<S: Signer> Aggregate for CertAuth<S> {
                 self.child_revoke_key(child, request, signer)
             }
             CmdDet::ChildRemove(child, signer) => self.child_remove(&child, signer),
+            CmdDet::ChildCertValidityDefaultUpdate(validity) => {
+                self.child_cert_validity_default_update(validity)
+            }
+            CmdDet::ChildInvitationAdd(invitation) => self.child_invitation_add(invitation),
+            CmdDet::ChildInvitationUpdate(id, expires) => self.child_invitation_update(id, expires),
+            CmdDet::ChildInvitationRevoke(id) => self.child_invitation_revoke(id),
+            CmdDet::ChildInvitationAccept(id, child, id_cert, validity) => {
+                self.child_invitation_accept(id, child, id_cert, validity)
+            }
 
             // being a child
-            CmdDet::GenerateNewIdKey(signer) => self.generate_new_id_key(signer),
+            CmdDet::GenerateNewIdKey(signer) => self.id_roll_init(signer),
+            CmdDet::ActivateNewIdKey => self.id_roll_activate(),
             CmdDet::AddParent(parent, info) => self.add_parent(parent, info),
             CmdDet::UpdateParentContact(parent, info) => self.update_parent(parent, info),
             CmdDet::RemoveParent(parent) => self.remove_parent(parent),
+            CmdDet::ParentPreviousIdRemove(parent) => self.remove_parent_previous_id(parent),
+            CmdDet::ParentMigrationStart(from, to, info) => {
+                self.parent_migration_start(from, to, info)
+            }
+            CmdDet::ParentMigrationComplete(published) => self.parent_migration_complete(published),
+            CmdDet::ParentMigrationCancel => self.parent_migration_cancel(),
 
             CmdDet::UpdateResourceClasses(parent, entitlements, signer) => {
                 self.update_resource_classes(parent, entitlements, signer)
@@ -374,11 +516,25 @@ impl<S: Signer> Aggregate for CertAuth<S> {
             CmdDet::RouteAuthorizationsUpdate(updates, signer) => {
                 self.route_authorizations_update(updates, signer)
             }
+            CmdDet::RouteAuthorizationsMetaUpdate(roas) => {
+                self.route_authorizations_meta_update(roas)
+            }
+            CmdDet::RouteAuthorizationsSchedule(roas, signer) => {
+                self.route_authorizations_schedule(roas, signer)
+            }
+            CmdDet::RouteAuthorizationsAs0Update(enabled, signer) => {
+                self.route_authorizations_as0_update(enabled, signer)
+            }
+            CmdDet::RouteAuthorizationsRefresh(signer) => self.route_authorizations_refresh(signer),
+            CmdDet::RouteAuthorizationsDormantPolicyUpdate(policy) => {
+                self.route_authorizations_dormant_policy_update(policy)
+            }
 
             // Republish
             CmdDet::Republish(signer) => self.republish(signer),
             CmdDet::RepoUpdate(new_contact, signer) => self.update_repo(new_contact, signer),
             CmdDet::RepoRemoveOld(signer) => self.clean_repo(signer),
+            CmdDet::RepoPreviousIdRemove => self.remove_repo_previous_id(),
         }
     }
 }
@@ -415,12 +571,38 @@ impl<S: Signer> CertAuth<S> {
             .collect()
     }
 
+    pub fn configured_roas(&self) -> Vec<ConfiguredRoa> {
+        self.routes.configured_roas()
+    }
+
+    /// Returns the updates needed to add or remove scheduled authorizations
+    /// which are due at the given time.
+    pub fn route_schedule_updates(&self, now: Time) -> RouteAuthorizationUpdates {
+        self.routes.scheduled_updates(now)
+    }
+
+    /// Lints the given ROA definitions against best practices, taking into
+    /// account the resources delegated to the children of this CA.
+    pub fn roa_lint(&self, roas: &[RoaDefinition]) -> RoaLintReport {
+        let delegated: Vec<(ChildHandle, ResourceSet)> = self
+            .children
+            .iter()
+            .map(|(handle, details)| (handle.clone(), details.resources().clone()))
+            .collect();
+        RoaLintReport::lint(roas, &delegated)
+    }
+
+    /// Returns the child request for parents. During an ID key roll this
+    /// includes the new ID cert, so that parents can be given it before the
+    /// roll is activated.
     pub fn child_request(&self) -> rfc8183::ChildRequest {
-        rfc8183::ChildRequest::new(self.handle.clone(), self.id.cert.clone())
+        rfc8183::ChildRequest::new(self.handle.clone(), self.advertised_id_cert().clone())
     }
 
+    /// Returns the publisher request for repositories. During an ID key roll
+    /// this includes the new ID cert, like the child request.
     pub fn publisher_request(&self) -> rfc8183::PublisherRequest {
-        rfc8183::PublisherRequest::new(None, self.handle.clone(), self.id_cert().clone())
+        rfc8183::PublisherRequest::new(None, self.handle.clone(), self.advertised_id_cert().clone())
     }
 
     pub fn id_cert(&self) -> &IdCert {
@@ -429,6 +611,89 @@ impl<S: Signer> CertAuth<S> {
     pub fn id_key(&self) -> &KeyIdentifier {
         &self.id.key
     }
+
+    /// Returns the ID cert to include in parent responses for children.
+    /// During an ID key roll this is the new ID cert, while responses are
+    /// still signed with the current key until the roll is activated.
+    pub fn advertised_id_cert(&self) -> &IdCert {
+        self.next_id
+            .as_ref()
+            .map(|id| &id.cert)
+            .unwrap_or(&self.id.cert)
+    }
+
+    /// Returns the new ID key while an ID key roll is in progress.
+    pub fn next_id_key(&self) -> Option<&KeyIdentifier> {
+        self.next_id.as_ref().map(|id| &id.key)
+    }
+
+    /// Returns the ID cert of the parent for which responses are accepted,
+    /// besides the ID cert in the contact used. If the contact has a new ID
+    /// cert, then this is the ID cert of the current contact. Otherwise this
+    /// is the previous ID cert, if the contact was updated with a new one.
+    pub fn parent_previous_id_cert(
+        &self,
+        parent: &ParentHandle,
+        contact: &rfc8183::ParentResponse,
+    ) -> Option<&IdCert> {
+        match self.parents.get(parent) {
+            Some(ParentCaContact::Rfc6492(current)) if current.id_cert() != contact.id_cert() => {
+                Some(current.id_cert())
+            }
+            _ => self.parent_previous_ids.get(parent),
+        }
+    }
+
+    /// Returns true if the previous ID cert of the parent is no longer needed,
+    /// once a response validated against the ID cert of the current contact.
+    pub fn parent_previous_id_obsolete(
+        &self,
+        parent: &ParentHandle,
+        contact: &rfc8183::ParentResponse,
+    ) -> bool {
+        match self.parents.get(parent) {
+            Some(ParentCaContact::Rfc6492(current)) => {
+                current.id_cert() == contact.id_cert()
+                    && self.parent_previous_ids.contains_key(parent)
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the ID cert of the repository for which responses are accepted,
+    /// besides the ID cert in the response used. If the response is for the
+    /// current repository with a new ID cert, then this is the ID cert of the
+    /// current contact. Otherwise this is the previous ID cert, if the contact
+    /// was updated with a new one.
+    pub fn repository_previous_id_cert(
+        &self,
+        repository: &rfc8183::RepositoryResponse,
+    ) -> Option<&IdCert> {
+        match &self.repository {
+            Some(RepositoryContact::Rfc8181(current))
+                if current.service_uri() == repository.service_uri()
+                    && current.id_cert() != repository.id_cert() =>
+            {
+                Some(current.id_cert())
+            }
+            _ => self.repository_previous_id.as_ref(),
+        }
+    }
+
+    /// Returns true if the previous ID cert of the repository is no longer
+    /// needed, once a response validated against the ID cert of the current
+    /// contact.
+    pub fn repository_previous_id_obsolete(
+        &self,
+        repository: &rfc8183::RepositoryResponse,
+    ) -> bool {
+        match &self.repository {
+            Some(RepositoryContact::Rfc8181(current)) => {
+                current.id_cert() == repository.id_cert() && self.repository_previous_id.is_some()
+            }
+            _ => false,
+        }
+    }
     pub fn handle(&self) -> &Handle {
         &self.handle
     }
@@ -615,6 +880,10 @@ impl<S: Signer> CertAuth<S> {
             Err(_) => return None,
         };
 
+        if child.is_suspended() {
+            return None;
+        }
+
         let child_resources = my_rcvd_cert.resources().intersection(child.resources());
         if child_resources.is_empty() {
             return None;
@@ -627,7 +896,7 @@ impl<S: Signer> CertAuth<S> {
         for ki in child_keys {
             if let Some(issued) = my_rc.issued(&ki) {
                 issued_certs.push(issued.clone());
-                let eligble_not_after = Self::eligible_not_after(issued);
+                let eligble_not_after = self.eligible_not_after(child, issued);
                 if eligble_not_after > not_after {
                     not_after = eligble_not_after
                 }
@@ -643,16 +912,40 @@ impl<S: Signer> CertAuth<S> {
         ))
     }
 
-    fn eligible_not_after(issued: &IssuedCert) -> Time {
+    /// Returns the not after time for a certificate which the child is
+    /// eligible for. This is the current expiration time, unless the current
+    /// certificate is due for re-issuance, or was issued under a previous
+    /// validity for this child.
+    fn eligible_not_after(&self, child: &ChildDetails, issued: &IssuedCert) -> Time {
+        let validity = self.child_validity(child);
         let expiration_time = issued.validity().not_after();
-        if expiration_time > Time::now() + chrono::Duration::weeks(CHILD_CERTIFICATE_REISSUE_WEEKS)
+        if expiration_time > Time::now() + Duration::weeks(validity.reissue_weeks())
+            && !child.should_reissue(&issued.subject_key_identifier())
         {
             expiration_time
         } else {
-            Time::next_year()
+            Time::now() + Duration::weeks(validity.validity_weeks())
         }
     }
 
+    /// Returns the validity for certificates issued to a child.
+    fn child_validity(&self, child: &ChildDetails) -> ChildCertValidity {
+        child.validity(self.child_cert_validity)
+    }
+
+    /// Returns the validity for certificates issued to children which do
+    /// not have their own validity.
+    pub fn child_cert_validity(&self) -> ChildCertValidity {
+        self.child_cert_validity
+    }
+
+    /// Returns the details of a child for presentation, or an error if the
+    /// child is unknown.
+    pub fn child_info(&self, child: &Handle) -> KrillResult<ChildCaInfo> {
+        let details = self.get_child(child)?.clone();
+        Ok(details.into_info(self.child_cert_validity))
+    }
+
     /// Returns a child, or an error if the child is unknown.
     pub fn get_child(&self, child: &Handle) -> KrillResult<&ChildDetails> {
         match self.children.get(child) {
@@ -666,6 +959,18 @@ impl<S: Signer> CertAuth<S> {
         self.children.keys()
     }
 
+    /// Returns all invitations for children which were not yet used or
+    /// revoked, including expired invitations.
+    pub fn child_invitations(&self) -> Vec<ChildInvitation> {
+        self.child_invitations.values().cloned().collect()
+    }
+
+    fn get_child_invitation(&self, id: &TokenHash) -> KrillResult<&ChildInvitation> {
+        self.child_invitations
+            .get(id)
+            .ok_or_else(|| Error::CaChildInvitationUnknown(self.handle.clone()))
+    }
+
     /// Adds the child, returns an error if the child is a duplicate,
     /// or if the resources are empty, or not held by this CA.
     fn child_add(
@@ -673,15 +978,18 @@ impl<S: Signer> CertAuth<S> {
         child: ChildHandle,
         id_cert: Option<IdCert>,
         resources: ResourceSet,
+        validity: Option<ChildCertValidity>,
     ) -> KrillResult<Vec<Evt>> {
         if resources.is_empty() {
             Err(Error::CaChildMustHaveResources(self.handle.clone(), child))
         } else if !self.all_resources().contains(&resources) {
             Err(Error::CaChildExtraResources(self.handle.clone(), child))
+        } else if validity.map_or(false, |v| !v.is_valid()) {
+            Err(Error::CaChildInvalidValidity(self.handle.clone(), child))
         } else if self.has_child(&child) {
             Err(Error::CaChildDuplicate(self.handle.clone(), child))
         } else {
-            let child_details = ChildDetails::new(id_cert, resources);
+            let child_details = ChildDetails::new(id_cert, resources, validity);
 
             Ok(vec![EvtDet::child_added(
                 &self.handle,
@@ -707,6 +1015,10 @@ impl<S: Signer> CertAuth<S> {
         let signer = signer.read().unwrap();
         let signer = signer.deref();
 
+        if self.get_child(&child)?.is_suspended() {
+            return Err(Error::CaChildSuspended(self.handle.clone(), child));
+        }
+
         let (rcn, limit, csr) = request.unpack();
         let csr_info = CsrInfo::try_from(&csr)?;
 
@@ -760,7 +1072,8 @@ impl<S: Signer> CertAuth<S> {
         let child = self.get_child(&child)?;
         child.resources().apply_limit(&limit)?;
 
-        my_rc.issue_cert(csr_info, child.resources(), limit, signer)
+        let validity = self.child_validity(child);
+        my_rc.issue_cert(csr_info, child.resources(), validity, limit, signer)
     }
 
     /// Create a publish event details including the revocations, update, withdrawals needed
@@ -780,17 +1093,20 @@ impl<S: Signer> CertAuth<S> {
             .republish_certs(issued_certs, removed_certs, repo.repo_info(), signer)
     }
 
-    /// Updates child IdCert and/or Resource entitlements.
+    /// Updates child IdCert and/or Resource entitlements, or suspends or
+    /// resumes the child.
     ///
-    /// Note: this does not yet revoke / reissue / republish anything. If the 'force' option was
-    /// used in the update request, then shrink_child should be called with a grace period that
-    /// is effective immediately.
+    /// Note: this does not yet revoke / reissue / republish anything, except
+    /// when the child is suspended. If the 'force' option was used in the
+    /// update request, then shrink_child should be called with a grace period
+    /// that is effective immediately.
     fn child_update(
         &self,
         child_handle: &Handle,
         req: UpdateChildRequest,
+        signer: Arc<RwLock<S>>,
     ) -> KrillResult<Vec<Evt>> {
-        let (cert_opt, resources_opt) = req.unpack();
+        let (cert_opt, resources_opt, validity_opt, suspended_opt) = req.unpack();
 
         let mut version = self.version;
         let mut res = vec![];
@@ -817,7 +1133,54 @@ impl<S: Signer> CertAuth<S> {
                     child_handle.clone(),
                     resources,
                 ));
+                version += 1;
+            }
+        }
+
+        if let Some(validity) = validity_opt {
+            if !validity.is_valid() {
+                return Err(Error::CaChildInvalidValidity(
+                    self.handle.clone(),
+                    child_handle.clone(),
+                ));
+            }
+            if validity != self.child_validity(child) || !child.has_own_validity() {
+                res.push(EvtDet::child_updated_validity(
+                    &self.handle,
+                    version,
+                    child_handle.clone(),
+                    validity,
+                ));
+                version += 1;
+            }
+        }
+
+        match suspended_opt {
+            Some(true) if !child.is_suspended() => {
+                // Suspend first, so that the keys for the revoked certificates
+                // may be used again when the child is resumed.
+                res.push(EvtDet::child_suspended(
+                    &self.handle,
+                    version,
+                    child_handle.clone(),
+                ));
+                version += 1;
+
+                let signer = signer.read().unwrap();
+                res.append(&mut self.child_certificates_remove(
+                    child,
+                    &mut version,
+                    signer.deref(),
+                )?);
+            }
+            Some(false) if child.is_suspended() => {
+                res.push(EvtDet::child_resumed(
+                    &self.handle,
+                    version,
+                    child_handle.clone(),
+                ));
             }
+            _ => {}
         }
 
         Ok(res)
@@ -838,14 +1201,15 @@ impl<S: Signer> CertAuth<S> {
 
         let child = self.get_child(&child_handle)?;
 
+        let my_rc = self
+            .resources
+            .get(&rcn)
+            .ok_or_else(|| Error::ResourceClassUnknown(rcn.clone()))?;
+
         if !child.is_issued(&key) {
             return Err(Error::KeyUseNoIssuedCert);
         }
 
-        let my_rc = self
-            .resources
-            .get(&rcn)
-            .ok_or_else(|| Error::KeyUseNoIssuedCert)?;
         let removed = my_rc
             .issued(&key)
             .ok_or_else(|| Error::KeyUseNoIssuedCert)?
@@ -877,6 +1241,129 @@ impl<S: Signer> CertAuth<S> {
         let child = self.get_child(&child_handle)?;
 
         let mut version = self.version;
+
+        let mut res = self.child_certificates_remove(child, &mut version, signer)?;
+        res.push(EvtDet::child_removed(
+            &self.handle,
+            version,
+            child_handle.clone(),
+        ));
+
+        Ok(res)
+    }
+
+    /// Updates the validity for certificates issued to children which do not
+    /// have their own validity. The current certificates of these children
+    /// will be re-issued on their next request. This is a no-op if the
+    /// validity did not change.
+    fn child_cert_validity_default_update(
+        &self,
+        validity: ChildCertValidity,
+    ) -> KrillResult<Vec<Evt>> {
+        if validity == self.child_cert_validity {
+            Ok(vec![])
+        } else {
+            Ok(vec![EvtDet::child_cert_validity_default_updated(
+                &self.handle,
+                self.version,
+                validity,
+            )])
+        }
+    }
+
+    /// Adds an invitation for a child, unless it expires in the past, or
+    /// its resources are empty or not held by this CA. Invitations which
+    /// have expired are removed at the same time.
+    fn child_invitation_add(&self, invitation: ChildInvitation) -> KrillResult<Vec<Evt>> {
+        let now = Time::now();
+        if invitation.is_expired(now) {
+            Err(Error::CaChildInvitationExpired(self.handle.clone()))
+        } else if invitation.resources().is_empty()
+            || !self.all_resources().contains(invitation.resources())
+        {
+            Err(Error::CaChildInvitationResources(self.handle.clone()))
+        } else {
+            let mut version = self.version;
+            let mut res = vec![];
+
+            for expired in self
+                .child_invitations
+                .values()
+                .filter(|existing| existing.is_expired(now))
+            {
+                res.push(EvtDet::child_invitation_removed(
+                    &self.handle,
+                    version,
+                    expired.id().clone(),
+                ));
+                version += 1;
+            }
+
+            res.push(EvtDet::child_invitation_added(
+                &self.handle,
+                version,
+                invitation,
+            ));
+
+            Ok(res)
+        }
+    }
+
+    fn child_invitation_update(&self, id: TokenHash, expires: Time) -> KrillResult<Vec<Evt>> {
+        self.get_child_invitation(&id)?;
+        if expires <= Time::now() {
+            Err(Error::CaChildInvitationExpired(self.handle.clone()))
+        } else {
+            Ok(vec![EvtDet::child_invitation_updated(
+                &self.handle,
+                self.version,
+                id,
+                expires,
+            )])
+        }
+    }
+
+    fn child_invitation_revoke(&self, id: TokenHash) -> KrillResult<Vec<Evt>> {
+        self.get_child_invitation(&id)?;
+        Ok(vec![EvtDet::child_invitation_removed(
+            &self.handle,
+            self.version,
+            id,
+        )])
+    }
+
+    /// Adds a child with the resources of the invitation, and removes the
+    /// invitation so that it cannot be used again.
+    fn child_invitation_accept(
+        &self,
+        id: TokenHash,
+        child: ChildHandle,
+        id_cert: IdCert,
+        validity: Option<ChildCertValidity>,
+    ) -> KrillResult<Vec<Evt>> {
+        let invitation = self.get_child_invitation(&id)?;
+        if invitation.is_expired(Time::now()) {
+            return Err(Error::CaChildInvitationExpired(self.handle.clone()));
+        }
+
+        let resources = invitation.resources().clone();
+        let mut res = self.child_add(child, Some(id_cert), resources, validity)?;
+        res.push(EvtDet::child_invitation_removed(
+            &self.handle,
+            self.version + 1,
+            id,
+        ));
+
+        Ok(res)
+    }
+
+    /// Revokes and unpublishes all certificates issued to a child.
+    fn child_certificates_remove(
+        &self,
+        child: &ChildDetails,
+        version: &mut u64,
+        signer: &S,
+    ) -> KrillResult<Vec<Evt>> {
         let handle = &self.handle;
 
         let mut res = vec![];
@@ -900,11 +1387,11 @@ impl<S: Signer> CertAuth<S> {
             let set_deltas = self.republish_certs(&rcn, &[], &removed, signer)?;
             res.push(EvtDet::current_set_updated(
                 handle,
-                version,
+                *version,
                 rcn.clone(),
                 set_deltas,
             ));
-            version += 1;
+            *version += 1;
 
             let mut cert_updates = ChildCertificateUpdates::default();
             for issued in issued_certs {
@@ -912,15 +1399,13 @@ impl<S: Signer> CertAuth<S> {
             }
             res.push(EvtDet::child_certificates_updated(
                 handle,
-                version,
+                *version,
                 rcn.clone(),
                 cert_updates,
             ));
-            version += 1;
+            *version += 1;
         }
 
-        res.push(EvtDet::child_removed(handle, version, child_handle.clone()));
-
         Ok(res)
     }
 
@@ -933,12 +1418,42 @@ impl<S: Signer> CertAuth<S> {
 /// # Being a child
 ///
 impl<S: Signer> CertAuth<S> {
-    /// Generates a new ID key for this CA.
-    fn generate_new_id_key(&self, signer: Arc<RwLock<S>>) -> KrillResult<Vec<Evt>> {
+    /// Generates a new ID key for this CA, and starts a roll to it.
+    fn id_roll_init(&self, signer: Arc<RwLock<S>>) -> KrillResult<Vec<Evt>> {
+        if self.next_id.is_some() {
+            return Err(Error::CaIdRollInProgress(self.handle.clone()));
+        }
+
         let mut signer = signer.write().unwrap();
         let id = Rfc8183Id::generate(signer.deref_mut())?;
 
-        Ok(vec![EvtDet::id_updated(&self.handle, self.version, id)])
+        Ok(vec![EvtDet::id_roll_started(
+            &self.handle,
+            self.version,
+            id,
+        )])
+    }
+
+    /// Activates the new ID key, it will be used for all communications
+    /// from now on.
+    fn id_roll_activate(&self) -> KrillResult<Vec<Evt>> {
+        if self.next_id.is_none() {
+            return Err(Error::CaIdRollUnknown(self.handle.clone()));
+        }
+        Ok(vec![EvtDet::id_roll_activated(&self.handle, self.version)])
+    }
+
+    /// Stops accepting responses signed with the previous ID key of a parent.
+    fn remove_parent_previous_id(&self, parent: ParentHandle) -> KrillResult<Vec<Evt>> {
+        if self.parent_previous_ids.contains_key(&parent) {
+            Ok(vec![EvtDet::parent_previous_id_removed(
+                &self.handle,
+                self.version,
+                parent,
+            )])
+        } else {
+            Ok(vec![])
+        }
     }
 
     /// List all parents
@@ -1035,42 +1550,177 @@ impl<S: Signer> CertAuth<S> {
         }
     }
 
-    /// Maps a parent and parent's resource class name to a ResourceClassName and
-    /// ResourceClass of our own.
-    fn find_parent_rc(
+    /// Starts moving this CA from a parent to a new parent, by adding the
+    /// new parent. The old parent is removed when the migration completes.
+    fn parent_migration_start(
         &self,
-        parent: &ParentHandle,
-        parent_rcn: &ResourceClassName,
-    ) -> Option<&ResourceClass> {
-        for rc in self.resources.values() {
-            if rc.parent_handle() == parent && rc.parent_rc_name() == parent_rcn {
-                return Some(rc);
+        from: ParentHandle,
+        to: ParentHandle,
+        info: ParentCaContact,
+    ) -> KrillResult<Vec<Evt>> {
+        if let Some(migration) = &self.parent_migration {
+            if !migration.is_completed() {
+                return Err(Error::CaParentMigrationInProgress(self.handle.clone()));
             }
         }
-        None
+        let _from = self.parent(&from)?;
+
+        let mut res = self.add_parent(to.clone(), info)?;
+        res.push(EvtDet::parent_migration_started(
+            &self.handle,
+            self.version + 1,
+            ParentMigration::new(from, to),
+        ));
+        Ok(res)
     }
 
-    /// Get all the current open certificate requests for a parent.
-    /// Returns an empty list if the parent is not found.
-    pub fn cert_requests(
-        &self,
-        parent_handle: &ParentHandle,
-    ) -> HashMap<ResourceClassName, Vec<IssuanceRequest>> {
-        let mut res = HashMap::new();
+    /// Completes the migration to a new parent by removing the old parent.
+    /// Returns an error if not all ROAs are issued under the new parent, or
+    /// if the repository did not confirm that the current version of this
+    /// CA, and therefore the ROAs under the new parent, is published.
+    fn parent_migration_complete(&self, published: u64) -> KrillResult<Vec<Evt>> {
+        let status = self
+            .parent_migration_status()?
+            .ok_or_else(|| Error::CaParentMigrationUnknown(self.handle.clone()))?;
 
-        for (name, rc) in self.resources.iter() {
-            if rc.parent_handle() == parent_handle {
-                res.insert(name.clone(), rc.cert_requests());
-            }
+        if status.phase() != ParentMigrationPhase::AwaitingPublication || published != self.version
+        {
+            return Err(Error::CaParentMigrationNotReady(self.handle.clone()));
         }
 
-        res
+        // The old parent may have been removed already by hand.
+        let mut res = if self.has_parent(status.from()) {
+            self.remove_parent(status.from().clone())?
+        } else {
+            vec![]
+        };
+
+        let version = self.version + res.len() as u64;
+        res.push(EvtDet::parent_migration_completed(
+            &self.handle,
+            version,
+            Time::now(),
+        ));
+        Ok(res)
     }
 
-    fn make_request_events(
-        &self,
-        version: &mut u64,
-        entitlement: &EntitlementClass,
+    /// Cancels a migration in progress by removing the new parent. ROAs
+    /// under the old parent are kept as they are.
+    fn parent_migration_cancel(&self) -> KrillResult<Vec<Evt>> {
+        match &self.parent_migration {
+            Some(migration) if !migration.is_completed() => {
+                // Removing the new parent ends the migration, see apply.
+                self.remove_parent(migration.to().clone())
+            }
+            _ => Err(Error::CaParentMigrationUnknown(self.handle.clone())),
+        }
+    }
+
+    /// Returns the status of the current, or last, migration to a new
+    /// parent, if any.
+    pub fn parent_migration_status(&self) -> KrillResult<Option<ParentMigrationStatus>> {
+        let migration = match &self.parent_migration {
+            Some(migration) => migration,
+            None => return Ok(None),
+        };
+
+        if migration.is_completed() {
+            return Ok(Some(migration.status(
+                ParentMigrationPhase::Completed,
+                vec![],
+                vec![],
+            )));
+        }
+
+        let new_rcs: Vec<&ResourceClass> = self
+            .resources
+            .values()
+            .filter(|rc| rc.parent_handle() == migration.to())
+            .collect();
+
+        if !new_rcs.iter().any(|rc| rc.current_resources().is_some()) {
+            return Ok(Some(migration.status(
+                ParentMigrationPhase::AwaitingCertificates,
+                vec![],
+                vec![],
+            )));
+        }
+
+        let mut new_auths = HashSet::new();
+        let mut new_resources = ResourceSet::default();
+        for rc in new_rcs {
+            new_auths.extend(rc.roa_authorizations()?);
+            if let Some(resources) = rc.current_resources() {
+                new_resources = new_resources.union(resources);
+            }
+        }
+
+        let mut pending = vec![];
+        for rc in self
+            .resources
+            .values()
+            .filter(|rc| rc.parent_handle() == migration.from())
+        {
+            for auth in rc.roa_authorizations()? {
+                if !new_auths.contains(&auth) {
+                    pending.push(*auth.as_ref());
+                }
+            }
+        }
+
+        // Pending ROAs for prefixes which the new parent did not (yet) give
+        // us will not be issued, unless the new parent's resources change.
+        let uncovered = pending
+            .iter()
+            .filter(|auth| !new_resources.contains(&auth.prefix().into()))
+            .cloned()
+            .collect();
+
+        let phase = if pending.is_empty() {
+            ParentMigrationPhase::AwaitingPublication
+        } else {
+            ParentMigrationPhase::AwaitingRoas
+        };
+
+        Ok(Some(migration.status(phase, pending, uncovered)))
+    }
+
+    /// Maps a parent and parent's resource class name to a ResourceClassName and
+    /// ResourceClass of our own.
+    fn find_parent_rc(
+        &self,
+        parent: &ParentHandle,
+        parent_rcn: &ResourceClassName,
+    ) -> Option<&ResourceClass> {
+        for rc in self.resources.values() {
+            if rc.parent_handle() == parent && rc.parent_rc_name() == parent_rcn {
+                return Some(rc);
+            }
+        }
+        None
+    }
+
+    /// Get all the current open certificate requests for a parent.
+    /// Returns an empty list if the parent is not found.
+    pub fn cert_requests(
+        &self,
+        parent_handle: &ParentHandle,
+    ) -> HashMap<ResourceClassName, Vec<IssuanceRequest>> {
+        let mut res = HashMap::new();
+
+        for (name, rc) in self.resources.iter() {
+            if rc.parent_handle() == parent_handle {
+                res.insert(name.clone(), rc.cert_requests());
+            }
+        }
+
+        res
+    }
+
+    fn make_request_events(
+        &self,
+        version: &mut u64,
+        entitlement: &EntitlementClass,
         rc: &ResourceClass,
         signer: &S,
     ) -> KrillResult<Vec<Evt>> {
@@ -1139,6 +1789,7 @@ impl<S: Signer> CertAuth<S> {
         // from the entitlements. In which case we will have to clean them
         // up and un-publish everything there was.
         let current_resource_classes = &self.resources;
+        let mut removed_classes = vec![];
 
         let entitled_classes: Vec<&ResourceClassName> = entitlements
             .classes()
@@ -1172,6 +1823,26 @@ impl<S: Signer> CertAuth<S> {
                 revocations,
             ));
             version += 1;
+            removed_classes.push(name);
+        }
+
+        // Record authorizations which are no longer covered by the resources
+        // held in the remaining resource classes.
+        if !removed_classes.is_empty() {
+            let mut remaining = ResourceSet::default();
+            for (_name, rc) in current_resource_classes
+                .iter()
+                .filter(|(name, _rc)| !removed_classes.contains(name))
+            {
+                if let Some(rc_resources) = rc.current_resources() {
+                    remaining = remaining.union(rc_resources);
+                }
+            }
+            res.append(&mut self.unpublishable_route_events(
+                &self.all_resources(),
+                &remaining,
+                &mut version,
+            ));
         }
 
         // Now check all the entitlements and either create an RC for them, or update.
@@ -1259,6 +1930,15 @@ impl<S: Signer> CertAuth<S> {
 
         let signer = signer.read().unwrap();
 
+        // The resources held after the received cert is applied, used to
+        // find authorizations which are no longer covered.
+        let mut resources_after = rcvd_cert.resources().clone();
+        for (_name, rc) in self.resources.iter().filter(|(name, _rc)| *name != &rcn) {
+            if let Some(rc_resources) = rc.current_resources() {
+                resources_after = resources_after.union(rc_resources);
+            }
+        }
+
         let rc = self
             .resources
             .get(&rcn)
@@ -1277,6 +1957,12 @@ impl<S: Signer> CertAuth<S> {
             version += 1;
         }
 
+        res.append(&mut self.unpublishable_route_events(
+            &self.all_resources(),
+            &resources_after,
+            &mut version,
+        ));
+
         Ok(res)
     }
 }
@@ -1401,10 +2087,10 @@ impl<S: Signer> CertAuth<S> {
     ) -> KrillResult<Vec<EvtDet>> {
         let mut res = vec![];
 
-        for rc in self.resources.values() {
+        for (rcn, rc) in self.resources.iter() {
             if rc.current_key().is_some() {
-                let auths: Vec<RouteAuthorization> =
-                    self.routes.authorizations().cloned().collect();
+                let auths = self.roa_authorizations();
+                let child_validities = self.child_cert_validities(rcn);
 
                 let repo_info = if let PublishMode::NewRepo(info) = mode {
                     info
@@ -1412,16 +2098,38 @@ impl<S: Signer> CertAuth<S> {
                     self.get_repository_contact()?.repo_info()
                 };
 
-                res.append(&mut rc.republish(auths.as_slice(), repo_info, mode, 
-                                             signer, &get_roa_prefix_grouping_strategy())?);
+                res.append(&mut rc.republish(
+                    auths.as_slice(),
+                    repo_info,
+                    mode,
+                    &child_validities,
+                    signer,
+                    &get_roa_prefix_grouping_strategy(),
+                )?);
             }
         }
 
         Ok(res)
     }
 
+    /// Returns the validity of the child owning each key with a current
+    /// certificate in the given resource class.
+    fn child_cert_validities(
+        &self,
+        rcn: &ResourceClassName,
+    ) -> HashMap<KeyIdentifier, ChildCertValidity> {
+        let mut res = HashMap::new();
+        for child in self.children.values() {
+            for ki in child.issued(rcn) {
+                res.insert(ki, self.child_validity(child));
+            }
+        }
+        res
+    }
+
     /// Update repository:
     /// - check that it is indeed different
+    /// - if only the ID cert of the repository changed, just update it
     /// - regenerate all objects under the new URI (CRL URIs updated)
     /// - request new certs for all keys
     ///
@@ -1441,6 +2149,21 @@ impl<S: Signer> CertAuth<S> {
             if contact == &new_contact {
                 return Err(Error::CaRepoInUse(self.handle.clone()));
             }
+
+            // The repository rolled its ID key, there is no need to move.
+            if let (RepositoryContact::Rfc8181(current), RepositoryContact::Rfc8181(new)) =
+                (contact, &new_contact)
+            {
+                if current.service_uri() == new.service_uri()
+                    && current.repo_info() == new.repo_info()
+                {
+                    return Ok(vec![StoredEvent::new(
+                        &self.handle,
+                        self.version,
+                        EvtDet::RepoIdUpdated(new_contact),
+                    )]);
+                }
+            }
         }
 
         let info = new_contact.repo_info().clone();
@@ -1480,6 +2203,20 @@ impl<S: Signer> CertAuth<S> {
         }
     }
 
+    /// Stops accepting responses signed with the previous ID key of the
+    /// repository.
+    fn remove_repo_previous_id(&self) -> KrillResult<Vec<Evt>> {
+        if self.repository_previous_id.is_some() {
+            Ok(vec![StoredEvent::new(
+                &self.handle,
+                self.version,
+                EvtDet::RepoPreviousIdRemoved,
+            )])
+        } else {
+            Ok(vec![])
+        }
+    }
+
     pub fn has_old_repo(&self) -> bool {
         self.repository_pending_withdraw.is_some()
     }
@@ -1496,39 +2233,78 @@ impl<S: Signer> CertAuth<S> {
         updates: RouteAuthorizationUpdates,
         signer: Arc<RwLock<S>>,
     ) -> KrillResult<Vec<Evt>> {
-        let (added, removed) = updates.unpack();
         let signer = signer.read().unwrap();
-        let mode = PublishMode::Normal;
+        self.route_authorizations_update_events(
+            updates,
+            self.routes.schedules(),
+            signer.deref(),
+            self.version,
+        )
+    }
 
-        let repo = self.get_repository_contact()?;
+    /// Returns the events for applying the route authorization updates,
+    /// starting at the given version. The schedules are those in effect
+    /// after any earlier events in the same command.
+    ///
+    /// Once a schedule is applied it is dropped, so that the Scheduler does
+    /// not undo later changes made by hand. I.e. the schedule is dropped when
+    /// its authorization is removed, and when it is added unless it should
+    /// still be removed at a later time.
+    fn route_authorizations_update_events(
+        &self,
+        updates: RouteAuthorizationUpdates,
+        schedules: &HashMap<RouteAuthorization, RoaSchedule>,
+        signer: &S,
+        mut version: u64,
+    ) -> KrillResult<Vec<Evt>> {
+        if let Some((_, e)) = self.route_authorization_errors(&updates).into_iter().next() {
+            return Err(e);
+        }
+
+        let meta = updates.meta().clone();
+        let (added, removed) = updates.unpack();
 
         let mut res = vec![];
-        let mut version = self.version;
-        let all_resources = self.all_resources();
+        let now = Time::now();
 
         let mut current_auths: HashSet<RouteAuthorization> =
             self.routes.authorizations().cloned().collect();
 
         for auth in added {
-            if !auth.max_length_valid() {
-                return Err(Error::CaAuthorisationInvalidMaxlength(
-                    self.handle.clone(),
-                    auth,
-                ));
-            }
-            if current_auths.contains(&auth) {
-                return Err(Error::CaAuthorisationDuplicate(self.handle.clone(), auth));
-            } else if !all_resources.contains(&auth.prefix().into()) {
-                return Err(Error::CaAuthorisationNotEntitled(self.handle.clone(), auth));
-            } else {
-                current_auths.insert(auth);
+            current_auths.insert(auth);
+            res.push(StoredEvent::new(
+                self.handle(),
+                version,
+                EvtDet::RouteAuthorizationAdded(auth),
+            ));
+            version += 1;
+
+            if let Some(meta) = meta.get(&auth) {
+                let current = self
+                    .routes
+                    .info(&auth)
+                    .map(|info| info.meta().clone())
+                    .unwrap_or_default();
+                let meta = current.updated_with(meta.clone());
                 res.push(StoredEvent::new(
                     self.handle(),
                     version,
-                    EvtDet::RouteAuthorizationAdded(auth),
+                    EvtDet::RouteAuthorizationMetaUpdated(auth, meta),
                 ));
                 version += 1;
             }
+
+            if let Some(schedule) = schedules.get(&auth) {
+                let removed_later = schedule.not_after().is_some() && !schedule.is_expired(now);
+                if !removed_later {
+                    res.push(StoredEvent::new(
+                        self.handle(),
+                        version,
+                        EvtDet::RouteAuthorizationScheduled(auth, RoaSchedule::default()),
+                    ));
+                    version += 1;
+                }
+            }
         }
 
         for auth in removed {
@@ -1540,19 +2316,88 @@ impl<S: Signer> CertAuth<S> {
                     EvtDet::RouteAuthorizationRemoved(auth),
                 ));
                 version += 1;
+
+                // An authorization which is removed should not be added, or
+                // removed again if it is added by hand, by the Scheduler.
+                if schedules.contains_key(&auth) {
+                    res.push(StoredEvent::new(
+                        self.handle(),
+                        version,
+                        EvtDet::RouteAuthorizationScheduled(auth, RoaSchedule::default()),
+                    ));
+                    version += 1;
+                }
             } else {
                 return Err(Error::CaAuthorisationUnknown(self.handle.clone(), auth));
             }
         }
 
         let current_auths: Vec<RouteAuthorization> = current_auths.into_iter().collect();
+        let mut auths = self.as0_authorizations(self.routes.as0_enabled(), &current_auths);
+        auths.extend(current_auths);
+
+        res.append(&mut self.roas_update(auths, signer, version)?);
+
+        Ok(res)
+    }
+
+    /// Returns the reason for each authorization added in the updates which
+    /// cannot be added to this CA: because its max length is invalid, it is
+    /// already present, or the CA does not hold its prefix.
+    pub fn route_authorization_errors(
+        &self,
+        updates: &RouteAuthorizationUpdates,
+    ) -> Vec<(RouteAuthorization, Error)> {
+        let all_resources = self.all_resources();
+        let mut res = vec![];
+
+        for auth in updates.added() {
+            let auth = *auth;
+            if !auth.max_length_valid() {
+                res.push((
+                    auth,
+                    Error::CaAuthorisationInvalidMaxlength(self.handle.clone(), auth),
+                ));
+            } else if self.routes.has(&auth) {
+                res.push((
+                    auth,
+                    Error::CaAuthorisationDuplicate(self.handle.clone(), auth),
+                ));
+            } else if !all_resources.contains(&auth.prefix().into()) {
+                res.push((
+                    auth,
+                    Error::CaAuthorisationNotEntitled(self.handle.clone(), auth),
+                ));
+            }
+        }
+
+        res
+    }
+
+    /// Updates the ROAs for all resource classes to match the given
+    /// authorizations, and publishes the changes as a single delta for
+    /// each resource class. Event versions start at the given version.
+    fn roas_update(
+        &self,
+        current_auths: Vec<RouteAuthorization>,
+        signer: &S,
+        mut version: u64,
+    ) -> KrillResult<Vec<Evt>> {
+        let mode = PublishMode::Normal;
+        let repo = self.get_repository_contact()?;
+
+        let mut res = vec![];
 
         let mut deltas = HashMap::new();
 
         // Update ROAs, and derive deltas and revocations for publishing.
         for (rcn, rc) in self.resources.iter() {
-            let updates = rc.update_roas(current_auths.as_slice(), &mode, 
-                                         signer.deref(), &get_roa_prefix_grouping_strategy())?;
+            let updates = rc.update_roas(
+                current_auths.as_slice(),
+                &mode,
+                signer,
+                &get_roa_prefix_grouping_strategy(),
+            )?;
             if updates.contains_changes() {
                 let mut delta = ObjectsDelta::new(repo.repo_info().ca_repository(rc.name_space()));
 
@@ -1584,7 +2429,7 @@ impl<S: Signer> CertAuth<S> {
             let rc = self.resources.get(&rcn).unwrap();
 
             let pub_detail =
-                rc.publish_objects(repo.repo_info(), delta, revocations, &mode, signer.deref())?;
+                rc.publish_objects(repo.repo_info(), delta, revocations, &mode, signer)?;
 
             res.push(StoredEvent::new(&self.handle, version, pub_detail));
             version += 1;
@@ -1592,6 +2437,344 @@ impl<S: Signer> CertAuth<S> {
 
         Ok(res)
     }
+
+    /// Returns AS0 authorizations for all held address space which is neither
+    /// covered by any of the given authorizations, nor delegated to a child.
+    /// Returns nothing if not enabled.
+    fn as0_authorizations(
+        &self,
+        enabled: bool,
+        routes: &[RouteAuthorization],
+    ) -> Vec<RouteAuthorization> {
+        if !enabled {
+            return vec![];
+        }
+
+        let mut used = ResourceSet::default();
+        for auth in routes {
+            used = used.union(&ResourceSet::from(auth.prefix()));
+        }
+        for child in self.children.values() {
+            used = used.union(child.resources());
+        }
+
+        TypedPrefix::unused(&self.all_resources(), &used)
+            .into_iter()
+            .map(|prefix| {
+                RouteAuthorization::new(RoaDefinition::new(AsNumber::new(0), prefix, None))
+            })
+            .collect()
+    }
+
+    /// Returns all authorizations for which ROAs should be issued, i.e. the
+    /// configured routes and, if enabled, AS0 authorizations for unused space.
+    fn roa_authorizations(&self) -> Vec<RouteAuthorization> {
+        let routes: Vec<RouteAuthorization> = self.routes.authorizations().cloned().collect();
+        let mut res = self.as0_authorizations(self.routes.as0_enabled(), &routes);
+        res.extend(routes);
+        res
+    }
+
+    /// Returns events recording the authorizations which were covered by the
+    /// resources held before, but no longer by the resources held after a
+    /// change in entitlements or certificates. Depending on the policy for
+    /// this CA these are kept dormant, so that ROAs are issued again if the
+    /// resources return, or they are removed.
+    fn unpublishable_route_events(
+        &self,
+        before: &ResourceSet,
+        after: &ResourceSet,
+        version: &mut u64,
+    ) -> Vec<Evt> {
+        let mut unpublishable: Vec<RouteAuthorization> = self
+            .routes
+            .dormant(after)
+            .into_iter()
+            .filter(|auth| before.contains(&auth.prefix().into()))
+            .collect();
+
+        if unpublishable.is_empty() {
+            return vec![];
+        }
+        unpublishable.sort_by_key(|auth| auth.to_string());
+
+        warn!(
+            "CA '{}' no longer holds the resources for {} ROA(s), policy: {}",
+            self.handle,
+            unpublishable.len(),
+            self.routes.dormant_policy()
+        );
+
+        let mut res = vec![StoredEvent::new(
+            self.handle(),
+            *version,
+            EvtDet::RouteAuthorizationsUnpublishable(unpublishable.clone()),
+        )];
+        *version += 1;
+
+        if self.routes.dormant_policy() == RoaDormantPolicy::Remove {
+            res.append(&mut self.dormant_removal_events(unpublishable, version));
+        }
+
+        res
+    }
+
+    /// Returns events removing dormant authorizations, as well as their
+    /// schedules so that the Scheduler will not try to add them again.
+    fn dormant_removal_events(
+        &self,
+        dormant: Vec<RouteAuthorization>,
+        version: &mut u64,
+    ) -> Vec<Evt> {
+        let mut res = vec![];
+        for auth in dormant {
+            res.push(StoredEvent::new(
+                self.handle(),
+                *version,
+                EvtDet::RouteAuthorizationRemoved(auth),
+            ));
+            *version += 1;
+
+            if self.routes.schedule(&auth).is_some() {
+                res.push(StoredEvent::new(
+                    self.handle(),
+                    *version,
+                    EvtDet::RouteAuthorizationScheduled(auth, RoaSchedule::default()),
+                ));
+                *version += 1;
+            }
+        }
+        res
+    }
+
+    /// Returns the authorizations which are not covered by the resources
+    /// currently held by this CA, and for which no ROAs are issued.
+    pub fn dormant_authorizations(&self) -> Vec<RoaDefinition> {
+        let mut res: Vec<RoaDefinition> = self
+            .routes
+            .dormant(&self.all_resources())
+            .into_iter()
+            .map(|auth| *auth.as_ref())
+            .collect();
+        res.sort_by_key(|def| def.to_string());
+        res
+    }
+
+    pub fn dormant_roas_info(&self) -> DormantRoasInfo {
+        DormantRoasInfo::new(self.routes.dormant_policy(), self.dormant_authorizations())
+    }
+
+    /// Sets the policy for authorizations which are no longer covered by the
+    /// resources of this CA. When set to remove, any currently dormant
+    /// authorizations are removed as well.
+    fn route_authorizations_dormant_policy_update(
+        &self,
+        policy: RoaDormantPolicy,
+    ) -> KrillResult<Vec<Evt>> {
+        let mut res = vec![];
+        let mut version = self.version;
+
+        if policy != self.routes.dormant_policy() {
+            res.push(StoredEvent::new(
+                self.handle(),
+                version,
+                EvtDet::RouteAuthorizationsDormantPolicyUpdated(policy),
+            ));
+            version += 1;
+        }
+
+        if policy == RoaDormantPolicy::Remove {
+            let dormant = self.routes.dormant(&self.all_resources());
+            res.append(&mut self.dormant_removal_events(dormant, &mut version));
+        }
+
+        Ok(res)
+    }
+
+    pub fn as0_roas_enabled(&self) -> bool {
+        self.routes.as0_enabled()
+    }
+
+    /// Returns whether AS0 ROAs are issued for unused space, and which
+    /// definitions are currently used for this.
+    pub fn as0_roas_info(&self) -> As0RoasInfo {
+        let enabled = self.routes.as0_enabled();
+        let routes: Vec<RouteAuthorization> = self.routes.authorizations().cloned().collect();
+        let roas = self
+            .as0_authorizations(enabled, &routes)
+            .into_iter()
+            .map(|auth| *auth.as_ref())
+            .collect();
+        As0RoasInfo::new(enabled, roas)
+    }
+
+    /// Enables or disables AS0 ROAs for unused space, and updates ROAs
+    /// accordingly.
+    fn route_authorizations_as0_update(
+        &self,
+        enabled: bool,
+        signer: Arc<RwLock<S>>,
+    ) -> KrillResult<Vec<Evt>> {
+        let signer = signer.read().unwrap();
+
+        let mut res = vec![];
+        let mut version = self.version;
+
+        if enabled != self.routes.as0_enabled() {
+            res.push(StoredEvent::new(
+                self.handle(),
+                version,
+                EvtDet::RouteAuthorizationsAs0Updated(enabled),
+            ));
+            version += 1;
+        }
+
+        // A CA without a repository or current keys has no ROAs yet, they
+        // will be issued once it has.
+        if self.can_update_roas() {
+            let routes: Vec<RouteAuthorization> = self.routes.authorizations().cloned().collect();
+            let mut auths = self.as0_authorizations(enabled, &routes);
+            auths.extend(routes);
+
+            res.append(&mut self.roas_update(auths, signer.deref(), version)?);
+        }
+
+        Ok(res)
+    }
+
+    /// Returns whether ROAs can be updated, i.e. this CA has a repository,
+    /// and all its resource classes have a current key.
+    fn can_update_roas(&self) -> bool {
+        self.repository.is_some() && self.resources.values().all(|rc| rc.current_key().is_some())
+    }
+
+    /// Re-derives the ROAs for all authorizations which are covered by the
+    /// current resources, e.g. dormant authorizations for which resources
+    /// returned, and the AS0 ROAs for unused space if enabled. This is a
+    /// no-op if nothing changed.
+    fn route_authorizations_refresh(&self, signer: Arc<RwLock<S>>) -> KrillResult<Vec<Evt>> {
+        if !self.can_update_roas() {
+            return Ok(vec![]);
+        }
+        let signer = signer.read().unwrap();
+        self.roas_update(self.roa_authorizations(), signer.deref(), self.version)
+    }
+
+    /// Updates the meta-information (comment, tags, ticket) for existing
+    /// route authorizations. Only the fields which are supplied are updated,
+    /// see `RoaDefinitionMeta::updated_with`. Will return an error if any
+    /// authorization is unknown. Unchanged meta-information does not result
+    /// in events.
+    fn route_authorizations_meta_update(&self, roas: Vec<ConfiguredRoa>) -> KrillResult<Vec<Evt>> {
+        let mut res = vec![];
+        let mut version = self.version;
+
+        for roa in roas {
+            let (definition, meta) = roa.unpack();
+            let auth = RouteAuthorization::from(definition);
+
+            match self.routes.info(&auth) {
+                None => return Err(Error::CaAuthorisationUnknown(self.handle.clone(), auth)),
+                Some(info) => {
+                    let meta = info.meta().updated_with(meta);
+                    if info.meta() != &meta {
+                        res.push(StoredEvent::new(
+                            self.handle(),
+                            version,
+                            EvtDet::RouteAuthorizationMetaUpdated(auth, meta),
+                        ));
+                        version += 1;
+                    }
+                }
+            }
+        }
+
+        Ok(res)
+    }
+
+    /// Sets the schedule for route authorizations, and adds or removes the
+    /// authorizations which are due right away as part of the same update.
+    /// If any of these cannot be applied, then nothing is changed. Other
+    /// changes are left to the Scheduler.
+    fn route_authorizations_schedule(
+        &self,
+        roas: Vec<ScheduledRoa>,
+        signer: Arc<RwLock<S>>,
+    ) -> KrillResult<Vec<Evt>> {
+        let mut res = vec![];
+        let mut version = self.version;
+
+        let mut routes = self.routes.clone();
+        let mut requested = HashSet::new();
+
+        for roa in roas {
+            let (definition, schedule) = roa.unpack();
+            let auth = RouteAuthorization::from(definition);
+
+            if !auth.max_length_valid() {
+                return Err(Error::CaAuthorisationInvalidMaxlength(
+                    self.handle.clone(),
+                    auth,
+                ));
+            }
+            if !schedule.is_valid() {
+                return Err(Error::CaAuthorisationInvalidSchedule(
+                    self.handle.clone(),
+                    auth,
+                ));
+            }
+
+            let current = self.routes.schedule(&auth).cloned().unwrap_or_default();
+            if current != schedule {
+                res.push(StoredEvent::new(
+                    self.handle(),
+                    version,
+                    EvtDet::RouteAuthorizationScheduled(auth, schedule),
+                ));
+                version += 1;
+                routes.set_schedule(auth, schedule);
+            }
+            requested.insert(auth);
+        }
+
+        let due = Self::requested_updates(routes.scheduled_updates(Time::now()), &requested);
+        if !due.is_empty() {
+            let signer = signer.read().unwrap();
+            res.append(&mut self.route_authorizations_update_events(
+                due,
+                routes.schedules(),
+                signer.deref(),
+                version,
+            )?);
+        }
+
+        Ok(res)
+    }
+
+    /// Returns only the updates for the requested authorizations.
+    fn requested_updates(
+        updates: RouteAuthorizationUpdates,
+        requested: &HashSet<RouteAuthorization>,
+    ) -> RouteAuthorizationUpdates {
+        let (added, removed) = updates.unpack();
+        RouteAuthorizationUpdates::new(
+            added.intersection(requested).cloned().collect(),
+            removed.intersection(requested).cloned().collect(),
+        )
+    }
+
+    /// Returns the updates needed to add or remove the given scheduled
+    /// authorizations which are due right away.
+    pub fn route_schedule_updates_for(&self, roas: &[ScheduledRoa]) -> RouteAuthorizationUpdates {
+        let mut routes = self.routes.clone();
+        let mut requested = HashSet::new();
+        for roa in roas {
+            let auth = RouteAuthorization::from(*roa.definition());
+            routes.set_schedule(auth, *roa.schedule());
+            requested.insert(auth);
+        }
+        Self::requested_updates(routes.scheduled_updates(Time::now()), &requested)
+    }
 }
 
 pub fn set_roa_prefix_grouping_strategy(roa_prefix_grouping_strategy: RoaPrefixGroupingStrategy) {
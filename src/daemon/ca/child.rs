@@ -1,15 +1,16 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use chrono::Duration;
 
 use rpki::crypto::KeyIdentifier;
 use rpki::x509::Time;
 
-use crate::commons::api::{ChildCaInfo, ChildHandle, IssuedCert, ResourceClassName, ResourceSet};
+use crate::commons::api::{
+    ChildCaInfo, ChildCertValidity, ChildHandle, IssuedCert, ResourceClassName, ResourceSet,
+};
 use crate::commons::error::Error;
 use crate::commons::remote::id::IdCert;
 use crate::commons::KrillResult;
-use crate::constants::CHILD_CERTIFICATE_REISSUE_WEEKS;
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[allow(clippy::large_enum_variant)]
@@ -32,14 +33,32 @@ pub struct ChildDetails {
     id_cert: Option<IdCert>,
     resources: ResourceSet,
     used_keys: HashMap<KeyIdentifier, LastResponse>,
+
+    // the server default is used if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    validity: Option<ChildCertValidity>,
+
+    // keys with certificates issued under a previous validity
+    #[serde(default)]
+    reissue_keys: HashSet<KeyIdentifier>,
+
+    #[serde(default)]
+    suspended: bool,
 }
 
 impl ChildDetails {
-    pub fn new(id_cert: Option<IdCert>, resources: ResourceSet) -> Self {
+    pub fn new(
+        id_cert: Option<IdCert>,
+        resources: ResourceSet,
+        validity: Option<ChildCertValidity>,
+    ) -> Self {
         ChildDetails {
             id_cert,
             resources,
             used_keys: HashMap::new(),
+            validity,
+            reissue_keys: HashSet::new(),
+            suspended: false,
         }
     }
 
@@ -59,6 +78,51 @@ impl ChildDetails {
         self.resources = resources;
     }
 
+    /// Returns the validity for certificates issued to this child, which is
+    /// the given default of the parent CA unless a validity was set for this
+    /// child.
+    pub fn validity(&self, default: ChildCertValidity) -> ChildCertValidity {
+        self.validity.unwrap_or(default)
+    }
+
+    /// Returns whether a validity was set for this child.
+    pub fn has_own_validity(&self) -> bool {
+        self.validity.is_some()
+    }
+
+    /// Sets the validity for certificates issued to this child. Certificates
+    /// which are currently issued will be re-issued on the next request.
+    pub fn set_validity(&mut self, validity: ChildCertValidity) {
+        self.validity = Some(validity);
+        self.reissue_current();
+    }
+
+    /// Marks the currently issued certificates to be re-issued on the next
+    /// request, because they were issued under a previous validity.
+    pub fn reissue_current(&mut self) {
+        for (ki, last_response) in self.used_keys.iter() {
+            if let LastResponse::Current(_) = last_response {
+                self.reissue_keys.insert(*ki);
+            }
+        }
+    }
+
+    /// Returns whether the certificate for this key was issued under a
+    /// previous validity, and should be re-issued.
+    pub fn should_reissue(&self, ki: &KeyIdentifier) -> bool {
+        self.reissue_keys.contains(ki)
+    }
+
+    /// Returns whether this child is suspended. Suspended children are not
+    /// entitled to any certificates until they are resumed.
+    pub fn is_suspended(&self) -> bool {
+        self.suspended
+    }
+
+    pub fn set_suspended(&mut self, suspended: bool) {
+        self.suspended = suspended;
+    }
+
     pub fn issued(&self, rcn: &ResourceClassName) -> Vec<KeyIdentifier> {
         let mut res = vec![];
 
@@ -83,10 +147,19 @@ impl ChildDetails {
 
     pub fn add_issue_response(&mut self, rcn: ResourceClassName, ki: KeyIdentifier) {
         self.used_keys.insert(ki, LastResponse::Current(rcn));
+        self.reissue_keys.remove(&ki);
     }
 
+    /// Marks the key as revoked, so that it cannot be used again. Keys
+    /// revoked while the child is suspended are forgotten instead, so that
+    /// the child may use them again when it is resumed.
     pub fn add_revoke_response(&mut self, ki: KeyIdentifier) {
-        self.used_keys.insert(ki, LastResponse::Revoked);
+        if self.suspended {
+            self.used_keys.remove(&ki);
+        } else {
+            self.used_keys.insert(ki, LastResponse::Revoked);
+        }
+        self.reissue_keys.remove(&ki);
     }
 
     /// Returns an error in case the key is already in use in another class.
@@ -106,11 +179,17 @@ impl ChildDetails {
         }
         Ok(())
     }
-}
 
-impl Into<ChildCaInfo> for ChildDetails {
-    fn into(self) -> ChildCaInfo {
-        ChildCaInfo::new(self.id_cert.as_ref(), self.resources)
+    /// Returns the info for this child, using the given default validity of
+    /// the parent CA if no validity was set for this child.
+    pub fn into_info(self, default_validity: ChildCertValidity) -> ChildCaInfo {
+        let validity = self.validity(default_validity);
+        ChildCaInfo::new(
+            self.id_cert.as_ref(),
+            self.resources,
+            validity,
+            self.suspended,
+        )
     }
 }
 
@@ -148,13 +227,33 @@ impl ChildCertificates {
         self.inner.values()
     }
 
-    pub fn expiring(&self) -> Vec<&IssuedCert> {
+    /// Returns the certificates which are about to expire. This is a safety
+    /// net for children which do not ask for a new certificate in time, so
+    /// it uses the re-issue time of the child owning the certificate, or
+    /// half of the certificate's validity time if that is shorter. The
+    /// standard re-issue time is used for keys not found in the given child
+    /// validities.
+    pub fn expiring(
+        &self,
+        child_validities: &HashMap<KeyIdentifier, ChildCertValidity>,
+    ) -> Vec<&IssuedCert> {
         self.inner
-            .values()
-            .filter(|issued| {
-                issued.validity().not_after()
-                    < Time::now() + Duration::weeks(CHILD_CERTIFICATE_REISSUE_WEEKS)
+            .iter()
+            .filter(|(ki, issued)| {
+                let reissue_weeks = child_validities
+                    .get(ki)
+                    .cloned()
+                    .unwrap_or_default()
+                    .reissue_weeks();
+
+                let validity = issued.validity();
+                let half_life = Duration::seconds(
+                    (validity.not_after().timestamp() - validity.not_before().timestamp()) / 2,
+                );
+                let threshold = std::cmp::min(Duration::weeks(reissue_weeks), half_life);
+                validity.not_after() < Time::now() + threshold
             })
+            .map(|(_, issued)| issued)
             .collect()
     }
 
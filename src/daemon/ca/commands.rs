@@ -4,11 +4,13 @@ use std::sync::{Arc, RwLock};
 use chrono::Duration;
 
 use rpki::uri;
+use rpki::x509::Time;
 
 use crate::commons::api::{
-    ChildHandle, Entitlements, Handle, IssuanceRequest, ParentCaContact, ParentHandle, RcvdCert,
-    RepositoryContact, ResourceClassName, ResourceSet, RevocationRequest, RevocationResponse,
-    UpdateChildRequest,
+    ChildCertValidity, ChildHandle, ChildInvitation, ConfiguredRoa, Entitlements, Handle,
+    IssuanceRequest, ParentCaContact, ParentHandle, RcvdCert, RepositoryContact, ResourceClassName,
+    ResourceSet, RevocationRequest, RevocationResponse, RoaDormantPolicy, ScheduledRoa, Token,
+    TokenHash, UpdateChildRequest,
 };
 use crate::commons::eventsourcing;
 use crate::commons::remote::id::IdCert;
@@ -33,27 +35,47 @@ pub enum CmdDet<S: Signer> {
     // ------------------------------------------------------------
 
     // Add a new child under this parent CA
-    ChildAdd(ChildHandle, Option<IdCert>, ResourceSet),
+    ChildAdd(
+        ChildHandle,
+        Option<IdCert>,
+        ResourceSet,
+        Option<ChildCertValidity>,
+    ),
     // Update some details for an existing child, e.g. resources.
-    ChildUpdate(ChildHandle, UpdateChildRequest),
+    ChildUpdate(ChildHandle, UpdateChildRequest, Arc<RwLock<S>>),
     // Process an issuance request by an existing child.
     ChildCertify(ChildHandle, IssuanceRequest, Arc<RwLock<S>>),
     // Process a revoke request by an existing child.
     ChildRevokeKey(ChildHandle, RevocationRequest, Arc<RwLock<S>>),
     // Remove child (also revokes, and removes issued certs, and republishes)
     ChildRemove(ChildHandle, Arc<RwLock<S>>),
+    // Set the validity for children which do not have their own validity,
+    // following the server configuration.
+    ChildCertValidityDefaultUpdate(ChildCertValidity),
+    // Invite a child to add itself, with the resources in the invitation.
+    ChildInvitationAdd(ChildInvitation),
+    // Change the expiry time of an invitation.
+    ChildInvitationUpdate(TokenHash, Time),
+    // Revoke an invitation, so that it can no longer be used.
+    ChildInvitationRevoke(TokenHash),
+    // Add a child using an invitation, which is then used up. The invitation
+    // is found by the hash of the token used, so that the token itself is
+    // never stored.
+    ChildInvitationAccept(TokenHash, ChildHandle, IdCert, Option<ChildCertValidity>),
 
     // ------------------------------------------------------------
     // Being a child (only allowed if this CA is not self-signed)
     // ------------------------------------------------------------
 
-    // Update our own ID key and cert. Note that this will break
-    // communications with RFC6492 parents. This command is added,
-    // because we need it for testing that we can update this ID
-    // for parents, and children. In practice however, one may not
-    // want to use this until RFC8183 is extended with some words
-    // on how to re-do the ID exchange.
+    // Start a roll of our own ID key and cert. The new ID cert is
+    // included in parent responses for children, and in our child and
+    // publisher requests, so that they can update, but the current key
+    // is still used until the roll is activated. The CaServer only
+    // activates the roll once RFC6492 parents and RFC8181 repositories
+    // accept the new ID.
     GenerateNewIdKey(Arc<RwLock<S>>),
+    // Activate the new ID key and cert.
+    ActivateNewIdKey,
 
     // Add a parent to this CA. Can have multiple parents.
     AddParent(ParentHandle, ParentCaContact),
@@ -61,6 +83,18 @@ pub enum CmdDet<S: Signer> {
     UpdateParentContact(ParentHandle, ParentCaContact),
     // Remove a parent, freeing up its handle for future (re-)use.
     RemoveParent(ParentHandle),
+    // Stop accepting responses signed with the previous ID key of a parent,
+    // once it has been seen to use its new key.
+    ParentPreviousIdRemove(ParentHandle),
+    // Start moving from a parent to a new parent. The new parent is added
+    // straight away, the old parent is kept until the migration completes.
+    ParentMigrationStart(ParentHandle, ParentHandle, ParentCaContact),
+    // Remove the old parent, once all ROAs are issued under the new parent
+    // and published. Holds the version of the CA for which the repository
+    // confirmed that all objects are published.
+    ParentMigrationComplete(u64),
+    // Cancel a migration in progress, by removing the new parent again.
+    ParentMigrationCancel,
 
     // Process new entitlements from a parent and remove/create/update
     // ResourceClasses and certificate requests or key revocation requests
@@ -98,6 +132,30 @@ pub enum CmdDet<S: Signer> {
     // ------------------------------------------------------------
     RouteAuthorizationsUpdate(RouteAuthorizationUpdates, Arc<RwLock<S>>),
 
+    // Update the meta-information (comment, tags, ticket) for existing
+    // authorizations. This does not affect the actual ROAs.
+    RouteAuthorizationsMetaUpdate(Vec<ConfiguredRoa>),
+
+    // Set the time window in which authorizations should be present, and
+    // add or remove the authorizations which are due right away. Later
+    // additions and removals are done by the Scheduler, using
+    // RouteAuthorizationsUpdate commands.
+    RouteAuthorizationsSchedule(Vec<ScheduledRoa>, Arc<RwLock<S>>),
+
+    // Enable or disable AS0 ROAs for all space which is neither covered by
+    // an authorization, nor delegated to a child.
+    RouteAuthorizationsAs0Update(bool, Arc<RwLock<S>>),
+
+    // Re-derive the ROAs for the current authorizations and, if enabled, the
+    // AS0 ROAs for unused space. Used when child resources or entitlements
+    // changed.
+    RouteAuthorizationsRefresh(Arc<RwLock<S>>),
+
+    // Set whether authorizations no longer covered by the resources of the
+    // CA are kept dormant, or removed. Removes any currently dormant
+    // authorizations when set to remove.
+    RouteAuthorizationsDormantPolicyUpdate(RoaDormantPolicy),
+
     // ------------------------------------------------------------
     // Publishing
     // ------------------------------------------------------------
@@ -110,6 +168,10 @@ pub enum CmdDet<S: Signer> {
 
     // Clean up the old pending to withdraw repo.
     RepoRemoveOld(Arc<RwLock<S>>),
+
+    // Stop accepting responses signed with the previous ID key of the
+    // repository, once it has been seen to use its new key.
+    RepoPreviousIdRemove,
 }
 
 impl<S: Signer> fmt::Display for CmdDet<S> {
@@ -123,17 +185,20 @@ impl<S: Signer> fmt::Display for CmdDet<S> {
             // ------------------------------------------------------------
             // Being a parent
             // ------------------------------------------------------------
-            CmdDet::ChildAdd(child, id_cert_opt, res) => write!(
+            CmdDet::ChildAdd(child, id_cert_opt, res, validity) => write!(
                 f,
-                "Add child '{}' with RFC8183 key '{}' and resources '{}'",
+                "Add child '{}' with RFC8183 key '{}', resources '{}' and validity '{}'",
                 child,
                 id_cert_opt
                     .as_ref()
                     .map(|c| c.ski_hex())
                     .unwrap_or_else(|| "<none>".to_string()),
-                res
+                res,
+                validity
+                    .map(|v| v.to_string())
+                    .unwrap_or_else(|| "server default".to_string())
             ),
-            CmdDet::ChildUpdate(child, update_req) => {
+            CmdDet::ChildUpdate(child, update_req, _) => {
                 write!(f, "Update child '{}' with {}", child, update_req)
             }
             CmdDet::ChildCertify(child, req, _) => {
@@ -145,11 +210,39 @@ impl<S: Signer> fmt::Display for CmdDet<S> {
             CmdDet::ChildRemove(child, _) => {
                 write!(f, "Remove child '{}' and revoke&remove its certs", child)
             }
+            CmdDet::ChildCertValidityDefaultUpdate(validity) => write!(
+                f,
+                "Update default child certificate validity to '{}'",
+                validity
+            ),
+            CmdDet::ChildInvitationAdd(invitation) => {
+                write!(f, "Add child invitation with {}", invitation)
+            }
+            CmdDet::ChildInvitationUpdate(id, expires) => write!(
+                f,
+                "Update child invitation '{}' to expire at {}",
+                id,
+                expires.to_rfc3339()
+            ),
+            CmdDet::ChildInvitationRevoke(id) => {
+                write!(f, "Revoke child invitation '{}'", id)
+            }
+            CmdDet::ChildInvitationAccept(id, child, id_cert, validity) => write!(
+                f,
+                "Add child '{}' with RFC8183 key '{}' and validity '{}' using invitation '{}'",
+                child,
+                id_cert.ski_hex(),
+                validity
+                    .map(|v| v.to_string())
+                    .unwrap_or_else(|| "server default".to_string()),
+                id
+            ),
 
             // ------------------------------------------------------------
             // Being a child (only allowed if this CA is not self-signed)
             // ------------------------------------------------------------
             CmdDet::GenerateNewIdKey(_) => write!(f, "Generate a new RFC8183 ID."),
+            CmdDet::ActivateNewIdKey => write!(f, "Activate the new RFC8183 ID."),
             CmdDet::AddParent(parent, contact) => {
                 write!(f, "Add parent '{}' as '{}'", parent, contact)
             }
@@ -157,6 +250,20 @@ impl<S: Signer> fmt::Display for CmdDet<S> {
                 write!(f, "Update contact for parent '{}' to '{}'", parent, contact)
             }
             CmdDet::RemoveParent(parent) => write!(f, "Remove parent '{}'", parent),
+            CmdDet::ParentPreviousIdRemove(parent) => {
+                write!(f, "Remove previous RFC8183 ID of parent '{}'", parent)
+            }
+            CmdDet::ParentMigrationStart(from, to, contact) => write!(
+                f,
+                "Start migration from parent '{}' to parent '{}' as '{}'",
+                from, to, contact
+            ),
+            CmdDet::ParentMigrationComplete(published) => write!(
+                f,
+                "Complete parent migration, published version '{}'",
+                published
+            ),
+            CmdDet::ParentMigrationCancel => write!(f, "Cancel parent migration"),
 
             CmdDet::UpdateResourceClasses(parent, entitlements, _) => write!(
                 f,
@@ -187,6 +294,31 @@ impl<S: Signer> fmt::Display for CmdDet<S> {
             // ROA Support
             // ------------------------------------------------------------
             CmdDet::RouteAuthorizationsUpdate(updates, _) => write!(f, "Update ROAs '{}'", updates),
+            CmdDet::RouteAuthorizationsMetaUpdate(roas) => {
+                write!(f, "Update meta-information for ROAs:")?;
+                for roa in roas {
+                    write!(f, " '{}'", roa)?;
+                }
+                Ok(())
+            }
+            CmdDet::RouteAuthorizationsSchedule(roas, _) => {
+                write!(f, "Schedule ROAs:")?;
+                for roa in roas {
+                    write!(f, " '{}'", roa)?;
+                }
+                Ok(())
+            }
+            CmdDet::RouteAuthorizationsAs0Update(enabled, _) => {
+                write!(f, "Set AS0 ROAs for unused space to '{}'", enabled)
+            }
+            CmdDet::RouteAuthorizationsRefresh(_) => {
+                write!(f, "Refresh ROAs for current resources")
+            }
+            CmdDet::RouteAuthorizationsDormantPolicyUpdate(policy) => write!(
+                f,
+                "Set policy for ROAs outside of held resources to '{}'",
+                policy
+            ),
 
             // ------------------------------------------------------------
             // Publishing
@@ -199,6 +331,7 @@ impl<S: Signer> fmt::Display for CmdDet<S> {
                 }
             },
             CmdDet::RepoRemoveOld(_) => write!(f, "Clean up old repository (if present)."),
+            CmdDet::RepoPreviousIdRemove => write!(f, "Remove previous RFC8183 ID of repository"),
         }
     }
 }
@@ -224,11 +357,12 @@ impl<S: Signer> CmdDet<S> {
         child_handle: Handle,
         child_id_cert: Option<IdCert>,
         child_resources: ResourceSet,
+        child_validity: Option<ChildCertValidity>,
     ) -> Cmd<S> {
         eventsourcing::SentCommand::new(
             handle,
             None,
-            CmdDet::ChildAdd(child_handle, child_id_cert, child_resources),
+            CmdDet::ChildAdd(child_handle, child_id_cert, child_resources, child_validity),
         )
     }
 
@@ -236,8 +370,13 @@ impl<S: Signer> CmdDet<S> {
         handle: &Handle,
         child_handle: ChildHandle,
         req: UpdateChildRequest,
+        signer: Arc<RwLock<S>>,
     ) -> Cmd<S> {
-        eventsourcing::SentCommand::new(handle, None, CmdDet::ChildUpdate(child_handle, req))
+        eventsourcing::SentCommand::new(
+            handle,
+            None,
+            CmdDet::ChildUpdate(child_handle, req, signer),
+        )
     }
 
     /// Certify a child. Will return an error in case the child is
@@ -277,10 +416,58 @@ impl<S: Signer> CmdDet<S> {
         eventsourcing::SentCommand::new(handle, None, CmdDet::ChildRemove(child_handle, signer))
     }
 
+    pub fn child_cert_validity_default_update(
+        handle: &Handle,
+        validity: ChildCertValidity,
+    ) -> Cmd<S> {
+        eventsourcing::SentCommand::new(
+            handle,
+            None,
+            CmdDet::ChildCertValidityDefaultUpdate(validity),
+        )
+    }
+
+    pub fn child_invitation_add(handle: &Handle, invitation: ChildInvitation) -> Cmd<S> {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::ChildInvitationAdd(invitation))
+    }
+
+    pub fn child_invitation_update(handle: &Handle, id: TokenHash, expires: Time) -> Cmd<S> {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::ChildInvitationUpdate(id, expires))
+    }
+
+    pub fn child_invitation_revoke(handle: &Handle, id: TokenHash) -> Cmd<S> {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::ChildInvitationRevoke(id))
+    }
+
+    /// Adds a child using an invitation. Will return an error in case the
+    /// invitation is unknown or expired.
+    pub fn child_invitation_accept(
+        handle: &Handle,
+        token: &Token,
+        child_handle: ChildHandle,
+        child_id_cert: IdCert,
+        child_validity: Option<ChildCertValidity>,
+    ) -> Cmd<S> {
+        eventsourcing::SentCommand::new(
+            handle,
+            None,
+            CmdDet::ChildInvitationAccept(
+                TokenHash::from(token),
+                child_handle,
+                child_id_cert,
+                child_validity,
+            ),
+        )
+    }
+
     pub fn update_id(handle: &Handle, signer: Arc<RwLock<S>>) -> Cmd<S> {
         eventsourcing::SentCommand::new(handle, None, CmdDet::GenerateNewIdKey(signer))
     }
 
+    pub fn activate_id(handle: &Handle) -> Cmd<S> {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::ActivateNewIdKey)
+    }
+
     pub fn add_parent(handle: &Handle, parent: ParentHandle, info: ParentCaContact) -> Cmd<S> {
         eventsourcing::SentCommand::new(handle, None, CmdDet::AddParent(parent, info))
     }
@@ -293,6 +480,33 @@ impl<S: Signer> CmdDet<S> {
         eventsourcing::SentCommand::new(handle, None, CmdDet::RemoveParent(parent))
     }
 
+    pub fn remove_parent_previous_id(handle: &Handle, parent: ParentHandle) -> Cmd<S> {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::ParentPreviousIdRemove(parent))
+    }
+
+    pub fn parent_migration_start(
+        handle: &Handle,
+        from: ParentHandle,
+        to: ParentHandle,
+        info: ParentCaContact,
+    ) -> Cmd<S> {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::ParentMigrationStart(from, to, info))
+    }
+
+    /// Completes a parent migration, but only if the CA is still at the
+    /// given version, and all its objects for that version were published.
+    pub fn parent_migration_complete(handle: &Handle, published: u64) -> Cmd<S> {
+        eventsourcing::SentCommand::new(
+            handle,
+            Some(published),
+            CmdDet::ParentMigrationComplete(published),
+        )
+    }
+
+    pub fn parent_migration_cancel(handle: &Handle) -> Cmd<S> {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::ParentMigrationCancel)
+    }
+
     pub fn upd_resource_classes(
         handle: &Handle,
         parent: ParentHandle,
@@ -355,6 +569,10 @@ impl<S: Signer> CmdDet<S> {
         eventsourcing::SentCommand::new(handle, None, CmdDet::RepoRemoveOld(signer))
     }
 
+    pub fn remove_repo_previous_id(handle: &Handle) -> Cmd<S> {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::RepoPreviousIdRemove)
+    }
+
     //-------------------------------------------------------------------------------
     // Route Authorizations
     //-------------------------------------------------------------------------------
@@ -369,4 +587,47 @@ impl<S: Signer> CmdDet<S> {
             CmdDet::RouteAuthorizationsUpdate(updates, signer),
         )
     }
+
+    pub fn route_authorizations_meta_update(handle: &Handle, roas: Vec<ConfiguredRoa>) -> Cmd<S> {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::RouteAuthorizationsMetaUpdate(roas))
+    }
+
+    pub fn route_authorizations_schedule(
+        handle: &Handle,
+        roas: Vec<ScheduledRoa>,
+        signer: Arc<RwLock<S>>,
+    ) -> Cmd<S> {
+        eventsourcing::SentCommand::new(
+            handle,
+            None,
+            CmdDet::RouteAuthorizationsSchedule(roas, signer),
+        )
+    }
+
+    pub fn route_authorizations_as0_update(
+        handle: &Handle,
+        enabled: bool,
+        signer: Arc<RwLock<S>>,
+    ) -> Cmd<S> {
+        eventsourcing::SentCommand::new(
+            handle,
+            None,
+            CmdDet::RouteAuthorizationsAs0Update(enabled, signer),
+        )
+    }
+
+    pub fn route_authorizations_refresh(handle: &Handle, signer: Arc<RwLock<S>>) -> Cmd<S> {
+        eventsourcing::SentCommand::new(handle, None, CmdDet::RouteAuthorizationsRefresh(signer))
+    }
+
+    pub fn route_authorizations_dormant_policy_update(
+        handle: &Handle,
+        policy: RoaDormantPolicy,
+    ) -> Cmd<S> {
+        eventsourcing::SentCommand::new(
+            handle,
+            None,
+            CmdDet::RouteAuthorizationsDormantPolicyUpdate(policy),
+        )
+    }
 }
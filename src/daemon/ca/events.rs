@@ -4,20 +4,22 @@ use std::ops::DerefMut;
 use std::sync::{Arc, RwLock};
 
 use rpki::crypto::KeyIdentifier;
+use rpki::x509::Time;
 
 use crate::commons::api::{
-    AddedObject, ChildHandle, Handle, IssuanceRequest, IssuedCert, ObjectName, ObjectsDelta,
-    ParentCaContact, ParentHandle, RcvdCert, RepoInfo, RepositoryContact, ResourceClassName,
-    ResourceSet, Revocation, RevocationRequest, RevokedObject, TaCertDetails, UpdatedObject,
-    WithdrawnObject,
+    AddedObject, ChildCertValidity, ChildHandle, ChildInvitation, Handle, IssuanceRequest,
+    IssuedCert, ObjectName, ObjectsDelta, ParentCaContact, ParentHandle, RcvdCert, RepoInfo,
+    RepositoryContact, ResourceClassName, ResourceSet, Revocation, RevocationRequest,
+    RevokedObject, RoaDefinitionMeta, RoaDormantPolicy, RoaSchedule, TaCertDetails, TokenHash,
+    UpdatedObject, WithdrawnObject,
 };
 use crate::commons::eventsourcing::StoredEvent;
 use crate::commons::remote::id::IdCert;
 use crate::commons::KrillResult;
 use crate::daemon::ca::signing::Signer;
 use crate::daemon::ca::{
-    CertifiedKey, ChildDetails, CurrentObjectSetDelta, ResourceClass, Rfc8183Id, RoaInfo,
-    RouteAuthorization,
+    CertifiedKey, ChildDetails, CurrentObjectSetDelta, ParentMigration, ResourceClass, Rfc8183Id,
+    RoaInfo, RouteAuthorization,
 };
 
 //------------ Ini -----------------------------------------------------------
@@ -223,13 +225,25 @@ pub enum EvtDet {
     ChildCertificatesUpdated(ResourceClassName, ChildCertificateUpdates),
     ChildUpdatedIdCert(ChildHandle, IdCert),
     ChildUpdatedResources(ChildHandle, ResourceSet),
+    ChildUpdatedValidity(ChildHandle, ChildCertValidity),
+    ChildCertValidityDefaultUpdated(ChildCertValidity),
+    ChildSuspended(ChildHandle),
+    ChildResumed(ChildHandle),
     ChildRemoved(ChildHandle),
+    ChildInvitationAdded(ChildInvitation),
+    ChildInvitationUpdated(TokenHash, Time),
+    ChildInvitationRemoved(TokenHash),
 
     // Being a child Events
-    IdUpdated(Rfc8183Id),
+    IdUpdated(Rfc8183Id), // no longer used, ID keys are rolled instead
+    IdRollStarted(Rfc8183Id),
+    IdRollActivated,
     ParentAdded(ParentHandle, ParentCaContact),
     ParentUpdated(ParentHandle, ParentCaContact),
     ParentRemoved(ParentHandle, Vec<ObjectsDelta>),
+    ParentPreviousIdRemoved(ParentHandle),
+    ParentMigrationStarted(ParentMigration),
+    ParentMigrationCompleted(Time),
 
     ResourceClassAdded(ResourceClassName, ResourceClass),
     ResourceClassRemoved(
@@ -252,6 +266,11 @@ pub enum EvtDet {
     // Route Authorizations
     RouteAuthorizationAdded(RouteAuthorization),
     RouteAuthorizationRemoved(RouteAuthorization),
+    RouteAuthorizationMetaUpdated(RouteAuthorization, RoaDefinitionMeta),
+    RouteAuthorizationScheduled(RouteAuthorization, RoaSchedule),
+    RouteAuthorizationsAs0Updated(bool),
+    RouteAuthorizationsDormantPolicyUpdated(RoaDormantPolicy),
+    RouteAuthorizationsUnpublishable(Vec<RouteAuthorization>),
     RoasUpdated(ResourceClassName, RoaUpdates),
 
     // Publishing
@@ -260,13 +279,20 @@ pub enum EvtDet {
         HashMap<KeyIdentifier, CurrentObjectSetDelta>,
     ),
     RepoUpdated(RepositoryContact),
+    RepoIdUpdated(RepositoryContact),
+    RepoPreviousIdRemoved,
     RepoCleaned(RepositoryContact),
 }
 
 impl EvtDet {
-    /// This marks the RFC8183Id as updated
-    pub(super) fn id_updated(handle: &Handle, version: u64, id: Rfc8183Id) -> Evt {
-        StoredEvent::new(handle, version, EvtDet::IdUpdated(id))
+    /// This marks the start of an RFC8183Id roll to a new id
+    pub(super) fn id_roll_started(handle: &Handle, version: u64, id: Rfc8183Id) -> Evt {
+        StoredEvent::new(handle, version, EvtDet::IdRollStarted(id))
+    }
+
+    /// This marks the new RFC8183Id as the current id
+    pub(super) fn id_roll_activated(handle: &Handle, version: u64) -> Evt {
+        StoredEvent::new(handle, version, EvtDet::IdRollActivated)
     }
 
     /// This marks a parent as added to the CA.
@@ -303,6 +329,33 @@ impl EvtDet {
         )
     }
 
+    /// This marks the previous ID cert of a parent as no longer accepted
+    pub(super) fn parent_previous_id_removed(
+        handle: &Handle,
+        version: u64,
+        parent_handle: ParentHandle,
+    ) -> Evt {
+        StoredEvent::new(
+            handle,
+            version,
+            EvtDet::ParentPreviousIdRemoved(parent_handle),
+        )
+    }
+
+    /// This marks the start of moving the CA to a new parent
+    pub(super) fn parent_migration_started(
+        handle: &Handle,
+        version: u64,
+        migration: ParentMigration,
+    ) -> Evt {
+        StoredEvent::new(handle, version, EvtDet::ParentMigrationStarted(migration))
+    }
+
+    /// This marks the move to a new parent as completed
+    pub(super) fn parent_migration_completed(handle: &Handle, version: u64, time: Time) -> Evt {
+        StoredEvent::new(handle, version, EvtDet::ParentMigrationCompleted(time))
+    }
+
     /// This marks a resource class as added under a parent for the CA.
     pub(super) fn resource_class_added(
         handle: &Handle,
@@ -364,6 +417,31 @@ impl EvtDet {
         )
     }
 
+    pub(super) fn child_updated_validity(
+        handle: &Handle,
+        version: u64,
+        child: ChildHandle,
+        validity: ChildCertValidity,
+    ) -> Evt {
+        StoredEvent::new(
+            handle,
+            version,
+            EvtDet::ChildUpdatedValidity(child, validity),
+        )
+    }
+
+    pub(super) fn child_cert_validity_default_updated(
+        handle: &Handle,
+        version: u64,
+        validity: ChildCertValidity,
+    ) -> Evt {
+        StoredEvent::new(
+            handle,
+            version,
+            EvtDet::ChildCertValidityDefaultUpdated(validity),
+        )
+    }
+
     pub(super) fn child_certificate_issued(
         handle: &Handle,
         version: u64,
@@ -401,10 +479,39 @@ impl EvtDet {
         )
     }
 
+    pub(super) fn child_suspended(handle: &Handle, version: u64, child: ChildHandle) -> Evt {
+        StoredEvent::new(handle, version, EvtDet::ChildSuspended(child))
+    }
+
+    pub(super) fn child_resumed(handle: &Handle, version: u64, child: ChildHandle) -> Evt {
+        StoredEvent::new(handle, version, EvtDet::ChildResumed(child))
+    }
+
     pub(super) fn child_removed(handle: &Handle, version: u64, child: ChildHandle) -> Evt {
         StoredEvent::new(handle, version, EvtDet::ChildRemoved(child))
     }
 
+    pub(super) fn child_invitation_added(
+        handle: &Handle,
+        version: u64,
+        invitation: ChildInvitation,
+    ) -> Evt {
+        StoredEvent::new(handle, version, EvtDet::ChildInvitationAdded(invitation))
+    }
+
+    pub(super) fn child_invitation_updated(
+        handle: &Handle,
+        version: u64,
+        id: TokenHash,
+        expires: Time,
+    ) -> Evt {
+        StoredEvent::new(handle, version, EvtDet::ChildInvitationUpdated(id, expires))
+    }
+
+    pub(super) fn child_invitation_removed(handle: &Handle, version: u64, id: TokenHash) -> Evt {
+        StoredEvent::new(handle, version, EvtDet::ChildInvitationRemoved(id))
+    }
+
     pub(super) fn current_set_updated(
         handle: &Handle,
         version: u64,
@@ -481,14 +588,42 @@ impl fmt::Display for EvtDet {
             EvtDet::ChildUpdatedResources(child, resources) => {
                 write!(f, "updated child '{}' resources to '{}'", child, resources)
             }
+            EvtDet::ChildUpdatedValidity(child, validity) => write!(
+                f,
+                "updated child '{}' certificate validity to '{}'",
+                child, validity
+            ),
+            EvtDet::ChildCertValidityDefaultUpdated(validity) => write!(
+                f,
+                "updated default child certificate validity to '{}'",
+                validity
+            ),
+            EvtDet::ChildSuspended(child) => write!(f, "suspended child '{}'", child),
+            EvtDet::ChildResumed(child) => write!(f, "resumed child '{}'", child),
             EvtDet::ChildRemoved(child) => {
                 write!(f, "removed child '{}'", child)
             }
+            EvtDet::ChildInvitationAdded(invitation) => {
+                write!(f, "added child invitation with {}", invitation)
+            }
+            EvtDet::ChildInvitationUpdated(id, expires) => write!(
+                f,
+                "child invitation '{}' now expires at {}",
+                id,
+                expires.to_rfc3339()
+            ),
+            EvtDet::ChildInvitationRemoved(id) => {
+                write!(f, "removed child invitation '{}'", id)
+            }
 
             // Being a child Events
             EvtDet::IdUpdated(id) => {
                 write!(f, "updated RFC8183 id to key '{}'", id.key_hash())
             }
+            EvtDet::IdRollStarted(id) => {
+                write!(f, "started RFC8183 id roll to key '{}'", id.key_hash())
+            }
+            EvtDet::IdRollActivated => write!(f, "activated new RFC8183 id"),
             EvtDet::ParentAdded(parent, contact) => {
                 let contact_str = match contact {
                     ParentCaContact::Embedded => "embedded",
@@ -508,6 +643,16 @@ impl fmt::Display for EvtDet {
             EvtDet::ParentRemoved(parent, _deltas) => {
                 write!(f, "removed parent '{}'", parent)
             }
+            EvtDet::ParentPreviousIdRemoved(parent) => {
+                write!(f, "removed previous RFC8183 id of parent '{}'", parent)
+            }
+            EvtDet::ParentMigrationStarted(migration) => write!(
+                f,
+                "started migration from parent '{}' to parent '{}'",
+                migration.from(),
+                migration.to()
+            ),
+            EvtDet::ParentMigrationCompleted(_time) => write!(f, "completed parent migration"),
 
             EvtDet::ResourceClassAdded(rcn, _) => {
                 write!(f, "added resource class with name '{}'", rcn)
@@ -573,6 +718,37 @@ impl fmt::Display for EvtDet {
                 "removed ROA: '{}'",
                 route
             ),
+            EvtDet::RouteAuthorizationMetaUpdated(route, meta) => write!(
+                f,
+                "updated meta-information for ROA: '{}' to: '{}'",
+                route, meta
+            ),
+            EvtDet::RouteAuthorizationScheduled(route, schedule) => {
+                if schedule.is_empty() {
+                    write!(f, "removed schedule for ROA: '{}'", route)
+                } else {
+                    write!(f, "scheduled ROA: '{}' {}", route, schedule)
+                }
+            }
+            EvtDet::RouteAuthorizationsAs0Updated(enabled) => {
+                if *enabled {
+                    write!(f, "enabled AS0 ROAs for unused space")
+                } else {
+                    write!(f, "disabled AS0 ROAs for unused space")
+                }
+            }
+            EvtDet::RouteAuthorizationsDormantPolicyUpdated(policy) => write!(
+                f,
+                "set policy for ROAs outside of held resources to: '{}'",
+                policy
+            ),
+            EvtDet::RouteAuthorizationsUnpublishable(routes) => {
+                write!(f, "ROAs no longer covered by held resources:")?;
+                for route in routes {
+                    write!(f, " '{}'", route)?;
+                }
+                Ok(())
+            }
             EvtDet::RoasUpdated(rcn, roa_updates) => {
                 write!(f, "updated ROAs under resource class '{}'", rcn)?;
                 if ! roa_updates.updated.is_empty() {
@@ -620,6 +796,13 @@ impl fmt::Display for EvtDet {
                     RepositoryContact::Rfc8181(res) => write!(f, "updated repository to remote server: {}", res.service_uri())
                 }
             }
+            EvtDet::RepoIdUpdated(updated) => {
+                match updated {
+                    RepositoryContact::Embedded(_) => write!(f, "updated id of embedded repository"),
+                    RepositoryContact::Rfc8181(res) => write!(f, "updated id of repository at remote server: {}", res.service_uri())
+                }
+            }
+            EvtDet::RepoPreviousIdRemoved => write!(f, "removed previous RFC8183 id of repository"),
             EvtDet::RepoCleaned(old) => {
                 match old {
                     RepositoryContact::Embedded(_) => write!(f, "cleaned old embedded repository"),
@@ -0,0 +1,67 @@
+//! Moving a CA from one parent to another, without breaking its ROAs.
+
+use rpki::x509::Time;
+
+use crate::commons::api::{
+    ParentHandle, ParentMigrationPhase, ParentMigrationStatus, RoaDefinition,
+};
+
+//------------ ParentMigration -----------------------------------------------
+
+/// Keeps track of moving a CA from one parent to another.
+///
+/// The new parent is added when the migration starts. The CA will then get
+/// resource classes under the new parent, and issue ROAs under them for the
+/// same authorizations as under the old parent. Only when all of these ROAs
+/// are published is the old parent removed.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ParentMigration {
+    from: ParentHandle,
+    to: ParentHandle,
+    started: Time,
+    completed: Option<Time>,
+}
+
+impl ParentMigration {
+    pub fn new(from: ParentHandle, to: ParentHandle) -> Self {
+        ParentMigration {
+            from,
+            to,
+            started: Time::now(),
+            completed: None,
+        }
+    }
+
+    pub fn from(&self) -> &ParentHandle {
+        &self.from
+    }
+
+    pub fn to(&self) -> &ParentHandle {
+        &self.to
+    }
+
+    pub fn is_completed(&self) -> bool {
+        self.completed.is_some()
+    }
+
+    pub fn complete(&mut self, time: Time) {
+        self.completed = Some(time);
+    }
+
+    pub fn status(
+        &self,
+        phase: ParentMigrationPhase,
+        pending_roas: Vec<RoaDefinition>,
+        uncovered_roas: Vec<RoaDefinition>,
+    ) -> ParentMigrationStatus {
+        ParentMigrationStatus::new(
+            self.from.clone(),
+            self.to.clone(),
+            self.started,
+            self.completed,
+            phase,
+            pending_roas,
+            uncovered_roas,
+        )
+    }
+}
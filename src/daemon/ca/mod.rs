@@ -11,6 +11,12 @@ pub use self::certauth::set_roa_prefix_grouping_strategy;
 mod child;
 pub use self::child::*;
 
+mod activity;
+pub use self::activity::*;
+
+mod migration;
+pub use self::migration::*;
+
 mod rc;
 pub use self::rc::ResourceClass;
 
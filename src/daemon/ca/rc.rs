@@ -6,17 +6,18 @@ use serde::{Deserialize, Serialize};
 
 use rpki::cert::Cert;
 use rpki::crypto::KeyIdentifier;
-use rpki::x509::Time;
+use rpki::x509::{Time, Validity};
 
 use uuid::Uuid;
 
 use crate::commons::api::rrdp::PublishElement;
 use crate::commons::api::Base64;
 use crate::commons::api::{
-    AddedObject, AsNumber, CurrentObject, CurrentObjects, EntitlementClass, HexEncodedHash, IssuanceRequest,
-    IssuedCert, ObjectName, ObjectsDelta, ParentHandle, RcvdCert, ReplacedObject, RepoInfo,
-    RequestResourceLimit, ResourceClassInfo, ResourceClassName, ResourceSet, Revocation,
-    RevocationRequest, RevokedObject, RoaDefinition, TypedPrefix, UpdatedObject, WithdrawnObject,
+    AddedObject, AsNumber, ChildCertValidity, CurrentObject, CurrentObjects, EntitlementClass,
+    HexEncodedHash, IssuanceRequest, IssuedCert, ObjectName, ObjectsDelta, ParentHandle, RcvdCert,
+    ReplacedObject, RepoInfo, RequestResourceLimit, ResourceClassInfo, ResourceClassName,
+    ResourceSet, Revocation, RevocationRequest, RevokedObject, RoaDefinition, TypedPrefix,
+    UpdatedObject, WithdrawnObject,
 };
 use crate::commons::error::Error;
 use crate::commons::KrillResult;
@@ -324,6 +325,7 @@ impl ResourceClass {
                 authorizations.as_slice(),
                 repo_info,
                 &publish_mode,
+                &HashMap::new(),
                 signer,
                 roa_prefix_grouping_strategy,
             )?)
@@ -458,6 +460,7 @@ impl ResourceClass {
         authorizations: &[RouteAuthorization],
         repo_info: &RepoInfo,
         mode: &PublishMode,
+        child_validities: &HashMap<KeyIdentifier, ChildCertValidity>,
         signer: &S,
         roa_prefix_grouping_strategy: &RoaPrefixGroupingStrategy,
     ) -> KrillResult<Vec<EvtDet>> {
@@ -484,7 +487,7 @@ impl ResourceClass {
             res.push(EvtDet::RoasUpdated(self.name.clone(), roa_updates));
         }
 
-        let child_cert_updates = self.update_child_certificates(mode, signer)?;
+        let child_cert_updates = self.update_child_certificates(mode, child_validities, signer)?;
         if !child_cert_updates.is_empty() {
             for issued in child_cert_updates.issued() {
                 match issued.replaces() {
@@ -820,6 +823,7 @@ impl ResourceClass {
             authorizations.as_slice(),
             repo_info,
             &PublishMode::KeyRollActivation,
+            &HashMap::new(),
             signer,
             roa_prefix_grouping_strategy,
         )?);
@@ -863,6 +867,7 @@ impl ResourceClass {
         &self,
         csr: CsrInfo,
         child_resources: &ResourceSet,
+        child_validity: ChildCertValidity,
         limit: RequestResourceLimit,
         signer: &S,
     ) -> KrillResult<IssuedCert> {
@@ -874,8 +879,20 @@ impl ResourceClass {
             .get(&csr.key_id())
             .map(ReplacedObject::from);
 
-        let issued =
-            SignSupport::make_issued_cert(csr, &resources, limit, replaces, signing_key, signer)?;
+        let validity = Validity::new(
+            Time::five_minutes_ago(),
+            Time::now() + Duration::weeks(child_validity.validity_weeks()),
+        );
+
+        let issued = SignSupport::make_issued_cert(
+            csr,
+            &resources,
+            validity,
+            limit,
+            replaces,
+            signing_key,
+            signer,
+        )?;
 
         Ok(issued)
     }
@@ -893,9 +910,20 @@ impl ResourceClass {
         let resource_set = updated_resources.unwrap_or(resource_set);
         let replaced = ReplacedObject::new(Revocation::from(&cert), HexEncodedHash::from(&cert));
 
+        // Keep the validity time used for the previous certificate, as this
+        // follows the validity configured for the child.
+        let previous = cert.validity();
+        let duration =
+            Duration::seconds(previous.not_after().timestamp() - previous.not_before().timestamp());
+        let validity = Validity::new(
+            Time::five_minutes_ago(),
+            Time::five_minutes_ago() + duration,
+        );
+
         let re_issued = SignSupport::make_issued_cert(
             csr,
             &resource_set,
+            validity,
             limit,
             Some(replaced),
             signing_key,
@@ -908,6 +936,7 @@ impl ResourceClass {
     fn update_child_certificates<S: Signer>(
         &self,
         mode: &PublishMode,
+        child_validities: &HashMap<KeyIdentifier, ChildCertValidity>,
         signer: &S,
     ) -> KrillResult<ChildCertificateUpdates> {
         let mut updates = ChildCertificateUpdates::default();
@@ -921,7 +950,7 @@ impl ResourceClass {
             PublishMode::Normal => {
                 // re-issue: things about to expire
                 // revoke: nothing
-                for issued in self.certificates.expiring() {
+                for issued in self.certificates.expiring(child_validities) {
                     let re_issued = self.re_issue(issued, None, signing_key, None, signer)?;
                     updates.issue(re_issued);
                 }
@@ -1110,6 +1139,17 @@ impl ResourceClass {
     pub fn roas_updated(&mut self, updates: RoaUpdates) {
         self.roas.updated(updates);
     }
+
+    /// Returns the authorizations covered by the current ROAs. These are
+    /// taken from the ROAs themselves, because a ROA may cover several
+    /// authorizations, depending on the prefix grouping strategy.
+    pub fn roa_authorizations(&self) -> KrillResult<Vec<RouteAuthorization>> {
+        let mut res = vec![];
+        for info in self.roas.current() {
+            res.append(&mut info.retrieve_route_authorizations()?);
+        }
+        Ok(res)
+    }
 }
 
 fn generate_uuid_roa_name() -> String {
@@ -13,11 +13,12 @@ use rpki::uri;
 use rpki::x509::{Serial, Time};
 
 use crate::commons::api::{
-    CurrentObject, ObjectName, ReplacedObject, RoaDefinition, RoaDefinitionUpdates,
+    ConfiguredRoa, CurrentObject, ObjectName, ReplacedObject, RoaDefinition, RoaDefinitionMeta,
+    RoaDefinitionUpdates, RoaDormantPolicy, RoaSchedule,
 };
 use crate::commons::KrillResult;
 use crate::commons::error::Error;
-use crate::commons::api::{AsNumber, TypedPrefix};
+use crate::commons::api::{AsNumber, ResourceSet, TypedPrefix};
 use crate::daemon::ca::events::RoaUpdates;
 use crate::daemon::ca::{self, CertifiedKey, SignSupport, Signer};
 
@@ -80,14 +81,50 @@ impl From<RoaDefinition> for RouteAuthorization {
 
 //------------ RouteAuthorizationUpdates -----------------------------------
 
-///
+/// The route authorizations to add and remove in a single update, and
+/// optionally the meta-information for added authorizations, so that e.g.
+/// imported comments are stored in the same update.
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct RouteAuthorizationUpdates {
     added: HashSet<RouteAuthorization>,
     removed: HashSet<RouteAuthorization>,
+    #[serde(default)]
+    meta: HashMap<RouteAuthorization, RoaDefinitionMeta>,
 }
 
 impl RouteAuthorizationUpdates {
+    pub fn new(added: HashSet<RouteAuthorization>, removed: HashSet<RouteAuthorization>) -> Self {
+        RouteAuthorizationUpdates {
+            added,
+            removed,
+            meta: HashMap::new(),
+        }
+    }
+
+    /// Adds the meta-information of the given ROAs for the authorizations
+    /// added in this update. Empty meta-information is ignored.
+    pub fn with_meta(mut self, roas: Vec<ConfiguredRoa>) -> Self {
+        for roa in roas {
+            let (definition, meta) = roa.unpack();
+            if !meta.is_empty() {
+                self.meta.insert(RouteAuthorization::from(definition), meta);
+            }
+        }
+        self
+    }
+
+    pub fn added(&self) -> &HashSet<RouteAuthorization> {
+        &self.added
+    }
+
+    pub fn meta(&self) -> &HashMap<RouteAuthorization, RoaDefinitionMeta> {
+        &self.meta
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
     pub fn unpack(self) -> (HashSet<RouteAuthorization>, HashSet<RouteAuthorization>) {
         (self.added, self.removed)
     }
@@ -98,7 +135,7 @@ impl From<RoaDefinitionUpdates> for RouteAuthorizationUpdates {
         let (added, removed) = definitions.unpack();
         let added = added.into_iter().map(RoaDefinition::into).collect();
         let removed = removed.into_iter().map(RoaDefinition::into).collect();
-        RouteAuthorizationUpdates { added, removed }
+        RouteAuthorizationUpdates::new(added, removed)
     }
 }
 
@@ -123,16 +160,31 @@ impl fmt::Display for RouteAuthorizationUpdates {
 
 //------------ Routes ------------------------------------------------------
 
-/// The current authorizations and corresponding meta-information for a CA.
+/// The current authorizations and corresponding meta-information for a CA,
+/// as well as the schedules for time-bounded authorizations, whether AS0
+/// ROAs should be issued for unused space, and what to do with authorizations
+/// no longer covered by the resources of the CA.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Routes {
     map: HashMap<RouteAuthorization, RouteInfo>,
+
+    #[serde(default)]
+    scheduled: HashMap<RouteAuthorization, RoaSchedule>,
+
+    #[serde(default)]
+    as0: bool, // issue AS0 ROAs for unused space
+
+    #[serde(default)]
+    dormant_policy: RoaDormantPolicy,
 }
 
 impl Default for Routes {
     fn default() -> Self {
         Routes {
             map: HashMap::new(),
+            scheduled: HashMap::new(),
+            as0: false,
+            dormant_policy: RoaDormantPolicy::default(),
         }
     }
 }
@@ -150,15 +202,108 @@ impl Routes {
         self.map.contains_key(auth)
     }
 
+    /// Returns all authorizations with their meta-information and schedule,
+    /// including scheduled authorizations which are pending or expired.
+    pub fn configured_roas(&self) -> Vec<ConfiguredRoa> {
+        let mut res: Vec<ConfiguredRoa> = self
+            .map
+            .iter()
+            .map(|(auth, info)| {
+                let schedule = self.scheduled.get(auth).cloned().unwrap_or_default();
+                ConfiguredRoa::new(*auth.as_ref(), info.meta().clone()).with_schedule(schedule)
+            })
+            .collect();
+
+        for (auth, schedule) in self.scheduled.iter() {
+            if !self.map.contains_key(auth) {
+                res.push(ConfiguredRoa::from(*auth.as_ref()).with_schedule(*schedule));
+            }
+        }
+
+        res
+    }
+
+    /// Returns whether AS0 ROAs should be issued for space which is neither
+    /// covered by an authorization, nor delegated to a child.
+    pub fn as0_enabled(&self) -> bool {
+        self.as0
+    }
+
+    pub fn set_as0(&mut self, enabled: bool) {
+        self.as0 = enabled;
+    }
+
+    /// Returns whether authorizations no longer covered by the resources of
+    /// the CA are kept dormant, or removed.
+    pub fn dormant_policy(&self) -> RoaDormantPolicy {
+        self.dormant_policy
+    }
+
+    pub fn set_dormant_policy(&mut self, policy: RoaDormantPolicy) {
+        self.dormant_policy = policy;
+    }
+
+    /// Returns the authorizations which are not covered by the given
+    /// resources, and for which no ROAs can therefore be published.
+    pub fn dormant(&self, resources: &ResourceSet) -> Vec<RouteAuthorization> {
+        self.map
+            .keys()
+            .filter(|auth| !resources.contains(&auth.prefix().into()))
+            .cloned()
+            .collect()
+    }
+
+    pub fn schedule(&self, auth: &RouteAuthorization) -> Option<&RoaSchedule> {
+        self.scheduled.get(auth)
+    }
+
+    pub fn schedules(&self) -> &HashMap<RouteAuthorization, RoaSchedule> {
+        &self.scheduled
+    }
+
+    /// Sets the schedule for an authorization, an empty schedule removes it.
+    pub fn set_schedule(&mut self, auth: RouteAuthorization, schedule: RoaSchedule) {
+        if schedule.is_empty() {
+            self.scheduled.remove(&auth);
+        } else {
+            self.scheduled.insert(auth, schedule);
+        }
+    }
+
+    /// Returns the updates needed to add scheduled authorizations which have
+    /// become active, and to remove authorizations which have expired.
+    pub fn scheduled_updates(&self, now: Time) -> RouteAuthorizationUpdates {
+        let mut added = HashSet::new();
+        let mut removed = HashSet::new();
+
+        for (auth, schedule) in self.scheduled.iter() {
+            if schedule.is_active(now) && !self.has(auth) {
+                added.insert(*auth);
+            } else if schedule.is_expired(now) && self.has(auth) {
+                removed.insert(*auth);
+            }
+        }
+
+        RouteAuthorizationUpdates::new(added, removed)
+    }
+
     /// Adds a new authorization, or updates an existing one.
     pub fn add(&mut self, auth: RouteAuthorization) {
-        self.map.insert(auth, RouteInfo::default());
+        // Keep the meta-information of an authorization which is added again
+        self.map.entry(auth).or_insert_with(RouteInfo::default);
     }
 
     /// Removes an authorization
     pub fn remove(&mut self, auth: &RouteAuthorization) {
         self.map.remove(auth);
     }
+
+    /// Updates the meta-information of an existing authorization
+    pub fn update_meta(&mut self, auth: &RouteAuthorization, meta: RoaDefinitionMeta) {
+        if let Some(info) = self.map.get_mut(auth) {
+            info.meta = meta;
+        }
+    }
 }
 
 //------------ RouteInfo ---------------------------------------------------
@@ -167,11 +312,27 @@ impl Routes {
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct RouteInfo {
     since: Time, // authorization first added by user
+
+    #[serde(default)]
+    meta: RoaDefinitionMeta, // comment, tags, ticket reference set by user
+}
+
+impl RouteInfo {
+    pub fn since(&self) -> Time {
+        self.since
+    }
+
+    pub fn meta(&self) -> &RoaDefinitionMeta {
+        &self.meta
+    }
 }
 
 impl Default for RouteInfo {
     fn default() -> Self {
-        RouteInfo { since: Time::now() }
+        RouteInfo {
+            since: Time::now(),
+            meta: RoaDefinitionMeta::default(),
+        }
     }
 }
 
@@ -366,6 +527,8 @@ mod tests {
 
     use super::*;
 
+    use chrono::Duration;
+
     #[test]
     fn serde_route_authorization() {
         fn parse_encode_authorization(s: &str) {
@@ -384,4 +547,72 @@ mod tests {
         parse_encode_authorization("2001:db8::/32 => 64496");
         parse_encode_authorization("2001:db8::/32-48 => 64496");
     }
+
+    #[test]
+    fn adding_again_keeps_meta() {
+        let auth = RouteAuthorization(RoaDefinition::from_str("192.168.0.0/24 => 64496").unwrap());
+        let meta = RoaDefinitionMeta::with_comment("important");
+
+        let mut routes = Routes::default();
+        routes.add(auth);
+        routes.update_meta(&auth, meta.clone());
+        routes.add(auth);
+
+        assert_eq!(&meta, routes.info(&auth).unwrap().meta());
+    }
+
+    #[test]
+    fn scheduled_updates() {
+        let auth = |s: &str| RouteAuthorization(RoaDefinition::from_str(s).unwrap());
+
+        let now = Time::now();
+        let hour = Duration::hours(1);
+
+        let pending = auth("192.168.0.0/24 => 64496");
+        let due = auth("192.168.1.0/24 => 64496");
+        let active = auth("192.168.2.0/24 => 64496");
+        let expired = auth("192.168.3.0/24 => 64496");
+
+        let mut routes = Routes::default();
+        routes.add(active);
+        routes.add(expired);
+
+        routes.set_schedule(pending, RoaSchedule::new(Some(now + hour), None));
+        routes.set_schedule(due, RoaSchedule::new(Some(now - hour), Some(now + hour)));
+        routes.set_schedule(active, RoaSchedule::new(None, Some(now + hour)));
+        routes.set_schedule(expired, RoaSchedule::new(None, Some(now - hour)));
+
+        let (added, removed) = routes.scheduled_updates(now).unpack();
+        assert_eq!(vec![due], added.into_iter().collect::<Vec<_>>());
+        assert_eq!(vec![expired], removed.into_iter().collect::<Vec<_>>());
+
+        // pending and expired authorizations are still listed
+        assert_eq!(4, routes.configured_roas().len());
+
+        routes.set_schedule(pending, RoaSchedule::default());
+        assert!(routes.schedule(&pending).is_none());
+        assert_eq!(3, routes.configured_roas().len());
+    }
+
+    #[test]
+    fn dormant_authorizations() {
+        let auth = |s: &str| RouteAuthorization(RoaDefinition::from_str(s).unwrap());
+
+        let covered = auth("192.168.0.0/24 => 64496");
+        let dormant = auth("10.0.0.0/24 => 64496");
+
+        let mut routes = Routes::default();
+        routes.add(covered);
+        routes.add(dormant);
+
+        let resources = ResourceSet::from_strs("", "192.168.0.0/16", "").unwrap();
+        assert_eq!(vec![dormant], routes.dormant(&resources));
+
+        assert_eq!(RoaDormantPolicy::Keep, routes.dormant_policy());
+        routes.set_dormant_policy(RoaDormantPolicy::Remove);
+
+        let json = serde_json::to_string(&routes).unwrap();
+        let routes: Routes = serde_json::from_str(&json).unwrap();
+        assert_eq!(RoaDormantPolicy::Remove, routes.dormant_policy());
+    }
 }
@@ -8,26 +8,35 @@ use chrono::Duration;
 
 use rpki::crypto::KeyIdentifier;
 use rpki::uri;
+use rpki::x509::Time;
 
 use crate::commons::api::{
-    self, AddChildRequest, Base64, CertAuthHistory, CertAuthList, CertAuthSummary,
-    ChildAuthRequest, ChildCaInfo, ChildHandle, Entitlements, Handle, IssuanceRequest,
-    IssuanceResponse, IssuedCert, ListReply, ParentCaContact, ParentCaReq, ParentHandle,
-    PublishDelta, RcvdCert, RepoInfo, RepositoryContact, ResourceClassName, ResourceSet,
-    RevocationRequest, RevocationResponse, UpdateChildRequest,
+    self, AddChildRequest, Base64, CertAuthHistory, CertAuthList, CertAuthSummary, ChildActivity,
+    ChildAuthRequest, ChildCaInfo, ChildCertValidity, ChildHandle, ChildInvitation,
+    ChildInvitationInit, ChildRequestType, ConfiguredRoa, Entitlements, Handle, IssuanceRequest,
+    IssuanceResponse, IssuedCert, ListReply, NewChildInvitation, ParentCaContact, ParentCaReq,
+    ParentHandle, ParentMigrationPhase, ParentMigrationStatus, PublishDelta, RcvdCert, RepoInfo,
+    RepositoryContact, ResourceClassName, ResourceSet, RevocationRequest, RevocationResponse,
+    RoaDefinitionUpdates, RoaDormantPolicy, RoaLintReport, RoaSchedule, ScheduledRoa, Token,
+    TokenHash, UpdateChildRequest,
 };
 use crate::commons::error::Error;
 use crate::commons::eventsourcing::{Aggregate, AggregateStore, DiskAggregateStore};
 use crate::commons::remote::builder::SignedMessageBuilder;
 use crate::commons::remote::cmslogger::CmsLogger;
+use crate::commons::remote::guard::{RateLimiter, ReplayGuard};
 use crate::commons::remote::id::IdCert;
+use crate::commons::remote::rfc6492::NotPerformedResponse;
 use crate::commons::remote::sigmsg::SignedMessage;
 use crate::commons::remote::{rfc6492, rfc8181, rfc8183};
 use crate::commons::util::httpclient;
 use crate::commons::KrillResult;
-use crate::constants::CASERVER_DIR;
+use crate::constants::{
+    CASERVER_DIR, RFC6492_CHILD_SIGNING_TIMES_FILE, RFC6492_PARENT_SIGNING_TIMES_FILE,
+};
 use crate::daemon::ca::{
-    self, ta_handle, CertAuth, Cmd, CmdDet, IniDet, RouteAuthorizationUpdates, Signer,
+    self, ta_handle, CertAuth, ChildActivityStore, Cmd, CmdDet, IniDet, ParentResponseStore,
+    RouteAuthorizationUpdates, Signer,
 };
 use crate::daemon::mq::EventQueueListener;
 
@@ -37,6 +46,14 @@ use crate::daemon::mq::EventQueueListener;
 pub struct CaServer<S: Signer> {
     signer: Arc<RwLock<S>>,
     ca_store: Arc<DiskAggregateStore<CertAuth<S>>>,
+    child_activity: Arc<ChildActivityStore>,
+    child_rate_limiter: Arc<RateLimiter>,
+    invitation_rate_limiter: Arc<RateLimiter>,
+    child_signing_times: Arc<ReplayGuard>,
+    parent_signing_times: Arc<ReplayGuard>,
+    parent_responses: Arc<ParentResponseStore>,
+    roa_lint_strict: bool,
+    child_cert_validity: ChildCertValidity,
     cms_logger_work_dir: PathBuf,
 }
 
@@ -48,16 +65,54 @@ impl<S: Signer> CaServer<S> {
         events_queue: Arc<EventQueueListener>,
         signer: Arc<RwLock<S>>,
         roa_prefix_grouping_strategy: RoaPrefixGroupingStrategy,
+        max_message_age: Duration,
+        rate_limit_per_minute: u32,
+        invitation_rate_limit_per_minute: u32,
+        roa_lint_strict: bool,
+        child_cert_validity: ChildCertValidity,
     ) -> KrillResult<Self> {
         let mut ca_store = DiskAggregateStore::<CertAuth<S>>::new(work_dir, CASERVER_DIR)?;
         ca_store.add_listener(events_queue);
         ca::set_roa_prefix_grouping_strategy(roa_prefix_grouping_strategy);
 
-        Ok(CaServer {
+        let server = CaServer {
             signer,
             ca_store: Arc::new(ca_store),
+            child_activity: Arc::new(ChildActivityStore::build(work_dir)),
+            child_rate_limiter: Arc::new(RateLimiter::new(rate_limit_per_minute)),
+            invitation_rate_limiter: Arc::new(RateLimiter::new(invitation_rate_limit_per_minute)),
+            child_signing_times: Arc::new(ReplayGuard::build(
+                work_dir,
+                RFC6492_CHILD_SIGNING_TIMES_FILE,
+                max_message_age,
+            )),
+            parent_signing_times: Arc::new(ReplayGuard::build(
+                work_dir,
+                RFC6492_PARENT_SIGNING_TIMES_FILE,
+                max_message_age,
+            )),
+            parent_responses: Arc::new(ParentResponseStore::build(work_dir)),
+            roa_lint_strict,
+            child_cert_validity,
             cms_logger_work_dir: work_dir.clone(),
-        })
+        };
+
+        for ca in server.ca_list().cas() {
+            server.ca_child_cert_validity_update(ca.handle())?;
+        }
+
+        Ok(server)
+    }
+
+    /// Updates the validity for child certificates of a CA to the configured
+    /// validity, if it changed, so that certificates of children without
+    /// their own validity are re-issued under the new validity.
+    fn ca_child_cert_validity_update(&self, handle: &Handle) -> KrillResult<()> {
+        if self.get_ca(handle)?.child_cert_validity() != self.child_cert_validity {
+            let cmd = CmdDet::child_cert_validity_default_update(handle, self.child_cert_validity);
+            self.send_command(cmd)?;
+        }
+        Ok(())
     }
 
     /// Gets the TrustAnchor, if present. Returns an error if the TA is uninitialized.
@@ -81,6 +136,7 @@ impl<S: Signer> CaServer<S> {
             // init normal CA
             let init = IniDet::init(&handle, self.signer.clone())?;
             self.ca_store.add(init)?;
+            self.ca_child_cert_validity_update(&handle)?;
 
             // add embedded repo
             let embedded = RepositoryContact::embedded(info);
@@ -169,6 +225,7 @@ impl<S: Signer> CaServer<S> {
         service_uri: &uri::Https,
     ) -> KrillResult<ParentCaContact> {
         info!("CA '{}' process add child request: {}", &parent, &req);
+        let child_validity = req.validity();
         let (child_handle, child_res, child_auth) = req.unwrap();
 
         let id_cert = match &child_auth {
@@ -176,7 +233,13 @@ impl<S: Signer> CaServer<S> {
             ChildAuthRequest::Rfc8183(req) => Some(req.id_cert().clone()),
         };
 
-        let add_child = CmdDet::child_add(&parent, child_handle.clone(), id_cert, child_res);
+        let add_child = CmdDet::child_add(
+            &parent,
+            child_handle.clone(),
+            id_cert,
+            child_res,
+            child_validity,
+        );
         self.ca_store.command(add_child)?;
 
         let tag = match child_auth {
@@ -221,14 +284,14 @@ impl<S: Signer> CaServer<S> {
 
         Ok(rfc8183::ParentResponse::new(
             tag,
-            ca.id_cert().clone(),
+            ca.advertised_id_cert().clone(),
             ca.handle().clone(),
             child_handle,
             service_uri,
         ))
     }
 
-    /// Show details for a child under the TA.
+    /// Show details for a child under the TA, including its recent activity.
     pub fn ca_show_child(
         &self,
         parent: &ParentHandle,
@@ -236,7 +299,30 @@ impl<S: Signer> CaServer<S> {
     ) -> KrillResult<ChildCaInfo> {
         trace!("Finding details for CA: {} under parent: {}", child, parent);
         let ca = self.get_ca(parent)?;
-        ca.get_child(child).map(|details| details.clone().into())
+        let info = ca.child_info(child)?;
+        Ok(info.with_activity(self.child_activity.get(parent, child)))
+    }
+
+    /// Returns the recent activity for the children of all CAs.
+    pub fn ca_children_activity(&self) -> HashMap<Handle, HashMap<ChildHandle, ChildActivity>> {
+        self.child_activity.all()
+    }
+
+    /// Returns the last rejected request which could not be attributed to a
+    /// child, for all CAs.
+    pub fn ca_children_rejected_requests(&self) -> HashMap<Handle, ChildActivity> {
+        self.child_activity.all_rejected()
+    }
+
+    /// Saves the state which is kept in memory rather than as events, i.e.
+    /// the recent activity of children, the last error responses from
+    /// parents, and the signing times of messages received from children and
+    /// parents.
+    pub fn persist_state(&self) {
+        self.child_activity.flush();
+        self.parent_responses.flush();
+        self.child_signing_times.flush();
+        self.parent_signing_times.flush();
     }
 
     /// Update a child under this CA.
@@ -246,13 +332,119 @@ impl<S: Signer> CaServer<S> {
         child: ChildHandle,
         req: UpdateChildRequest,
     ) -> KrillResult<()> {
-        self.send_command(CmdDet::child_update(handle, child, req))
+        let signer = self.signer.clone();
+        self.send_command(CmdDet::child_update(handle, child, req, signer))
+    }
+
+    /// Suspends all children which have not asked for their entitlements
+    /// for longer than the given duration.
+    pub fn ca_children_suspend_inactive(&self, max_inactive: Duration) {
+        for handle in self.ca_store.list() {
+            let ca = match self.get_ca(&handle) {
+                Ok(ca) => ca,
+                Err(e) => {
+                    error!("Could not load CA '{}', error: {}", handle, e);
+                    continue;
+                }
+            };
+
+            for child_handle in ca.children() {
+                let child = match ca.get_child(child_handle) {
+                    Ok(child) => child,
+                    Err(_) => continue,
+                };
+
+                if child.is_suspended()
+                    || !self
+                        .child_activity
+                        .is_inactive(&handle, child_handle, max_inactive)
+                {
+                    continue;
+                }
+
+                info!(
+                    "Suspending child '{}' under CA '{}' because it has been inactive",
+                    child_handle, handle
+                );
+                if let Err(e) = self.ca_child_update(
+                    &handle,
+                    child_handle.clone(),
+                    UpdateChildRequest::suspend(),
+                ) {
+                    error!(
+                        "Could not suspend child '{}' under CA '{}', error: {}",
+                        child_handle, handle, e
+                    );
+                }
+            }
+        }
     }
 
     /// Update a child under this CA.
     pub fn ca_child_remove(&self, handle: &Handle, child: ChildHandle) -> KrillResult<()> {
+        let sender = format!("{}/{}", handle, child);
         let signer = self.signer.clone();
-        self.send_command(CmdDet::child_remove(handle, child, signer))
+        self.send_command(CmdDet::child_remove(handle, child, signer))?;
+        self.child_rate_limiter.remove(&sender);
+        self.child_signing_times.remove(&sender);
+        Ok(())
+    }
+
+    /// Invites a child to add itself under this CA, and returns the
+    /// invitation including the (random) token the child should use. Only
+    /// the hash of the token is kept, so this is the only time it is known.
+    pub fn ca_child_invitation_add(
+        &self,
+        handle: &Handle,
+        init: ChildInvitationInit,
+    ) -> KrillResult<NewChildInvitation> {
+        let token = Token::random(self.signer.read().unwrap().deref());
+        let (resources, expires) = init.unpack();
+        let invitation = ChildInvitation::new(&token, resources, expires);
+
+        self.send_command(CmdDet::child_invitation_add(handle, invitation.clone()))?;
+        Ok(NewChildInvitation::new(token, invitation))
+    }
+
+    pub fn ca_child_invitation_update(
+        &self,
+        handle: &Handle,
+        id: TokenHash,
+        expires: Time,
+    ) -> KrillResult<()> {
+        self.send_command(CmdDet::child_invitation_update(handle, id, expires))
+    }
+
+    pub fn ca_child_invitation_revoke(&self, handle: &Handle, id: TokenHash) -> KrillResult<()> {
+        self.send_command(CmdDet::child_invitation_revoke(handle, id))
+    }
+
+    /// Adds a child using an invitation, and returns the parent response
+    /// for the child. Because anyone may try to accept an invitation, the
+    /// failed attempts are rate limited per peer. A peer which keeps failing
+    /// does not keep other peers from accepting their invitations.
+    pub fn ca_child_invitation_accept(
+        &self,
+        handle: &Handle,
+        token: Token,
+        req: rfc8183::ChildRequest,
+        service_uri: &uri::Https,
+        peer: &str,
+    ) -> KrillResult<rfc8183::ParentResponse> {
+        self.invitation_rate_limiter
+            .check_uncounted(peer)
+            .map_err(|_| Error::CaChildInvitationRateLimited(handle.clone()))?;
+
+        let (tag, child, id_cert) = req.unwrap();
+        info!("CA '{}' process invitation for child '{}'", handle, child);
+
+        let accept = CmdDet::child_invitation_accept(handle, &token, child.clone(), id_cert, None);
+        if let Err(e) = self.send_command(accept) {
+            self.invitation_rate_limiter.count(peer);
+            return Err(e);
+        }
+
+        self.ca_parent_response(handle, child, tag, service_uri)
     }
 }
 
@@ -280,8 +472,16 @@ impl<S: Signer> CaServer<S> {
         self.ca_store.has(handle)
     }
 
-    /// Processes an RFC6492 sent to this CA.
-    pub fn rfc6492(&self, ca_handle: &Handle, msg_bytes: Bytes) -> KrillResult<Bytes> {
+    /// Processes an RFC6492 sent to this CA. The request is recorded as
+    /// activity for the child, with the user agent if known. Requests which
+    /// cannot be decoded or validated are recorded as well, for the child
+    /// they claim to be from if it is known, or else for the CA.
+    pub fn rfc6492(
+        &self,
+        ca_handle: &Handle,
+        msg_bytes: Bytes,
+        user_agent: Option<String>,
+    ) -> KrillResult<Bytes> {
         let ca = self.ca_store.get_latest(ca_handle)?;
 
         let msg = match SignedMessage::decode(msg_bytes.clone(), false) {
@@ -293,35 +493,101 @@ impl<S: Signer> CaServer<S> {
                     Base64::from_content(msg_bytes.as_ref()),
                     e
                 );
-                return Err(Error::custom(msg));
+                let e = Error::custom(msg);
+                self.child_activity
+                    .request_rejected(ca_handle, user_agent, e.to_string());
+                return Err(e);
             }
         };
 
-        let content = ca.verify_rfc6492(msg)?;
+        let signing_time = msg.signing_time();
+        let content = match ca.verify_rfc6492(msg) {
+            Ok(content) => content,
+            Err(e) => {
+                match Self::rfc6492_claimed_request(&msg_bytes) {
+                    Some((child, request_type)) if ca.get_child(&child).is_ok() => {
+                        self.child_activity.request_received(
+                            ca_handle,
+                            &child,
+                            request_type,
+                            user_agent,
+                            Some(e.to_string()),
+                        )
+                    }
+                    _ => self
+                        .child_activity
+                        .request_rejected(ca_handle, user_agent, e.to_string()),
+                }
+                return Err(e);
+            }
+        };
 
         let (child, recipient, content) = content.unwrap();
 
         let cms_logger = CmsLogger::for_rfc6492_rcvd(&self.cms_logger_work_dir, &recipient, &child);
 
-        let (res, should_log_cms) = match content {
+        let sender = format!("{}/{}", ca_handle, child);
+        let accepted = self
+            .child_rate_limiter
+            .check(&sender)
+            .and_then(|_| {
+                self.child_signing_times
+                    .check(&sender, signing_time, &msg_bytes)
+            })
+            .map_err(|e| Error::CaChildMessageRejected(ca_handle.clone(), child.clone(), e));
+
+        let (request_type, res, should_log_cms) = match content {
             rfc6492::Content::Qry(rfc6492::Qry::Revoke(req)) => {
-                let res = self.revoke(ca_handle, child.clone(), req)?;
-                let msg = rfc6492::Message::revoke_response(child, recipient, res);
-                (self.wrap_rfc6492_response(ca_handle, msg), true)
+                let res = accepted
+                    .and_then(|_| self.revoke(ca_handle, child.clone(), req))
+                    .and_then(|res| {
+                        let msg = rfc6492::Message::revoke_response(
+                            child.clone(),
+                            recipient.clone(),
+                            res,
+                        );
+                        self.wrap_rfc6492_response(ca_handle, msg)
+                    });
+                (ChildRequestType::Revoke, res, true)
             }
             rfc6492::Content::Qry(rfc6492::Qry::List) => {
-                let entitlements = self.list(ca_handle, &child)?;
-                let msg = rfc6492::Message::list_response(child, recipient, entitlements);
-                (self.wrap_rfc6492_response(ca_handle, msg), false)
+                let res = accepted
+                    .and_then(|_| self.list(ca_handle, &child))
+                    .and_then(|entitlements| {
+                        let msg = rfc6492::Message::list_response(
+                            child.clone(),
+                            recipient.clone(),
+                            entitlements,
+                        );
+                        self.wrap_rfc6492_response(ca_handle, msg)
+                    });
+                (ChildRequestType::List, res, false)
             }
             rfc6492::Content::Qry(rfc6492::Qry::Issue(req)) => {
-                let res = self.issue(ca_handle, &child, req)?;
-                let msg = rfc6492::Message::issue_response(child, recipient, res);
-                (self.wrap_rfc6492_response(ca_handle, msg), true)
+                let res = accepted
+                    .and_then(|_| self.issue(ca_handle, &child, req))
+                    .and_then(|res| {
+                        let msg =
+                            rfc6492::Message::issue_response(child.clone(), recipient.clone(), res);
+                        self.wrap_rfc6492_response(ca_handle, msg)
+                    });
+                (ChildRequestType::Issue, res, true)
             }
-            _ => (Err(Error::custom("Unsupported RFC6492 message")), true),
+            _ => (
+                ChildRequestType::Other,
+                accepted.and_then(|_| Err(Error::Rfc6492(rfc6492::Error::WrongMessageType))),
+                true,
+            ),
         };
 
+        self.child_activity.request_received(
+            ca_handle,
+            &child,
+            request_type,
+            user_agent,
+            res.as_ref().err().map(|e| e.to_string()),
+        );
+
         match &res {
             Ok(reply_bytes) => {
                 if should_log_cms {
@@ -335,7 +601,34 @@ impl<S: Signer> CaServer<S> {
             }
         }
 
-        res
+        // The child is known and its message was validated, so any error
+        // is returned to it in a signed RFC 6492 error response.
+        match res {
+            Ok(reply_bytes) => Ok(reply_bytes),
+            Err(e) => {
+                warn!("RFC 6492 request from child '{}' failed: {}", child, e);
+                let msg = rfc6492::Message::not_performed_response(
+                    child,
+                    recipient,
+                    e.to_rfc6492_error_code(Some(request_type)),
+                )?;
+                self.wrap_rfc6492_response(ca_handle, msg)
+            }
+        }
+    }
+
+    /// Returns the child and request type claimed in an RFC 6492 message,
+    /// without validating it, if the message can be decoded at all.
+    fn rfc6492_claimed_request(msg_bytes: &Bytes) -> Option<(ChildHandle, ChildRequestType)> {
+        let msg = SignedMessage::decode(msg_bytes.clone(), false).ok()?;
+        let (child, _, content) = rfc6492::Message::from_signed_message(&msg).ok()?.unwrap();
+        let request_type = match content {
+            rfc6492::Content::Qry(rfc6492::Qry::List) => ChildRequestType::List,
+            rfc6492::Content::Qry(rfc6492::Qry::Issue(_)) => ChildRequestType::Issue,
+            rfc6492::Content::Qry(rfc6492::Qry::Revoke(_)) => ChildRequestType::Revoke,
+            _ => ChildRequestType::Other,
+        };
+        Some((child, request_type))
     }
 
     fn wrap_rfc6492_response(&self, handle: &Handle, msg: rfc6492::Message) -> KrillResult<Bytes> {
@@ -410,15 +703,58 @@ impl<S: Signer> CaServer<S> {
         } else {
             let init = IniDet::init(handle, self.signer.clone())?;
             self.ca_store.add(init)?;
-            Ok(())
+            self.ca_child_cert_validity_update(handle)
         }
     }
 
+    /// Starts an ID key roll for a CA. The new ID cert is included in
+    /// parent responses for its children from now on.
     pub fn ca_update_id(&self, handle: Handle) -> KrillResult<()> {
         let cmd = CmdDet::update_id(&handle, self.signer.clone());
         self.send_command(cmd)
     }
 
+    /// Activates the new ID key of a CA, and removes the old key. This is
+    /// refused while an RFC 6492 parent, or an RFC 8181 repository, does not
+    /// accept messages signed with the new key yet, because they must have
+    /// been given the new ID cert before the old key can be removed.
+    pub fn ca_activate_id(&self, handle: Handle) -> KrillResult<()> {
+        let ca = self.get_ca(&handle)?;
+        let old_key = ca.id_key().clone();
+        let new_key = ca
+            .next_id_key()
+            .ok_or_else(|| Error::CaIdRollUnknown(handle.clone()))?
+            .clone();
+
+        for parent in ca.parents() {
+            if let ParentCaContact::Rfc6492(res) = ca.parent(parent)? {
+                if let Err(e) = self.get_entitlements_rfc6492(&handle, parent, &new_key, res) {
+                    return Err(Error::CaIdRollNotAccepted(
+                        handle,
+                        format!("parent '{}': {}", parent, e),
+                    ));
+                }
+            }
+        }
+
+        if let Ok(RepositoryContact::Rfc8181(res)) = ca.get_repository_contact() {
+            if let Err(e) = self.send_rfc8181_list_signed(&handle, res, &new_key) {
+                return Err(Error::CaIdRollNotAccepted(
+                    handle,
+                    format!("repository: {}", e),
+                ));
+            }
+        }
+
+        let cmd = CmdDet::activate_id(&handle);
+        self.send_command(cmd)?;
+
+        if let Err(e) = self.signer.write().unwrap().destroy_key(&old_key) {
+            warn!("Could not remove old ID key for CA '{}': {}", handle, e);
+        }
+        Ok(())
+    }
+
     /// Adds a parent to a CA
     pub fn ca_parent_add(&self, handle: Handle, parent: ParentCaReq) -> KrillResult<()> {
         let (parent_handle, parent_contact) = parent.unpack();
@@ -440,8 +776,104 @@ impl<S: Signer> CaServer<S> {
 
     /// Removes a parent from a CA
     pub fn ca_parent_remove(&self, handle: Handle, parent: ParentHandle) -> KrillResult<()> {
+        self.parent_response_received(&handle, &parent, None);
+        let sender = format!("{}/{}", handle, parent);
         let upd = CmdDet::remove_parent(&handle, parent);
-        self.send_command(upd)
+        self.send_command(upd)?;
+        self.parent_signing_times.remove(&sender);
+        Ok(())
+    }
+
+    /// Starts moving a CA from one parent to another. The new parent is
+    /// added, and the old parent is removed when all ROAs are issued and
+    /// published under the new parent.
+    pub fn ca_parent_migration_start(
+        &self,
+        handle: Handle,
+        from: ParentHandle,
+        to: ParentCaReq,
+    ) -> KrillResult<()> {
+        let (to_handle, to_contact) = to.unpack();
+        let cmd = CmdDet::parent_migration_start(&handle, from, to_handle, to_contact);
+        self.send_command(cmd)
+    }
+
+    /// Cancels the parent migration of a CA which is in progress. The new
+    /// parent is removed, and the CA stays under the old parent.
+    pub fn ca_parent_migration_cancel(&self, handle: &Handle) -> KrillResult<()> {
+        let cmd = CmdDet::parent_migration_cancel(handle);
+        self.send_command(cmd)
+    }
+
+    /// Returns the status of the current, or last, parent migration of a CA.
+    pub fn ca_parent_migration_status(
+        &self,
+        handle: &Handle,
+    ) -> KrillResult<ParentMigrationStatus> {
+        let ca = self.get_ca(handle)?;
+        ca.parent_migration_status()?
+            .ok_or_else(|| Error::CaParentMigrationUnknown(handle.clone()))
+    }
+
+    /// Completes the parent migration of a CA, if it only waits for its ROAs
+    /// to be published, now that the given version of the CA is published.
+    pub fn ca_parent_migration_published(&self, handle: &Handle, version: u64) {
+        let ca = match self.get_ca(handle) {
+            Ok(ca) => ca,
+            Err(_) => return,
+        };
+
+        // If the CA changed since, we will be called again for the new version.
+        if ca.version() != version {
+            return;
+        }
+
+        match ca.parent_migration_status() {
+            Ok(Some(status)) => {
+                if status.phase() == ParentMigrationPhase::AwaitingPublication {
+                    info!(
+                        "Completing migration of CA '{}' from parent '{}' to '{}'",
+                        handle,
+                        status.from(),
+                        status.to()
+                    );
+                    let cmd = CmdDet::parent_migration_complete(handle, version);
+                    if let Err(e) = self.send_command(cmd) {
+                        error!(
+                            "Could not complete parent migration for CA '{}': {}",
+                            handle, e
+                        );
+                    }
+                }
+            }
+            Ok(None) => {}
+            Err(e) => error!(
+                "Could not determine parent migration status for CA '{}': {}",
+                handle, e
+            ),
+        }
+    }
+
+    /// Returns the error response to the last issuance or revocation request
+    /// sent to the parent, unless a later request succeeded.
+    pub fn ca_parent_not_performed(
+        &self,
+        handle: &Handle,
+        parent: &ParentHandle,
+    ) -> Option<NotPerformedResponse> {
+        self.parent_responses.get(handle, parent)
+    }
+
+    /// Remembers the error response to an issuance or revocation request sent
+    /// to a parent, or forgets it if the request succeeded.
+    fn parent_response_received(
+        &self,
+        handle: &Handle,
+        parent: &ParentHandle,
+        not_performed: Option<NotPerformedResponse>,
+    ) {
+        self.parent_responses
+            .response_received(handle, parent, not_performed);
     }
 
     /// Perform a key roll for all active keys in a CA older than the specified duration.
@@ -525,6 +957,20 @@ impl<S: Signer> CaServer<S> {
         }
     }
 
+    /// Gets updates from all parents of a CA, and sends any resulting requests
+    /// right away rather than leaving them to the scheduler. This is used when
+    /// the caller needs the CA to hold its new certificates when this returns.
+    pub fn ca_sync_parents(&self, handle: &Handle) -> KrillResult<()> {
+        let ca = self.get_ca(handle)?;
+
+        for parent in ca.parents() {
+            self.get_updates_from_parent(handle, parent)?;
+            self.send_requests(handle, parent)?;
+        }
+
+        Ok(())
+    }
+
     /// Sends requests to a specific parent for the CA matching handle.
     pub fn send_requests(&self, handle: &Handle, parent: &ParentHandle) -> KrillResult<()> {
         self.send_revoke_requests_handle_responses(handle, parent)?;
@@ -574,9 +1020,13 @@ impl<S: Signer> CaServer<S> {
             ParentCaContact::Embedded => {
                 self.send_revoke_requests_embedded(revoke_requests, handle, parent)
             }
-            ParentCaContact::Rfc6492(parent_res) => {
-                self.send_revoke_requests_rfc6492(revoke_requests, child.id_key(), parent_res)
-            }
+            ParentCaContact::Rfc6492(parent_res) => self.send_revoke_requests_rfc6492(
+                revoke_requests,
+                handle,
+                parent,
+                child.id_key(),
+                parent_res,
+            ),
         }
     }
 
@@ -621,6 +1071,8 @@ impl<S: Signer> CaServer<S> {
     fn send_revoke_requests_rfc6492(
         &self,
         revoke_requests: HashMap<ResourceClassName, Vec<RevocationRequest>>,
+        handle: &Handle,
+        parent: &ParentHandle,
         signing_key: &KeyIdentifier,
         parent_res: &rfc8183::ParentResponse,
     ) -> KrillResult<HashMap<ResourceClassName, Vec<RevocationResponse>>> {
@@ -637,6 +1089,8 @@ impl<S: Signer> CaServer<S> {
                 let revoke = rfc6492::Message::revoke(sender, recipient, req.clone());
 
                 match self.send_rfc6492_and_validate_response(
+                    handle,
+                    parent,
                     signing_key,
                     parent_res,
                     revoke.into_bytes(),
@@ -644,8 +1098,14 @@ impl<S: Signer> CaServer<S> {
                 ) {
                     Err(e) => error!("Could not send/validate revoke: {}", e),
                     Ok(response) => match response {
-                        rfc6492::Res::Revoke(revoke_response) => revocations.push(revoke_response),
-                        rfc6492::Res::NotPerformed(e) => error!("We got an error response: {}", e),
+                        rfc6492::Res::Revoke(revoke_response) => {
+                            self.parent_response_received(handle, parent, None);
+                            revocations.push(revoke_response)
+                        }
+                        rfc6492::Res::NotPerformed(e) => {
+                            error!("We got an error response: {}", e);
+                            self.parent_response_received(handle, parent, Some(e));
+                        }
                         rfc6492::Res::List(_) => error!("List response to revoke request??"),
                         rfc6492::Res::Issue(_) => error!("Issue response to revoke request??"),
                     },
@@ -671,9 +1131,13 @@ impl<S: Signer> CaServer<S> {
             ParentCaContact::Embedded => {
                 self.send_cert_requests_embedded(cert_requests, handle, parent)
             }
-            ParentCaContact::Rfc6492(parent_res) => {
-                self.send_cert_requests_rfc6492(cert_requests, child.id_key(), &parent_res)
-            }
+            ParentCaContact::Rfc6492(parent_res) => self.send_cert_requests_rfc6492(
+                cert_requests,
+                handle,
+                parent,
+                child.id_key(),
+                &parent_res,
+            ),
         }?;
 
         for (class_name, issued_certs) in issued_certs.into_iter() {
@@ -728,6 +1192,8 @@ impl<S: Signer> CaServer<S> {
     fn send_cert_requests_rfc6492(
         &self,
         requests: HashMap<ResourceClassName, Vec<IssuanceRequest>>,
+        handle: &Handle,
+        parent: &ParentHandle,
         signing_key: &KeyIdentifier,
         parent_res: &rfc8183::ParentResponse,
     ) -> KrillResult<HashMap<ResourceClassName, Vec<IssuedCert>>> {
@@ -746,6 +1212,8 @@ impl<S: Signer> CaServer<S> {
                 let issue = rfc6492::Message::issue(sender, recipient, req);
 
                 match self.send_rfc6492_and_validate_response(
+                    handle,
+                    parent,
                     signing_key,
                     parent_res,
                     issue.into_bytes(),
@@ -753,8 +1221,12 @@ impl<S: Signer> CaServer<S> {
                 ) {
                     Err(e) => error!("Could not send/validate csr: {}", e),
                     Ok(response) => match response {
-                        rfc6492::Res::NotPerformed(e) => error!("We got an error response: {}", e),
+                        rfc6492::Res::NotPerformed(e) => {
+                            error!("We got an error response: {}", e);
+                            self.parent_response_received(handle, parent, Some(e));
+                        }
                         rfc6492::Res::Issue(issue_response) => {
+                            self.parent_response_received(handle, parent, None);
                             let (_, _, _, issued) = issue_response.unwrap();
                             issued_certs.push(issued);
                         }
@@ -812,7 +1284,10 @@ impl<S: Signer> CaServer<S> {
         match contact {
             ParentCaContact::Ta(_) => Err(Error::TaNotAllowed),
             ParentCaContact::Embedded => self.get_entitlements_embedded(handle, parent),
-            ParentCaContact::Rfc6492(res) => self.get_entitlements_rfc6492(handle, res),
+            ParentCaContact::Rfc6492(res) => {
+                let signing_key = self.get_ca(handle)?.id_key().clone();
+                self.get_entitlements_rfc6492(handle, parent, &signing_key, res)
+            }
         }
     }
 
@@ -821,17 +1296,24 @@ impl<S: Signer> CaServer<S> {
         handle: &Handle,
         parent: &ParentHandle,
     ) -> KrillResult<api::Entitlements> {
-        let parent = self.ca_store.get_latest(parent)?;
-        parent.list(handle)
+        let res = self.list(parent, handle);
+        self.child_activity.request_received(
+            parent,
+            handle,
+            ChildRequestType::List,
+            None,
+            res.as_ref().err().map(|e| e.to_string()),
+        );
+        res
     }
 
     fn get_entitlements_rfc6492(
         &self,
         handle: &Handle,
+        parent: &ParentHandle,
+        signing_key: &KeyIdentifier,
         parent_res: &rfc8183::ParentResponse,
     ) -> KrillResult<api::Entitlements> {
-        let child = self.ca_store.get_latest(handle)?;
-
         // create a list request
         let sender = parent_res.child_handle().clone();
         let recipient = parent_res.parent_handle().clone();
@@ -839,14 +1321,20 @@ impl<S: Signer> CaServer<S> {
         let list = rfc6492::Message::list(sender, recipient);
 
         let response = self.send_rfc6492_and_validate_response(
-            child.id_key(),
+            handle,
+            parent,
+            signing_key,
             parent_res,
             list.into_bytes(),
             None,
         )?;
 
         match response {
-            rfc6492::Res::NotPerformed(np) => Err(Error::Custom(format!("Not performed: {}", np))),
+            rfc6492::Res::NotPerformed(np) => Err(Error::CaParentNotPerformed(
+                handle.clone(),
+                parent.clone(),
+                np,
+            )),
             rfc6492::Res::List(ent) => Ok(ent),
             _ => Err(Error::custom("Got unexpected response to list query")),
         }
@@ -854,19 +1342,45 @@ impl<S: Signer> CaServer<S> {
 
     fn send_rfc6492_and_validate_response(
         &self,
+        handle: &Handle,
+        parent: &ParentHandle,
         signing_key: &KeyIdentifier,
         parent_res: &rfc8183::ParentResponse,
         msg: Bytes,
         cms_logger: Option<CmsLogger>,
     ) -> KrillResult<rfc6492::Res> {
-        let response = self.send_procotol_msg_and_validate(
-            signing_key,
-            parent_res.service_uri(),
-            parent_res.id_cert(),
-            rfc6492::CONTENT_TYPE,
-            msg,
-            cms_logger,
-        )?;
+        let ca = self.get_ca(handle)?;
+
+        let send = |key: &KeyIdentifier| {
+            self.send_procotol_msg_and_validate(
+                key,
+                parent_res.service_uri(),
+                parent_res.id_cert(),
+                ca.parent_previous_id_cert(parent, parent_res),
+                rfc6492::CONTENT_TYPE,
+                msg.clone(),
+                cms_logger.as_ref(),
+            )
+        };
+
+        // While an ID key roll is in progress the parent may already have
+        // been given the new ID cert, so then it rejects the current key.
+        let next_key = ca.next_id_key().filter(|key| *key != signing_key);
+        let (response, response_bytes, by_previous_id) = match (send(signing_key), next_key) {
+            (Err(e), Some(next_key)) => send(next_key).map_err(|_| e)?,
+            (res, _) => res?,
+        };
+
+        // The parent uses its new ID key, so its previous ID cert is no
+        // longer accepted.
+        if !by_previous_id && ca.parent_previous_id_obsolete(parent, parent_res) {
+            self.send_command(CmdDet::remove_parent_previous_id(handle, parent.clone()))?;
+        }
+
+        let sender = format!("{}/{}", handle, parent);
+        self.parent_signing_times
+            .check(&sender, response.signing_time(), &response_bytes)
+            .map_err(|e| Error::CaParentMessageRejected(handle.clone(), parent.clone(), e))?;
 
         rfc6492::Message::from_signed_message(&response)
             .map_err(Error::custom)?
@@ -883,10 +1397,11 @@ impl<S: Signer> CaServer<S> {
         signing_key: &KeyIdentifier,
         service_uri: &rfc8183::ServiceUri,
         service_id: &IdCert,
+        previous_service_id: Option<&IdCert>,
         content_type: &str,
         msg: Bytes,
-        cms_logger: Option<CmsLogger>,
-    ) -> KrillResult<SignedMessage> {
+        cms_logger: Option<&CmsLogger>,
+    ) -> KrillResult<(SignedMessage, Bytes, bool)> {
         let signed_msg =
             SignedMessageBuilder::create(signing_key, self.signer.read().unwrap().deref(), msg)
                 .map_err(Error::signer)?
@@ -911,35 +1426,61 @@ impl<S: Signer> CaServer<S> {
             }
         };
 
-        if let Err(e) = msg.validate(service_id) {
-            error!(
-                "Could not validate protocol response: {}",
-                base64::encode(res.as_ref())
-            );
-            return Err(Error::custom(e));
-        }
+        // Responses signed with the previous ID key of the service are still
+        // accepted, until the service activates its new key.
+        let by_previous_id = match msg.validate(service_id) {
+            Ok(()) => false,
+            Err(e) => match previous_service_id {
+                Some(previous) if msg.validate(previous).is_ok() => true,
+                _ => {
+                    error!(
+                        "Could not validate protocol response: {}",
+                        base64::encode(res.as_ref())
+                    );
+                    return Err(Error::custom(e));
+                }
+            },
+        };
 
-        Ok(msg)
+        Ok((msg, res, by_previous_id))
     }
 
     fn send_rfc8181_and_validate_response(
         &self,
         ca_handle: &Handle,
         repository: &rfc8183::RepositoryResponse,
+        signing_key: &KeyIdentifier,
         msg: Bytes,
     ) -> KrillResult<rfc8181::ReplyMessage> {
         let ca = self.get_ca(ca_handle)?;
 
         let cms_logger = CmsLogger::for_rfc8181_sent(&self.cms_logger_work_dir, ca_handle);
 
-        let response = self.send_procotol_msg_and_validate(
-            ca.id_key(),
-            repository.service_uri(),
-            repository.id_cert(),
-            rfc8181::CONTENT_TYPE,
-            msg,
-            Some(cms_logger),
-        )?;
+        let send = |key: &KeyIdentifier| {
+            self.send_procotol_msg_and_validate(
+                key,
+                repository.service_uri(),
+                repository.id_cert(),
+                ca.repository_previous_id_cert(repository),
+                rfc8181::CONTENT_TYPE,
+                msg.clone(),
+                Some(&cms_logger),
+            )
+        };
+
+        // While an ID key roll is in progress the repository may already
+        // have been given the new ID cert, so then it rejects the current key.
+        let next_key = ca.next_id_key().filter(|key| *key != signing_key);
+        let (response, _, by_previous_id) = match (send(signing_key), next_key) {
+            (Err(e), Some(next_key)) => send(next_key).map_err(|_| e)?,
+            (res, _) => res?,
+        };
+
+        // The repository uses its new ID key, so its previous ID cert is no
+        // longer accepted.
+        if !by_previous_id && ca.repository_previous_id_obsolete(repository) {
+            self.send_command(CmdDet::remove_repo_previous_id(ca_handle))?;
+        }
 
         rfc8181::Message::from_signed_message(&response)
             .map_err(Error::custom)?
@@ -951,10 +1492,21 @@ impl<S: Signer> CaServer<S> {
         &self,
         ca_handle: &Handle,
         repository: &rfc8183::RepositoryResponse,
+    ) -> KrillResult<ListReply> {
+        let signing_key = self.get_ca(ca_handle)?.id_key().clone();
+        self.send_rfc8181_list_signed(ca_handle, repository, &signing_key)
+    }
+
+    fn send_rfc8181_list_signed(
+        &self,
+        ca_handle: &Handle,
+        repository: &rfc8183::RepositoryResponse,
+        signing_key: &KeyIdentifier,
     ) -> KrillResult<ListReply> {
         let reply = self.send_rfc8181_and_validate_response(
             ca_handle,
             repository,
+            signing_key,
             rfc8181::Message::list_query().into_bytes(),
         )?;
 
@@ -974,9 +1526,14 @@ impl<S: Signer> CaServer<S> {
         delta: PublishDelta,
     ) -> KrillResult<()> {
         let message = rfc8181::Message::publish_delta_query(delta);
+        let signing_key = self.get_ca(ca_handle)?.id_key().clone();
 
-        let reply =
-            self.send_rfc8181_and_validate_response(ca_handle, repository, message.into_bytes())?;
+        let reply = self.send_rfc8181_and_validate_response(
+            ca_handle,
+            repository,
+            &signing_key,
+            message.into_bytes(),
+        )?;
 
         match reply {
             rfc8181::ReplyMessage::SuccessReply => Ok(()),
@@ -1000,6 +1557,186 @@ impl<S: Signer> CaServer<S> {
         let cmd = CmdDet::route_authorizations_update(&handle, updates, self.signer.clone());
         self.send_command(cmd)
     }
+
+    /// Update the routes authorized by a CA, and return the lint warnings
+    /// introduced by the update. If the update is strict, or the server is
+    /// configured to be strict, then any introduced warning causes the
+    /// update to be rejected instead.
+    pub fn ca_routes_update_linted(
+        &self,
+        handle: Handle,
+        updates: RoaDefinitionUpdates,
+        strict: bool,
+    ) -> KrillResult<RoaLintReport> {
+        self.ca_routes_update_linted_with_meta(handle, updates, vec![], strict)
+    }
+
+    /// Update the routes authorized by a CA like `ca_routes_update_linted`,
+    /// and set the meta-information for the added routes as part of the
+    /// same update.
+    pub fn ca_routes_update_linted_with_meta(
+        &self,
+        handle: Handle,
+        updates: RoaDefinitionUpdates,
+        meta: Vec<ConfiguredRoa>,
+        strict: bool,
+    ) -> KrillResult<RoaLintReport> {
+        let introduced = self.ca_routes_lint_updates(&handle, &updates, strict)?;
+
+        let updates = RouteAuthorizationUpdates::from(updates).with_meta(meta);
+        self.ca_routes_update(handle, updates)?;
+
+        Ok(introduced)
+    }
+
+    /// Returns the lint warnings which the updates would introduce for a CA,
+    /// or an error if any of these are not informational and the update is
+    /// strict, or the server is configured to be strict.
+    fn ca_routes_lint_updates(
+        &self,
+        handle: &Handle,
+        updates: &RoaDefinitionUpdates,
+        strict: bool,
+    ) -> KrillResult<RoaLintReport> {
+        let ca = self.get_ca(handle)?;
+        let current = ca.roa_definitions();
+        let before = ca.roa_lint(&current);
+        let after = ca.roa_lint(&updates.apply_to(&current));
+
+        let introduced = after.new_since(&before);
+        if strict || self.roa_lint_strict {
+            let blocking = introduced.blocking();
+            if !blocking.is_empty() {
+                return Err(Error::CaAuthorisationLint(handle.clone(), blocking));
+            }
+        }
+        for warning in introduced.warnings() {
+            warn!("ROA update for CA '{}': {}", handle, warning);
+        }
+
+        Ok(introduced)
+    }
+
+    /// Update the meta-information (comment, tags, ticket) for routes authorized by a CA
+    pub fn ca_routes_meta_update(
+        &self,
+        handle: Handle,
+        roas: Vec<ConfiguredRoa>,
+    ) -> KrillResult<()> {
+        let cmd = CmdDet::route_authorizations_meta_update(&handle, roas);
+        self.send_command(cmd)
+    }
+
+    /// Set the time window for routes authorized by a CA, and apply the
+    /// schedule right away in case authorizations are due. The due changes
+    /// are linted like other updates, and stored together with the schedule
+    /// so that nothing is changed if they cannot be applied.
+    pub fn ca_routes_schedule(&self, handle: Handle, roas: Vec<ScheduledRoa>) -> KrillResult<()> {
+        let ca = self.get_ca(&handle)?;
+        let (added, removed) = ca.route_schedule_updates_for(&roas).unpack();
+
+        let mut due = RoaDefinitionUpdates::empty();
+        for auth in added {
+            due.add(*auth.as_ref());
+        }
+        for auth in removed {
+            due.remove(*auth.as_ref());
+        }
+        self.ca_routes_lint_updates(&handle, &due, false)?;
+
+        let cmd = CmdDet::route_authorizations_schedule(&handle, roas, self.signer.clone());
+        self.send_command(cmd)
+    }
+
+    /// Enable or disable AS0 ROAs for unused space of a CA.
+    pub fn ca_routes_as0_update(&self, handle: &Handle, enabled: bool) -> KrillResult<()> {
+        let cmd = CmdDet::route_authorizations_as0_update(handle, enabled, self.signer.clone());
+        self.send_command(cmd)
+    }
+
+    /// Keep route authorizations no longer covered by the resources of a CA
+    /// dormant, or remove them.
+    pub fn ca_routes_dormant_policy_update(
+        &self,
+        handle: &Handle,
+        policy: RoaDormantPolicy,
+    ) -> KrillResult<()> {
+        let cmd = CmdDet::route_authorizations_dormant_policy_update(handle, policy);
+        self.send_command(cmd)
+    }
+
+    /// Re-derive the ROAs for the current resources of a CA, including AS0
+    /// ROAs for unused space if enabled.
+    pub fn ca_routes_refresh(&self, handle: &Handle) -> KrillResult<()> {
+        let cmd = CmdDet::route_authorizations_refresh(handle, self.signer.clone());
+        self.send_command(cmd)
+    }
+
+    /// Add and remove scheduled route authorizations which are due, for all CAs.
+    pub fn ca_routes_apply_schedules(&self) {
+        for ca in self.ca_list().cas() {
+            if let Err(e) = self.ca_routes_apply_schedule(ca.handle()) {
+                error!(
+                    "Failed to apply ROA schedule for CA '{}', error: {}",
+                    ca.handle(),
+                    e
+                )
+            }
+        }
+    }
+
+    /// Add and remove scheduled route authorizations which are due for a CA.
+    ///
+    /// Each due authorization is applied on its own, and subject to the
+    /// same lint checks as other updates. The schedule of an authorization
+    /// which cannot be applied is dropped, so that it does not block other
+    /// scheduled changes or fail again on every run. The first such error is
+    /// returned after all due authorizations have been handled.
+    pub fn ca_routes_apply_schedule(&self, handle: &Handle) -> KrillResult<()> {
+        let ca = self.get_ca(handle)?;
+        let (added, removed) = ca.route_schedule_updates(Time::now()).unpack();
+
+        let mut first_error = None;
+
+        let due = added
+            .into_iter()
+            .map(|auth| (auth, true))
+            .chain(removed.into_iter().map(|auth| (auth, false)));
+
+        for (auth, add) in due {
+            let definition = *auth.as_ref();
+            let mut updates = RoaDefinitionUpdates::empty();
+            if add {
+                updates.add(definition);
+            } else {
+                updates.remove(definition);
+            }
+
+            info!("Applying ROA schedule for CA '{}': {}", handle, updates);
+            if let Err(e) = self.ca_routes_update_linted(handle.clone(), updates, false) {
+                error!(
+                    "Dropping schedule for ROA '{}' of CA '{}', error: {}",
+                    definition, handle, e
+                );
+                let cancel = ScheduledRoa::new(definition, RoaSchedule::default());
+                let cmd = CmdDet::route_authorizations_schedule(
+                    handle,
+                    vec![cancel],
+                    self.signer.clone(),
+                );
+                self.send_command(cmd)?;
+
+                if first_error.is_none() {
+                    first_error = Some(e);
+                }
+            }
+        }
+
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
 }
 
 
@@ -1042,8 +1779,18 @@ mod tests {
 
             let event_queue = Arc::new(EventQueueListener::in_mem());
 
-            let server = CaServer::<OpenSslSigner>::build(&d, event_queue, signer, 
-                    RoaPrefixGroupingStrategy::RoaPerPrefix).unwrap();
+            let server = CaServer::<OpenSslSigner>::build(
+                &d,
+                event_queue,
+                signer,
+                RoaPrefixGroupingStrategy::RoaPerPrefix,
+                Duration::minutes(10),
+                60,
+                0,
+                false,
+                ChildCertValidity::default(),
+            )
+            .unwrap();
 
             let repo_info = {
                 let base_uri = test::rsync("rsync://localhost/repo/ta/");
@@ -1061,4 +1808,82 @@ mod tests {
             assert!(server.get_trust_anchor().is_ok());
         })
     }
+
+    #[test]
+    fn limit_failed_invitation_attempts_per_peer() {
+        test::test_under_tmp(|d| {
+            let mut signer = OpenSslSigner::build(&d).unwrap();
+            let child_id_cert = test::id_cert(&mut signer);
+            let signer = Arc::new(RwLock::new(signer));
+
+            let event_queue = Arc::new(EventQueueListener::in_mem());
+
+            let server = CaServer::<OpenSslSigner>::build(
+                &d,
+                event_queue,
+                signer,
+                RoaPrefixGroupingStrategy::RoaPerPrefix,
+                Duration::minutes(10),
+                60,
+                2,
+                false,
+                ChildCertValidity::default(),
+            )
+            .unwrap();
+
+            let repo_info = {
+                let base_uri = test::rsync("rsync://localhost/repo/ta/");
+                let rrdp_uri = test::https("https://localhost/repo/notification.xml");
+                RepoInfo::new(base_uri, rrdp_uri)
+            };
+            let ta_uri = test::https("https://localhost/ta/ta.cer");
+            let ta_aia = test::rsync("rsync://localhost/repo/ta.cer");
+            server.init_ta(repo_info, ta_aia, vec![ta_uri]).unwrap();
+
+            let ta = ta_handle();
+            let resources = ResourceSet::from_strs("", "10.0.0.0/24", "").unwrap();
+            let init = ChildInvitationInit::new(resources, Time::now() + Duration::hours(1));
+            let invitation = server.ca_child_invitation_add(&ta, init).unwrap();
+
+            let service_uri = test::https("https://localhost/");
+            let child = Handle::from_str_unsafe("child");
+            let req = rfc8183::ChildRequest::new(child, child_id_cert);
+
+            // Only failed attempts are counted, for the peer which made them
+            for _ in 0..2 {
+                match server.ca_child_invitation_accept(
+                    &ta,
+                    Token::from("guess"),
+                    req.clone(),
+                    &service_uri,
+                    "192.0.2.1",
+                ) {
+                    Err(Error::CaChildInvitationRateLimited(_)) => panic!("Rate limited too soon"),
+                    Err(_) => {}
+                    Ok(_) => panic!("Expected unknown invitation"),
+                }
+            }
+            match server.ca_child_invitation_accept(
+                &ta,
+                Token::from("guess"),
+                req.clone(),
+                &service_uri,
+                "192.0.2.1",
+            ) {
+                Err(Error::CaChildInvitationRateLimited(_)) => {}
+                _ => panic!("Expected rate limited"),
+            }
+
+            // Other peers can still accept their invitations
+            assert!(server
+                .ca_child_invitation_accept(
+                    &ta,
+                    invitation.token().clone(),
+                    req,
+                    &service_uri,
+                    "192.0.2.2",
+                )
+                .is_ok());
+        })
+    }
 }
@@ -137,6 +137,7 @@ impl SignSupport {
     pub fn make_issued_cert<S: Signer>(
         csr: CsrInfo,
         resources: &ResourceSet,
+        validity: Validity,
         limit: RequestResourceLimit,
         replaces: Option<ReplacedObject>,
         signing_key: &CertifiedKey,
@@ -155,8 +156,6 @@ impl SignSupport {
         let serial = { Serial::random(signer).map_err(ca::Error::signer)? };
         let issuer = signing_cert.cert().subject().clone();
 
-        let validity = Validity::new(Time::five_minutes_ago(), Time::next_year());
-
         let subject = Some(Name::from_pub_key(&pub_key));
 
         let key_usage = KeyUsage::Ca;
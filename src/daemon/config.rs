@@ -3,9 +3,10 @@ use std::fs::File;
 use std::io;
 use std::io::Read;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
-use std::path::PathBuf;
+use std::path::{Component, PathBuf};
 use std::str::FromStr;
 
+use chrono::Duration;
 use clap::{App, Arg};
 use log::{error, LevelFilter};
 use serde::de;
@@ -16,11 +17,13 @@ use toml;
 
 use rpki::uri;
 
-use crate::commons::api::Token;
+use crate::commons::api::rrdp::RrdpRetention;
+use crate::commons::api::{ChildCertValidity, Token};
 use crate::commons::util::ext_serde;
 use crate::constants::*;
 use crate::daemon::http::ssl;
 use crate::daemon::ca::RoaPrefixGroupingStrategy;
+use crate::pubd::{OutputKind, OutputTarget, PubServerSettings};
 //------------ ConfigDefaults ------------------------------------------------
 
 pub struct ConfigDefaults;
@@ -97,6 +100,66 @@ impl ConfigDefaults {
     fn roa_prefix_grouping_strategy() -> RoaPrefixGroupingStrategy {
         RoaPrefixGroupingStrategy::RoaPerPrefix
     }
+
+    fn roa_lint_strict() -> bool {
+        false
+    }
+
+    fn child_cert_validity_weeks() -> i64 {
+        CHILD_CERTIFICATE_VALIDITY_WEEKS
+    }
+
+    fn child_cert_reissue_weeks() -> i64 {
+        CHILD_CERTIFICATE_REISSUE_WEEKS
+    }
+
+    fn protocol_message_max_age_seconds() -> i64 {
+        600
+    }
+
+    fn rfc6492_rate_limit_per_minute() -> u32 {
+        60
+    }
+
+    fn rfc8181_rate_limit_per_minute() -> u32 {
+        60
+    }
+
+    fn invitation_rate_limit_per_minute() -> u32 {
+        10
+    }
+
+    fn repo_validate_objects() -> bool {
+        false
+    }
+
+    fn rrdp_delta_max_count() -> usize {
+        RRDP_DELTA_MAX_COUNT
+    }
+
+    fn rrdp_delta_max_age_seconds() -> i64 {
+        RRDP_DELTA_MAX_AGE_SECONDS
+    }
+
+    fn rrdp_delta_size_ratio() -> f64 {
+        RRDP_DELTA_SIZE_RATIO
+    }
+
+    fn rrdp_delta_removal_grace_seconds() -> i64 {
+        RRDP_DELTA_REMOVAL_GRACE_SECONDS
+    }
+
+    fn rrdp_batch_window_millis() -> u64 {
+        RRDP_BATCH_WINDOW_MILLIS
+    }
+
+    fn repo_rrdp_output_dirs() -> Vec<PathBuf> {
+        vec![]
+    }
+
+    fn repo_rsync_output_dirs() -> Vec<PathBuf> {
+        vec![]
+    }
 }
 
 //------------ Config --------------------------------------------------------
@@ -106,6 +169,10 @@ impl ConfigDefaults {
 /// This will parse a default config file ('./defaults/krill.conf') unless
 /// another file is explicitly specified. Command line arguments may be used
 /// to override any of the settings in the config file.
+///
+/// A standalone publication server reads its settings from a PubdConfig,
+/// which only allows the settings for the publication server. Its default
+/// config file is './defaults/krillpubd.conf'.
 #[derive(Debug, Deserialize)]
 pub struct Config {
     #[serde(default = "ConfigDefaults::ip")]
@@ -169,6 +236,194 @@ pub struct Config {
 
     #[serde(default = "ConfigDefaults::roa_prefix_grouping_strategy")]
     pub roa_prefix_grouping_strategy: RoaPrefixGroupingStrategy,
+
+    #[serde(default = "ConfigDefaults::roa_lint_strict")]
+    pub roa_lint_strict: bool,
+
+    #[serde(default = "ConfigDefaults::child_cert_validity_weeks")]
+    child_cert_validity_weeks: i64,
+
+    #[serde(default = "ConfigDefaults::child_cert_reissue_weeks")]
+    child_cert_reissue_weeks: i64,
+
+    #[serde(default)]
+    pub suspend_child_after_inactive_hours: Option<i64>,
+
+    #[serde(default = "ConfigDefaults::protocol_message_max_age_seconds")]
+    protocol_message_max_age_seconds: i64,
+
+    #[serde(default = "ConfigDefaults::rfc6492_rate_limit_per_minute")]
+    pub rfc6492_rate_limit_per_minute: u32,
+
+    #[serde(default = "ConfigDefaults::rfc8181_rate_limit_per_minute")]
+    pub rfc8181_rate_limit_per_minute: u32,
+
+    #[serde(default = "ConfigDefaults::invitation_rate_limit_per_minute")]
+    pub invitation_rate_limit_per_minute: u32,
+
+    #[serde(default = "ConfigDefaults::repo_validate_objects")]
+    pub repo_validate_objects: bool,
+
+    #[serde(default = "ConfigDefaults::rrdp_delta_max_count")]
+    rrdp_delta_max_count: usize,
+
+    #[serde(default = "ConfigDefaults::rrdp_delta_max_age_seconds")]
+    rrdp_delta_max_age_seconds: i64,
+
+    #[serde(default = "ConfigDefaults::rrdp_delta_size_ratio")]
+    rrdp_delta_size_ratio: f64,
+
+    #[serde(default = "ConfigDefaults::rrdp_delta_removal_grace_seconds")]
+    rrdp_delta_removal_grace_seconds: i64,
+
+    #[serde(default = "ConfigDefaults::rrdp_batch_window_millis")]
+    rrdp_batch_window_millis: u64,
+
+    #[serde(default = "ConfigDefaults::repo_rrdp_output_dirs")]
+    repo_rrdp_output_dirs: Vec<PathBuf>,
+
+    #[serde(default = "ConfigDefaults::repo_rsync_output_dirs")]
+    repo_rsync_output_dirs: Vec<PathBuf>,
+
+    // Set for a standalone publication server, cannot be set in the file
+    #[serde(skip)]
+    pubd_only: bool,
+}
+
+//------------ PubdConfig ----------------------------------------------------
+
+/// The settings of a standalone publication server, as read from its config
+/// file. This is a subset of the settings in the Config. Settings which only
+/// apply to CAs are rejected, rather than ignored silently.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PubdConfig {
+    #[serde(default = "ConfigDefaults::ip")]
+    ip: IpAddr,
+
+    #[serde(default = "ConfigDefaults::port")]
+    port: u16,
+
+    #[serde(default = "ConfigDefaults::test_mode")]
+    test_mode: bool,
+
+    #[serde(default = "ConfigDefaults::https_mode")]
+    https_mode: HttpsMode,
+
+    #[serde(default = "ConfigDefaults::data_dir")]
+    data_dir: PathBuf,
+
+    #[serde(default = "ConfigDefaults::rsync_base")]
+    rsync_base: uri::Rsync,
+
+    #[serde(default = "ConfigDefaults::service_uri")]
+    service_uri: String,
+
+    rrdp_service_uri: Option<String>,
+
+    #[serde(
+        default = "ConfigDefaults::log_level",
+        deserialize_with = "ext_serde::de_level_filter"
+    )]
+    log_level: LevelFilter,
+
+    #[serde(default = "ConfigDefaults::log_type")]
+    log_type: LogType,
+
+    #[serde(default = "ConfigDefaults::log_file")]
+    log_file: PathBuf,
+
+    #[serde(default = "ConfigDefaults::syslog_facility")]
+    syslog_facility: String,
+
+    #[serde(default = "ConfigDefaults::auth_token")]
+    auth_token: Token,
+
+    #[serde(default = "ConfigDefaults::post_limit_api")]
+    post_limit_api: usize,
+
+    #[serde(default = "ConfigDefaults::post_limit_rfc8181")]
+    post_limit_rfc8181: usize,
+
+    #[serde(default = "ConfigDefaults::protocol_message_max_age_seconds")]
+    protocol_message_max_age_seconds: i64,
+
+    #[serde(default = "ConfigDefaults::rfc8181_rate_limit_per_minute")]
+    rfc8181_rate_limit_per_minute: u32,
+
+    #[serde(default = "ConfigDefaults::invitation_rate_limit_per_minute")]
+    invitation_rate_limit_per_minute: u32,
+
+    #[serde(default = "ConfigDefaults::repo_validate_objects")]
+    repo_validate_objects: bool,
+
+    #[serde(default = "ConfigDefaults::rrdp_delta_max_count")]
+    rrdp_delta_max_count: usize,
+
+    #[serde(default = "ConfigDefaults::rrdp_delta_max_age_seconds")]
+    rrdp_delta_max_age_seconds: i64,
+
+    #[serde(default = "ConfigDefaults::rrdp_delta_size_ratio")]
+    rrdp_delta_size_ratio: f64,
+
+    #[serde(default = "ConfigDefaults::rrdp_delta_removal_grace_seconds")]
+    rrdp_delta_removal_grace_seconds: i64,
+
+    #[serde(default = "ConfigDefaults::rrdp_batch_window_millis")]
+    rrdp_batch_window_millis: u64,
+
+    #[serde(default = "ConfigDefaults::repo_rrdp_output_dirs")]
+    repo_rrdp_output_dirs: Vec<PathBuf>,
+
+    #[serde(default = "ConfigDefaults::repo_rsync_output_dirs")]
+    repo_rsync_output_dirs: Vec<PathBuf>,
+}
+
+impl PubdConfig {
+    /// Converts into a Config for a standalone publication server. The
+    /// embedded repository is always enabled, the embedded TA is never used,
+    /// and the settings for CAs get their defaults.
+    pub fn into_config(self) -> Config {
+        Config {
+            ip: self.ip,
+            port: self.port,
+            test_mode: self.test_mode,
+            use_ta: false,
+            repo_enabled: true,
+            https_mode: self.https_mode,
+            data_dir: self.data_dir,
+            rsync_base: self.rsync_base,
+            service_uri: self.service_uri,
+            rrdp_service_uri: self.rrdp_service_uri,
+            log_level: self.log_level,
+            log_type: self.log_type,
+            log_file: self.log_file,
+            syslog_facility: self.syslog_facility,
+            auth_token: self.auth_token,
+            ca_refresh: ConfigDefaults::ca_refresh(),
+            post_limit_api: self.post_limit_api,
+            post_limit_rfc8181: self.post_limit_rfc8181,
+            post_limit_rfc6492: ConfigDefaults::post_limit_rfc6492(),
+            roa_prefix_grouping_strategy: ConfigDefaults::roa_prefix_grouping_strategy(),
+            roa_lint_strict: ConfigDefaults::roa_lint_strict(),
+            child_cert_validity_weeks: ConfigDefaults::child_cert_validity_weeks(),
+            child_cert_reissue_weeks: ConfigDefaults::child_cert_reissue_weeks(),
+            suspend_child_after_inactive_hours: None,
+            protocol_message_max_age_seconds: self.protocol_message_max_age_seconds,
+            rfc6492_rate_limit_per_minute: ConfigDefaults::rfc6492_rate_limit_per_minute(),
+            rfc8181_rate_limit_per_minute: self.rfc8181_rate_limit_per_minute,
+            invitation_rate_limit_per_minute: self.invitation_rate_limit_per_minute,
+            repo_validate_objects: self.repo_validate_objects,
+            rrdp_delta_max_count: self.rrdp_delta_max_count,
+            rrdp_delta_max_age_seconds: self.rrdp_delta_max_age_seconds,
+            rrdp_delta_size_ratio: self.rrdp_delta_size_ratio,
+            rrdp_delta_removal_grace_seconds: self.rrdp_delta_removal_grace_seconds,
+            rrdp_batch_window_millis: self.rrdp_batch_window_millis,
+            repo_rrdp_output_dirs: self.repo_rrdp_output_dirs,
+            repo_rsync_output_dirs: self.repo_rsync_output_dirs,
+            pubd_only: true,
+        }
+    }
 }
 
 /// # Accessors
@@ -213,6 +468,60 @@ impl Config {
     pub fn use_ta(&self) -> bool {
         self.use_ta
     }
+
+    /// Whether this is the config for a standalone publication server,
+    /// which runs without any CAs.
+    pub fn pubd_only(&self) -> bool {
+        self.pubd_only
+    }
+
+    /// The default validity for certificates issued to children, used
+    /// unless a validity is specified when the child is added.
+    pub fn child_cert_validity(&self) -> ChildCertValidity {
+        ChildCertValidity::new(
+            self.child_cert_validity_weeks,
+            self.child_cert_reissue_weeks,
+        )
+    }
+
+    /// Which deltas are listed in the RRDP notification file, and how long
+    /// delta files are kept on disk after they are no longer listed.
+    pub fn rrdp_retention(&self) -> RrdpRetention {
+        RrdpRetention::new(
+            self.rrdp_delta_max_count,
+            Duration::seconds(self.rrdp_delta_max_age_seconds),
+            self.rrdp_delta_size_ratio,
+            Duration::seconds(self.rrdp_delta_removal_grace_seconds),
+        )
+    }
+
+    /// How the publication server handles published objects, which dirs it
+    /// writes the RRDP and rsync files to in addition to the dirs under the
+    /// data dir, and how long it waits to combine concurrent publications
+    /// into a single RRDP delta.
+    pub fn pub_server_settings(&self) -> PubServerSettings {
+        let rrdp = self
+            .repo_rrdp_output_dirs
+            .iter()
+            .map(|dir| OutputTarget::new(OutputKind::Rrdp, dir.clone()));
+        let rsync = self
+            .repo_rsync_output_dirs
+            .iter()
+            .map(|dir| OutputTarget::new(OutputKind::Rsync, dir.clone()));
+
+        PubServerSettings::new(
+            self.repo_validate_objects,
+            self.rrdp_retention(),
+            rrdp.chain(rsync).collect(),
+            std::time::Duration::from_millis(self.rrdp_batch_window_millis),
+        )
+    }
+
+    /// The maximum difference between the signing time of a received RFC
+    /// 6492 or RFC 8181 message and the current time.
+    pub fn protocol_message_max_age(&self) -> Duration {
+        Duration::seconds(self.protocol_message_max_age_seconds)
+    }
 }
 
 /// # Create
@@ -239,6 +548,23 @@ impl Config {
         let post_limit_rfc8181 = ConfigDefaults::post_limit_rfc8181();
         let post_limit_rfc6492 = ConfigDefaults::post_limit_rfc6492();
         let roa_prefix_grouping_strategy = ConfigDefaults::roa_prefix_grouping_strategy();
+        let roa_lint_strict = ConfigDefaults::roa_lint_strict();
+        let child_cert_validity_weeks = ConfigDefaults::child_cert_validity_weeks();
+        let child_cert_reissue_weeks = ConfigDefaults::child_cert_reissue_weeks();
+        let suspend_child_after_inactive_hours = None;
+        let protocol_message_max_age_seconds = ConfigDefaults::protocol_message_max_age_seconds();
+        let rfc6492_rate_limit_per_minute = ConfigDefaults::rfc6492_rate_limit_per_minute();
+        let rfc8181_rate_limit_per_minute = ConfigDefaults::rfc8181_rate_limit_per_minute();
+        let invitation_rate_limit_per_minute = ConfigDefaults::invitation_rate_limit_per_minute();
+        let repo_validate_objects = ConfigDefaults::repo_validate_objects();
+        let rrdp_delta_max_count = ConfigDefaults::rrdp_delta_max_count();
+        let rrdp_delta_max_age_seconds = ConfigDefaults::rrdp_delta_max_age_seconds();
+        let rrdp_delta_size_ratio = ConfigDefaults::rrdp_delta_size_ratio();
+        let rrdp_delta_removal_grace_seconds = ConfigDefaults::rrdp_delta_removal_grace_seconds();
+        let rrdp_batch_window_millis = ConfigDefaults::rrdp_batch_window_millis();
+        let repo_rrdp_output_dirs = ConfigDefaults::repo_rrdp_output_dirs();
+        let repo_rsync_output_dirs = ConfigDefaults::repo_rsync_output_dirs();
+        let pubd_only = false;
 
         Config {
             ip,
@@ -261,6 +587,23 @@ impl Config {
             post_limit_rfc8181,
             post_limit_rfc6492,
             roa_prefix_grouping_strategy,
+            roa_lint_strict,
+            child_cert_validity_weeks,
+            child_cert_reissue_weeks,
+            suspend_child_after_inactive_hours,
+            protocol_message_max_age_seconds,
+            rfc6492_rate_limit_per_minute,
+            rfc8181_rate_limit_per_minute,
+            invitation_rate_limit_per_minute,
+            repo_validate_objects,
+            rrdp_delta_max_count,
+            rrdp_delta_max_age_seconds,
+            rrdp_delta_size_ratio,
+            rrdp_delta_removal_grace_seconds,
+            rrdp_batch_window_millis,
+            repo_rrdp_output_dirs,
+            repo_rsync_output_dirs,
+            pubd_only,
         }
     }
 
@@ -275,44 +618,67 @@ impl Config {
         let mut config = Self::test_config(data_dir);
         config.port = 3001;
         config.use_ta = false;
+        config.pubd_only = true;
         config.service_uri = "https://localhost:3001/".to_string();
         config.rsync_base = uri::Rsync::from_str("rsync://remotehost/repo/").unwrap();
         config
     }
 
-    pub fn get_config_filename() -> String {
-        let matches = App::new(KRILL_SERVER_APP)
+    pub fn get_config_filename(app: &str, default_file: &str) -> String {
+        let help = format!(
+            "Specify non-default config file. If no file is \
+             specified '{}' will be used to \
+             determine default values for all settings. Note that you \
+             can use any of the following options to override any of \
+             these values..",
+            default_file
+        );
+
+        let matches = App::new(app)
             .version(KRILL_VERSION)
             .arg(
                 Arg::with_name("config")
                     .short("c")
                     .long("config")
                     .value_name("FILE")
-                    .help(
-                        "Specify non-default config file. If no file is \
-                         specified './daemon/defaults/krill.conf' will be used to \
-                         determine default values for all settings. Note that you \
-                         can use any of the following options to override any of \
-                         these values..",
-                    )
+                    .help(help.as_str())
                     .required(false),
             )
             .get_matches();
 
-        let config_file = matches
-            .value_of("config")
-            .unwrap_or(KRILL_DEFAULT_CONFIG_FILE);
+        let config_file = matches.value_of("config").unwrap_or(default_file);
 
         config_file.to_string()
     }
 
     /// Creates the config (at startup). Panics in case of issues.
     pub fn create() -> Result<Self, ConfigError> {
-        let config_file = Self::get_config_filename();
+        Self::create_for(
+            KRILL_SERVER_APP,
+            KRILL_DEFAULT_CONFIG_FILE,
+            Self::read_config,
+        )
+    }
+
+    /// Creates the config for a standalone publication server (at startup).
+    /// The config file may only contain settings for the publication server.
+    pub fn create_pubd() -> Result<Self, ConfigError> {
+        Self::create_for(
+            KRILL_PUBD_APP,
+            KRILL_PUBD_DEFAULT_CONFIG_FILE,
+            Self::read_pubd_config,
+        )
+    }
+
+    fn create_for<R>(app: &str, default_file: &str, read: R) -> Result<Self, ConfigError>
+    where
+        R: FnOnce(&str) -> Result<Self, ConfigError>,
+    {
+        let config_file = Self::get_config_filename(app, default_file);
 
-        let config = match Self::read_config(&config_file) {
+        let config = match read(&config_file) {
             Err(e) => {
-                if config_file == KRILL_DEFAULT_CONFIG_FILE {
+                if config_file == default_file {
                     Err(ConfigError::other(
                         "Cannot find config file. Please use --config to specify its location.",
                     ))
@@ -325,10 +691,7 @@ impl Config {
             }
             Ok(config) => {
                 config.init_logging()?;
-                info!(
-                    "{} uses configuration file: {}",
-                    KRILL_SERVER_APP, config_file
-                );
+                info!("{} uses configuration file: {}", app, config_file);
                 Ok(config)
             }
         }?;
@@ -383,6 +746,34 @@ impl Config {
             return Err(ConfigError::other("rsync base URI must end with a slash"));
         }
 
+        if !self.child_cert_validity().is_valid() {
+            return Err(ConfigError::other(
+                "child_cert_reissue_weeks must be shorter than child_cert_validity_weeks",
+            ));
+        }
+
+        if let Some(hours) = self.suspend_child_after_inactive_hours {
+            if hours < 1 {
+                return Err(ConfigError::other(
+                    "suspend_child_after_inactive_hours must be 1 or more",
+                ));
+            }
+        }
+
+        if self.protocol_message_max_age_seconds < 1 {
+            return Err(ConfigError::other(
+                "protocol_message_max_age_seconds must be 1 or more",
+            ));
+        }
+
+        if !self.rrdp_retention().is_valid() {
+            return Err(ConfigError::other(
+                "rrdp_delta_max_count, rrdp_delta_max_age_seconds and rrdp_delta_size_ratio must be more than 0, and rrdp_delta_removal_grace_seconds may not be negative",
+            ));
+        }
+
+        self.verify_output_dirs()?;
+
         if !self.service_uri.ends_with('/') {
             return Err(ConfigError::other("service URI must end with a slash"));
         }
@@ -397,9 +788,63 @@ impl Config {
             ));
         }
 
+        if self.use_ta && self.pubd_only {
+            return Err(ConfigError::other(
+                "Cannot use embedded TA in a standalone publication server",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Verifies that the RRDP and rsync output dirs do not overlap with the
+    /// dirs under the data dir which are always written to, or with each
+    /// other. Writing to a target removes files which it does not expect,
+    /// so overlapping targets would remove each other's files.
+    fn verify_output_dirs(&self) -> Result<(), ConfigError> {
+        let mut repo_dir = self.data_dir.clone();
+        repo_dir.push(REPOSITORY_DIR);
+
+        let mut dirs = vec![
+            Self::absolute_dir(&repo_dir.join(REPOSITORY_RRDP_DIR)),
+            Self::absolute_dir(&repo_dir.join(REPOSITORY_RSYNC_DIR)),
+        ];
+
+        let output_dirs = self
+            .repo_rrdp_output_dirs
+            .iter()
+            .chain(self.repo_rsync_output_dirs.iter());
+
+        for output_dir in output_dirs {
+            let dir = Self::absolute_dir(output_dir);
+            if dirs
+                .iter()
+                .any(|other| dir.starts_with(other) || other.starts_with(&dir))
+            {
+                return Err(ConfigError::Other(format!(
+                    "Output dir '{}' overlaps with the repository dirs in the data dir, or with another output dir",
+                    output_dir.to_string_lossy()
+                )));
+            }
+            dirs.push(dir);
+        }
+
         Ok(())
     }
 
+    /// Returns the dir as an absolute path, without resolving links, so that
+    /// it can be compared to other dirs which may not exist yet.
+    fn absolute_dir(dir: &PathBuf) -> PathBuf {
+        let dir = if dir.is_absolute() {
+            dir.clone()
+        } else {
+            env::current_dir().unwrap_or_default().join(dir)
+        };
+        dir.components()
+            .filter(|component| component != &Component::CurDir)
+            .collect()
+    }
+
     pub fn read_config(file: &str) -> Result<Self, ConfigError> {
         let mut v = Vec::new();
         let mut f = File::open(file)?;
@@ -409,6 +854,16 @@ impl Config {
         Ok(c)
     }
 
+    /// Reads the config file of a standalone publication server.
+    pub fn read_pubd_config(file: &str) -> Result<Self, ConfigError> {
+        let mut v = Vec::new();
+        let mut f = File::open(file)?;
+        f.read_to_end(&mut v)?;
+
+        let c: PubdConfig = toml::from_slice(v.as_slice())?;
+        Ok(c.into_config())
+    }
+
     pub fn init_logging(&self) -> Result<(), ConfigError> {
         match self.log_type {
             LogType::File => self.file_logger(&self.log_file),
@@ -640,4 +1095,56 @@ mod tests {
         assert_eq!(c.socket_addr(), expected_socket_addr);
     }
 
+    #[test]
+    fn should_parse_default_pubd_config_file() {
+        use std::env;
+        env::set_var("KRILL_AUTH_TOKEN", "secret");
+        env::set_var("KRILL_TEST", "1");
+
+        let c = Config::read_pubd_config("./defaults/krillpubd.conf").unwrap();
+        let expected_socket_addr = ([127, 0, 0, 1], 3000).into();
+        assert_eq!(c.socket_addr(), expected_socket_addr);
+        assert!(c.pubd_only());
+        assert!(c.repo_enabled);
+        assert!(!c.use_ta());
+    }
+
+    #[test]
+    fn should_reject_ca_settings_in_pubd_config() {
+        use std::env;
+        env::set_var("KRILL_AUTH_TOKEN", "secret");
+
+        let pubd_config = |content: &str| toml::from_str::<PubdConfig>(content);
+
+        assert!(pubd_config("rrdp_delta_max_count = 10").is_ok());
+        assert!(pubd_config("ca_refresh = 600").is_err());
+        assert!(pubd_config("use_ta = true").is_err());
+        assert!(pubd_config("repo_enabled = false").is_err());
+        assert!(pubd_config("child_cert_validity_weeks = 10").is_err());
+    }
+
+    #[test]
+    fn should_reject_overlapping_output_dirs() {
+        let data_dir = PathBuf::from("./data");
+
+        let mut c = Config::test_config(&data_dir);
+        c.repo_rrdp_output_dirs = vec![PathBuf::from("/var/www/rrdp")];
+        c.repo_rsync_output_dirs = vec![PathBuf::from("/var/rsync/repo")];
+        assert!(c.verify().is_ok());
+
+        // Inside the RRDP dir in the data dir
+        c.repo_rrdp_output_dirs = vec![PathBuf::from("data/repo/rrdp/extra")];
+        assert!(c.verify().is_err());
+
+        // Containing the rsync dir in the data dir
+        c.repo_rrdp_output_dirs = vec![];
+        c.repo_rsync_output_dirs = vec![PathBuf::from("./data/repo")];
+        assert!(c.verify().is_err());
+
+        // The same dir for RRDP and rsync
+        c.repo_rrdp_output_dirs = vec![PathBuf::from("/var/www/repo")];
+        c.repo_rsync_output_dirs = vec![PathBuf::from("/var/www/repo/")];
+        assert!(c.verify().is_err());
+    }
+
 }
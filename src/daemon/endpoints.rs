@@ -1,13 +1,19 @@
 //! Process requests received, delegate, and wrap up the responses.
-use actix_web::http::StatusCode;
-use actix_web::web::{self, Json, Path};
-use actix_web::HttpResponse;
+use std::str::FromStr;
+
+use actix_web::http::{header, StatusCode};
+use actix_web::web::{self, Json, Path, Query};
+use actix_web::{HttpRequest, HttpResponse};
 use bytes::Bytes;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::commons::api::{
-    AddChildRequest, CertAuthInit, Handle, ParentCaContact, ParentCaReq, ParentHandle,
-    PublisherHandle, PublisherList, RepositoryUpdate, RoaDefinitionUpdates, UpdateChildRequest,
+    AddChildRequest, AuthorizationFmtError, CertAuthInit, ChildInvitationInit,
+    ChildInvitationUpdate, ChildResourceTransfer, ConfiguredRoa, Handle, HexEncodedHash,
+    ParentCaContact, ParentCaReq, ParentHandle, ParentMigrationReq, PublisherHandle,
+    PublisherInvitationInit, PublisherInvitationUpdate, PublisherList, PublisherQuota,
+    PublisherSummary, RepositoryUpdate, RoaDefinitionUpdates, RoaDefinitionsFormat,
+    RoaDormantPolicy, ScheduledRoa, Token, TokenHash, UpdateChildRequest,
 };
 use crate::commons::error::Error;
 use crate::commons::remote::{rfc6492, rfc8181, rfc8183};
@@ -165,6 +171,36 @@ pub fn metrics(server: web::Data<AppServer>) -> HttpResponse {
                 ));
             }
         }
+
+        res.push_str("\n");
+        res.push_str(
+            "# HELP krill_repo_next_update timestamp of earliest manifest or CRL next update for publisher\n",
+        );
+        res.push_str("# TYPE krill_repo_next_update gauge\n");
+        for (publisher, stats) in publishers {
+            if let Some(next_update) = stats.next_update() {
+                res.push_str(&format!(
+                    "krill_repo_next_update{{publisher=\"{}\"}} {}\n",
+                    publisher,
+                    next_update.timestamp()
+                ));
+            }
+        }
+
+        res.push_str("\n");
+        res.push_str(
+            "# HELP krill_repo_not_after timestamp of earliest manifest EE certificate expiry for publisher\n",
+        );
+        res.push_str("# TYPE krill_repo_not_after gauge\n");
+        for (publisher, stats) in publishers {
+            if let Some(not_after) = stats.not_after() {
+                res.push_str(&format!(
+                    "krill_repo_not_after{{publisher=\"{}\"}} {}\n",
+                    publisher,
+                    not_after.timestamp()
+                ));
+            }
+        }
     }
 
     let cas_status = server.read().cas_stats();
@@ -186,6 +222,19 @@ pub fn metrics(server: web::Data<AppServer>) -> HttpResponse {
         ));
     }
 
+    res.push_str("\n");
+    res.push_str(
+        "# HELP krill_cas_dormant_roas number of roas for CA not covered by its resources\n",
+    );
+    res.push_str("# TYPE krill_cas_dormant_roas gauge\n");
+    for (ca, status) in cas_status.iter() {
+        res.push_str(&format!(
+            "krill_cas_dormant_roas{{ca=\"{}\"}} {}\n",
+            ca,
+            status.dormant_roa_count()
+        ));
+    }
+
     res.push_str("\n");
     res.push_str("# HELP krill_cas_children number of children for CA\n");
     res.push_str("# TYPE krill_cas_children gauge\n");
@@ -197,6 +246,60 @@ pub fn metrics(server: web::Data<AppServer>) -> HttpResponse {
         ));
     }
 
+    let children_activity = server.read().children_activity();
+
+    res.push_str("\n");
+    res.push_str("# HELP krill_ca_child_last_request timestamp of last request by child\n");
+    res.push_str("# TYPE krill_ca_child_last_request gauge\n");
+    for (ca, children) in children_activity.iter() {
+        for (child, activity) in children.iter() {
+            if let (Some(time), Some(request_type)) =
+                (activity.last_request(), activity.last_request_type())
+            {
+                res.push_str(&format!(
+                    "krill_ca_child_last_request{{ca=\"{}\",child=\"{}\",type=\"{}\"}} {}\n",
+                    ca,
+                    child,
+                    request_type,
+                    time.timestamp()
+                ));
+            }
+        }
+    }
+
+    res.push_str("\n");
+    res.push_str("# HELP krill_ca_child_last_error timestamp of last error returned to child\n");
+    res.push_str("# TYPE krill_ca_child_last_error gauge\n");
+    for (ca, children) in children_activity.iter() {
+        for (child, activity) in children.iter() {
+            if let Some(time) = activity.last_error_time() {
+                res.push_str(&format!(
+                    "krill_ca_child_last_error{{ca=\"{}\",child=\"{}\"}} {}\n",
+                    ca,
+                    child,
+                    time.timestamp()
+                ));
+            }
+        }
+    }
+
+    let rejected_requests = server.read().children_rejected_requests();
+
+    res.push_str("\n");
+    res.push_str(
+        "# HELP krill_ca_rejected_request timestamp of last request not attributed to a child\n",
+    );
+    res.push_str("# TYPE krill_ca_rejected_request gauge\n");
+    for (ca, activity) in rejected_requests.iter() {
+        if let Some(time) = activity.last_error_time() {
+            res.push_str(&format!(
+                "krill_ca_rejected_request{{ca=\"{}\"}} {}\n",
+                ca,
+                time.timestamp()
+            ));
+        }
+    }
+
     HttpResponse::Ok().body(res)
 }
 
@@ -212,13 +315,23 @@ pub fn repo_stats(server: web::Data<AppServer>) -> HttpResponse {
 }
 
 /// Returns a list of publisher which have not updated for more
-/// than the given number of seconds.
+/// than the given number of seconds, or which have an expired manifest
+/// or CRL. The earliest expiry times of their manifests and CRLs are
+/// included.
 pub fn stale_publishers(server: web::Data<AppServer>, seconds: web::Path<i64>) -> HttpResponse {
     render_json_res(server.read().repo_stats().map(|stats| {
-        PublisherList::build(
-            &stats.stale_publishers(seconds.into_inner()),
-            "/api/v1/publishers",
-        )
+        let publishers = stats
+            .stale_publishers(seconds.into_inner())
+            .iter()
+            .map(|handle| {
+                let summary = PublisherSummary::from(handle, "/api/v1/publishers");
+                match stats.get_publishers().get(handle) {
+                    Some(p) => summary.with_expiry(p.next_update(), p.not_after()),
+                    None => summary,
+                }
+            })
+            .collect();
+        PublisherList::new(publishers)
     }))
 }
 
@@ -266,6 +379,117 @@ pub fn show_pbl(server: web::Data<AppServer>, auth: Auth, publisher: Path<Handle
     })
 }
 
+/// Returns the current objects of a publisher
+#[allow(clippy::needless_pass_by_value)]
+pub fn publisher_objects(
+    server: web::Data<AppServer>,
+    auth: Auth,
+    publisher: Path<Handle>,
+) -> HttpResponse {
+    if_api_allowed(&server, &auth, || {
+        render_json_res(server.read().publisher_objects(&publisher.into_inner()))
+    })
+}
+
+/// Returns a current object of a publisher, with its decoded content
+#[allow(clippy::needless_pass_by_value)]
+pub fn publisher_object(
+    server: web::Data<AppServer>,
+    auth: Auth,
+    publisher_and_hash: Path<(Handle, HexEncodedHash)>,
+) -> HttpResponse {
+    let (publisher, hash) = publisher_and_hash.into_inner();
+
+    if_api_allowed(&server, &auth, || {
+        render_json_res(server.read().publisher_object(&publisher, &hash))
+    })
+}
+
+/// Replaces the quota of a publisher
+#[allow(clippy::needless_pass_by_value)]
+pub fn update_publisher_quota(
+    server: web::Data<AppServer>,
+    auth: Auth,
+    publisher: Path<Handle>,
+    quota: Json<PublisherQuota>,
+) -> HttpResponse {
+    if_api_allowed(&server, &auth, || {
+        render_empty_res(
+            server
+                .read()
+                .update_publisher_quota(publisher.into_inner(), quota.into_inner()),
+        )
+    })
+}
+
+/// Starts a new RRDP session with a fresh snapshot of the current objects
+pub fn reset_rrdp_session(server: web::Data<AppServer>, auth: Auth) -> HttpResponse {
+    if_api_allowed(&server, &auth, || {
+        render_empty_res(server.read().reset_rrdp_session())
+    })
+}
+
+/// Starts an ID key roll for the repository server
+pub fn repo_id_roll_init(server: web::Data<AppServer>, auth: Auth) -> HttpResponse {
+    if_api_allowed(&server, &auth, || {
+        render_empty_res(server.read().repo_id_roll_init())
+    })
+}
+
+/// Activates the new ID key of the repository server
+pub fn repo_id_roll_activate(server: web::Data<AppServer>, auth: Auth) -> HttpResponse {
+    if_api_allowed(&server, &auth, || {
+        render_empty_res(server.read().repo_id_roll_activate())
+    })
+}
+
+/// Returns the publisher invitations which were not yet used or revoked
+pub fn publisher_invitations(server: web::Data<AppServer>, auth: Auth) -> HttpResponse {
+    if_api_allowed(&server, &auth, || {
+        render_json_res(server.read().publisher_invitations())
+    })
+}
+
+/// Invite a publisher, returns the invitation including its token
+pub fn publisher_invitation_add(
+    server: web::Data<AppServer>,
+    auth: Auth,
+    init: Json<PublisherInvitationInit>,
+) -> HttpResponse {
+    if_api_allowed(&server, &auth, || {
+        render_json_res(server.read().add_publisher_invitation(init.into_inner()))
+    })
+}
+
+pub fn publisher_invitation_update(
+    server: web::Data<AppServer>,
+    auth: Auth,
+    id: Path<String>,
+    update: Json<PublisherInvitationUpdate>,
+) -> HttpResponse {
+    if_api_allowed(&server, &auth, || {
+        render_empty_res(
+            server
+                .read()
+                .update_publisher_invitation(TokenHash::from(id.into_inner()), update.into_inner()),
+        )
+    })
+}
+
+pub fn publisher_invitation_revoke(
+    server: web::Data<AppServer>,
+    auth: Auth,
+    id: Path<String>,
+) -> HttpResponse {
+    if_api_allowed(&server, &auth, || {
+        render_empty_res(
+            server
+                .read()
+                .revoke_publisher_invitation(TokenHash::from(id.into_inner())),
+        )
+    })
+}
+
 //------------ Publication ---------------------------------------------------
 
 /// Processes an RFC8181 query and returns the appropriate response.
@@ -368,6 +592,81 @@ pub fn ca_child_update(
     })
 }
 
+pub fn ca_child_invitations(
+    server: web::Data<AppServer>,
+    ca: Path<Handle>,
+    auth: Auth,
+) -> HttpResponse {
+    if_api_allowed(&server, &auth, || {
+        render_json_res(server.read().ca_child_invitations(&ca.into_inner()))
+    })
+}
+
+/// Invite a child, returns the invitation including its token
+pub fn ca_child_invitation_add(
+    server: web::Data<AppServer>,
+    ca: Path<Handle>,
+    init: Json<ChildInvitationInit>,
+    auth: Auth,
+) -> HttpResponse {
+    if_api_allowed(&server, &auth, || {
+        render_json_res(
+            server
+                .read()
+                .ca_child_invitation_add(&ca.into_inner(), init.into_inner()),
+        )
+    })
+}
+
+pub fn ca_child_invitation_update(
+    server: web::Data<AppServer>,
+    ca_and_id: Path<(Handle, String)>,
+    update: Json<ChildInvitationUpdate>,
+    auth: Auth,
+) -> HttpResponse {
+    let (ca, id) = ca_and_id.into_inner();
+
+    if_api_allowed(&server, &auth, || {
+        render_empty_res(server.read().ca_child_invitation_update(
+            &ca,
+            TokenHash::from(id),
+            update.into_inner(),
+        ))
+    })
+}
+
+pub fn ca_child_invitation_revoke(
+    server: web::Data<AppServer>,
+    ca_and_id: Path<(Handle, String)>,
+    auth: Auth,
+) -> HttpResponse {
+    let (ca, id) = ca_and_id.into_inner();
+
+    if_api_allowed(&server, &auth, || {
+        render_empty_res(
+            server
+                .read()
+                .ca_child_invitation_revoke(&ca, TokenHash::from(id)),
+        )
+    })
+}
+
+/// Move prefixes, and their ROAs, from one child of a CA to another
+pub fn ca_child_transfer(
+    server: web::Data<AppServer>,
+    ca: Path<Handle>,
+    transfer: Json<ChildResourceTransfer>,
+    auth: Auth,
+) -> HttpResponse {
+    if_api_allowed(&server, &auth, || {
+        render_empty_res(
+            server
+                .read()
+                .ca_child_transfer(&ca.into_inner(), transfer.into_inner()),
+        )
+    })
+}
+
 pub fn ca_child_remove(
     server: web::Data<AppServer>,
     ca_and_child: Path<(Handle, Handle)>,
@@ -483,6 +782,16 @@ pub fn ca_regenerate_id(
     })
 }
 
+pub fn ca_activate_id(
+    server: web::Data<AppServer>,
+    auth: Auth,
+    handle: Path<Handle>,
+) -> HttpResponse {
+    if_api_allowed(&server, &auth, || {
+        render_empty_res(server.read().ca_activate_id(handle.into_inner()))
+    })
+}
+
 pub fn ca_info(server: web::Data<AppServer>, auth: Auth, handle: Path<Handle>) -> HttpResponse {
     if_api_allowed(&server, &auth, || {
         render_json_res(server.read().ca_info(&handle.into_inner()))
@@ -718,6 +1027,44 @@ pub fn ca_remove_parent(
     })
 }
 
+/// Start moving a CA from one parent to another
+pub fn ca_parent_migration_start(
+    server: web::Data<AppServer>,
+    auth: Auth,
+    ca: Path<Handle>,
+    req: Json<ParentMigrationReq>,
+) -> HttpResponse {
+    if_api_allowed(&server, &auth, || {
+        render_empty_res(
+            server
+                .read()
+                .ca_parent_migration_start(ca.into_inner(), req.into_inner()),
+        )
+    })
+}
+
+/// Show the progress of moving a CA from one parent to another
+pub fn ca_parent_migration_status(
+    server: web::Data<AppServer>,
+    auth: Auth,
+    ca: Path<Handle>,
+) -> HttpResponse {
+    if_api_allowed(&server, &auth, || {
+        render_json_res(server.read().ca_parent_migration_status(&ca.into_inner()))
+    })
+}
+
+/// Cancel moving a CA from one parent to another
+pub fn ca_parent_migration_cancel(
+    server: web::Data<AppServer>,
+    auth: Auth,
+    ca: Path<Handle>,
+) -> HttpResponse {
+    if_api_allowed(&server, &auth, || {
+        render_empty_res(server.read().ca_parent_migration_cancel(&ca.into_inner()))
+    })
+}
+
 /// Force a key roll for a CA, i.e. use a max key age of 0 seconds.
 pub fn ca_kr_init(server: web::Data<AppServer>, auth: Auth, handle: Path<Handle>) -> HttpResponse {
     if_api_allowed(&server, &auth, || {
@@ -736,20 +1083,136 @@ pub fn ca_kr_activate(
     })
 }
 
+/// Import route authorizations for this CA, in csv, json or slurm format.
+/// Authorizations which are already present are left alone. Content which
+/// cannot be parsed or validated results in a bad request, listing each
+/// offending line. Responds with the lint warnings introduced by the import.
+pub fn ca_routes_import(
+    server: web::Data<AppServer>,
+    auth: Auth,
+    handle_and_format: Path<(Handle, String)>,
+    params: Query<RoutesUpdateParams>,
+    bytes: Bytes,
+) -> HttpResponse {
+    if_api_allowed(&server, &auth, || {
+        let (handle, format) = handle_and_format.into_inner();
+        let format = match RoaDefinitionsFormat::from_str(&format) {
+            Ok(format) => format,
+            Err(_) => return api_not_found(),
+        };
+        let content = match String::from_utf8(bytes.to_vec()) {
+            Ok(content) => content,
+            Err(e) => {
+                let e = AuthorizationFmtError::auth(&e.to_string());
+                return server_error(Error::CaAuthorisationImport(handle, e));
+            }
+        };
+
+        render_json_res(
+            server
+                .read()
+                .ca_routes_import(handle, format, &content, params.strict),
+        )
+    })
+}
+
+fn render_roas_export(format: RoaDefinitionsFormat, res: Result<String, Error>) -> HttpResponse {
+    match res {
+        Ok(export) => HttpResponse::Ok()
+            .content_type(format.content_type())
+            .body(export),
+        Err(e) => server_error(e),
+    }
+}
+
+/// Export the route authorizations for this CA, in csv, json or slurm format
+pub fn ca_routes_export(
+    server: web::Data<AppServer>,
+    auth: Auth,
+    handle_and_format: Path<(Handle, String)>,
+) -> HttpResponse {
+    let (handle, format) = handle_and_format.into_inner();
+    if_api_allowed(&server, &auth, || {
+        match RoaDefinitionsFormat::from_str(&format) {
+            Ok(format) => {
+                render_roas_export(format, server.read().ca_routes_export(&handle, format))
+            }
+            Err(_) => api_not_found(),
+        }
+    })
+}
+
+/// Export the route authorizations for all CAs, in csv, json or slurm format
+pub fn cas_routes_export(
+    server: web::Data<AppServer>,
+    auth: Auth,
+    format: Path<String>,
+) -> HttpResponse {
+    if_api_allowed(&server, &auth, || {
+        match RoaDefinitionsFormat::from_str(&format.into_inner()) {
+            Ok(format) => render_roas_export(format, server.read().cas_routes_export(format)),
+            Err(_) => api_not_found(),
+        }
+    })
+}
+
 //------------ Admin: Force republish ----------------------------------------
 
-/// Update the route authorizations for this CA
+/// Query parameters for updating route authorizations.
+#[derive(Deserialize)]
+pub struct RoutesUpdateParams {
+    /// Reject the update if it introduces any lint warnings.
+    #[serde(default)]
+    strict: bool,
+}
+
+/// Update the route authorizations for this CA, responding with the lint
+/// warnings introduced by the update.
 pub fn ca_routes_update(
     server: web::Data<AppServer>,
     auth: Auth,
     handle: Path<Handle>,
+    params: Query<RoutesUpdateParams>,
     updates: Json<RoaDefinitionUpdates>,
+) -> HttpResponse {
+    if_api_allowed(&server, &auth, || {
+        render_json_res(server.read().ca_routes_update(
+            handle.into_inner(),
+            updates.into_inner(),
+            params.strict,
+        ))
+    })
+}
+
+/// Update the meta-information (comment, tags, ticket) of existing route
+/// authorizations for this CA
+pub fn ca_routes_meta_update(
+    server: web::Data<AppServer>,
+    auth: Auth,
+    handle: Path<Handle>,
+    roas: Json<Vec<ConfiguredRoa>>,
+) -> HttpResponse {
+    if_api_allowed(&server, &auth, || {
+        render_empty_res(
+            server
+                .read()
+                .ca_routes_meta_update(handle.into_inner(), roas.into_inner()),
+        )
+    })
+}
+
+/// set the time window for route authorizations for this CA
+pub fn ca_routes_schedule(
+    server: web::Data<AppServer>,
+    auth: Auth,
+    handle: Path<Handle>,
+    roas: Json<Vec<ScheduledRoa>>,
 ) -> HttpResponse {
     if_api_allowed(&server, &auth, || {
         render_empty_res(
             server
                 .read()
-                .ca_routes_update(handle.into_inner(), updates.into_inner()),
+                .ca_routes_schedule(handle.into_inner(), roas.into_inner()),
         )
     })
 }
@@ -769,6 +1232,112 @@ pub fn ca_routes_show(
     })
 }
 
+/// show whether AS0 ROAs are issued for unused space of this CA
+pub fn ca_routes_as0_show(
+    server: web::Data<AppServer>,
+    auth: Auth,
+    handle: Path<Handle>,
+) -> HttpResponse {
+    if_api_allowed(&server, &auth, || {
+        let handle = handle.into_inner();
+        match server.read().ca_routes_as0_show(&handle) {
+            Ok(info) => render_json(info),
+            Err(_) => api_not_found(),
+        }
+    })
+}
+
+/// issue AS0 ROAs for unused space of this CA
+pub fn ca_routes_as0_enable(
+    server: web::Data<AppServer>,
+    auth: Auth,
+    handle: Path<Handle>,
+) -> HttpResponse {
+    if_api_allowed(&server, &auth, || {
+        render_empty_res(
+            server
+                .read()
+                .ca_routes_as0_update(&handle.into_inner(), true),
+        )
+    })
+}
+
+/// stop issuing AS0 ROAs for unused space of this CA
+pub fn ca_routes_as0_disable(
+    server: web::Data<AppServer>,
+    auth: Auth,
+    handle: Path<Handle>,
+) -> HttpResponse {
+    if_api_allowed(&server, &auth, || {
+        render_empty_res(
+            server
+                .read()
+                .ca_routes_as0_update(&handle.into_inner(), false),
+        )
+    })
+}
+
+/// show the policy for ROAs no longer covered by the resources of this CA,
+/// and the ROAs which are currently dormant
+pub fn ca_routes_dormant_show(
+    server: web::Data<AppServer>,
+    auth: Auth,
+    handle: Path<Handle>,
+) -> HttpResponse {
+    if_api_allowed(&server, &auth, || {
+        let handle = handle.into_inner();
+        match server.read().ca_routes_dormant_show(&handle) {
+            Ok(info) => render_json(info),
+            Err(_) => api_not_found(),
+        }
+    })
+}
+
+/// keep ROAs no longer covered by the resources of this CA dormant
+pub fn ca_routes_dormant_keep(
+    server: web::Data<AppServer>,
+    auth: Auth,
+    handle: Path<Handle>,
+) -> HttpResponse {
+    if_api_allowed(&server, &auth, || {
+        render_empty_res(
+            server
+                .read()
+                .ca_routes_dormant_policy_update(&handle.into_inner(), RoaDormantPolicy::Keep),
+        )
+    })
+}
+
+/// remove ROAs no longer covered by the resources of this CA
+pub fn ca_routes_dormant_remove(
+    server: web::Data<AppServer>,
+    auth: Auth,
+    handle: Path<Handle>,
+) -> HttpResponse {
+    if_api_allowed(&server, &auth, || {
+        render_empty_res(
+            server
+                .read()
+                .ca_routes_dormant_policy_update(&handle.into_inner(), RoaDormantPolicy::Remove),
+        )
+    })
+}
+
+/// show the lint warnings for the route authorizations of this CA
+pub fn ca_routes_lint(
+    server: web::Data<AppServer>,
+    auth: Auth,
+    handle: Path<Handle>,
+) -> HttpResponse {
+    if_api_allowed(&server, &auth, || {
+        let handle = handle.into_inner();
+        match server.read().ca_routes_lint(&handle) {
+            Ok(report) => render_json(report),
+            Err(_) => api_not_found(),
+        }
+    })
+}
+
 //------------ Admin: Force republish ----------------------------------------
 
 pub fn republish_all(server: web::Data<AppServer>, auth: Auth) -> HttpResponse {
@@ -790,16 +1359,95 @@ pub fn refresh_all(server: web::Data<AppServer>, auth: Auth) -> HttpResponse {
     })
 }
 
+//------------ Invitations ---------------------------------------------------
+
+/// Returns the IP address of the peer which sent the request, or an empty
+/// string if it is not known. Forwarding headers are ignored, because they
+/// can be set to anything by the peer.
+fn peer_ip(req: &HttpRequest) -> String {
+    req.peer_addr()
+        .map(|addr| addr.ip().to_string())
+        .unwrap_or_default()
+}
+
+/// Adds a child which holds an invitation. The body is the RFC 8183 child
+/// request XML of the child, the RFC 8183 parent response XML is returned.
+pub fn ca_child_invitation_accept(
+    req: HttpRequest,
+    server: web::Data<AppServer>,
+    ca_and_token: Path<(Handle, String)>,
+    bytes: Bytes,
+) -> HttpResponse {
+    let (ca, token) = ca_and_token.into_inner();
+
+    let child_req = match rfc8183::ChildRequest::validate(bytes.as_ref())
+        .map_err(|e| Error::CaChildRequestInvalidXml(ca.clone(), e.to_string()))
+    {
+        Ok(child_req) => child_req,
+        Err(e) => return server_error(e),
+    };
+
+    match server.read().ca_child_invitation_accept(
+        &ca,
+        Token::from(token),
+        child_req,
+        &peer_ip(&req),
+    ) {
+        Ok(res) => HttpResponse::Ok()
+            .content_type("application/xml")
+            .body(res.encode_vec()),
+        Err(e) => server_error(e),
+    }
+}
+
+/// Adds a publisher which holds an invitation. The body is the RFC 8183
+/// publisher request XML of the publisher, the RFC 8183 repository response
+/// XML is returned.
+pub fn publisher_invitation_accept(
+    req: HttpRequest,
+    server: web::Data<AppServer>,
+    token: Path<String>,
+    bytes: Bytes,
+) -> HttpResponse {
+    let publisher_req = match rfc8183::PublisherRequest::validate(bytes.as_ref())
+        .map_err(|e| Error::PublisherRequestInvalidXml(e.to_string()))
+    {
+        Ok(publisher_req) => publisher_req,
+        Err(e) => return server_error(e),
+    };
+
+    match server.read().accept_publisher_invitation(
+        Token::from(token.into_inner()),
+        publisher_req,
+        &peer_ip(&req),
+    ) {
+        Ok(res) => HttpResponse::Ok()
+            .content_type("application/xml")
+            .body(res.encode_vec()),
+        Err(e) => server_error(e),
+    }
+}
+
 //------------ Provisioning (RFC6492) ----------------------------------------
 
 /// Process an RFC 6492 request
 ///
 pub fn rfc6492(
+    req: HttpRequest,
     server: web::Data<AppServer>,
     parent: Path<ParentHandle>,
     msg_bytes: Bytes,
 ) -> HttpResponse {
-    match server.read().rfc6492(parent.into_inner(), msg_bytes) {
+    let user_agent = req
+        .headers()
+        .get(header::USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string());
+
+    match server
+        .read()
+        .rfc6492(parent.into_inner(), msg_bytes, user_agent)
+    {
         Ok(bytes) => HttpResponse::build(StatusCode::OK)
             .content_type(rfc6492::CONTENT_TYPE)
             .body(bytes),
@@ -3,12 +3,15 @@
 //! Here we deal with booting and setup, and once active deal with parsing
 //! arguments and routing of requests, typically handing off to the
 //! daemon::api::endpoints functions for processing and responding.
+//!
+//! A standalone publication server only serves the routes for the
+//! publication server, i.e. no CA, TA or UI routes.
 use std::fs::File;
 use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 use actix_web::http::StatusCode;
 use actix_web::web::{delete, get, post, scope, Path};
-use actix_web::{guard, middleware, web, Resource};
+use actix_web::{guard, middleware, web, Resource, Scope};
 use actix_web::{App, HttpResponse, HttpServer};
 use openssl::ssl::{SslAcceptor, SslAcceptorBuilder, SslFiletype, SslMethod};
 
@@ -47,136 +50,247 @@ pub fn start(config: &Config) -> Result<(), Error> {
     let post_limit_rfc8181 = config.post_limit_rfc8181;
     let post_limit_rfc6492 = config.post_limit_rfc6492;
 
-    HttpServer::new(move || {
-        App::new()
+    let pubd_only = config.pubd_only();
+
+    let state_server = server.clone();
+
+    let res = HttpServer::new(move || {
+        // API end-points
+        let api = scope("/api/v1")
+            .data(web::JsonConfig::default().limit(post_limit_api))
+            // Let the UI check if it's authorized
+            .route("/authorized", get().to(api_authorized))
+            // Repositories and their publishers (both embedded and remote)
+            .route("/publishers", get().to(list_pbl))
+            .route("/publishers", post().to(add_pbl))
+            .route("/publishers/invitations", get().to(publisher_invitations))
+            .route(
+                "/publishers/invitations",
+                post().to(publisher_invitation_add),
+            )
+            .route(
+                "/publishers/invitations/{id}",
+                post().to(publisher_invitation_update),
+            )
+            .route(
+                "/publishers/invitations/{id}",
+                delete().to(publisher_invitation_revoke),
+            )
+            .route("/publishers/{handle}", get().to(show_pbl))
+            .route("/publishers/{handle}", delete().to(remove_pbl))
+            .route(
+                "/publishers/{handle}/quota",
+                post().to(update_publisher_quota),
+            )
+            .route("/publishers/{handle}/objects", get().to(publisher_objects))
+            .route(
+                "/publishers/{handle}/objects/{hash}",
+                get().to(publisher_object),
+            )
+            .route(
+                "/publishers/{handle}/response.xml",
+                get().to(repository_response_xml),
+            )
+            .route(
+                "/publishers/{handle}/response.json",
+                get().to(repository_response_json),
+            )
+            .route("/publishers/stale/{seconds}", get().to(stale_publishers))
+            .route("/pubd/reset_session", post().to(reset_rrdp_session))
+            .route("/pubd/id_roll_init", post().to(repo_id_roll_init))
+            .route("/pubd/id_roll_activate", post().to(repo_id_roll_activate));
+
+        // A standalone publication server has no CAs
+        let api = if pubd_only { api } else { ca_api(api) };
+
+        let app = App::new()
             .data(server.clone())
             .wrap(middleware::Logger::default())
             .route("/health", get().to(endpoints::health))
             .route("/metrics", get().to(metrics))
             .route("/stats/info", get().to(server_info))
             .route("/stats/repo", get().to(repo_stats))
-            .route("/stats/cas", get().to(cas_stats))
-            // API end-points
-            .service(
-                scope("/api/v1")
-                    .data(web::JsonConfig::default().limit(post_limit_api))
-                    // Let the UI check if it's authorized
-                    .route("/authorized", get().to(api_authorized))
-                    // Repositories and their publishers (both embedded and remote)
-                    .route("/publishers", get().to(list_pbl))
-                    .route("/publishers", post().to(add_pbl))
-                    .route("/publishers/{handle}", get().to(show_pbl))
-                    .route("/publishers/{handle}", delete().to(remove_pbl))
-                    .route(
-                        "/publishers/{handle}/response.xml",
-                        get().to(repository_response_xml),
-                    )
-                    .route(
-                        "/publishers/{handle}/response.json",
-                        get().to(repository_response_json),
-                    )
-                    .route("/publishers/stale/{seconds}", get().to(stale_publishers))
-                    // CAs (both embedded and remote)
-                    .route("/cas", post().to(ca_init))
-                    .route("/cas", get().to(cas))
-                    .route("/cas/issues", get().to(endpoints::all_ca_issues))
-                    .route("/cas/issues/{ca}", get().to(endpoints::ca_issues))
-                    .route("/cas/{ca}", get().to(ca_info))
-                    .route("/cas/{ca}/id", post().to(ca_regenerate_id))
-                    .route("/cas/{ca}/history", get().to(ca_history))
-                    .route("/cas/{ca}/child_request.xml", get().to(ca_child_req_xml))
-                    .route("/cas/{ca}/child_request.json", get().to(ca_child_req_json))
-                    .route("/cas/{ca}/repo", get().to(ca_repo_details))
-                    .route("/cas/{ca}/repo/state", get().to(ca_repo_state))
-                    .route(
-                        "/cas/{ca}/repo/request.json",
-                        get().to(ca_publisher_req_json),
-                    )
-                    .route("/cas/{ca}/repo/request.xml", get().to(ca_publisher_req_xml))
-                    .route("/cas/{ca}/repo", post().to(ca_repo_update))
-                    .route("/cas/{ca}/parents", post().to(ca_add_parent))
-                    .route(
-                        "/cas/{ca}/parents-xml/{parent}",
-                        post().to(ca_add_parent_xml),
-                    )
-                    .route("/cas/{ca}/parents/{parent}", get().to(ca_my_parent_contact))
-                    .route("/cas/{ca}/parents/{parent}", post().to(ca_update_parent))
-                    .route("/cas/{ca}/parents/{parent}", delete().to(ca_remove_parent))
-                    .route("/cas/{ca}/children", post().to(ca_add_child))
-                    .route(
-                        "/cas/{ca}/children/{child}/contact",
-                        get().to(ca_parent_contact),
-                    )
-                    .route(
-                        "/cas/{ca}/children/{child}/parent_response.json",
-                        get().to(ca_parent_res_json),
-                    )
-                    .route(
-                        "/cas/{ca}/children/{child}/parent_response.xml",
-                        get().to(ca_parent_res_xml),
-                    )
-                    .route("/cas/{ca}/children/{child}", get().to(ca_show_child))
-                    .route("/cas/{ca}/children/{child}", post().to(ca_child_update))
-                    .route("/cas/{ca}/children/{child}", delete().to(ca_child_remove))
-                    .route("/cas/{ca}/keys/roll_init", post().to(ca_kr_init))
-                    .route("/cas/{ca}/keys/roll_activate", post().to(ca_kr_activate))
-                    .route("/cas/{ca}/routes", post().to(ca_routes_update))
-                    .route("/cas/{ca}/routes", get().to(ca_routes_show))
-                    // Republish ALL CAs
-                    .route("/cas/republish_all", post().to(republish_all))
-                    // Force resyncing of all CAs at repo servers
-                    .route("/cas/resync_all", post().to(resync_all))
-                    // Force refresh of ALL CA certificates
-                    .route("/cas/refresh_all", post().to(refresh_all))
-                    // Methods that are not found should return a bad request and some explanation
-                    .default_service(web::route().to(api_bad_request)),
-            )
+            // Methods that are not found should return a bad request and some explanation
+            .service(api.default_service(web::route().to(api_bad_request)))
             // Publication Protocol (RFC 8181)
             .service(
                 Resource::new("/rfc8181/{handle}")
                     .data(web::PayloadConfig::default().limit(post_limit_rfc8181))
                     .route(post().to(rfc8181)),
             )
-            // Uo-Down Protocol (RFC 6492)
-            .service(
-                Resource::new("/rfc6492/{handle}")
-                    .data(web::PayloadConfig::default().limit(post_limit_rfc6492))
-                    .route(post().to(rfc6492)),
-            )
-            // Public TA related methods
-            .route("/ta/ta.tal", get().to(tal))
-            .route("/ta/ta.cer", get().to(ta_cer))
-            // RRDP repository
-            .route("/rrdp/{path:.*}", get().to(serve_rrdp_files))
-            // UI
+            // Invited publishers adding themselves
             .route(
-                "/",
-                get().to(|| {
-                    HttpResponse::Found()
-                        .header("location", "/index.html")
-                        .finish()
-                }),
-            )
-            .add_statics()
-            // Catch all (not found or not allowed)
-            .default_service(
-                // 404 for GET request
-                web::resource("")
-                    .route(web::get().to(not_found))
-                    // all requests that are not `GET`
-                    .route(
-                        web::route()
-                            .guard(guard::Not(guard::Get()))
-                            .to(HttpResponse::MethodNotAllowed),
-                    ),
+                "/invitation/publisher/{token}",
+                post().to(publisher_invitation_accept),
             )
+            // RRDP repository
+            .route("/rrdp/{path:.*}", get().to(serve_rrdp_files));
+
+        let app = if pubd_only {
+            app
+        } else {
+            app.route("/stats/cas", get().to(cas_stats))
+                // Uo-Down Protocol (RFC 6492)
+                .service(
+                    Resource::new("/rfc6492/{handle}")
+                        .data(web::PayloadConfig::default().limit(post_limit_rfc6492))
+                        .route(post().to(rfc6492)),
+                )
+                // Invited children adding themselves
+                .route(
+                    "/invitation/ca/{ca}/{token}",
+                    post().to(ca_child_invitation_accept),
+                )
+                // Public TA related methods
+                .route("/ta/ta.tal", get().to(tal))
+                .route("/ta/ta.cer", get().to(ta_cer))
+                // UI
+                .route(
+                    "/",
+                    get().to(|| {
+                        HttpResponse::Found()
+                            .header("location", "/index.html")
+                            .finish()
+                    }),
+                )
+                .add_statics()
+        };
+
+        // Catch all (not found or not allowed)
+        app.default_service(
+            // 404 for GET request
+            web::resource("")
+                .route(web::get().to(not_found))
+                // all requests that are not `GET`
+                .route(
+                    web::route()
+                        .guard(guard::Not(guard::Get()))
+                        .to(HttpResponse::MethodNotAllowed),
+                ),
+        )
     })
     .bind_ssl(config.socket_addr(), https_builder)?
-    .run()?;
+    .run();
 
+    // The HTTP server stops gracefully on SIGINT and SIGTERM, so save the
+    // state which is otherwise only saved periodically.
+    state_server.read().persist_state();
+
+    res?;
     Ok(())
 }
 
+/// Adds the API end-points for CAs.
+fn ca_api(api: Scope) -> Scope {
+    api
+        // CAs (both embedded and remote)
+        .route("/cas", post().to(ca_init))
+        .route("/cas", get().to(cas))
+        .route("/cas/issues", get().to(endpoints::all_ca_issues))
+        .route("/cas/issues/{ca}", get().to(endpoints::ca_issues))
+        .route("/cas/routes/export/{format}", get().to(cas_routes_export))
+        .route("/cas/{ca}", get().to(ca_info))
+        .route("/cas/{ca}/id", post().to(ca_regenerate_id))
+        .route("/cas/{ca}/id/activate", post().to(ca_activate_id))
+        .route("/cas/{ca}/history", get().to(ca_history))
+        .route("/cas/{ca}/child_request.xml", get().to(ca_child_req_xml))
+        .route("/cas/{ca}/child_request.json", get().to(ca_child_req_json))
+        .route("/cas/{ca}/repo", get().to(ca_repo_details))
+        .route("/cas/{ca}/repo/state", get().to(ca_repo_state))
+        .route(
+            "/cas/{ca}/repo/request.json",
+            get().to(ca_publisher_req_json),
+        )
+        .route("/cas/{ca}/repo/request.xml", get().to(ca_publisher_req_xml))
+        .route("/cas/{ca}/repo", post().to(ca_repo_update))
+        .route("/cas/{ca}/parents", post().to(ca_add_parent))
+        .route(
+            "/cas/{ca}/parents-xml/{parent}",
+            post().to(ca_add_parent_xml),
+        )
+        .route("/cas/{ca}/parents/{parent}", get().to(ca_my_parent_contact))
+        .route("/cas/{ca}/parents/{parent}", post().to(ca_update_parent))
+        .route("/cas/{ca}/parents/{parent}", delete().to(ca_remove_parent))
+        .route(
+            "/cas/{ca}/parent_migration",
+            post().to(ca_parent_migration_start),
+        )
+        .route(
+            "/cas/{ca}/parent_migration",
+            get().to(ca_parent_migration_status),
+        )
+        .route(
+            "/cas/{ca}/parent_migration",
+            delete().to(ca_parent_migration_cancel),
+        )
+        .route("/cas/{ca}/children", post().to(ca_add_child))
+        .route("/cas/{ca}/child_transfer", post().to(ca_child_transfer))
+        .route("/cas/{ca}/invitations", get().to(ca_child_invitations))
+        .route("/cas/{ca}/invitations", post().to(ca_child_invitation_add))
+        .route(
+            "/cas/{ca}/invitations/{id}",
+            post().to(ca_child_invitation_update),
+        )
+        .route(
+            "/cas/{ca}/invitations/{id}",
+            delete().to(ca_child_invitation_revoke),
+        )
+        .route(
+            "/cas/{ca}/children/{child}/contact",
+            get().to(ca_parent_contact),
+        )
+        .route(
+            "/cas/{ca}/children/{child}/parent_response.json",
+            get().to(ca_parent_res_json),
+        )
+        .route(
+            "/cas/{ca}/children/{child}/parent_response.xml",
+            get().to(ca_parent_res_xml),
+        )
+        .route("/cas/{ca}/children/{child}", get().to(ca_show_child))
+        .route("/cas/{ca}/children/{child}", post().to(ca_child_update))
+        .route("/cas/{ca}/children/{child}", delete().to(ca_child_remove))
+        .route("/cas/{ca}/keys/roll_init", post().to(ca_kr_init))
+        .route("/cas/{ca}/keys/roll_activate", post().to(ca_kr_activate))
+        .route("/cas/{ca}/routes", post().to(ca_routes_update))
+        .route("/cas/{ca}/routes", get().to(ca_routes_show))
+        .route("/cas/{ca}/routes/meta", post().to(ca_routes_meta_update))
+        .route("/cas/{ca}/routes/lint", get().to(ca_routes_lint))
+        .route("/cas/{ca}/routes/schedule", post().to(ca_routes_schedule))
+        .route("/cas/{ca}/routes/as0", get().to(ca_routes_as0_show))
+        .route(
+            "/cas/{ca}/routes/as0/enable",
+            post().to(ca_routes_as0_enable),
+        )
+        .route(
+            "/cas/{ca}/routes/as0/disable",
+            post().to(ca_routes_as0_disable),
+        )
+        .route("/cas/{ca}/routes/dormant", get().to(ca_routes_dormant_show))
+        .route(
+            "/cas/{ca}/routes/dormant/keep",
+            post().to(ca_routes_dormant_keep),
+        )
+        .route(
+            "/cas/{ca}/routes/dormant/remove",
+            post().to(ca_routes_dormant_remove),
+        )
+        .route(
+            "/cas/{ca}/routes/import/{format}",
+            post().to(ca_routes_import),
+        )
+        .route(
+            "/cas/{ca}/routes/export/{format}",
+            get().to(ca_routes_export),
+        )
+        // Republish ALL CAs
+        .route("/cas/republish_all", post().to(republish_all))
+        // Force resyncing of all CAs at repo servers
+        .route("/cas/resync_all", post().to(resync_all))
+        // Force refresh of ALL CA certificates
+        .route("/cas/refresh_all", post().to(refresh_all))
+}
+
 /// Used to set up HTTPS. Creates keypair and self signed certificate
 /// if config has 'use_ssl=test'.
 fn https_builder(config: &Config) -> Result<SslAcceptorBuilder, Error> {
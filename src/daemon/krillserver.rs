@@ -12,11 +12,17 @@ use rpki::uri;
 use rpki::x509::Time;
 
 use crate::commons::api::{
-    AddChildRequest, AllCertAuthIssues, CaRepoDetails, CertAuthHistory, CertAuthInfo, CertAuthInit,
-    CertAuthIssues, CertAuthList, CertAuthStats, ChildCaInfo, ChildHandle, CurrentRepoState,
-    Handle, ListReply, ParentCaContact, ParentCaReq, ParentHandle, PublishDelta, PublisherDetails,
-    PublisherHandle, RepoInfo, RepositoryContact, RepositoryUpdate, RoaDefinition,
-    RoaDefinitionUpdates, ServerInfo, TaCertDetails, UpdateChildRequest,
+    AddChildRequest, AllCertAuthIssues, As0RoasInfo, AuthorizationFmtError, CaRepoDetails,
+    CaRoaDefinitions, CertAuthHistory, CertAuthInfo, CertAuthInit, CertAuthIssues, CertAuthList,
+    CertAuthStats, ChildActivity, ChildCaInfo, ChildHandle, ChildInvitation, ChildInvitationInit,
+    ChildInvitationUpdate, ChildResourceTransfer, ConfiguredRoa, CurrentRepoState, DormantRoasInfo,
+    Handle, HexEncodedHash, ListReply, NewChildInvitation, NewPublisherInvitation, ParentCaContact,
+    ParentCaReq, ParentHandle, ParentMigrationReq, ParentMigrationStatus, PublishDelta,
+    PublishedObject, PublishedObjectDetails, PublisherDetails, PublisherHandle,
+    PublisherInvitation, PublisherInvitationInit, PublisherInvitationUpdate, PublisherQuota,
+    RepoInfo, RepositoryContact, RepositoryUpdate, RoaDefinition, RoaDefinitionUpdates,
+    RoaDefinitionsFormat, RoaDormantPolicy, RoaLintReport, RoaSchedule, ScheduledRoa, ServerInfo,
+    TaCertDetails, Token, TokenHash, UpdateChildRequest,
 };
 use crate::commons::error::Error;
 use crate::commons::remote::rfc8183;
@@ -27,7 +33,7 @@ use crate::daemon::auth::{Auth, Authorizer};
 use crate::daemon::ca::{self, ta_handle};
 use crate::daemon::config::Config;
 use crate::daemon::mq::EventQueueListener;
-use crate::daemon::scheduler::Scheduler;
+use crate::daemon::scheduler::{RepositoryScheduler, Scheduler};
 use crate::pubd::{PubServer, RepoStats};
 use crate::publish::CaPublisher;
 
@@ -58,12 +64,18 @@ pub struct KrillServer {
     // Publication server, with configured publishers
     pubserver: Option<Arc<PubServer>>,
 
-    // Handles the internal TA and/or CAs
-    caserver: Arc<ca::CaServer<OpenSslSigner>>,
+    // Handles the internal TA and/or CAs, not used by a standalone
+    // publication server
+    caserver: Option<Arc<ca::CaServer<OpenSslSigner>>>,
 
     // Responsible for background tasks, e.g. re-publishing
     #[allow(dead_code)] // just need to keep this in scope
-    scheduler: Scheduler,
+    scheduler: Option<Scheduler>,
+
+    // Responsible for background tasks of the publication server, e.g.
+    // retrying writes to output targets
+    #[allow(dead_code)] // just need to keep this in scope
+    repository_scheduler: Option<RepositoryScheduler>,
 
     // Time this server was started
     started: Time,
@@ -73,17 +85,24 @@ pub struct KrillServer {
 impl KrillServer {
     /// Creates a new publication server. Note that state is preserved
     /// on disk in the work_dir provided.
+    ///
+    /// If the config is for a standalone publication server, then only the
+    /// publication server is built.
     pub fn build(config: &Config) -> KrillResult<Self> {
         let work_dir = &config.data_dir;
         let base_uri = &config.rsync_base;
         let service_uri = config.service_uri();
         let rrdp_base_uri = &config.rrdp_service_uri();
         let token = &config.auth_token;
-        let ca_refresh_rate = config.ca_refresh;
-        let roa_prefix_grouping_strategy = config.roa_prefix_grouping_strategy;
 
-        info!("Starting {} v{}", KRILL_SERVER_APP, KRILL_VERSION);
-        info!("{} uses service uri: {}", KRILL_SERVER_APP, service_uri);
+        let app = if config.pubd_only() {
+            KRILL_PUBD_APP
+        } else {
+            KRILL_SERVER_APP
+        };
+
+        info!("Starting {} v{}", app, KRILL_VERSION);
+        info!("{} uses service uri: {}", app, service_uri);
 
         let mut repo_dir = work_dir.clone();
         repo_dir.push("repo");
@@ -100,6 +119,10 @@ impl KrillServer {
                     rrdp_base_uri.clone(),
                     work_dir,
                     signer.clone(),
+                    config.protocol_message_max_age(),
+                    config.rfc8181_rate_limit_per_minute,
+                    config.invitation_rate_limit_per_minute,
+                    config.pub_server_settings(),
                 )?)
             } else {
                 PubServer::remove_if_empty(
@@ -107,14 +130,58 @@ impl KrillServer {
                     rrdp_base_uri.clone(),
                     work_dir,
                     signer.clone(),
+                    config.protocol_message_max_age(),
+                    config.rfc8181_rate_limit_per_minute,
+                    config.invitation_rate_limit_per_minute,
+                    config.pub_server_settings(),
                 )?
             }
         };
         let pubserver: Option<Arc<PubServer>> = pubserver.map(Arc::new);
+        let repository_scheduler = pubserver.clone().map(RepositoryScheduler::build);
+
+        let (caserver, scheduler) = if config.pubd_only() {
+            (None, None)
+        } else {
+            let (caserver, scheduler) = Self::build_ca_server(config, signer, &pubserver)?;
+            (Some(caserver), Some(scheduler))
+        };
+
+        Ok(KrillServer {
+            service_uri,
+            work_dir: work_dir.clone(),
+            authorizer,
+            pubserver,
+            caserver,
+            scheduler,
+            repository_scheduler,
+            started: Time::now(),
+        })
+    }
+
+    /// Creates the CA server, with the embedded TA if configured, and the
+    /// scheduler for its background tasks.
+    fn build_ca_server(
+        config: &Config,
+        signer: Arc<RwLock<OpenSslSigner>>,
+        pubserver: &Option<Arc<PubServer>>,
+    ) -> KrillResult<(Arc<ca::CaServer<OpenSslSigner>>, Scheduler)> {
+        let work_dir = &config.data_dir;
+        let ca_refresh_rate = config.ca_refresh;
+        let roa_prefix_grouping_strategy = config.roa_prefix_grouping_strategy;
 
         let event_queue = Arc::new(EventQueueListener::in_mem());
-        let caserver = Arc::new(ca::CaServer::build(work_dir, event_queue.clone(), 
-                                                    signer, roa_prefix_grouping_strategy)?);
+        let caserver = Arc::new(ca::CaServer::build(
+            work_dir,
+            event_queue.clone(),
+            signer,
+            roa_prefix_grouping_strategy,
+            config.protocol_message_max_age(),
+            config.rfc6492_rate_limit_per_minute,
+            config.invitation_rate_limit_per_minute,
+            config.roa_lint_strict,
+            config.child_cert_validity(),
+        )?);
 
         if config.use_ta() {
             let ta_handle = ta_handle();
@@ -152,17 +219,10 @@ impl KrillServer {
             caserver.clone(),
             pubserver.clone(),
             ca_refresh_rate,
+            config.suspend_child_after_inactive_hours,
         );
 
-        Ok(KrillServer {
-            service_uri,
-            work_dir: work_dir.clone(),
-            authorizer,
-            pubserver,
-            caserver,
-            scheduler,
-            started: Time::now(),
-        })
+        Ok((caserver, scheduler))
     }
 
     pub fn service_base_uri(&self) -> &uri::Https {
@@ -189,6 +249,39 @@ impl KrillServer {
             .ok_or_else(|| Error::PublisherNoEmbeddedRepo)
     }
 
+    /// Saves the state which the CA and publication servers keep in memory
+    /// rather than as events. This is done periodically by the schedulers,
+    /// and should be done when the server shuts down.
+    pub fn persist_state(&self) {
+        if let Some(caserver) = &self.caserver {
+            caserver.persist_state();
+        }
+        if let Some(pubserver) = &self.pubserver {
+            pubserver.persist_state();
+        }
+    }
+
+    fn get_caserver(&self) -> KrillResult<&Arc<ca::CaServer<OpenSslSigner>>> {
+        self.caserver
+            .as_ref()
+            .ok_or_else(|| Error::CaServerDisabled)
+    }
+
+    /// Starts a new RRDP session for the repository server
+    pub fn reset_rrdp_session(&self) -> KrillEmptyResult {
+        self.get_embedded()?.reset_rrdp_session()
+    }
+
+    /// Starts an ID key roll for the repository server
+    pub fn repo_id_roll_init(&self) -> KrillEmptyResult {
+        self.get_embedded()?.id_roll_init()
+    }
+
+    /// Activates the new ID key of the repository server
+    pub fn repo_id_roll_activate(&self) -> KrillEmptyResult {
+        self.get_embedded()?.id_roll_activate()
+    }
+
     /// Returns the repository server stats
     pub fn repo_stats(&self) -> KrillResult<RepoStats> {
         self.get_embedded()?.repo_stats()
@@ -221,6 +314,71 @@ impl KrillServer {
         self.get_embedded()?.get_publisher_details(publisher)
     }
 
+    /// Returns the current objects of a publisher.
+    pub fn publisher_objects(
+        &self,
+        publisher: &PublisherHandle,
+    ) -> KrillResult<Vec<PublishedObject>> {
+        self.get_embedded()?.publisher_objects(publisher)
+    }
+
+    /// Returns a current object of a publisher, decoded.
+    pub fn publisher_object(
+        &self,
+        publisher: &PublisherHandle,
+        hash: &HexEncodedHash,
+    ) -> KrillResult<PublishedObjectDetails> {
+        self.get_embedded()?.publisher_object(publisher, hash)
+    }
+
+    /// Replaces the quota of a publisher.
+    pub fn update_publisher_quota(
+        &self,
+        publisher: PublisherHandle,
+        quota: PublisherQuota,
+    ) -> KrillEmptyResult {
+        self.get_embedded()?.update_quota(publisher, quota)
+    }
+
+    /// Invites a publisher to add itself, returns the invitation with the
+    /// token that should be given to the publisher.
+    pub fn add_publisher_invitation(
+        &self,
+        init: PublisherInvitationInit,
+    ) -> KrillResult<NewPublisherInvitation> {
+        self.get_embedded()?.add_invitation(init)
+    }
+
+    /// Shows the publisher invitations which were not yet used or revoked.
+    pub fn publisher_invitations(&self) -> KrillResult<Vec<PublisherInvitation>> {
+        self.get_embedded()?.invitations()
+    }
+
+    pub fn update_publisher_invitation(
+        &self,
+        id: TokenHash,
+        update: PublisherInvitationUpdate,
+    ) -> KrillEmptyResult {
+        self.get_embedded()?.update_invitation(id, update.expires())
+    }
+
+    pub fn revoke_publisher_invitation(&self, id: TokenHash) -> KrillEmptyResult {
+        self.get_embedded()?.revoke_invitation(id)
+    }
+
+    /// Adds a publisher which was invited, and returns the repository
+    /// response it needs to publish here. The peer is the address the
+    /// invitation was accepted from.
+    pub fn accept_publisher_invitation(
+        &self,
+        token: Token,
+        req: rfc8183::PublisherRequest,
+        peer: &str,
+    ) -> KrillResult<rfc8183::RepositoryResponse> {
+        let publisher = self.get_embedded()?.accept_invitation(token, req, peer)?;
+        self.repository_response(&publisher)
+    }
+
     pub fn rrdp_base_path(&self) -> PathBuf {
         let mut path = self.work_dir.clone();
         path.push("repo/rrdp");
@@ -251,7 +409,7 @@ impl KrillServer {
 ///
 impl KrillServer {
     pub fn ta(&self) -> KrillResult<TaCertDetails> {
-        let ta = self.caserver.get_ca(&ta_handle())?;
+        let ta = self.get_caserver()?.get_ca(&ta_handle())?;
         if let ParentCaContact::Ta(ta) = ta.parent(&ta_handle()).unwrap() {
             Ok(ta.clone())
         } else {
@@ -270,7 +428,9 @@ impl KrillServer {
         parent: &ParentHandle,
         req: AddChildRequest,
     ) -> KrillResult<ParentCaContact> {
-        let contact = self.caserver.ca_add_child(parent, req, &self.service_uri)?;
+        let contact = self
+            .get_caserver()?
+            .ca_add_child(parent, req, &self.service_uri)?;
         Ok(contact)
     }
 
@@ -280,9 +440,9 @@ impl KrillServer {
         parent: &ParentHandle,
         child: ChildHandle,
     ) -> KrillResult<ParentCaContact> {
-        let contact = self
-            .caserver
-            .ca_parent_contact(parent, child, None, &self.service_uri)?;
+        let contact =
+            self.get_caserver()?
+                .ca_parent_contact(parent, child, None, &self.service_uri)?;
         Ok(contact)
     }
 
@@ -292,9 +452,9 @@ impl KrillServer {
         parent: &ParentHandle,
         child: ChildHandle,
     ) -> KrillResult<rfc8183::ParentResponse> {
-        let contact = self
-            .caserver
-            .ca_parent_response(parent, child, None, &self.service_uri)?;
+        let contact =
+            self.get_caserver()?
+                .ca_parent_response(parent, child, None, &self.service_uri)?;
         Ok(contact)
     }
 
@@ -305,23 +465,250 @@ impl KrillServer {
         child: ChildHandle,
         req: UpdateChildRequest,
     ) -> KrillEmptyResult {
-        self.caserver.ca_child_update(parent, child, req)?;
+        self.get_caserver()?.ca_child_update(parent, child, req)?;
         Ok(())
     }
 
     /// Update IdCert or resources of a child.
     pub fn ca_child_remove(&self, handle: &Handle, child: ChildHandle) -> KrillEmptyResult {
-        self.caserver.ca_child_remove(handle, child)?;
+        self.get_caserver()?.ca_child_remove(handle, child)?;
+        Ok(())
+    }
+
+    /// Moves prefixes from one child of a CA to another, where both children
+    /// are CAs in this server. The ROAs of the sending child for the moved
+    /// prefixes are moved to the receiving child. ROAs of the sending child
+    /// for less specific prefixes are split: each child gets ROAs for its
+    /// part of them, unless the max length of a ROA does not authorize that
+    /// part. Scheduled ROAs are moved or split as schedules, so that they
+    /// are only authorized when they are due.
+    ///
+    /// All ROA changes are checked before anything is changed. Every step
+    /// completes before the next one starts, so that there are always ROAs
+    /// for the moved prefixes: the receiving child is given the resources
+    /// first and publishes the ROAs, then the sending child withdraws its
+    /// ROAs, and only then are its resources shrunk. The changes are derived
+    /// from the current ROAs of the sending child, and each step leaves
+    /// things as they are if it was done before, so a transfer which failed
+    /// halfway can simply be retried.
+    pub fn ca_child_transfer(
+        &self,
+        parent: &ParentHandle,
+        transfer: ChildResourceTransfer,
+    ) -> KrillEmptyResult {
+        let (from, to, resources) = transfer.unpack();
+
+        if !resources.asn().is_empty() {
+            return Err(Error::CaChildTransferAsns(parent.clone()));
+        }
+        if from == to {
+            return Ok(());
+        }
+
+        let ca = self.get_caserver()?.get_ca(parent)?;
+        let from_resources = ca.get_child(&from)?.resources().clone();
+        let to_resources = ca.get_child(&to)?.resources().clone();
+
+        if !from_resources.contains(&resources) && !to_resources.contains(&resources) {
+            return Err(Error::CaChildTransferNotHeld(parent.clone(), from));
+        }
+
+        let from_ca = self
+            .get_caserver()?
+            .get_ca(&from)
+            .map_err(|_| Error::CaChildNotLocal(parent.clone(), from.clone()))?;
+        let to_ca = self
+            .get_caserver()?
+            .get_ca(&to)
+            .map_err(|_| Error::CaChildNotLocal(parent.clone(), to.clone()))?;
+
+        // Configured ROAs include schedules which are not (or no longer)
+        // authorized. Only the authorized ones are moved as ROAs.
+        let active = from_ca.roa_definitions();
+        let (moved, covering): (Vec<ConfiguredRoa>, Vec<ConfiguredRoa>) = from_ca
+            .configured_roas()
+            .into_iter()
+            .partition(|roa| resources.contains(&roa.definition().prefix().into()));
+
+        // The ROAs and schedules for the receiving child
+        let to_current = to_ca.roa_definitions();
+        let mut to_added = RoaDefinitionUpdates::empty();
+        let mut to_scheduled = vec![];
+        for roa in moved.iter() {
+            let def = roa.definition();
+            if active.contains(def) && !to_current.contains(def) {
+                to_added.add(*def);
+            }
+            if !roa.schedule().is_empty() {
+                to_scheduled.push(ScheduledRoa::new(*def, *roa.schedule()));
+            }
+        }
+        for roa in covering.iter() {
+            for def in roa.definition().restrict_to(&resources) {
+                if active.contains(roa.definition()) && !to_current.contains(&def) {
+                    to_added.add(def);
+                }
+                if !roa.schedule().is_empty() {
+                    to_scheduled.push(ScheduledRoa::new(def, *roa.schedule()));
+                }
+            }
+        }
+
+        let with_meta: Vec<ConfiguredRoa> = moved
+            .iter()
+            .filter(|roa| active.contains(roa.definition()) && !roa.meta().is_empty())
+            .cloned()
+            .collect();
+
+        // The ROAs and schedules which the sending child withdraws, or narrows
+        // down to the resources it keeps
+        let remaining = from_resources.without_prefixes(&resources);
+        let mut from_updated = RoaDefinitionUpdates::empty();
+        let mut from_scheduled = vec![];
+        for roa in moved.iter() {
+            let def = roa.definition();
+            if active.contains(def) {
+                from_updated.remove(*def);
+            } else {
+                from_scheduled.push(ScheduledRoa::new(*def, RoaSchedule::default()));
+            }
+        }
+        for roa in covering.iter() {
+            let def = roa.definition();
+            let narrowed = def.restrict_to(&remaining);
+            if narrowed == vec![*def] {
+                continue;
+            }
+            if active.contains(def) {
+                from_updated.remove(*def);
+                for narrowed_def in narrowed.iter() {
+                    if !active.contains(narrowed_def) {
+                        from_updated.add(*narrowed_def);
+                    }
+                }
+            } else {
+                from_scheduled.push(ScheduledRoa::new(*def, RoaSchedule::default()));
+            }
+            if !roa.schedule().is_empty() {
+                for narrowed_def in narrowed {
+                    from_scheduled.push(ScheduledRoa::new(narrowed_def, *roa.schedule()));
+                }
+            }
+        }
+
+        // Check the ROA updates before changing anything. The receiving child
+        // is only entitled to the moved prefixes once it has received them.
+        let to_updates = ca::RouteAuthorizationUpdates::from(to_added);
+        let from_updates = ca::RouteAuthorizationUpdates::from(from_updated);
+        let to_errors = to_ca.route_authorization_errors(&to_updates);
+        let from_errors = from_ca.route_authorization_errors(&from_updates);
+        for (auth, e) in to_errors.into_iter().chain(from_errors.into_iter()) {
+            let transferred = resources.contains(&auth.prefix().into());
+            if let Error::CaAuthorisationNotEntitled(_, _) = e {
+                if transferred {
+                    continue;
+                }
+            }
+            return Err(e);
+        }
+
+        // Give the receiving child the resources, and the ROAs
+        let to_update = UpdateChildRequest::resources(to_resources.union(&resources));
+        self.get_caserver()?
+            .ca_child_update(parent, to.clone(), to_update)?;
+        self.get_caserver()?.ca_sync_parents(&to)?;
+
+        if !to_updates.is_empty() {
+            self.get_caserver()?
+                .ca_routes_update(to.clone(), to_updates)?;
+        }
+        if !with_meta.is_empty() {
+            self.get_caserver()?
+                .ca_routes_meta_update(to.clone(), with_meta)?;
+        }
+        if !to_scheduled.is_empty() {
+            self.get_caserver()?
+                .ca_routes_schedule(to.clone(), to_scheduled)?;
+        }
+
+        let publisher = CaPublisher::new(self.get_caserver()?.clone(), self.pubserver.clone());
+        publisher.publish(&to)?;
+
+        // Withdraw the ROAs from the sending child
+        if !from_updates.is_empty() {
+            self.get_caserver()?
+                .ca_routes_update(from.clone(), from_updates)?;
+        }
+        if !from_scheduled.is_empty() {
+            self.get_caserver()?
+                .ca_routes_schedule(from.clone(), from_scheduled)?;
+        }
+        publisher.publish(&from)?;
+
+        // Shrink the resources of the sending child
+        let from_update = UpdateChildRequest::resources(remaining);
+        self.get_caserver()?
+            .ca_child_update(parent, from.clone(), from_update)?;
+        self.get_caserver()?.ca_sync_parents(&from)?;
+
         Ok(())
     }
 
+    /// Invites a child to add itself, returns the invitation with the token
+    /// that should be given to the child.
+    pub fn ca_child_invitation_add(
+        &self,
+        parent: &ParentHandle,
+        init: ChildInvitationInit,
+    ) -> KrillResult<NewChildInvitation> {
+        self.get_caserver()?.ca_child_invitation_add(parent, init)
+    }
+
+    /// Shows the invitations of a CA which were not yet used or revoked.
+    pub fn ca_child_invitations(&self, parent: &ParentHandle) -> KrillResult<Vec<ChildInvitation>> {
+        let ca = self.get_caserver()?.get_ca(parent)?;
+        Ok(ca.child_invitations())
+    }
+
+    pub fn ca_child_invitation_update(
+        &self,
+        parent: &ParentHandle,
+        id: TokenHash,
+        update: ChildInvitationUpdate,
+    ) -> KrillEmptyResult {
+        self.get_caserver()?
+            .ca_child_invitation_update(parent, id, update.expires())
+    }
+
+    pub fn ca_child_invitation_revoke(
+        &self,
+        parent: &ParentHandle,
+        id: TokenHash,
+    ) -> KrillEmptyResult {
+        self.get_caserver()?.ca_child_invitation_revoke(parent, id)
+    }
+
+    /// Adds a child which was invited, and returns the parent response
+    /// it needs to contact this CA. The peer is the address the invitation
+    /// was accepted from.
+    pub fn ca_child_invitation_accept(
+        &self,
+        parent: &ParentHandle,
+        token: Token,
+        req: rfc8183::ChildRequest,
+        peer: &str,
+    ) -> KrillResult<rfc8183::ParentResponse> {
+        self.get_caserver()?
+            .ca_child_invitation_accept(parent, token, req, &self.service_uri, peer)
+    }
+
     /// Show details for a child under the TA.
     pub fn ca_show_child(
         &self,
         parent: &ParentHandle,
         child: &ChildHandle,
     ) -> KrillResult<ChildCaInfo> {
-        let child = self.caserver.ca_show_child(parent, child)?;
+        let child = self.get_caserver()?.ca_show_child(parent, child)?;
         Ok(child)
     }
 }
@@ -331,13 +718,15 @@ impl KrillServer {
 impl KrillServer {
     /// Returns the child request for a CA, or NONE if the CA cannot be found.
     pub fn ca_child_req(&self, handle: &Handle) -> KrillResult<rfc8183::ChildRequest> {
-        self.caserver.get_ca(handle).map(|ca| ca.child_request())
+        self.get_caserver()?
+            .get_ca(handle)
+            .map(|ca| ca.child_request())
     }
 
     /// Adds a parent to a CA, will check first if the parent can be reached.
     pub fn ca_parent_add(&self, handle: Handle, parent: ParentCaReq) -> KrillEmptyResult {
         self.ca_parent_reachable(&handle, parent.handle(), parent.contact())?;
-        Ok(self.caserver.ca_parent_add(handle, parent)?)
+        Ok(self.get_caserver()?.ca_parent_add(handle, parent)?)
     }
 
     /// Updates a parent contact for a CA
@@ -348,7 +737,9 @@ impl KrillServer {
         contact: ParentCaContact,
     ) -> KrillEmptyResult {
         self.ca_parent_reachable(&handle, &parent, &contact)?;
-        Ok(self.caserver.ca_parent_update(handle, parent, contact)?)
+        Ok(self
+            .get_caserver()?
+            .ca_parent_update(handle, parent, contact)?)
     }
 
     fn ca_parent_reachable(
@@ -357,13 +748,39 @@ impl KrillServer {
         parent: &ParentHandle,
         contact: &ParentCaContact,
     ) -> KrillEmptyResult {
-        self.caserver
+        self.get_caserver()?
             .get_entitlements_from_parent_and_contact(handle, parent, contact)?;
         Ok(())
     }
 
     pub fn ca_parent_remove(&self, handle: Handle, parent: ParentHandle) -> KrillEmptyResult {
-        Ok(self.caserver.ca_parent_remove(handle, parent)?)
+        Ok(self.get_caserver()?.ca_parent_remove(handle, parent)?)
+    }
+
+    /// Starts moving a CA from one parent to another
+    pub fn ca_parent_migration_start(
+        &self,
+        handle: Handle,
+        req: ParentMigrationReq,
+    ) -> KrillEmptyResult {
+        let (from, to) = req.unpack();
+        self.ca_parent_reachable(&handle, to.handle(), to.contact())?;
+        Ok(self
+            .get_caserver()?
+            .ca_parent_migration_start(handle, from, to)?)
+    }
+
+    /// Cancels moving a CA from one parent to another
+    pub fn ca_parent_migration_cancel(&self, handle: &Handle) -> KrillEmptyResult {
+        Ok(self.get_caserver()?.ca_parent_migration_cancel(handle)?)
+    }
+
+    /// Shows the progress of moving a CA from one parent to another
+    pub fn ca_parent_migration_status(
+        &self,
+        handle: &Handle,
+    ) -> KrillResult<ParentMigrationStatus> {
+        self.get_caserver()?.ca_parent_migration_status(handle)
     }
 }
 
@@ -373,21 +790,43 @@ impl KrillServer {
     pub fn cas_stats(&self) -> HashMap<Handle, CertAuthStats> {
         let mut res = HashMap::new();
 
-        for ca in self.caserver.ca_list().cas() {
+        let caserver = match self.get_caserver() {
+            Ok(caserver) => caserver,
+            Err(_) => return res,
+        };
+
+        for ca in caserver.ca_list().cas() {
             // can't fail really, but to be sure
-            if let Ok(ca) = self.caserver.get_ca(ca.handle()) {
+            if let Ok(ca) = caserver.get_ca(ca.handle()) {
                 let roa_count = ca.roa_definitions().len();
+                let dormant_roa_count = ca.dormant_authorizations().len();
                 let child_count = ca.children().count();
 
                 res.insert(
                     ca.handle().clone(),
-                    CertAuthStats::new(roa_count, child_count),
+                    CertAuthStats::new(roa_count, dormant_roa_count, child_count),
                 );
             }
         }
 
         res
     }
+
+    /// Returns the recent activity for the children of all CAs.
+    pub fn children_activity(&self) -> HashMap<Handle, HashMap<ChildHandle, ChildActivity>> {
+        self.get_caserver()
+            .map(|caserver| caserver.ca_children_activity())
+            .unwrap_or_default()
+    }
+
+    /// Returns the last rejected request which could not be attributed to a
+    /// child, for all CAs.
+    pub fn children_rejected_requests(&self) -> HashMap<Handle, ChildActivity> {
+        self.get_caserver()
+            .map(|caserver| caserver.ca_children_rejected_requests())
+            .unwrap_or_default()
+    }
+
     pub fn all_ca_issues(&self) -> KrillResult<AllCertAuthIssues> {
         let mut all_issues = AllCertAuthIssues::default();
         for ca in self.cas().cas() {
@@ -407,17 +846,29 @@ impl KrillServer {
             issues.add_repo_issue(msg);
         }
 
-        let ca = self.caserver.get_ca(ca_handle)?;
+        let ca = self.get_caserver()?.get_ca(ca_handle)?;
 
         for parent_handle in ca.parents() {
             let contact = ca.parent(parent_handle).unwrap(); // parent is always known
             if !contact.is_ta() {
                 if let Err(e) = self.ca_parent_reachable(ca_handle, parent_handle, contact) {
                     issues.add_parent_issue(parent_handle.clone(), e.to_error_response());
+                } else if let Some(not_performed) = self
+                    .get_caserver()?
+                    .ca_parent_not_performed(ca_handle, parent_handle)
+                {
+                    let e = Error::CaParentNotPerformed(
+                        ca_handle.clone(),
+                        parent_handle.clone(),
+                        not_performed,
+                    );
+                    issues.add_parent_issue(parent_handle.clone(), e.to_error_response());
                 }
             }
         }
 
+        issues.add_dormant_roas(ca.dormant_authorizations());
+
         Ok(issues)
     }
 }
@@ -427,26 +878,29 @@ impl KrillServer {
 impl KrillServer {
     /// Republish all CAs that need it.
     pub fn republish_all(&self) -> KrillEmptyResult {
-        self.caserver.republish_all()?;
+        self.get_caserver()?.republish_all()?;
         Ok(())
     }
 
     /// Re-sync all CAs with their repositories
     pub fn resync_all(&self) -> KrillEmptyResult {
-        let publisher = CaPublisher::new(self.caserver.clone(), self.pubserver.clone());
+        let publisher = CaPublisher::new(self.get_caserver()?.clone(), self.pubserver.clone());
 
-        for ca in self.caserver.ca_list().cas() {
-            if let Err(e) = publisher.publish(ca.handle()) {
-                error!("Failed to sync ca: {}. Got error: {}", ca.handle(), e)
-            }
-        }
+        let cas: Vec<Handle> = self
+            .get_caserver()?
+            .ca_list()
+            .cas()
+            .iter()
+            .map(|ca| ca.handle().clone())
+            .collect();
+        publisher.publish_all(&cas);
 
         Ok(())
     }
 
     /// Refresh all CAs: ask for updates and shrink as needed.
     pub fn refresh_all(&self) -> KrillEmptyResult {
-        let server = self.caserver.clone();
+        let server = self.get_caserver()?.clone();
         thread::spawn(move || {
             server.refresh_all();
         });
@@ -458,12 +912,16 @@ impl KrillServer {
 ///
 impl KrillServer {
     pub fn cas(&self) -> CertAuthList {
-        self.caserver.ca_list()
+        self.get_caserver()
+            .map(|caserver| caserver.ca_list())
+            .unwrap_or_else(|_| CertAuthList::new(vec![]))
     }
 
     /// Returns the public CA info for a CA, or NONE if the CA cannot be found.
     pub fn ca_info(&self, handle: &Handle) -> KrillResult<CertAuthInfo> {
-        self.caserver.get_ca(handle).map(|ca| ca.as_ca_info())
+        self.get_caserver()?
+            .get_ca(handle)
+            .map(|ca| ca.as_ca_info())
     }
 
     /// Returns the parent contact for a CA and parent, or NONE if either the CA or the parent cannot be found.
@@ -472,18 +930,19 @@ impl KrillServer {
         handle: &Handle,
         parent: &ParentHandle,
     ) -> KrillResult<ParentCaContact> {
-        let ca = self.caserver.get_ca(handle)?;
+        let ca = self.get_caserver()?.get_ca(handle)?;
         ca.parent(parent).map(|p| p.clone())
     }
 
     /// Returns the history for a CA, or NONE in case of issues (i.e. it does not exist).
     pub fn ca_history(&self, handle: &Handle) -> Option<CertAuthHistory> {
-        self.caserver.get_ca_history(handle).ok()
+        self.get_caserver().ok()?.get_ca_history(handle).ok()
     }
 
     /// Returns the publisher request for a CA, or NONE of the CA cannot be found.
     pub fn ca_publisher_req(&self, handle: &Handle) -> Option<rfc8183::PublisherRequest> {
-        self.caserver
+        self.get_caserver()
+            .ok()?
             .get_ca(handle)
             .map(|ca| ca.publisher_request())
             .ok()
@@ -493,7 +952,7 @@ impl KrillServer {
         let handle = init.unpack();
 
         // Create CA
-        self.caserver.init_ca(&handle)?;
+        self.get_caserver()?.init_ca(&handle)?;
 
         Ok(())
     }
@@ -501,14 +960,14 @@ impl KrillServer {
     /// Return the info about the configured repository server for a given Ca.
     /// and the actual objects published there, as reported by a list reply.
     pub fn ca_repo_details(&self, handle: &Handle) -> KrillResult<CaRepoDetails> {
-        let ca = self.caserver.get_ca(handle)?;
+        let ca = self.get_caserver()?.get_ca(handle)?;
         let contact = ca.get_repository_contact()?;
         Ok(CaRepoDetails::new(contact.clone()))
     }
 
     /// Returns the state of the current configured repo for a ca
     pub fn ca_repo_state(&self, handle: &Handle) -> KrillResult<CurrentRepoState> {
-        let ca = self.caserver.get_ca(handle)?;
+        let ca = self.get_caserver()?.get_ca(handle)?;
         let contact = ca.get_repository_contact()?;
         Ok(self.repo_state(handle, contact.as_reponse_opt()))
     }
@@ -519,7 +978,7 @@ impl KrillServer {
             RepositoryUpdate::Embedded => {
                 // Add to embedded publication server if not present
                 if self.get_embedded()?.get_publisher_details(&handle).is_err() {
-                    let ca = self.caserver.get_ca(&handle)?;
+                    let ca = self.get_caserver()?.get_ca(&handle)?;
                     let id_cert = ca.id_cert().clone();
 
                     // Add publisher
@@ -539,7 +998,7 @@ impl KrillServer {
             }
         };
 
-        Ok(self.caserver.update_repo(handle, contact)?)
+        Ok(self.get_caserver()?.update_repo(handle, contact)?)
     }
 
     fn repo_state(
@@ -555,7 +1014,10 @@ impl KrillServer {
                     Ok(list) => CurrentRepoState::list(list),
                 },
             },
-            Some(repo) => match self.caserver.send_rfc8181_list(handle, repo) {
+            Some(repo) => match self
+                .get_caserver()
+                .and_then(|caserver| caserver.send_rfc8181_list(handle, repo))
+            {
                 Err(e) => CurrentRepoState::error(e.to_error_response()),
                 Ok(list) => CurrentRepoState::list(list),
             },
@@ -563,40 +1025,183 @@ impl KrillServer {
     }
 
     pub fn ca_update_id(&self, handle: Handle) -> KrillEmptyResult {
-        Ok(self.caserver.ca_update_id(handle)?)
+        Ok(self.get_caserver()?.ca_update_id(handle)?)
+    }
+
+    pub fn ca_activate_id(&self, handle: Handle) -> KrillEmptyResult {
+        Ok(self.get_caserver()?.ca_activate_id(handle)?)
     }
 
     pub fn ca_keyroll_init(&self, handle: Handle) -> KrillEmptyResult {
         Ok(self
-            .caserver
+            .get_caserver()?
             .ca_keyroll_init(handle, Duration::seconds(0))?)
     }
 
     pub fn ca_keyroll_activate(&self, handle: Handle) -> KrillEmptyResult {
         Ok(self
-            .caserver
+            .get_caserver()?
             .ca_keyroll_activate(handle, Duration::seconds(0))?)
     }
 
-    pub fn rfc6492(&self, handle: Handle, msg_bytes: Bytes) -> KrillResult<Bytes> {
-        Ok(self.caserver.rfc6492(&handle, msg_bytes)?)
+    pub fn rfc6492(
+        &self,
+        handle: Handle,
+        msg_bytes: Bytes,
+        user_agent: Option<String>,
+    ) -> KrillResult<Bytes> {
+        Ok(self
+            .get_caserver()?
+            .rfc6492(&handle, msg_bytes, user_agent)?)
     }
 }
 
 /// # Handle route authorization requests
 ///
 impl KrillServer {
+    /// Updates the route authorizations of a CA, and returns the lint
+    /// warnings introduced by the update. If the update is strict, or the
+    /// server is configured to be strict, then any introduced warning causes
+    /// the update to be rejected instead.
     pub fn ca_routes_update(
         &self,
         handle: Handle,
         updates: RoaDefinitionUpdates,
+        strict: bool,
+    ) -> KrillResult<RoaLintReport> {
+        Ok(self
+            .get_caserver()?
+            .ca_routes_update_linted(handle, updates, strict)?)
+    }
+
+    /// Sets the time window for route authorizations of a CA.
+    pub fn ca_routes_schedule(&self, handle: Handle, roas: Vec<ScheduledRoa>) -> KrillEmptyResult {
+        Ok(self.get_caserver()?.ca_routes_schedule(handle, roas)?)
+    }
+
+    /// Shows whether AS0 ROAs are issued for unused space of a CA.
+    pub fn ca_routes_as0_show(&self, handle: &Handle) -> KrillResult<As0RoasInfo> {
+        let ca = self.get_caserver()?.get_ca(handle)?;
+        Ok(ca.as0_roas_info())
+    }
+
+    /// Enables or disables AS0 ROAs for unused space of a CA.
+    pub fn ca_routes_as0_update(&self, handle: &Handle, enabled: bool) -> KrillEmptyResult {
+        Ok(self.get_caserver()?.ca_routes_as0_update(handle, enabled)?)
+    }
+
+    /// Show the policy for ROAs no longer covered by the resources of a CA,
+    /// and the ROAs which are currently dormant.
+    pub fn ca_routes_dormant_show(&self, handle: &Handle) -> KrillResult<DormantRoasInfo> {
+        let ca = self.get_caserver()?.get_ca(handle)?;
+        Ok(ca.dormant_roas_info())
+    }
+
+    /// Keep ROAs no longer covered by the resources of a CA dormant, or
+    /// remove them.
+    pub fn ca_routes_dormant_policy_update(
+        &self,
+        handle: &Handle,
+        policy: RoaDormantPolicy,
+    ) -> KrillEmptyResult {
+        Ok(self
+            .get_caserver()?
+            .ca_routes_dormant_policy_update(handle, policy)?)
+    }
+
+    /// Lints the current route authorizations of a CA.
+    pub fn ca_routes_lint(&self, handle: &Handle) -> KrillResult<RoaLintReport> {
+        let ca = self.get_caserver()?.get_ca(handle)?;
+        Ok(ca.roa_lint(&ca.roa_definitions()))
+    }
+
+    /// Updates the meta-information (comment, tags, ticket) for existing
+    /// route authorizations.
+    pub fn ca_routes_meta_update(
+        &self,
+        handle: Handle,
+        roas: Vec<ConfiguredRoa>,
     ) -> KrillEmptyResult {
-        Ok(self.caserver.ca_routes_update(handle, updates.into())?)
+        Ok(self.get_caserver()?.ca_routes_meta_update(handle, roas)?)
     }
 
-    pub fn ca_routes_show(&self, handle: &Handle) -> KrillResult<Vec<RoaDefinition>> {
-        let ca = self.caserver.get_ca(handle)?;
-        Ok(ca.roa_definitions())
+    pub fn ca_routes_show(&self, handle: &Handle) -> KrillResult<Vec<ConfiguredRoa>> {
+        let ca = self.get_caserver()?.get_ca(handle)?;
+        Ok(ca.configured_roas())
+    }
+
+    /// Imports ROA definitions in the given format. Definitions which are
+    /// already present are skipped, all others are added, together with
+    /// their meta-information, in a single update which is validated and
+    /// linted just like any other update.
+    ///
+    /// Definitions are checked before the update, so that all offending
+    /// lines (csv) or entries (json, slurm) can be reported at once.
+    pub fn ca_routes_import(
+        &self,
+        handle: Handle,
+        format: RoaDefinitionsFormat,
+        content: &str,
+        strict: bool,
+    ) -> KrillResult<RoaLintReport> {
+        let imported = format
+            .parse_numbered(&handle, content)
+            .map_err(|e| Error::CaAuthorisationImport(handle.clone(), e))?;
+
+        let caserver = self.get_caserver()?;
+        let ca = caserver.get_ca(&handle)?;
+        let current = ca.roa_definitions();
+
+        let mut lines: HashMap<RoaDefinition, usize> = HashMap::new();
+        let mut errors = vec![];
+        let mut updates = RoaDefinitionUpdates::empty();
+        let mut roas = vec![];
+
+        for (line, roa) in imported {
+            let definition = *roa.definition();
+            if let Some(first) = lines.get(&definition) {
+                errors.push((line, format!("Duplicate of line {}", first)));
+                continue;
+            }
+            lines.insert(definition, line);
+            if !current.contains(&definition) {
+                updates.add(definition);
+                roas.push(roa);
+            }
+        }
+
+        let route_updates = ca::RouteAuthorizationUpdates::from(updates.clone());
+        for (auth, e) in ca.route_authorization_errors(&route_updates) {
+            errors.push((lines[auth.as_ref()], e.to_string()));
+        }
+
+        if !errors.is_empty() {
+            errors.sort();
+            return Err(Error::CaAuthorisationImport(
+                handle,
+                AuthorizationFmtError::lines(errors),
+            ));
+        }
+
+        Ok(caserver.ca_routes_update_linted_with_meta(handle, updates, roas, strict)?)
+    }
+
+    pub fn ca_routes_export(
+        &self,
+        handle: &Handle,
+        format: RoaDefinitionsFormat,
+    ) -> KrillResult<String> {
+        let roas = self.ca_routes_show(handle)?;
+        Ok(format.export(&roas))
+    }
+
+    pub fn cas_routes_export(&self, format: RoaDefinitionsFormat) -> KrillResult<String> {
+        let mut all = vec![];
+        for ca in self.get_caserver()?.ca_list().cas() {
+            let roas = self.ca_routes_show(ca.handle())?;
+            all.push(CaRoaDefinitions::new(ca.handle().clone(), roas));
+        }
+        Ok(format.export_all(&all))
     }
 }
 
@@ -619,4 +1224,32 @@ impl KrillServer {
     }
 }
 
-// Tested through integration tests
+// Mostly tested through integration tests
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::commons::util::test;
+
+    #[test]
+    fn should_build_pubd_only() {
+        test::test_under_tmp(|d| {
+            let config = Config::pubd_test(&d);
+            let server = KrillServer::build(&config).unwrap();
+
+            assert!(server.pubserver.is_some());
+            assert!(server.repository_scheduler.is_some());
+            assert!(server.caserver.is_none());
+            assert!(server.scheduler.is_none());
+
+            assert!(server.cas().cas().is_empty());
+            match server.ta() {
+                Err(Error::CaServerDisabled) => {}
+                _ => panic!("Expected CA server to be disabled"),
+            }
+            assert!(!d.join(CASERVER_DIR).exists());
+        });
+    }
+}
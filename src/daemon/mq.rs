@@ -48,6 +48,9 @@ pub enum QueueEvent {
 
     #[display(fmt = "clean up old repo *if it exists* for '{}' version '{}'", _0, _1)]
     CleanOldRepo(Handle, u64),
+
+    #[display(fmt = "refresh ROAs for '{}' version '{}'", _0, _1)]
+    RoasRefresh(Handle, u64),
 }
 
 #[derive(Debug)]
@@ -79,7 +82,7 @@ unsafe impl Sync for EventQueueListener {}
 
 /// Implement listening for CertAuth Published events.
 impl<S: Signer> eventsourcing::EventListener<CertAuth<S>> for EventQueueListener {
-    fn listen(&self, _ca: &CertAuth<S>, event: &Evt) {
+    fn listen(&self, ca: &CertAuth<S>, event: &Evt) {
         trace!("Seen CertAuth event '{}'", event);
 
         let handle = event.handle();
@@ -138,6 +141,23 @@ impl<S: Signer> eventsourcing::EventListener<CertAuth<S>> for EventQueueListener
             }
             _ => {}
         }
+
+        // Unused space changes when children or entitlements change, and
+        // dormant authorizations may be covered again by a new certificate.
+        match event.details() {
+            EvtDet::ChildAdded(_, _)
+            | EvtDet::ChildUpdatedResources(_, _)
+            | EvtDet::ChildRemoved(_)
+            | EvtDet::ResourceClassRemoved(_, _, _, _) => {
+                if ca.as0_roas_enabled() {
+                    self.push_back(QueueEvent::RoasRefresh(handle.clone(), version));
+                }
+            }
+            EvtDet::CertificateReceived(_, _, _) => {
+                self.push_back(QueueEvent::RoasRefresh(handle.clone(), version));
+            }
+            _ => {}
+        }
     }
 }
 
@@ -6,6 +6,7 @@ use std::time::Duration;
 
 use clokwerk::{self, ScheduleHandle, TimeUnits};
 
+use crate::commons::api::Handle;
 use crate::commons::util::softsigner::OpenSslSigner;
 use crate::daemon::ca::CaServer;
 use crate::daemon::mq::{EventQueueListener, QueueEvent};
@@ -27,6 +28,21 @@ pub struct Scheduler {
     /// they are not renewed within the configured grace period.
     #[allow(dead_code)] // just need to keep this in scope
     ca_refresh_sh: ScheduleHandle,
+
+    /// Responsible for adding and removing time-bounded route authorizations
+    /// when they become active, or expire.
+    #[allow(dead_code)] // just need to keep this in scope
+    roa_schedule_sh: ScheduleHandle,
+
+    /// Responsible for suspending children which have been inactive for too
+    /// long, if configured.
+    #[allow(dead_code)] // just need to keep this in scope
+    child_suspend_sh: Option<ScheduleHandle>,
+
+    /// Responsible for periodically saving state which is kept in memory,
+    /// such as the recent activity of children.
+    #[allow(dead_code)] // just need to keep this in scope
+    persist_state_sh: ScheduleHandle,
 }
 
 impl Scheduler {
@@ -35,19 +51,72 @@ impl Scheduler {
         caserver: Arc<CaServer<OpenSslSigner>>,
         pubserver: Option<Arc<PubServer>>,
         ca_refresh_rate: u32,
+        suspend_child_after_inactive_hours: Option<i64>,
     ) -> Self {
         let event_sh = make_event_sh(event_queue, caserver.clone(), pubserver);
         let republish_sh = make_republish_sh(caserver.clone());
-        let ca_refresh_sh = make_ca_refresh_sh(caserver, ca_refresh_rate);
+        let ca_refresh_sh = make_ca_refresh_sh(caserver.clone(), ca_refresh_rate);
+        let roa_schedule_sh = make_roa_schedule_sh(caserver.clone());
+        let persist_state_sh = make_persist_state_sh(caserver.clone());
+        let child_suspend_sh = suspend_child_after_inactive_hours
+            .map(|hours| make_child_suspend_sh(caserver, chrono::Duration::hours(hours)));
 
         Scheduler {
             event_sh,
             republish_sh,
             ca_refresh_sh,
+            roa_schedule_sh,
+            child_suspend_sh,
+            persist_state_sh,
         }
     }
 }
 
+//------------ RepositoryScheduler -------------------------------------------
+
+/// Responsible for the background tasks of the publication server, which
+/// also runs without a CA server.
+pub struct RepositoryScheduler {
+    /// Responsible for retrying writes to the repository output targets
+    /// which failed, so that they do not wait for the next publication.
+    #[allow(dead_code)] // just need to keep this in scope
+    write_targets_sh: ScheduleHandle,
+
+    /// Responsible for periodically saving state which is kept in memory,
+    /// such as the signing times of messages received from publishers.
+    #[allow(dead_code)] // just need to keep this in scope
+    persist_state_sh: ScheduleHandle,
+}
+
+impl RepositoryScheduler {
+    pub fn build(pubserver: Arc<PubServer>) -> Self {
+        let write_targets_sh = make_write_targets_sh(pubserver.clone());
+        let persist_state_sh = make_repository_persist_state_sh(pubserver);
+        RepositoryScheduler {
+            write_targets_sh,
+            persist_state_sh,
+        }
+    }
+}
+
+fn make_write_targets_sh(pubserver: Arc<PubServer>) -> ScheduleHandle {
+    let mut scheduler = clokwerk::Scheduler::new();
+    scheduler.every(1.minutes()).run(move || {
+        if let Err(e) = pubserver.write_outdated_targets() {
+            error!("Could not write repository output targets: {}", e);
+        }
+    });
+    scheduler.watch_thread(Duration::from_millis(100))
+}
+
+fn make_repository_persist_state_sh(pubserver: Arc<PubServer>) -> ScheduleHandle {
+    let mut scheduler = clokwerk::Scheduler::new();
+    scheduler
+        .every(1.minutes())
+        .run(move || pubserver.persist_state());
+    scheduler.watch_thread(Duration::from_millis(100))
+}
+
 fn make_event_sh(
     event_queue: Arc<EventQueueListener>,
     caserver: Arc<CaServer<OpenSslSigner>>,
@@ -55,16 +124,27 @@ fn make_event_sh(
 ) -> ScheduleHandle {
     let mut scheduler = clokwerk::Scheduler::new();
     scheduler.every(1.seconds()).run(move || {
-        while let Some(evt) = event_queue.pop() {
-            match evt {
-                QueueEvent::Delta(handle, version) => {
-                    info!("Trigger publication for '{}' version '{}'", handle, version);
-                    let publisher = CaPublisher::new(caserver.clone(), pubserver.clone());
+        let publisher = CaPublisher::new(caserver.clone(), pubserver.clone());
 
-                    if let Err(e) = publisher.publish(&handle) {
-                        error!("Failed to publish for CA: {}, error: {}", handle, e);
-                    }
+        // CAs are published together, so that their objects can end up in
+        // a single RRDP delta. They are published before any other event
+        // is handled, so that events are still handled in order.
+        let mut to_publish: Vec<Handle> = vec![];
+
+        while let Some(evt) = event_queue.pop() {
+            if let QueueEvent::Delta(handle, version) = &evt {
+                info!("Trigger publication for '{}' version '{}'", handle, version);
+                if !to_publish.contains(handle) {
+                    to_publish.push(handle.clone());
                 }
+                continue;
+            }
+
+            publisher.publish_all(&to_publish);
+            to_publish.clear();
+
+            match evt {
+                QueueEvent::Delta(_, _) => {} // collected above
                 QueueEvent::ResourceClassRemoved(handle, _, parent, revocations) => {
                     info!(
                         "Trigger send revoke requests for removed RC for '{}' under '{}'",
@@ -124,8 +204,16 @@ fn make_event_sh(
                         );
                     }
                 }
+                QueueEvent::RoasRefresh(handle, _) => {
+                    info!("Refresh ROAs for '{}'", handle);
+                    if let Err(e) = caserver.ca_routes_refresh(&handle) {
+                        error!(
+                            "Failed to refresh ROAs for '{}', error '{}'",
+                            &handle, e
+                        );
+                    }
+                }
                 QueueEvent::CleanOldRepo(handle, _) => {
-                    let publisher = CaPublisher::new(caserver.clone(), pubserver.clone());
                     if let Err(e) = publisher.clean_up(&handle) {
                         info!(
                             "Could not clean up old repo for '{}', it may be that it's no longer available. Got error '{}'",
@@ -141,6 +229,8 @@ fn make_event_sh(
                 }
             }
         }
+
+        publisher.publish_all(&to_publish);
     });
     scheduler.watch_thread(Duration::from_millis(100))
 }
@@ -164,3 +254,31 @@ fn make_ca_refresh_sh(caserver: Arc<CaServer<OpenSslSigner>>, refresh_rate: u32)
     });
     scheduler.watch_thread(Duration::from_millis(100))
 }
+
+fn make_roa_schedule_sh(caserver: Arc<CaServer<OpenSslSigner>>) -> ScheduleHandle {
+    let mut scheduler = clokwerk::Scheduler::new();
+    scheduler
+        .every(1.minutes())
+        .run(move || caserver.ca_routes_apply_schedules());
+    scheduler.watch_thread(Duration::from_millis(100))
+}
+
+fn make_persist_state_sh(caserver: Arc<CaServer<OpenSslSigner>>) -> ScheduleHandle {
+    let mut scheduler = clokwerk::Scheduler::new();
+    scheduler
+        .every(1.minutes())
+        .run(move || caserver.persist_state());
+    scheduler.watch_thread(Duration::from_millis(100))
+}
+
+fn make_child_suspend_sh(
+    caserver: Arc<CaServer<OpenSslSigner>>,
+    max_inactive: chrono::Duration,
+) -> ScheduleHandle {
+    let mut scheduler = clokwerk::Scheduler::new();
+    scheduler.every(1.hours()).run(move || {
+        info!("Triggering suspension of inactive children");
+        caserver.ca_children_suspend_inactive(max_inactive)
+    });
+    scheduler.watch_thread(Duration::from_millis(100))
+}
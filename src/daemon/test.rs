@@ -3,21 +3,29 @@
 use std::path::PathBuf;
 use std::{thread, time};
 
+use bytes::Bytes;
+use chrono::Duration;
+
 use rpki::uri::Rsync;
 use rpki::roa::Roa;
+use rpki::x509::Time;
 
 use crate::cli::options::{BulkCaCommand, CaCommand, Command, Options, PublishersCommand};
 use crate::cli::report::{ApiResponse, ReportFormat};
 use crate::cli::{Error, KrillClient};
 use crate::commons::api::{
-    AddChildRequest, AsNumber, Base64, CertAuthInfo, CertAuthInit, CertifiedKeyInfo, ChildAuthRequest, ChildHandle,
-    Handle, ParentCaContact, ParentCaReq, ParentHandle, Publish, PublisherDetails, PublisherHandle,
-    RepositoryUpdate, ResourceClassKeysInfo, ResourceClassName, ResourceSet, RoaDefinition, RoaDefinitionUpdates,
-    UpdateChildRequest,
+    AddChildRequest, As0RoasInfo, AsNumber, Base64, CertAuthHistory, CertAuthInfo, CertAuthInit,
+    CertifiedKeyInfo, ChildAuthRequest, ChildHandle, ChildInvitation, ChildInvitationInit,
+    ChildResourceTransfer, ConfiguredRoa, Handle, NewChildInvitation, NewPublisherInvitation,
+    ParentCaContact, ParentCaReq, ParentHandle, ParentMigrationPhase, ParentMigrationReq,
+    ParentMigrationStatus, Publish, PublisherDetails, PublisherHandle, PublisherInvitation,
+    PublisherInvitationInit, PublisherInvitationUpdate, PublisherQuota, RepositoryUpdate,
+    ResourceClassKeysInfo, ResourceClassName, ResourceSet, RoaDefinition, RoaDefinitionUpdates,
+    RoaDefinitionsFormat, RoaLintReport, ScheduledRoa, Token, TokenHash, UpdateChildRequest,
 };
 use crate::commons::remote::rfc8183;
 use crate::commons::remote::rfc8183::ChildRequest;
-use crate::commons::util::test;
+use crate::commons::util::{httpclient, test};
 use crate::daemon::ca::{convert_friendly_roa_ip_addr_to_typed_prefix, ta_handle};
 use crate::daemon::config::Config;
 use crate::daemon::http::server;
@@ -139,6 +147,19 @@ pub fn krill_pubd_admin(command: Command, server: PubdTestContext) -> ApiRespons
     }
 }
 
+pub fn krill_pubd_admin_expect_error(command: Command) -> Error {
+    let krillc_opts = Options::new(
+        test::https("https://localhost:3001/"),
+        "secret",
+        ReportFormat::Json,
+        command,
+    );
+    match KrillClient::process(krillc_opts) {
+        Ok(_res) => panic!("Expected error"),
+        Err(e) => e,
+    }
+}
+
 pub fn krill_admin_expect_error(command: Command) -> Error {
     let krillc_opts = Options::new(
         test::https("https://localhost:3000/"),
@@ -156,18 +177,34 @@ fn refresh_all() {
     krill_admin(Command::Bulk(BulkCaCommand::Refresh));
 }
 
-pub fn init_child_with_embedded_repo(handle: &Handle) {
+pub fn init_ca(handle: &Handle) {
     krill_admin(Command::CertAuth(CaCommand::Init(CertAuthInit::new(
         handle.clone(),
     ))));
+}
+
+pub fn ca_repo_update_embedded(handle: &Handle) {
     krill_admin(Command::CertAuth(CaCommand::RepoUpdate(
         handle.clone(),
         RepositoryUpdate::Embedded,
     )));
 }
 
-pub fn generate_new_id(handle: &Handle) {
-    krill_admin(Command::CertAuth(CaCommand::UpdateId(handle.clone())));
+pub fn init_child_with_embedded_repo(handle: &Handle) {
+    init_ca(handle);
+    ca_repo_update_embedded(handle);
+}
+
+pub fn id_roll_init(handle: &Handle) {
+    krill_admin(Command::CertAuth(CaCommand::IdRollInit(handle.clone())));
+}
+
+pub fn id_roll_activate(handle: &Handle) {
+    krill_admin(Command::CertAuth(CaCommand::IdRollActivate(handle.clone())));
+}
+
+pub fn id_roll_activate_expect_error(handle: &Handle) -> Error {
+    krill_admin_expect_error(Command::CertAuth(CaCommand::IdRollActivate(handle.clone())))
 }
 
 pub fn parent_contact(handle: &Handle, child: &ChildHandle) -> ParentCaContact {
@@ -234,6 +271,14 @@ pub fn update_child(ca: &Handle, child: &ChildHandle, resources: &ResourceSet) {
     send_child_request(ca, child, req)
 }
 
+pub fn suspend_child(ca: &Handle, child: &ChildHandle) {
+    send_child_request(ca, child, UpdateChildRequest::suspend())
+}
+
+pub fn resume_child(ca: &Handle, child: &ChildHandle) {
+    send_child_request(ca, child, UpdateChildRequest::resume())
+}
+
 pub fn update_child_id(ca: &Handle, child: &ChildHandle, req: ChildRequest) {
     let (_, _, id) = req.unwrap();
     let req = UpdateChildRequest::id_cert(id);
@@ -247,6 +292,81 @@ pub fn delete_child(ca: &Handle, child: &ChildHandle) {
     )));
 }
 
+pub fn transfer_child_resources(
+    ca: &Handle,
+    from: &ChildHandle,
+    to: &ChildHandle,
+    resources: &ResourceSet,
+) {
+    let transfer = ChildResourceTransfer::new(from.clone(), to.clone(), resources.clone());
+    match krill_admin(Command::CertAuth(CaCommand::ChildTransfer(
+        ca.clone(),
+        transfer,
+    ))) {
+        ApiResponse::Empty => {}
+        _ => panic!("Expected empty ok response"),
+    }
+}
+
+pub fn transfer_child_resources_expect_error(
+    ca: &Handle,
+    from: &ChildHandle,
+    to: &ChildHandle,
+    resources: &ResourceSet,
+) {
+    let transfer = ChildResourceTransfer::new(from.clone(), to.clone(), resources.clone());
+    krill_admin_expect_error(Command::CertAuth(CaCommand::ChildTransfer(
+        ca.clone(),
+        transfer,
+    )));
+}
+
+/// Invites a child for the resources, the invitation expires in an hour.
+pub fn invite_child(ca: &Handle, resources: &ResourceSet) -> NewChildInvitation {
+    let init = ChildInvitationInit::new(resources.clone(), Time::now() + Duration::hours(1));
+    match krill_admin(Command::CertAuth(CaCommand::ChildInvitationAdd(
+        ca.clone(),
+        init,
+    ))) {
+        ApiResponse::ChildInvitation(invitation) => invitation,
+        _ => panic!("Expected child invitation"),
+    }
+}
+
+pub fn child_invitations(ca: &Handle) -> Vec<ChildInvitation> {
+    match krill_admin(Command::CertAuth(CaCommand::ChildInvitations(ca.clone()))) {
+        ApiResponse::ChildInvitations(invitations) => invitations,
+        _ => panic!("Expected child invitations"),
+    }
+}
+
+pub fn revoke_child_invitation(ca: &Handle, id: &TokenHash) {
+    krill_admin(Command::CertAuth(CaCommand::ChildInvitationRevoke(
+        ca.clone(),
+        id.clone(),
+    )));
+}
+
+/// Sends the child request to the public invitation end-point, the way a
+/// child holding the token would.
+pub fn accept_child_invitation(
+    ca: &Handle,
+    token: &Token,
+    req: rfc8183::ChildRequest,
+) -> Result<ParentCaContact, httpclient::Error> {
+    let uri = format!("https://localhost:3000/invitation/ca/{}/{}", ca, token);
+    let bytes = httpclient::post_binary(&uri, &Bytes::from(req.encode_vec()), "application/xml")?;
+    let response = rfc8183::ParentResponse::validate(bytes.as_ref()).unwrap();
+    Ok(ParentCaContact::for_rfc6492(response))
+}
+
+pub fn ca_history(ca: &Handle) -> CertAuthHistory {
+    match krill_admin(Command::CertAuth(CaCommand::ShowHistory(ca.clone()))) {
+        ApiResponse::CertAuthHistory(history) => history,
+        _ => panic!("Expected CA history"),
+    }
+}
+
 fn send_child_request(ca: &Handle, child: &Handle, req: UpdateChildRequest) {
     match krill_admin(Command::CertAuth(CaCommand::ChildUpdate(
         ca.clone(),
@@ -278,6 +398,45 @@ pub fn delete_parent(ca: &Handle, parent: &ParentHandle) {
     )));
 }
 
+pub fn start_parent_migration(ca: &Handle, from: &ParentHandle, to: ParentCaReq) {
+    let req = ParentMigrationReq::new(from.clone(), to);
+    krill_admin(Command::CertAuth(CaCommand::ParentMigrationStart(
+        ca.clone(),
+        req,
+    )));
+}
+
+pub fn parent_migration_status(ca: &Handle) -> ParentMigrationStatus {
+    match krill_admin(Command::CertAuth(CaCommand::ParentMigrationStatus(
+        ca.clone(),
+    ))) {
+        ApiResponse::ParentMigrationStatus(status) => status,
+        _ => panic!("Expected parent migration status"),
+    }
+}
+
+pub fn parent_migration_phase(ca: &Handle) -> ParentMigrationPhase {
+    parent_migration_status(ca).phase()
+}
+
+pub fn parent_migration_status_expect_error(ca: &Handle) -> Error {
+    krill_admin_expect_error(Command::CertAuth(CaCommand::ParentMigrationStatus(
+        ca.clone(),
+    )))
+}
+
+pub fn cancel_parent_migration(ca: &Handle) {
+    krill_admin(Command::CertAuth(CaCommand::ParentMigrationCancel(
+        ca.clone(),
+    )));
+}
+
+pub fn wait_for_parent_migration_completed(ca: &Handle) {
+    wait_for(30, "Parent migration did not complete", move || {
+        parent_migration_phase(ca) == ParentMigrationPhase::Completed
+    })
+}
+
 pub fn ca_roll_init(handle: &Handle) {
     krill_admin(Command::CertAuth(CaCommand::KeyRollInit(handle.clone())));
 }
@@ -288,17 +447,113 @@ pub fn ca_roll_activate(handle: &Handle) {
     )));
 }
 
-pub fn ca_route_authorizations_update(handle: &Handle, updates: RoaDefinitionUpdates) {
-    krill_admin(Command::CertAuth(CaCommand::RouteAuthorizationsUpdate(
+pub fn ca_route_authorizations_update(
+    handle: &Handle,
+    updates: RoaDefinitionUpdates,
+) -> RoaLintReport {
+    match krill_admin(Command::CertAuth(CaCommand::RouteAuthorizationsUpdate(
         handle.clone(),
         updates,
-    )));
+        false,
+    ))) {
+        ApiResponse::RouteAuthorizationsLint(report) => report,
+        _ => panic!("Expected ROA lint report"),
+    }
 }
 
 pub fn ca_route_authorizations_update_expect_error(handle: &Handle, updates: RoaDefinitionUpdates) {
     krill_admin_expect_error(Command::CertAuth(CaCommand::RouteAuthorizationsUpdate(
         handle.clone(),
         updates,
+        false,
+    )));
+}
+
+pub fn ca_route_authorizations_update_strict(
+    handle: &Handle,
+    updates: RoaDefinitionUpdates,
+) -> RoaLintReport {
+    match krill_admin(Command::CertAuth(CaCommand::RouteAuthorizationsUpdate(
+        handle.clone(),
+        updates,
+        true,
+    ))) {
+        ApiResponse::RouteAuthorizationsLint(report) => report,
+        _ => panic!("Expected ROA lint report"),
+    }
+}
+
+pub fn ca_route_authorizations_update_strict_expect_error(
+    handle: &Handle,
+    updates: RoaDefinitionUpdates,
+) {
+    krill_admin_expect_error(Command::CertAuth(CaCommand::RouteAuthorizationsUpdate(
+        handle.clone(),
+        updates,
+        true,
+    )));
+}
+
+pub fn ca_route_authorizations_list(handle: &Handle) -> Vec<ConfiguredRoa> {
+    match krill_admin(Command::CertAuth(CaCommand::RouteAuthorizationsList(
+        handle.clone(),
+    ))) {
+        ApiResponse::RouteAuthorizations(roas) => roas,
+        _ => panic!("Expected route authorizations"),
+    }
+}
+
+pub fn ca_route_authorizations_import(handle: &Handle, roas: Vec<ConfiguredRoa>) {
+    krill_admin(Command::CertAuth(CaCommand::RouteAuthorizationsImport(
+        handle.clone(),
+        roas,
+    )));
+}
+
+pub fn ca_route_authorizations_import_expect_error(handle: &Handle, roas: Vec<ConfiguredRoa>) {
+    krill_admin_expect_error(Command::CertAuth(CaCommand::RouteAuthorizationsImport(
+        handle.clone(),
+        roas,
+    )));
+}
+
+pub fn ca_route_authorizations_export(handle: &Handle, format: RoaDefinitionsFormat) -> String {
+    match krill_admin(Command::CertAuth(CaCommand::RouteAuthorizationsExport(
+        Some(handle.clone()),
+        format,
+    ))) {
+        ApiResponse::GenericBody(export) => export,
+        _ => panic!("Expected exported route authorizations"),
+    }
+}
+
+pub fn ca_route_authorizations_as0_update(handle: &Handle, enabled: bool) {
+    krill_admin(Command::CertAuth(CaCommand::RouteAuthorizationsAs0Update(
+        handle.clone(),
+        enabled,
+    )));
+}
+
+pub fn ca_route_authorizations_as0_info(handle: &Handle) -> As0RoasInfo {
+    match krill_admin(Command::CertAuth(CaCommand::RouteAuthorizationsAs0Show(
+        handle.clone(),
+    ))) {
+        ApiResponse::As0RoasInfo(info) => info,
+        _ => panic!("Expected AS0 ROAs info"),
+    }
+}
+
+pub fn ca_route_authorizations_schedule(handle: &Handle, roas: Vec<ScheduledRoa>) {
+    krill_admin(Command::CertAuth(CaCommand::RouteAuthorizationsSchedule(
+        handle.clone(),
+        roas,
+    )));
+}
+
+pub fn ca_route_authorizations_schedule_expect_error(handle: &Handle, roas: Vec<ScheduledRoa>) {
+    krill_admin_expect_error(Command::CertAuth(CaCommand::RouteAuthorizationsSchedule(
+        handle.clone(),
+        roas,
     )));
 }
 
@@ -418,6 +673,43 @@ pub fn publisher_details(publisher: &PublisherHandle) -> PublisherDetails {
     }
 }
 
+pub fn invite_publisher(
+    handle_prefix: Option<Handle>,
+    quota: PublisherQuota,
+) -> NewPublisherInvitation {
+    let init = PublisherInvitationInit::new(handle_prefix, quota, Time::now() + Duration::hours(1));
+    match krill_admin(Command::Publishers(PublishersCommand::InvitationAdd(init))) {
+        ApiResponse::PublisherInvitation(invitation) => invitation,
+        _ => panic!("Expected publisher invitation"),
+    }
+}
+
+pub fn publisher_invitations() -> Vec<PublisherInvitation> {
+    match krill_admin(Command::Publishers(PublishersCommand::Invitations)) {
+        ApiResponse::PublisherInvitations(invitations) => invitations,
+        _ => panic!("Expected publisher invitations"),
+    }
+}
+
+pub fn update_publisher_invitation(id: &TokenHash, expires: Time) {
+    let update = PublisherInvitationUpdate::new(expires);
+    krill_admin(Command::Publishers(PublishersCommand::InvitationUpdate(
+        id.clone(),
+        update,
+    )));
+}
+
+/// Sends the publisher request to the public invitation end-point, the way
+/// a publisher holding the token would.
+pub fn accept_publisher_invitation(
+    token: &Token,
+    req: rfc8183::PublisherRequest,
+) -> Result<rfc8183::RepositoryResponse, httpclient::Error> {
+    let uri = format!("https://localhost:3000/invitation/publisher/{}", token);
+    let bytes = httpclient::post_binary(&uri, &Bytes::from(req.encode_vec()), "application/xml")?;
+    Ok(rfc8183::RepositoryResponse::validate(bytes.as_ref()).unwrap())
+}
+
 pub fn wait_for_published_objects(publisher: &PublisherHandle, objects: &[&str]) {
     let mut details = publisher_details(publisher);
 
@@ -1,10 +1,16 @@
 use std::fmt;
 
-use crate::commons::api::{PublishDelta, PublisherHandle, RepositoryHandle};
+use rpki::x509::Time;
+
+use crate::commons::api::{
+    PublishDelta, PublisherHandle, PublisherInvitation, PublisherQuota, RepositoryHandle, Token,
+    TokenHash,
+};
 use crate::commons::eventsourcing::CommandDetails;
 use crate::commons::eventsourcing::SentCommand;
+use crate::commons::remote::id::IdCert;
 use crate::commons::remote::rfc8183;
-use crate::pubd::Evt;
+use crate::pubd::{Evt, RepositorySettings};
 
 //------------ Cmd ---------------------------------------------------------
 pub type Cmd = SentCommand<CmdDet>;
@@ -15,7 +21,22 @@ pub type Cmd = SentCommand<CmdDet>;
 pub enum CmdDet {
     AddPublisher(rfc8183::PublisherRequest),
     RemovePublisher(PublisherHandle),
+    UpdateQuota(PublisherHandle, PublisherQuota),
     Publish(PublisherHandle, PublishDelta),
+    // Publish the deltas of several publishers in a single RRDP delta.
+    PublishBatch(Vec<(PublisherHandle, PublishDelta)>),
+    ResetRrdpSession,
+    IdRollInit(IdCert),
+    IdRollActivate,
+    AddInvitation(PublisherInvitation),
+    UpdateInvitation(TokenHash, Time),
+    RevokeInvitation(TokenHash),
+    // The invitation is found by the hash of the token used, so that the
+    // token itself is never stored.
+    AcceptInvitation(TokenHash, rfc8183::PublisherRequest),
+    // Set how published objects are handled, following the server
+    // configuration.
+    UpdateSettings(RepositorySettings),
 }
 
 impl CommandDetails for CmdDet {
@@ -31,6 +52,14 @@ impl CmdDet {
         SentCommand::new(handle, None, CmdDet::RemovePublisher(publisher))
     }
 
+    pub fn update_quota(
+        handle: &RepositoryHandle,
+        publisher: PublisherHandle,
+        quota: PublisherQuota,
+    ) -> Cmd {
+        SentCommand::new(handle, None, CmdDet::UpdateQuota(publisher, quota))
+    }
+
     pub fn publish(
         handle: &RepositoryHandle,
         publisher: PublisherHandle,
@@ -38,6 +67,50 @@ impl CmdDet {
     ) -> Cmd {
         SentCommand::new(handle, None, CmdDet::Publish(publisher, delta))
     }
+
+    pub fn publish_batch(
+        handle: &RepositoryHandle,
+        deltas: Vec<(PublisherHandle, PublishDelta)>,
+    ) -> Cmd {
+        SentCommand::new(handle, None, CmdDet::PublishBatch(deltas))
+    }
+
+    pub fn reset_rrdp_session(handle: &RepositoryHandle) -> Cmd {
+        SentCommand::new(handle, None, CmdDet::ResetRrdpSession)
+    }
+
+    pub fn id_roll_init(handle: &RepositoryHandle, id_cert: IdCert) -> Cmd {
+        SentCommand::new(handle, None, CmdDet::IdRollInit(id_cert))
+    }
+
+    pub fn id_roll_activate(handle: &RepositoryHandle) -> Cmd {
+        SentCommand::new(handle, None, CmdDet::IdRollActivate)
+    }
+
+    pub fn add_invitation(handle: &RepositoryHandle, invitation: PublisherInvitation) -> Cmd {
+        SentCommand::new(handle, None, CmdDet::AddInvitation(invitation))
+    }
+
+    pub fn update_invitation(handle: &RepositoryHandle, id: TokenHash, expires: Time) -> Cmd {
+        SentCommand::new(handle, None, CmdDet::UpdateInvitation(id, expires))
+    }
+
+    pub fn revoke_invitation(handle: &RepositoryHandle, id: TokenHash) -> Cmd {
+        SentCommand::new(handle, None, CmdDet::RevokeInvitation(id))
+    }
+
+    pub fn accept_invitation(
+        handle: &RepositoryHandle,
+        token: &Token,
+        request: rfc8183::PublisherRequest,
+    ) -> Cmd {
+        let id = TokenHash::from(token);
+        SentCommand::new(handle, None, CmdDet::AcceptInvitation(id, request))
+    }
+
+    pub fn update_settings(handle: &RepositoryHandle, settings: RepositorySettings) -> Cmd {
+        SentCommand::new(handle, None, CmdDet::UpdateSettings(settings))
+    }
 }
 
 impl fmt::Display for CmdDet {
@@ -52,6 +125,9 @@ impl fmt::Display for CmdDet {
             CmdDet::RemovePublisher(publisher) => {
                 write!(f, "Remove publisher '{}' and all its objects", publisher)
             }
+            CmdDet::UpdateQuota(publisher, quota) => {
+                write!(f, "Update quota for publisher '{}' to {}", publisher, quota)
+            }
             CmdDet::Publish(handle, delta) => write!(
                 f,
                 "Publish for '{}': {} new, {} updated, {} withdrawn objects",
@@ -60,6 +136,43 @@ impl fmt::Display for CmdDet {
                 delta.updates().len(),
                 delta.withdraws().len()
             ),
+            CmdDet::PublishBatch(deltas) => write!(
+                f,
+                "Publish batch for {} publishers: {}",
+                deltas.len(),
+                deltas
+                    .iter()
+                    .map(|(handle, _)| handle.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            CmdDet::ResetRrdpSession => write!(f, "Reset RRDP session"),
+            CmdDet::IdRollInit(id_cert) => write!(
+                f,
+                "Start ID key roll to new id cert with hash '{}'",
+                id_cert.ski_hex()
+            ),
+            CmdDet::IdRollActivate => write!(f, "Activate new ID key"),
+            CmdDet::AddInvitation(invitation) => {
+                write!(f, "Add publisher invitation, {}", invitation)
+            }
+            CmdDet::UpdateInvitation(id, expires) => write!(
+                f,
+                "Update publisher invitation '{}' to expire at {}",
+                id,
+                expires.to_rfc3339()
+            ),
+            CmdDet::RevokeInvitation(id) => write!(f, "Revoke publisher invitation '{}'", id),
+            CmdDet::AcceptInvitation(id, request) => write!(
+                f,
+                "Accept publisher invitation '{}' for publisher '{}' with id cert hash '{}'",
+                id,
+                request.publisher_handle(),
+                request.id_cert().ski_hex(),
+            ),
+            CmdDet::UpdateSettings(settings) => {
+                write!(f, "Update repository settings to {}", settings)
+            }
         }
     }
 }
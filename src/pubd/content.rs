@@ -0,0 +1,103 @@
+//! Decoding of the content of objects published by publishers.
+
+use std::convert::TryFrom;
+
+use rpki::cert::Cert;
+use rpki::crl::Crl;
+use rpki::manifest::Manifest;
+use rpki::roa::Roa;
+use rpki::uri;
+
+use crate::commons::api::ca::ResourceSet;
+use crate::commons::api::{
+    AsNumber, Base64, HexEncodedHash, ManifestEntry, PublishedObjectContent, RoaDefinition,
+};
+use crate::daemon::ca::convert_friendly_roa_ip_addr_to_typed_prefix;
+use crate::pubd::ObjectType;
+
+//------------ ObjectDecoder -------------------------------------------------
+
+/// Decodes published objects into a summary of their content, for display
+/// through the API.
+pub struct ObjectDecoder;
+
+impl ObjectDecoder {
+    /// Decodes the object for its type, as identified by the uri. Objects of
+    /// other types, or which cannot be parsed, are returned as base64.
+    pub fn decode(uri: &uri::Rsync, base64: &Base64) -> PublishedObjectContent {
+        let bytes = base64.to_bytes();
+        let decoded = match ObjectType::for_uri(uri) {
+            Some(ObjectType::Cert) => Cert::decode(bytes).ok().map(|cert| Self::cert(&cert)),
+            Some(ObjectType::Manifest) => Manifest::decode(bytes, false)
+                .ok()
+                .map(|mft| Self::manifest(&mft)),
+            Some(ObjectType::Crl) => Crl::decode(bytes).ok().map(|crl| Self::crl(&crl)),
+            Some(ObjectType::Roa) => Roa::decode(bytes, false).ok().map(|roa| Self::roa(&roa)),
+            _ => None,
+        };
+
+        decoded.unwrap_or_else(|| PublishedObjectContent::Other {
+            base64: base64.clone(),
+        })
+    }
+
+    fn cert(cert: &Cert) -> PublishedObjectContent {
+        PublishedObjectContent::Cert {
+            not_before: cert.validity().not_before(),
+            not_after: cert.validity().not_after(),
+            resources: ResourceSet::try_from(cert).ok(),
+        }
+    }
+
+    fn manifest(mft: &Manifest) -> PublishedObjectContent {
+        let content = mft.content();
+        let entries = content
+            .iter()
+            .map(|entry| {
+                ManifestEntry::new(
+                    String::from_utf8_lossy(entry.file()).into_owned(),
+                    HexEncodedHash::from(hex::encode(entry.hash())),
+                )
+            })
+            .collect();
+
+        PublishedObjectContent::Manifest {
+            number: content.manifest_number(),
+            this_update: content.this_update(),
+            next_update: content.next_update(),
+            entries,
+        }
+    }
+
+    fn crl(crl: &Crl) -> PublishedObjectContent {
+        let revoked = crl
+            .revoked_certs()
+            .iter()
+            .map(|entry| entry.user_certificate())
+            .collect();
+
+        PublishedObjectContent::Crl {
+            number: crl.crl_number(),
+            this_update: crl.this_update(),
+            next_update: crl.next_update(),
+            revoked,
+        }
+    }
+
+    fn roa(roa: &Roa) -> PublishedObjectContent {
+        let content = roa.content();
+        let asn = AsNumber::new(u32::from(content.as_id()));
+        let authorizations = content
+            .iter()
+            .map(|fria| {
+                let (prefix, max_length) = convert_friendly_roa_ip_addr_to_typed_prefix(&fria);
+                RoaDefinition::new(asn, prefix, max_length)
+            })
+            .collect();
+
+        PublishedObjectContent::Roa {
+            not_after: roa.cert().validity().not_after(),
+            authorizations,
+        }
+    }
+}
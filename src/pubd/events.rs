@@ -6,7 +6,9 @@ use rpki::uri;
 use rpki::x509::Time;
 
 use crate::commons::api::rrdp::{Delta, DeltaElements, Notification, RrdpSession};
-use crate::commons::api::{Handle, PublisherHandle, RepositoryHandle};
+use crate::commons::api::{
+    Handle, PublisherHandle, PublisherInvitation, PublisherQuota, RepositoryHandle, TokenHash,
+};
 use crate::commons::error::Error;
 use crate::commons::eventsourcing::StoredEvent;
 use crate::commons::remote::builder::IdCertBuilder;
@@ -14,7 +16,7 @@ use crate::commons::remote::id::IdCert;
 use crate::commons::KrillResult;
 use crate::constants::REPOSITORY_DIR;
 use crate::daemon::ca::Signer;
-use crate::pubd::Publisher;
+use crate::pubd::{Publisher, RepositorySettings};
 
 //------------ Ini -----------------------------------------------------------
 
@@ -133,9 +135,40 @@ pub enum EvtDet {
     #[display(fmt = "Publisher with handle '{}', and its contents, removed", _0)]
     PublisherRemoved(PublisherHandle, RrdpUpdate),
 
+    #[display(fmt = "Quota for publisher with handle '{}' updated to {}", _0, _1)]
+    PublisherQuotaUpdated(PublisherHandle, PublisherQuota),
+
     // RRDP publication events
     #[display(fmt = "Publisher with handle '{}' published", _0)]
     Published(PublisherHandle, RrdpUpdate),
+
+    // Several publishers published in a single RRDP delta, the elements for
+    // each publisher are kept so that their current objects can be updated.
+    #[display(fmt = "Batch of publications published")]
+    BatchPublished(Vec<(PublisherHandle, DeltaElements)>, RrdpUpdate),
+
+    #[display(fmt = "RRDP session reset")]
+    RrdpSessionReset(Notification),
+
+    // ID key roll events
+    #[display(fmt = "ID key roll started")]
+    IdRollStarted(IdCert),
+
+    #[display(fmt = "ID key roll activated")]
+    IdRollActivated,
+
+    // Invitation events
+    #[display(fmt = "Publisher invitation added, {}", _0)]
+    InvitationAdded(PublisherInvitation),
+
+    #[display(fmt = "Publisher invitation '{}' updated", _0)]
+    InvitationUpdated(TokenHash, Time),
+
+    #[display(fmt = "Publisher invitation '{}' removed", _0)]
+    InvitationRemoved(TokenHash),
+
+    #[display(fmt = "Repository settings updated to {}", _0)]
+    SettingsUpdated(RepositorySettings),
 }
 
 impl EvtDet {
@@ -165,6 +198,19 @@ impl EvtDet {
         )
     }
 
+    pub(super) fn publisher_quota_updated(
+        handle: &Handle,
+        version: u64,
+        publisher_handle: PublisherHandle,
+        quota: PublisherQuota,
+    ) -> Evt {
+        StoredEvent::new(
+            handle,
+            version,
+            EvtDet::PublisherQuotaUpdated(publisher_handle, quota),
+        )
+    }
+
     pub(super) fn published(
         repository: &RepositoryHandle,
         version: u64,
@@ -173,4 +219,70 @@ impl EvtDet {
     ) -> Evt {
         StoredEvent::new(repository, version, EvtDet::Published(publisher, update))
     }
+
+    pub(super) fn batch_published(
+        repository: &RepositoryHandle,
+        version: u64,
+        publishers: Vec<(PublisherHandle, DeltaElements)>,
+        update: RrdpUpdate,
+    ) -> Evt {
+        StoredEvent::new(
+            repository,
+            version,
+            EvtDet::BatchPublished(publishers, update),
+        )
+    }
+
+    pub(super) fn rrdp_session_reset(
+        repository: &RepositoryHandle,
+        version: u64,
+        notification: Notification,
+    ) -> Evt {
+        StoredEvent::new(repository, version, EvtDet::RrdpSessionReset(notification))
+    }
+
+    pub(super) fn id_roll_started(
+        repository: &RepositoryHandle,
+        version: u64,
+        id_cert: IdCert,
+    ) -> Evt {
+        StoredEvent::new(repository, version, EvtDet::IdRollStarted(id_cert))
+    }
+
+    pub(super) fn id_roll_activated(repository: &RepositoryHandle, version: u64) -> Evt {
+        StoredEvent::new(repository, version, EvtDet::IdRollActivated)
+    }
+
+    pub(super) fn invitation_added(
+        repository: &RepositoryHandle,
+        version: u64,
+        invitation: PublisherInvitation,
+    ) -> Evt {
+        StoredEvent::new(repository, version, EvtDet::InvitationAdded(invitation))
+    }
+
+    pub(super) fn invitation_updated(
+        repository: &RepositoryHandle,
+        version: u64,
+        id: TokenHash,
+        expires: Time,
+    ) -> Evt {
+        StoredEvent::new(repository, version, EvtDet::InvitationUpdated(id, expires))
+    }
+
+    pub(super) fn invitation_removed(
+        repository: &RepositoryHandle,
+        version: u64,
+        id: TokenHash,
+    ) -> Evt {
+        StoredEvent::new(repository, version, EvtDet::InvitationRemoved(id))
+    }
+
+    pub(super) fn settings_updated(
+        repository: &RepositoryHandle,
+        version: u64,
+        settings: RepositorySettings,
+    ) -> Evt {
+        StoredEvent::new(repository, version, EvtDet::SettingsUpdated(settings))
+    }
 }
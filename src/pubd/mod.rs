@@ -1,12 +1,18 @@
 mod commands;
+mod content;
 mod events;
 mod publishers;
 mod pubserver;
 mod repository;
+mod validation;
 
 pub use self::commands::{Cmd, CmdDet};
+pub use self::content::ObjectDecoder;
 pub use self::events::{Evt, EvtDet, Ini, IniDet, RrdpUpdate};
 pub use self::publishers::Publisher;
 pub use self::pubserver::PubServer;
+pub use self::pubserver::PubServerSettings;
 pub use self::repository::RepoStats;
-pub use self::repository::Repository;
+pub use self::repository::{OutputKind, OutputTarget, OutputTargetStatus};
+pub use self::repository::{Repository, RepositorySettings};
+pub use self::validation::{ObjectType, ObjectValidationError};
@@ -1,10 +1,19 @@
+use std::collections::HashMap;
+
+use rpki::crl::Crl;
+use rpki::manifest::Manifest;
 use rpki::uri;
+use rpki::x509::Time;
 
 use crate::commons::api::rrdp::{CurrentObjects, DeltaElements};
-use crate::commons::api::{ListReply, PublisherDetails, PublisherHandle};
+use crate::commons::api::{
+    HexEncodedHash, ListReply, PublishedObject, PublishedObjectDetails, PublisherDetails,
+    PublisherHandle, PublisherQuota,
+};
 use crate::commons::error::Error;
 use crate::commons::remote::id::IdCert;
 use crate::commons::KrillResult;
+use crate::pubd::{ObjectDecoder, ObjectType};
 
 //------------ Publisher -----------------------------------------------------
 
@@ -19,6 +28,14 @@ pub struct Publisher {
 
     /// All objects currently published by this publisher, by hash
     current_objects: CurrentObjects,
+
+    /// Limits on the objects this publisher may publish
+    #[serde(default)]
+    quota: PublisherQuota,
+
+    /// When the current objects were published, by uri
+    #[serde(default)]
+    published_at: HashMap<String, Time>,
 }
 
 /// # Accessors
@@ -32,6 +49,9 @@ impl Publisher {
     pub fn current_objects(&self) -> &CurrentObjects {
         &self.current_objects
     }
+    pub fn quota(&self) -> &PublisherQuota {
+        &self.quota
+    }
 
     pub fn as_api_details(&self, handle: &PublisherHandle) -> PublisherDetails {
         let objects = self
@@ -41,18 +61,93 @@ impl Publisher {
             .cloned()
             .collect();
 
-        PublisherDetails::new(handle, self.id_cert.clone(), &self.base_uri(), objects)
+        PublisherDetails::new(
+            handle,
+            self.id_cert.clone(),
+            &self.base_uri(),
+            objects,
+            self.quota.clone(),
+        )
+    }
+
+    /// Returns the earliest 'nextUpdate' of the current manifests and CRLs,
+    /// and the earliest 'notAfter' of the EE certificates of the current
+    /// manifests. Objects which cannot be parsed are ignored.
+    pub fn expiry(&self) -> (Option<Time>, Option<Time>) {
+        fn earliest(current: Option<Time>, time: Time) -> Option<Time> {
+            match current {
+                Some(current) if current <= time => Some(current),
+                _ => Some(time),
+            }
+        }
+
+        let mut next_update = None;
+        let mut not_after = None;
+
+        for el in self.current_objects.elements() {
+            let bytes = el.base64().to_bytes();
+            match ObjectType::for_uri(el.uri()) {
+                Some(ObjectType::Manifest) => {
+                    if let Ok(mft) = Manifest::decode(bytes, false) {
+                        next_update = earliest(next_update, mft.content().next_update());
+                        not_after = earliest(not_after, mft.cert().validity().not_after());
+                    }
+                }
+                Some(ObjectType::Crl) => {
+                    if let Ok(crl) = Crl::decode(bytes) {
+                        next_update = earliest(next_update, crl.next_update());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        (next_update, not_after)
+    }
+
+    /// Returns the current objects, ordered by uri.
+    pub fn objects(&self) -> Vec<PublishedObject> {
+        let mut objects: Vec<PublishedObject> = self
+            .current_objects
+            .elements()
+            .into_iter()
+            .map(|el| {
+                let published = self.published_at.get(&el.uri().to_string()).cloned();
+                PublishedObject::new(el, published)
+            })
+            .collect();
+
+        objects.sort_by_key(|o| o.uri().to_string());
+        objects
+    }
+
+    /// Returns the current object for the hash with its decoded content.
+    pub fn object_details(&self, hash: &HexEncodedHash) -> Option<PublishedObjectDetails> {
+        self.current_objects.get(hash).map(|el| {
+            let published = self.published_at.get(&el.uri().to_string()).cloned();
+            PublishedObjectDetails::new(
+                PublishedObject::new(el, published),
+                ObjectDecoder::decode(el.uri(), el.base64()),
+            )
+        })
     }
 }
 
 /// # Life cycle
 ///
 impl Publisher {
-    pub fn new(id_cert: IdCert, base_uri: uri::Rsync, current_objects: CurrentObjects) -> Self {
+    pub fn new(
+        id_cert: IdCert,
+        base_uri: uri::Rsync,
+        current_objects: CurrentObjects,
+        quota: PublisherQuota,
+    ) -> Self {
         Publisher {
             id_cert,
             base_uri,
             current_objects,
+            quota,
+            published_at: HashMap::new(),
         }
     }
 }
@@ -77,7 +172,91 @@ impl Publisher {
             .map_err(Error::Rfc8181Delta)
     }
 
-    pub fn apply_delta(&mut self, delta: DeltaElements) {
+    /// Applies the delta, and keeps the time when the new objects were
+    /// published.
+    pub fn apply_delta(&mut self, delta: DeltaElements, time: Time) {
+        for p in delta.publishes() {
+            self.published_at.insert(p.uri().to_string(), time);
+        }
+        for u in delta.updates() {
+            self.published_at.insert(u.uri().to_string(), time);
+        }
+        for w in delta.withdraws() {
+            self.published_at.remove(&w.uri().to_string());
+        }
+
         self.current_objects.apply_delta(delta);
     }
+
+    pub fn set_quota(&mut self, quota: PublisherQuota) {
+        self.quota = quota;
+    }
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use chrono::Duration;
+
+    use rpki::crypto::{PublicKeyFormat, Signer};
+
+    use crate::commons::api::PublishDeltaBuilder;
+    use crate::commons::remote::builder::IdCertBuilder;
+    use crate::commons::util::file::CurrentFile;
+    use crate::commons::util::softsigner::OpenSslSigner;
+    use crate::commons::util::test;
+
+    use super::*;
+
+    fn publish(files: &[(&str, Bytes)]) -> DeltaElements {
+        let mut builder = PublishDeltaBuilder::new();
+        for (name, content) in files {
+            let uri = test::rsync(&format!("rsync://localhost/repo/alice/{}", name));
+            builder.add_publish(CurrentFile::new(uri, content).as_publish());
+        }
+        DeltaElements::from(builder.finish())
+    }
+
+    #[test]
+    fn should_find_earliest_expiry() {
+        test::test_under_tmp(|d| {
+            let mut signer = OpenSslSigner::build(&d).unwrap();
+            let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+            let id_cert = IdCertBuilder::new_ta_id_cert(&key, &signer).unwrap();
+
+            let mut publisher = Publisher::new(
+                id_cert,
+                test::rsync("rsync://localhost/repo/alice/"),
+                CurrentObjects::default(),
+                PublisherQuota::default(),
+            );
+            assert_eq!((None, None), publisher.expiry());
+
+            // The test manifests are valid until a day after next update
+            let in_an_hour = Time::now() + Duration::hours(1);
+            let in_two_hours = Time::now() + Duration::hours(2);
+            let mft = test::manifest(&mut signer, &[], in_two_hours);
+            let crl = test::crl(&mut signer, in_an_hour);
+            let text = Bytes::from("example content");
+            let delta = publish(&[("ca.mft", mft), ("ca.crl", crl), ("file.txt", text)]);
+            publisher.apply_delta(delta, Time::now());
+
+            let (next_update, not_after) = publisher.expiry();
+            let mft_not_after = in_two_hours + Duration::days(1);
+            assert_eq!(next_update.unwrap().timestamp(), in_an_hour.timestamp());
+            assert_eq!(not_after.unwrap().timestamp(), mft_not_after.timestamp());
+
+            // The earliest times of all manifests and CRLs are used
+            let a_minute_ago = Time::now() - Duration::minutes(1);
+            let mft = test::manifest(&mut signer, &[], a_minute_ago);
+            publisher.apply_delta(publish(&[("other.mft", mft)]), Time::now());
+
+            let (next_update, not_after) = publisher.expiry();
+            let mft_not_after = a_minute_ago + Duration::days(1);
+            assert_eq!(next_update.unwrap().timestamp(), a_minute_ago.timestamp());
+            assert_eq!(not_after.unwrap().timestamp(), mft_not_after.timestamp());
+        });
+    }
 }
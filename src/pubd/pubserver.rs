@@ -1,25 +1,36 @@
-use std::fs;
+use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
 use std::path::PathBuf;
-use std::sync::{Arc, RwLock};
+use std::sync::{mpsc, Arc, Mutex, RwLock};
+use std::{fs, thread, time};
 
 use bytes::Bytes;
+use chrono::Duration;
+use rpki::crypto::{PublicKeyFormat, Signer};
 use rpki::uri;
+use rpki::x509::Time;
 
+use crate::commons::api::rrdp::{DeltaElements, RrdpRetention};
 use crate::commons::api::{
-    Handle, ListReply, PublishDelta, PublisherDetails, PublisherHandle, RepoInfo, RepositoryHandle,
+    Handle, HexEncodedHash, ListReply, NewPublisherInvitation, PublishDelta, PublishedObject,
+    PublishedObjectDetails, PublisherDetails, PublisherHandle, PublisherInvitation,
+    PublisherInvitationInit, PublisherQuota, RepoInfo, RepositoryHandle, Token, TokenHash,
 };
 use crate::commons::error::Error;
 use crate::commons::eventsourcing::{AggregateStore, AggregateStoreError, DiskAggregateStore};
-use crate::commons::remote::builder::SignedMessageBuilder;
+use crate::commons::remote::builder::{IdCertBuilder, SignedMessageBuilder};
 use crate::commons::remote::cmslogger::CmsLogger;
+use crate::commons::remote::guard::{RateLimiter, ReplayGuard};
 use crate::commons::remote::rfc8181;
 use crate::commons::remote::rfc8183;
 use crate::commons::remote::sigmsg::SignedMessage;
+use crate::commons::util::file::PersistedState;
 use crate::commons::util::softsigner::OpenSslSigner;
 use crate::commons::KrillResult;
 use crate::constants::*;
-use crate::pubd::{self, CmdDet, RepoStats, Repository};
+use crate::pubd::{
+    self, CmdDet, OutputTarget, OutputTargetStatus, RepoStats, Repository, RepositorySettings,
+};
 
 //------------ PubServer -----------------------------------------------------
 
@@ -37,7 +48,82 @@ use crate::pubd::{self, CmdDet, RepoStats, Repository};
 pub struct PubServer {
     store: Arc<DiskAggregateStore<Repository>>,
     signer: Arc<RwLock<OpenSslSigner>>,
+    rate_limiter: RateLimiter,
+    invitation_rate_limiter: RateLimiter,
+    signing_times: ReplayGuard,
     cms_logger_work_dir: PathBuf,
+    output_status: PersistedState<Vec<OutputTargetStatus>>,
+    expiry: RwLock<HashMap<PublisherHandle, PublisherExpiry>>,
+    batch_window: time::Duration,
+    publish_queue: PublishQueue,
+}
+
+/// The earliest 'nextUpdate' of the manifests and CRLs, and the earliest
+/// 'notAfter' of the manifest EE certificates of a publisher.
+type PublisherExpiry = (Option<Time>, Option<Time>);
+
+//------------ PubServerSettings ---------------------------------------------
+
+/// Settings for how the Publication Server handles and writes the objects
+/// of its publishers.
+#[derive(Clone, Debug, Default)]
+pub struct PubServerSettings {
+    repository: RepositorySettings,
+
+    /// Output targets in addition to the RRDP and rsync dirs under the
+    /// repository dir.
+    output_targets: Vec<OutputTarget>,
+
+    /// How long to wait for publications from other publishers, so that
+    /// they can be published together in a single RRDP delta.
+    batch_window: time::Duration,
+}
+
+impl PubServerSettings {
+    pub fn new(
+        validate_objects: bool,
+        rrdp_retention: RrdpRetention,
+        output_targets: Vec<OutputTarget>,
+        batch_window: time::Duration,
+    ) -> Self {
+        PubServerSettings {
+            repository: RepositorySettings::new(validate_objects, rrdp_retention),
+            output_targets,
+            batch_window,
+        }
+    }
+}
+
+//------------ PublishQueue --------------------------------------------------
+
+/// A publication waiting to be published in a batch, with the channel on
+/// which the outcome is reported back to the publisher.
+struct PendingPublication {
+    publisher: PublisherHandle,
+    delta: PublishDelta,
+    outcome: mpsc::Sender<KrillResult<()>>,
+}
+
+impl PendingPublication {
+    fn report(self, outcome: KrillResult<()>) {
+        // The publisher waits for the outcome, so this cannot fail.
+        let _ = self.outcome.send(outcome);
+    }
+}
+
+/// Collects the publications of concurrent publishers, so that they can be
+/// published in a single RRDP delta.
+///
+/// If publications of other publishers are queued as well, then a publisher
+/// first waits for the batch window so that more can be included. It does
+/// not hold any lock while waiting. The publisher which then holds the
+/// leader lock publishes all publications queued so far. Other publishers
+/// wait for the lock, and then either find that their publication was
+/// published by the previous leader, or publish the next batch.
+#[derive(Default)]
+struct PublishQueue {
+    pending: Mutex<Vec<PendingPublication>>,
+    leader: Mutex<()>,
 }
 
 /// # Constructing
@@ -48,11 +134,24 @@ impl PubServer {
         rrdp_base_uri: uri::Https, // for the RRDP files
         work_dir: &PathBuf,        // for the aggregate stores
         signer: Arc<RwLock<OpenSslSigner>>,
+        max_message_age: Duration,
+        rate_limit_per_minute: u32,
+        invitation_rate_limit_per_minute: u32,
+        settings: PubServerSettings,
     ) -> Result<Option<Self>, Error> {
         let mut pub_server_dir = work_dir.clone();
         pub_server_dir.push(PUBSERVER_DIR);
         if pub_server_dir.exists() {
-            let server = PubServer::build(rsync_base, rrdp_base_uri, work_dir, signer)?;
+            let server = PubServer::build(
+                rsync_base,
+                rrdp_base_uri,
+                work_dir,
+                signer,
+                max_message_age,
+                rate_limit_per_minute,
+                invitation_rate_limit_per_minute,
+                settings,
+            )?;
             if server.publishers()?.is_empty() {
                 let _result = fs::remove_dir_all(pub_server_dir);
                 Ok(None)
@@ -69,6 +168,10 @@ impl PubServer {
         rrdp_base_uri: uri::Https, // for the RRDP files
         work_dir: &PathBuf,        // for the aggregate stores
         signer: Arc<RwLock<OpenSslSigner>>,
+        max_message_age: Duration,
+        rate_limit_per_minute: u32,
+        invitation_rate_limit_per_minute: u32,
+        settings: PubServerSettings,
     ) -> Result<Self, Error> {
         let default = Self::repository_handle();
 
@@ -91,12 +194,67 @@ impl PubServer {
             store.add(ini)?;
         }
 
+        let rate_limiter = RateLimiter::new(rate_limit_per_minute);
+        let invitation_rate_limiter = RateLimiter::new(invitation_rate_limit_per_minute);
+        let signing_times = ReplayGuard::build(
+            work_dir,
+            RFC8181_PUBLISHER_SIGNING_TIMES_FILE,
+            max_message_age,
+        );
         let cms_logger_work_dir = work_dir.clone();
 
+        let repository = store.get_latest(&default)?;
+        if repository.settings() != &settings.repository {
+            let cmd = CmdDet::update_settings(&default, settings.repository);
+            store.command(cmd)?;
+        }
+
+        let mut targets = repository.default_output_targets();
+        targets.extend(settings.output_targets);
+
+        // Keep the status of targets which were configured before, so that
+        // targets which failed before a restart are retried.
+        let output_status = PersistedState::load(
+            work_dir,
+            REPOSITORY_OUTPUT_STATUS_FILE,
+            "repository output status",
+        );
+        output_status.update(|status: &mut Vec<OutputTargetStatus>| {
+            let previous = std::mem::replace(status, vec![]);
+            for target in targets.into_iter() {
+                let target_status = previous
+                    .iter()
+                    .find(|s| s.target() == &target)
+                    .cloned()
+                    .unwrap_or_else(|| OutputTargetStatus::new(target));
+                status.push(target_status);
+            }
+            true
+        });
+        output_status.flush();
+
+        // The expiry of the objects of publishers is kept here, so that
+        // their manifests and CRLs are only decoded when they publish.
+        let expiry = repository
+            .publishers()
+            .into_iter()
+            .filter_map(|handle| {
+                let expiry = repository.get_publisher(&handle).ok()?.expiry();
+                Some((handle, expiry))
+            })
+            .collect();
+
         Ok(PubServer {
             store,
             signer,
+            rate_limiter,
+            invitation_rate_limiter,
+            signing_times,
             cms_logger_work_dir,
+            output_status,
+            expiry: RwLock::new(expiry),
+            batch_window: settings.batch_window,
+            publish_queue: PublishQueue::default(),
         })
     }
 }
@@ -136,23 +294,35 @@ impl PubServer {
         msg.validate(publisher.id_cert())
             .map_err(Error::Rfc8181Validation)?;
 
-        let content = rfc8181::Message::from_signed_message(&msg)?;
-        let query = content.into_query()?;
-
-        let (response, should_log_cms) = match query {
-            rfc8181::QueryMessage::ListQuery => {
-                let list_reply = publisher.list_current();
-                (rfc8181::Message::list_reply(list_reply), false)
+        let sender = publisher_handle.to_string();
+        let accepted = self
+            .rate_limiter
+            .check(&sender)
+            .and_then(|_| {
+                self.signing_times
+                    .check(&sender, msg.signing_time(), &msg_bytes)
+            })
+            .map_err(|e| Error::PublisherMessageRejected(publisher_handle.clone(), e));
+
+        let (response, should_log_cms) = match accepted {
+            Err(e) => {
+                warn!("{}", e);
+                (Self::error_reply(&e), true)
             }
-            rfc8181::QueryMessage::PublishDelta(delta) => {
-                match self.publish(publisher_handle, delta) {
-                    Ok(()) => (rfc8181::Message::success_reply(), true),
-                    Err(e) => {
-                        let error_code = e.to_rfc8181_error_code();
-                        let report_error = rfc8181::ReportError::reply(error_code, None);
-                        let mut builder = rfc8181::ErrorReply::build_with_capacity(1);
-                        builder.add(report_error);
-                        (builder.build_message(), true)
+            Ok(()) => {
+                let content = rfc8181::Message::from_signed_message(&msg)?;
+                let query = content.into_query()?;
+
+                match query {
+                    rfc8181::QueryMessage::ListQuery => {
+                        let list_reply = publisher.list_current();
+                        (rfc8181::Message::list_reply(list_reply), false)
+                    }
+                    rfc8181::QueryMessage::PublishDelta(delta) => {
+                        match self.publish(publisher_handle, delta) {
+                            Ok(()) => (rfc8181::Message::success_reply(), true),
+                            Err(e) => (Self::error_reply(&e), true),
+                        }
                     }
                 }
             }
@@ -176,17 +346,274 @@ impl PubServer {
         Ok(response_bytes)
     }
 
+    fn error_reply(e: &Error) -> rfc8181::Message {
+        let error_code = e.to_rfc8181_error_code();
+        let mut report_error = rfc8181::ReportError::reply(error_code, None);
+        match e {
+            Error::PublisherQuotaExceeded(_, _) | Error::PublisherObjectInvalid(_, _) => {
+                // There are no specific error codes for these, so tell the
+                // publisher what it is in the text.
+                report_error = report_error.with_text(e.to_string());
+            }
+            _ => {}
+        }
+        let mut builder = rfc8181::ErrorReply::build_with_capacity(1);
+        builder.add(report_error);
+        builder.build_message()
+    }
+
     /// Let a known publisher publish in a repository.
+    ///
+    /// Publications by concurrent publishers are combined into a single RRDP
+    /// delta, see `PublishQueue`. The outcome returned only concerns the
+    /// delta of this publisher.
     pub fn publish(&self, publisher: PublisherHandle, delta: PublishDelta) -> KrillResult<()> {
+        self.publish_all(vec![(publisher, delta)])
+            .pop()
+            .unwrap_or_else(|| Err(Error::custom("Publication was not processed")))
+    }
+
+    /// Let several known publishers publish in a repository, in a single
+    /// RRDP delta where possible. Returns the outcome for each delta, in
+    /// the same order as the deltas.
+    pub fn publish_all(
+        &self,
+        deltas: Vec<(PublisherHandle, PublishDelta)>,
+    ) -> Vec<KrillResult<()>> {
+        let count = deltas.len();
+        let receivers: Vec<_> = {
+            let mut pending = self.publish_queue.pending.lock().unwrap();
+            deltas
+                .into_iter()
+                .map(|(publisher, delta)| {
+                    let (outcome, receiver) = mpsc::channel();
+                    pending.push(PendingPublication {
+                        publisher,
+                        delta,
+                        outcome,
+                    });
+                    receiver
+                })
+                .collect()
+        };
+
+        // Only wait for more publications if other publishers are publishing
+        // too.
+        let queued = self.publish_queue.pending.lock().unwrap().len();
+        if queued > count && self.batch_window > time::Duration::from_millis(0) {
+            thread::sleep(self.batch_window);
+        }
+
+        {
+            let _leader = self.publish_queue.leader.lock().unwrap();
+
+            // The queue is drained as a whole, so if it is empty then these
+            // publications were published by the previous leader.
+            let pending = self
+                .publish_queue
+                .pending
+                .lock()
+                .unwrap()
+                .drain(..)
+                .collect();
+            self.publish_pending(pending);
+        }
+
+        receivers
+            .into_iter()
+            .map(|receiver| {
+                receiver
+                    .recv()
+                    .unwrap_or_else(|_| Err(Error::custom("Publication was not processed")))
+            })
+            .collect()
+    }
+
+    /// Publishes the pending publications in batches. A batch contains at
+    /// most one delta for each publisher, any further deltas from the same
+    /// publisher are published in a following batch.
+    fn publish_pending(&self, mut pending: Vec<PendingPublication>) {
+        while !pending.is_empty() {
+            let mut batch: Vec<PendingPublication> = vec![];
+            let mut later = vec![];
+            for publication in pending.into_iter() {
+                if batch.iter().any(|p| p.publisher == publication.publisher) {
+                    later.push(publication);
+                } else {
+                    batch.push(publication);
+                }
+            }
+            self.publish_batch(batch);
+            pending = later;
+        }
+    }
+
+    /// Publishes the deltas in the batch in a single RRDP delta, and reports
+    /// the outcome to each publisher. Deltas which are rejected are reported
+    /// to their publisher, without holding up the other publishers.
+    fn publish_batch(&self, batch: Vec<PendingPublication>) {
+        let mut accepted = if batch.len() > 1 {
+            self.reject_invalid(batch)
+        } else {
+            batch
+        };
+
+        if accepted.len() == 1 {
+            let publication = accepted.pop().unwrap();
+            let outcome = self.publish_single(&publication.publisher, &publication.delta);
+            publication.report(outcome);
+        } else if accepted.len() > 1 {
+            let deltas = accepted
+                .iter()
+                .map(|p| (p.publisher.clone(), p.delta.clone()))
+                .collect();
+            let cmd = CmdDet::publish_batch(&Self::repository_handle(), deltas);
+
+            match self.store.command(cmd) {
+                Ok(_) => {
+                    let publishers: Vec<_> = accepted.iter().map(|p| p.publisher.clone()).collect();
+                    self.update_expiry(&publishers);
+
+                    // The repository is written once for the batch, and the
+                    // write is retried once if it fails. Errors cannot be
+                    // copied, so each publisher gets the error as a message.
+                    let written = self.write_repository().or_else(|e| {
+                        warn!("Could not write repository, will retry once: {}", e);
+                        self.write_repository()
+                    });
+                    match written {
+                        Ok(()) => {
+                            for publication in accepted.into_iter() {
+                                publication.report(Ok(()));
+                            }
+                        }
+                        Err(e) => {
+                            for publication in accepted.into_iter() {
+                                publication.report(Err(Error::custom(&e)));
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    warn!(
+                        "Could not publish batch, will publish for each publisher instead: {}",
+                        e
+                    );
+                    for publication in accepted.into_iter() {
+                        let outcome =
+                            self.publish_single(&publication.publisher, &publication.delta);
+                        publication.report(outcome);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reports publications which the repository would reject to their
+    /// publishers, and returns the publications which are accepted.
+    fn reject_invalid(&self, batch: Vec<PendingPublication>) -> Vec<PendingPublication> {
+        let repository = match self.repository() {
+            Ok(repository) => repository,
+            Err(_) => return batch, // will be reported when publishing
+        };
+
+        let mut accepted = vec![];
+        for publication in batch.into_iter() {
+            let delta_elements = DeltaElements::from(publication.delta.clone());
+            match repository.verify_publish(&publication.publisher, &delta_elements) {
+                Ok(()) => accepted.push(publication),
+                Err(e) => publication.report(Err(e)),
+            }
+        }
+        accepted
+    }
+
+    /// Publishes the delta of a single publisher in its own RRDP delta.
+    fn publish_single(&self, publisher: &PublisherHandle, delta: &PublishDelta) -> KrillResult<()> {
         let repository_handle = Self::repository_handle();
-        let cmd = CmdDet::publish(&repository_handle, publisher, delta);
+        let cmd = CmdDet::publish(&repository_handle, publisher.clone(), delta.clone());
         self.store.command(cmd)?;
+        self.update_expiry(&[publisher.clone()]);
         self.write_repository()
     }
 
+    /// Updates the expiry of the objects of the publishers, after they
+    /// published or were removed.
+    fn update_expiry(&self, publishers: &[PublisherHandle]) {
+        if let Ok(repository) = self.repository() {
+            let mut expiry = self.expiry.write().unwrap();
+            for handle in publishers {
+                match repository.get_publisher(handle) {
+                    Ok(publisher) => expiry.insert(handle.clone(), publisher.expiry()),
+                    Err(_) => expiry.remove(handle),
+                };
+            }
+        }
+    }
+
+    /// Starts a new RRDP session at serial 1, with a fresh snapshot of the
+    /// current objects. The old session dirs are kept until the grace period
+    /// for removal has passed.
+    pub fn reset_rrdp_session(&self) -> KrillResult<()> {
+        let repository_handle = Self::repository_handle();
+        let cmd = CmdDet::reset_rrdp_session(&repository_handle);
+        self.store.command(cmd)?;
+        self.write_repository()
+    }
+
+    /// Saves the state which is kept in memory rather than as events, i.e.
+    /// the signing times of messages received from publishers.
+    pub fn persist_state(&self) {
+        self.signing_times.flush();
+    }
+
+    /// Starts an ID key roll for the repository. A new key and id cert are
+    /// generated, and the new id cert is included in repository responses
+    /// so that publishers can update. Responses are still signed with the
+    /// current key until the roll is activated.
+    pub fn id_roll_init(&self) -> KrillResult<()> {
+        if self.repository()?.has_id_roll() {
+            return Err(Error::PublisherIdRollInProgress);
+        }
+
+        let id_cert = {
+            let mut signer = self.signer.write().map_err(Error::signer)?;
+            let key = signer
+                .create_key(PublicKeyFormat::default())
+                .map_err(Error::signer)?;
+            IdCertBuilder::new_ta_id_cert(&key, signer.deref()).map_err(Error::signer)?
+        };
+
+        let repository_handle = Self::repository_handle();
+        let cmd = CmdDet::id_roll_init(&repository_handle, id_cert);
+        self.store.command(cmd)?;
+        Ok(())
+    }
+
+    /// Activates the new ID key of a roll in progress. Responses are signed
+    /// with the new key from now on, and the old key is removed.
+    pub fn id_roll_activate(&self) -> KrillResult<()> {
+        let old_key = self.repository()?.key_id().clone();
+
+        let repository_handle = Self::repository_handle();
+        let cmd = CmdDet::id_roll_activate(&repository_handle);
+        self.store.command(cmd)?;
+
+        let mut signer = self.signer.write().map_err(Error::signer)?;
+        if let Err(e) = signer.destroy_key(&old_key) {
+            warn!("Could not remove old repository ID key: {}", e);
+        }
+        Ok(())
+    }
+
     pub fn repo_stats(&self) -> KrillResult<RepoStats> {
         let repo = self.repository()?;
-        Ok(repo.stats().clone())
+        let mut stats = repo.stats().clone();
+        stats.set_targets(self.output_status.read(|status| status.clone()));
+        for (publisher, (next_update, not_after)) in self.expiry.read().unwrap().iter() {
+            stats.set_expiry(publisher, *next_update, *not_after);
+        }
+        Ok(stats)
     }
 
     pub fn publishers(&self) -> KrillResult<Vec<PublisherHandle>> {
@@ -220,6 +647,28 @@ impl PubServer {
             .map(|p| p.as_api_details(publisher_handle))
     }
 
+    /// Returns the current objects of the publisher
+    pub fn publisher_objects(
+        &self,
+        publisher_handle: &PublisherHandle,
+    ) -> KrillResult<Vec<PublishedObject>> {
+        let repository = self.repository()?;
+        Ok(repository.get_publisher(publisher_handle)?.objects())
+    }
+
+    /// Returns a current object of the publisher with its decoded content
+    pub fn publisher_object(
+        &self,
+        publisher_handle: &PublisherHandle,
+        hash: &HexEncodedHash,
+    ) -> KrillResult<PublishedObjectDetails> {
+        let repository = self.repository()?;
+        repository
+            .get_publisher(publisher_handle)?
+            .object_details(hash)
+            .ok_or_else(|| Error::PublisherObjectUnknown(publisher_handle.clone(), hash.clone()))
+    }
+
     /// Returns the RFC8183 Repository Response for the publisher
     pub fn repository_response(
         &self,
@@ -245,19 +694,162 @@ impl PubServer {
     /// entities that would get confusing.
     pub fn remove_publisher(&self, publisher: PublisherHandle) -> KrillResult<()> {
         let repository_handle = Self::repository_handle();
-        let cmd = CmdDet::remove_publisher(&repository_handle, publisher);
+        let sender = publisher.to_string();
+        let cmd = CmdDet::remove_publisher(&repository_handle, publisher.clone());
         self.store.command(cmd)?;
+        self.update_expiry(&[publisher]);
+        self.rate_limiter.remove(&sender);
+        self.signing_times.remove(&sender);
         self.write_repository()
     }
+
+    /// Replaces the quota of a publisher.
+    pub fn update_quota(
+        &self,
+        publisher: PublisherHandle,
+        quota: PublisherQuota,
+    ) -> KrillResult<()> {
+        let repository_handle = Self::repository_handle();
+        let cmd = CmdDet::update_quota(&repository_handle, publisher, quota);
+        self.store.command(cmd)?;
+        Ok(())
+    }
+}
+
+/// # Invite publishers
+///
+impl PubServer {
+    /// Adds an invitation, and returns it with the token which the publisher
+    /// should use to accept it. Only the hash of the token is kept, so this
+    /// is the only time it is known.
+    pub fn add_invitation(
+        &self,
+        init: PublisherInvitationInit,
+    ) -> KrillResult<NewPublisherInvitation> {
+        let token = Token::random(self.signer.read().unwrap().deref());
+        let (handle_prefix, quota, expires) = init.unpack();
+        let invitation = PublisherInvitation::new(&token, handle_prefix, quota, expires);
+
+        let repository_handle = Self::repository_handle();
+        let cmd = CmdDet::add_invitation(&repository_handle, invitation.clone());
+        self.store.command(cmd)?;
+        Ok(NewPublisherInvitation::new(token, invitation))
+    }
+
+    /// Returns the invitations which were not yet used or revoked.
+    pub fn invitations(&self) -> KrillResult<Vec<PublisherInvitation>> {
+        let repository = self.repository()?;
+        Ok(repository.invitations())
+    }
+
+    /// Changes the expiry time of an invitation.
+    pub fn update_invitation(&self, id: TokenHash, expires: Time) -> KrillResult<()> {
+        let repository_handle = Self::repository_handle();
+        let cmd = CmdDet::update_invitation(&repository_handle, id, expires);
+        self.store.command(cmd)?;
+        Ok(())
+    }
+
+    pub fn revoke_invitation(&self, id: TokenHash) -> KrillResult<()> {
+        let repository_handle = Self::repository_handle();
+        let cmd = CmdDet::revoke_invitation(&repository_handle, id);
+        self.store.command(cmd)?;
+        Ok(())
+    }
+
+    /// Adds the publisher which holds the invitation, and returns the handle
+    /// it was added under. Because anyone may try to accept an invitation,
+    /// the failed attempts are rate limited per peer. A peer which keeps
+    /// failing does not keep other peers from accepting their invitations.
+    pub fn accept_invitation(
+        &self,
+        token: Token,
+        req: rfc8183::PublisherRequest,
+        peer: &str,
+    ) -> KrillResult<PublisherHandle> {
+        self.invitation_rate_limiter
+            .check_uncounted(peer)
+            .map_err(|_| Error::PublisherInvitationRateLimited)?;
+
+        let res = self.add_invited_publisher(token, req);
+        if res.is_err() {
+            self.invitation_rate_limiter.count(peer);
+        }
+        res
+    }
+
+    fn add_invited_publisher(
+        &self,
+        token: Token,
+        req: rfc8183::PublisherRequest,
+    ) -> KrillResult<PublisherHandle> {
+        let repository_handle = Self::repository_handle();
+        let publisher = self
+            .repository()?
+            .invited_publisher_handle(&TokenHash::from(&token), req.publisher_handle())?;
+
+        let cmd = CmdDet::accept_invitation(&repository_handle, &token, req);
+        self.store.command(cmd)?;
+        Ok(publisher)
+    }
 }
 
 /// # Publishing RRDP and rsync
 ///
 impl PubServer {
-    /// Update the RRDP files and rsync content on disk.
+    /// Update the RRDP files and rsync content on disk, in each output
+    /// target in turn. The outcome for each target is kept for the repo
+    /// stats, and saved so that failed targets are retried after a restart.
     pub fn write_repository(&self) -> KrillResult<()> {
-        let repository = self.repository()?;
-        repository.write()
+        self.write_targets(|_| true)
+    }
+
+    /// Writes to the output targets for which the last write failed, or
+    /// which were not written to since they were configured, so that they
+    /// catch up without waiting for the next publication.
+    pub fn write_outdated_targets(&self) -> KrillResult<()> {
+        self.write_targets(|status| !status.is_up_to_date())
+    }
+
+    fn write_targets(&self, select: impl Fn(&OutputTargetStatus) -> bool) -> KrillResult<()> {
+        let mut res = Ok(());
+
+        self.output_status.update(|output_status| {
+            // The repository is only read while holding the lock for the
+            // status, so that a target is never written with an older state
+            // than the last write.
+            let repository = match self.repository() {
+                Ok(repository) => repository,
+                Err(e) => {
+                    res = Err(e);
+                    return false;
+                }
+            };
+            let default_targets = repository.default_output_targets();
+
+            let mut written = false;
+            for status in output_status.iter_mut().filter(|status| select(status)) {
+                let target = status.target().clone();
+                match repository.write_target(&target) {
+                    Ok(()) => status.succeeded(),
+                    Err(e) => {
+                        error!("Could not write to output target {}: {}", target, e);
+                        status.failed(&e);
+
+                        // Only failing to write under the repository dir is
+                        // fatal, the other targets are retried later.
+                        if res.is_ok() && default_targets.contains(&target) {
+                            res = Err(e);
+                        }
+                    }
+                }
+                written = true;
+            }
+            written
+        });
+        self.output_status.flush();
+
+        res
     }
 }
 
@@ -273,12 +865,14 @@ mod tests {
     use rpki::crypto::{PublicKeyFormat, Signer};
 
     use crate::commons::api::rrdp::CurrentObjects;
-    use crate::commons::api::{ListElement, PublishDeltaBuilder};
+    use crate::commons::api::{
+        ListElement, PublishDeltaBuilder, PublishedObjectContent, QuotaExceeded,
+    };
     use crate::commons::remote::builder::IdCertBuilder;
     use crate::commons::remote::id::IdCert;
-    use crate::commons::util::file::CurrentFile;
-    use crate::commons::util::test;
-    use crate::pubd::Publisher;
+    use crate::commons::util::file::{self, CurrentFile};
+    use crate::commons::util::test::{self, files_containing};
+    use crate::pubd::{ObjectValidationError, OutputKind, Publisher};
 
     use super::*;
     use commons::api::rrdp::PublicationDeltaError;
@@ -299,7 +893,12 @@ mod tests {
 
         let base_uri = uri::Rsync::from_str("rsync://localhost/repo/alice/").unwrap();
 
-        Publisher::new(id_cert, base_uri, CurrentObjects::default())
+        Publisher::new(
+            id_cert,
+            base_uri,
+            CurrentObjects::default(),
+            PublisherQuota::default(),
+        )
     }
 
     fn make_publisher_req(handle: &str, id_cert: &IdCert) -> rfc8183::PublisherRequest {
@@ -308,10 +907,32 @@ mod tests {
     }
 
     fn make_server(work_dir: &PathBuf) -> PubServer {
+        make_server_with(work_dir, PubServerSettings::default())
+    }
+
+    fn make_server_with(work_dir: &PathBuf, settings: PubServerSettings) -> PubServer {
+        make_server_with_invitation_limit(work_dir, settings, 0)
+    }
+
+    fn make_server_with_invitation_limit(
+        work_dir: &PathBuf,
+        settings: PubServerSettings,
+        invitation_rate_limit_per_minute: u32,
+    ) -> PubServer {
         let signer = OpenSslSigner::build(work_dir).unwrap();
         let signer = Arc::new(RwLock::new(signer));
 
-        PubServer::build(&server_base_uri(), server_base_http_uri(), work_dir, signer).unwrap()
+        PubServer::build(
+            &server_base_uri(),
+            server_base_http_uri(),
+            work_dir,
+            signer,
+            Duration::minutes(10),
+            0,
+            invitation_rate_limit_per_minute,
+            settings,
+        )
+        .unwrap()
     }
 
     #[test]
@@ -353,6 +974,110 @@ mod tests {
         })
     }
 
+    #[test]
+    fn should_add_invited_publisher() {
+        test::test_under_tmp(|d| {
+            let server = make_server(&d);
+            let alice = publisher_alice(&d);
+
+            let quota = PublisherQuota::new(Some(10), None, None);
+            let prefix = Handle::from_str_unsafe("member-");
+
+            let expired = Time::now() - Duration::hours(1);
+            let init = PublisherInvitationInit::new(None, quota.clone(), expired);
+            match server.add_invitation(init) {
+                Err(Error::PublisherInvitationExpired) => {}
+                _ => panic!("Expected error adding expired invitation"),
+            }
+
+            let expires = Time::now() + Duration::hours(1);
+            let nested = Handle::from_str_unsafe("member/");
+            let init = PublisherInvitationInit::new(Some(nested), quota.clone(), expires);
+            match server.add_invitation(init) {
+                Err(Error::PublisherInvitationPrefix(_)) => {}
+                _ => panic!("Expected error adding invitation with '/' in prefix"),
+            }
+
+            let init = PublisherInvitationInit::new(Some(prefix), quota.clone(), expires);
+            let invitation = server.add_invitation(init).unwrap();
+            assert_eq!(
+                server.invitations().unwrap(),
+                vec![invitation.invitation().clone()]
+            );
+
+            let token = invitation.token().clone();
+            let id = invitation.invitation().id().clone();
+
+            // The invitation can be extended, but not expired this way
+            let extended = expires + Duration::hours(1);
+            server.update_invitation(id.clone(), extended).unwrap();
+            assert_eq!(server.invitations().unwrap()[0].expires(), extended);
+            match server.update_invitation(id, expired) {
+                Err(Error::PublisherInvitationExpired) => {}
+                _ => panic!("Expected error updating invitation to expire in the past"),
+            }
+
+            // The publisher may not ask for a handle with '/'
+            let nested_req = make_publisher_req("alice/nested", alice.id_cert());
+            match server.accept_invitation(token.clone(), nested_req, "192.0.2.1") {
+                Err(Error::PublisherInvitationHandle(_)) => {}
+                _ => panic!("Expected error accepting invitation for nested handle"),
+            }
+
+            let publisher_req = make_publisher_req("alice", alice.id_cert());
+
+            let added = server
+                .accept_invitation(token.clone(), publisher_req.clone(), "192.0.2.1")
+                .unwrap();
+            assert_eq!(added, Handle::from_str_unsafe("member-alice"));
+
+            let repository = server.repository().unwrap();
+            let publisher = repository.get_publisher(&added).unwrap();
+            assert_eq!(publisher.id_cert(), alice.id_cert());
+            assert_eq!(publisher.quota(), &quota);
+
+            // The invitation can only be used once
+            assert!(server.invitations().unwrap().is_empty());
+            match server.accept_invitation(token.clone(), publisher_req, "192.0.2.1") {
+                Err(Error::PublisherInvitationUnknown) => {}
+                _ => panic!("Expected error accepting used invitation"),
+            }
+
+            // Only the hash of the token is kept
+            assert!(files_containing(&d, token.as_ref()).is_empty());
+        })
+    }
+
+    #[test]
+    fn should_rate_limit_failed_invitation_attempts_per_peer() {
+        test::test_under_tmp(|d| {
+            let server = make_server_with_invitation_limit(&d, PubServerSettings::default(), 1);
+            let alice = publisher_alice(&d);
+            let publisher_req = make_publisher_req("alice", alice.id_cert());
+            let guess = Token::from("guess");
+
+            let quota = PublisherQuota::default();
+            let expires = Time::now() + Duration::hours(1);
+            let init = PublisherInvitationInit::new(None, quota, expires);
+            let invitation = server.add_invitation(init).unwrap();
+
+            match server.accept_invitation(guess.clone(), publisher_req.clone(), "192.0.2.1") {
+                Err(Error::PublisherInvitationUnknown) => {}
+                _ => panic!("Expected error accepting unknown invitation"),
+            }
+            match server.accept_invitation(guess, publisher_req.clone(), "192.0.2.1") {
+                Err(Error::PublisherInvitationRateLimited) => {}
+                _ => panic!("Expected error accepting too many invitations"),
+            }
+
+            // Other peers can still accept their invitations
+            let token = invitation.token().clone();
+            assert!(server
+                .accept_invitation(token, publisher_req, "192.0.2.2")
+                .is_ok());
+        })
+    }
+
     #[test]
     fn should_list_files() {
         test::test_under_tmp(|d| {
@@ -519,4 +1244,675 @@ mod tests {
             }
         });
     }
+
+    #[test]
+    fn should_publish_deltas_in_one_serial() {
+        test::test_under_tmp(|d| {
+            // A single publication is never held up by the batch window
+            let batch_window = time::Duration::from_secs(3600);
+            let settings =
+                PubServerSettings::new(false, RrdpRetention::default(), vec![], batch_window);
+            let server = make_server_with(&d, settings);
+
+            let mut signer = OpenSslSigner::build(&d).unwrap();
+            for name in &["alice", "bob", "carol"] {
+                let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+                let id_cert = IdCertBuilder::new_ta_id_cert(&key, &signer).unwrap();
+                server
+                    .create_publisher(make_publisher_req(name, &id_cert))
+                    .unwrap();
+            }
+
+            let alice = Handle::from_str_unsafe("alice");
+            let bob = Handle::from_str_unsafe("bob");
+            let carol = Handle::from_str_unsafe("carol");
+
+            let delta = |path: &str| {
+                let uri = test::rsync(&format!("rsync://localhost/repo/{}", path));
+                let file = CurrentFile::new(uri, &Bytes::from("example content"));
+                let mut builder = PublishDeltaBuilder::new();
+                builder.add_publish(file.as_publish());
+                builder.finish()
+            };
+
+            let started = time::Instant::now();
+            let serial_before = server.repo_stats().unwrap().serial();
+
+            server
+                .publish(alice.clone(), delta("alice/first.txt"))
+                .unwrap();
+            assert_eq!(serial_before + 1, server.repo_stats().unwrap().serial());
+
+            // Carol publishes outside of her base uri, this should not
+            // hold up alice and bob. The second delta of alice is published
+            // in the next RRDP delta.
+            let outcomes = server.publish_all(vec![
+                (alice.clone(), delta("alice/file.txt")),
+                (bob.clone(), delta("bob/file.txt")),
+                (carol.clone(), delta("alice/carol.txt")),
+                (alice.clone(), delta("alice/second.txt")),
+            ]);
+
+            assert_eq!(4, outcomes.len());
+            assert!(outcomes[0].is_ok());
+            assert!(outcomes[1].is_ok());
+            match &outcomes[2] {
+                Err(Error::Rfc8181Delta(PublicationDeltaError::UriOutsideJail(_, _))) => {} // ok
+                _ => panic!("Expected error publishing outside of base uri"),
+            }
+            assert!(outcomes[3].is_ok());
+            assert!(started.elapsed() < batch_window);
+
+            // Alice and bob published in a single RRDP delta, followed by
+            // the second delta of alice
+            let stats = server.repo_stats().unwrap();
+            assert_eq!(serial_before + 3, stats.serial());
+
+            assert_eq!(3, server.list(&alice).unwrap().elements().len());
+            assert_eq!(1, server.list(&bob).unwrap().elements().len());
+            assert!(server.list(&carol).unwrap().elements().is_empty());
+
+            // Each publisher's stats reflect its own objects
+            assert_eq!(3, stats.get_publishers().get(&alice).unwrap().objects());
+            assert_eq!(1, stats.get_publishers().get(&bob).unwrap().objects());
+        })
+    }
+
+    #[test]
+    fn should_validate_objects() {
+        test::test_under_tmp(|d| {
+            let settings = PubServerSettings::new(
+                true,
+                RrdpRetention::default(),
+                vec![],
+                time::Duration::default(),
+            );
+            let server = make_server_with(&d, settings);
+            let alice = publisher_alice(&d);
+
+            let alice_handle = Handle::from_str_unsafe("alice");
+            let publisher_req = make_publisher_req(alice_handle.as_str(), alice.id_cert());
+
+            server.create_publisher(publisher_req).unwrap();
+
+            let cert = CurrentFile::new(
+                test::rsync("rsync://localhost/repo/alice/ta.cer"),
+                &Bytes::from_static(include_bytes!("../../test-resources/ta.cer")),
+            );
+            let text = CurrentFile::new(
+                test::rsync("rsync://localhost/repo/alice/file.txt"),
+                &Bytes::from("example content"),
+            );
+
+            let mut builder = PublishDeltaBuilder::new();
+            builder.add_publish(text.as_publish());
+            match server.publish(alice_handle.clone(), builder.finish()) {
+                Err(Error::PublisherObjectInvalid(_, ObjectValidationError::UnknownType(uri))) => {
+                    assert_eq!(&uri, text.uri())
+                }
+                _ => panic!("Expected error publishing object of unknown type"),
+            }
+
+            let mut builder = PublishDeltaBuilder::new();
+            builder.add_publish(cert.as_publish());
+            server
+                .publish(alice_handle.clone(), builder.finish())
+                .unwrap();
+            assert_eq!(1, server.list(&alice_handle).unwrap().elements().len());
+
+            // The repository follows the configuration when the server is
+            // built again
+            let server = make_server(&d);
+            assert!(!server.repository().unwrap().settings().validate_objects());
+
+            let mut builder = PublishDeltaBuilder::new();
+            builder.add_publish(text.as_publish());
+            server
+                .publish(alice_handle.clone(), builder.finish())
+                .unwrap();
+            assert_eq!(2, server.list(&alice_handle).unwrap().elements().len());
+        });
+    }
+
+    #[test]
+    fn should_retain_deltas() {
+        test::test_under_tmp(|d| {
+            let retention = RrdpRetention::new(2, Duration::hours(1), 100.0, Duration::hours(1));
+            let server = make_server_with(
+                &d,
+                PubServerSettings::new(false, retention, vec![], time::Duration::default()),
+            );
+            let alice = publisher_alice(&d);
+
+            let alice_handle = Handle::from_str_unsafe("alice");
+            let publisher_req = make_publisher_req(alice_handle.as_str(), alice.id_cert());
+
+            server.create_publisher(publisher_req).unwrap();
+
+            let session = server.repo_stats().unwrap().session();
+            let delta_path = |serial: u64| {
+                let mut path = d.clone();
+                path.push(format!("repo/rrdp/{}/{}/delta.xml", session, serial));
+                path
+            };
+
+            let publish_file = |name: &str| {
+                let file = CurrentFile::new(
+                    test::rsync(&format!("rsync://localhost/repo/alice/{}", name)),
+                    &Bytes::from(format!("content of {}", name)),
+                );
+                let mut builder = PublishDeltaBuilder::new();
+                builder.add_publish(file.as_publish());
+                server
+                    .publish(alice_handle.clone(), builder.finish())
+                    .unwrap();
+            };
+
+            let retired_path = |serial: u64| {
+                let mut path = delta_path(serial);
+                path.set_file_name(RRDP_RETIRED_MARKER_FILE);
+                path
+            };
+
+            publish_file("file1.txt");
+            publish_file("file2.txt");
+            publish_file("file3.txt");
+
+            // Delta 1 is no longer listed, so it is retired now, but it is
+            // kept during the grace period
+            assert!(delta_path(1).exists());
+            assert!(retired_path(1).exists());
+            assert!(delta_path(2).exists());
+            assert!(!retired_path(2).exists());
+            assert!(delta_path(3).exists());
+
+            let retired: Time = file::load_json(&retired_path(1)).unwrap();
+            assert!(retired <= Time::now());
+
+            // Delta 1 was retired longer ago than the grace period
+            file::save_json(&(Time::now() - Duration::hours(2)), &retired_path(1)).unwrap();
+
+            publish_file("file4.txt");
+
+            assert!(!delta_path(1).exists());
+            assert!(delta_path(2).exists());
+            assert!(retired_path(2).exists());
+            assert!(delta_path(4).exists());
+        });
+    }
+
+    #[test]
+    fn should_retain_minimum_deltas() {
+        test::test_under_tmp(|d| {
+            // The deltas always exceed this size ratio
+            let retention = RrdpRetention::new(100, Duration::hours(1), 0.01, Duration::seconds(0));
+            let server = make_server_with(
+                &d,
+                PubServerSettings::new(false, retention, vec![], time::Duration::default()),
+            );
+            let alice = publisher_alice(&d);
+
+            let alice_handle = Handle::from_str_unsafe("alice");
+            let publisher_req = make_publisher_req(alice_handle.as_str(), alice.id_cert());
+
+            server.create_publisher(publisher_req).unwrap();
+
+            for i in 1..=7 {
+                let file = CurrentFile::new(
+                    test::rsync(&format!("rsync://localhost/repo/alice/file{}.txt", i)),
+                    &Bytes::from(format!("content of file {}", i)),
+                );
+                let mut builder = PublishDeltaBuilder::new();
+                builder.add_publish(file.as_publish());
+                server
+                    .publish(alice_handle.clone(), builder.finish())
+                    .unwrap();
+            }
+
+            let mut path = d.clone();
+            path.push("repo/rrdp/notification.xml");
+            let notification = file::read(&path).unwrap();
+            let notification = String::from_utf8_lossy(notification.as_ref()).to_string();
+            assert_eq!(5, notification.matches("<delta ").count());
+        });
+    }
+
+    #[test]
+    fn should_reset_rrdp_session() {
+        test::test_under_tmp(|d| {
+            let retention = RrdpRetention::new(2, Duration::hours(1), 100.0, Duration::seconds(0));
+            let server = make_server_with(
+                &d,
+                PubServerSettings::new(false, retention, vec![], time::Duration::default()),
+            );
+            let alice = publisher_alice(&d);
+
+            let alice_handle = Handle::from_str_unsafe("alice");
+            let publisher_req = make_publisher_req(alice_handle.as_str(), alice.id_cert());
+
+            server.create_publisher(publisher_req).unwrap();
+
+            let rrdp_path = |rel: String| {
+                let mut path = d.clone();
+                path.push(format!("repo/rrdp/{}", rel));
+                path
+            };
+
+            let publish_file = |name: &str| {
+                let file = CurrentFile::new(
+                    test::rsync(&format!("rsync://localhost/repo/alice/{}", name)),
+                    &Bytes::from(format!("content of {}", name)),
+                );
+                let mut builder = PublishDeltaBuilder::new();
+                builder.add_publish(file.as_publish());
+                server
+                    .publish(alice_handle.clone(), builder.finish())
+                    .unwrap();
+            };
+
+            publish_file("file1.txt");
+            publish_file("file2.txt");
+
+            let old_session = server.repo_stats().unwrap().session();
+
+            server.reset_rrdp_session().unwrap();
+
+            let stats = server.repo_stats().unwrap();
+            let new_session = stats.session();
+            assert_ne!(old_session, new_session);
+            assert_eq!(stats.serial(), 1);
+
+            let snapshot =
+                file::read(&rrdp_path(format!("{}/1/snapshot.xml", new_session))).unwrap();
+            let snapshot = String::from_utf8_lossy(snapshot.as_ref()).to_string();
+            assert!(snapshot.contains("rsync://localhost/repo/alice/file1.txt"));
+            assert!(snapshot.contains("rsync://localhost/repo/alice/file2.txt"));
+
+            let notification = file::read(&rrdp_path("notification.xml".to_string())).unwrap();
+            let notification = String::from_utf8_lossy(notification.as_ref()).to_string();
+            assert!(notification.contains(&new_session.to_string()));
+            assert!(!notification.contains("<delta "));
+            assert!(!rrdp_path("notification.xml.tmp".to_string()).exists());
+
+            // The old session is retired, and removed at once because there
+            // is no grace period
+            assert!(!rrdp_path(old_session.to_string()).exists());
+
+            publish_file("file3.txt");
+
+            assert!(rrdp_path(format!("{}/2/delta.xml", new_session)).exists());
+            assert_eq!(server.repo_stats().unwrap().serial(), 2);
+        });
+    }
+
+    #[test]
+    fn should_write_to_output_targets() {
+        test::test_under_tmp(|d| {
+            let sub_dir = |rel: &str| {
+                let mut path = d.clone();
+                path.push(rel);
+                path
+            };
+
+            // A target which cannot be written, because a file is in the way
+            file::save(&Bytes::from("not a dir"), &sub_dir("blocked")).unwrap();
+
+            let targets = vec![
+                OutputTarget::new(OutputKind::Rrdp, sub_dir("mirror/rrdp")),
+                OutputTarget::new(OutputKind::Rsync, sub_dir("mirror/rsync")),
+                OutputTarget::new(OutputKind::Rsync, sub_dir("blocked/rsync")),
+            ];
+            let settings = PubServerSettings::new(
+                false,
+                RrdpRetention::default(),
+                targets,
+                time::Duration::default(),
+            );
+            let server = make_server_with(&d, settings.clone());
+            let alice = publisher_alice(&d);
+
+            let alice_handle = Handle::from_str_unsafe("alice");
+            let publisher_req = make_publisher_req(alice_handle.as_str(), alice.id_cert());
+
+            server.create_publisher(publisher_req).unwrap();
+
+            let file = CurrentFile::new(
+                test::rsync("rsync://localhost/repo/alice/file.txt"),
+                &Bytes::from("example content"),
+            );
+            let mut builder = PublishDeltaBuilder::new();
+            builder.add_publish(file.as_publish());
+
+            // Failing to write to an additional target does not fail the
+            // publication itself.
+            server
+                .publish(alice_handle.clone(), builder.finish())
+                .unwrap();
+
+            let stats = server.repo_stats().unwrap();
+            let session = stats.session();
+
+            assert!(sub_dir("mirror/rrdp/notification.xml").exists());
+            assert!(sub_dir(&format!("mirror/rrdp/{}/1/snapshot.xml", session)).exists());
+            assert!(sub_dir(&format!("mirror/rrdp/{}/1/delta.xml", session)).exists());
+            assert!(sub_dir("mirror/rsync/current/alice/file.txt").exists());
+
+            let targets = stats.targets();
+            assert_eq!(targets.len(), 5);
+            for status in &targets[0..4] {
+                assert!(status.last_success().is_some());
+                assert!(status.error().is_none());
+            }
+
+            let blocked = &targets[4];
+            assert_eq!(blocked.target().dir(), &sub_dir("blocked/rsync"));
+            assert!(blocked.last_attempt().is_some());
+            assert!(blocked.last_success().is_none());
+            assert!(blocked.error().is_some());
+
+            // The failed target is still known to be outdated when the
+            // server is built again, and catches up once it can be written
+            let server = make_server_with(&d, settings);
+            assert!(!server.repo_stats().unwrap().targets()[4].is_up_to_date());
+
+            fs::remove_file(sub_dir("blocked")).unwrap();
+            server.write_outdated_targets().unwrap();
+
+            assert!(sub_dir("blocked/rsync/current/alice/file.txt").exists());
+            assert!(server.repo_stats().unwrap().targets()[4].is_up_to_date());
+        });
+    }
+
+    #[test]
+    fn should_show_objects() {
+        test::test_under_tmp(|d| {
+            let server = make_server(&d);
+            let alice = publisher_alice(&d);
+
+            let alice_handle = Handle::from_str_unsafe("alice");
+            let publisher_req = make_publisher_req(alice_handle.as_str(), alice.id_cert());
+
+            server.create_publisher(publisher_req).unwrap();
+
+            let cert = CurrentFile::new(
+                test::rsync("rsync://localhost/repo/alice/ta.cer"),
+                &Bytes::from_static(include_bytes!("../../test-resources/ta.cer")),
+            );
+            let text = CurrentFile::new(
+                test::rsync("rsync://localhost/repo/alice/file.txt"),
+                &Bytes::from("example content"),
+            );
+
+            let mut builder = PublishDeltaBuilder::new();
+            builder.add_publish(cert.as_publish());
+            builder.add_publish(text.as_publish());
+            server
+                .publish(alice_handle.clone(), builder.finish())
+                .unwrap();
+
+            let objects = server.publisher_objects(&alice_handle).unwrap();
+            assert_eq!(objects.len(), 2);
+            assert_eq!(objects[0].uri(), text.uri());
+            assert_eq!(objects[1].uri(), cert.uri());
+            assert_eq!(objects[1].hash(), cert.hash());
+            assert!(objects.iter().all(|o| o.published().is_some()));
+
+            let details = server.publisher_object(&alice_handle, cert.hash()).unwrap();
+            match details.content() {
+                PublishedObjectContent::Cert { resources, .. } => assert!(resources.is_some()),
+                _ => panic!("Expected decoded certificate"),
+            }
+
+            let details = server.publisher_object(&alice_handle, text.hash()).unwrap();
+            match details.content() {
+                PublishedObjectContent::Other { base64 } => assert_eq!(base64, text.content()),
+                _ => panic!("Expected undecoded object"),
+            }
+
+            let unknown = HexEncodedHash::from_content(b"unknown");
+            match server.publisher_object(&alice_handle, &unknown) {
+                Err(Error::PublisherObjectUnknown(_, _)) => {}
+                _ => panic!("Expected unknown object"),
+            }
+
+            // Identical content at another uri is tracked separately
+            let copy = CurrentFile::new(
+                test::rsync("rsync://localhost/repo/alice/copy.txt"),
+                &Bytes::from("example content"),
+            );
+
+            let mut builder = PublishDeltaBuilder::new();
+            builder.add_publish(copy.as_publish());
+            server
+                .publish(alice_handle.clone(), builder.finish())
+                .unwrap();
+
+            let mut builder = PublishDeltaBuilder::new();
+            builder.add_withdraw(copy.as_withdraw());
+            server
+                .publish(alice_handle.clone(), builder.finish())
+                .unwrap();
+
+            let objects = server.publisher_objects(&alice_handle).unwrap();
+            assert_eq!(objects.len(), 2);
+            assert_eq!(objects[0].uri(), text.uri());
+            assert!(objects[0].published().is_some());
+        });
+    }
+
+    #[test]
+    fn should_roll_id_key() {
+        test::test_under_tmp(|d| {
+            let server = make_server(&d);
+
+            let mut signer = OpenSslSigner::build(&d).unwrap();
+            let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+            let id_cert = IdCertBuilder::new_ta_id_cert(&key, &signer).unwrap();
+
+            let alice_handle = Handle::from_str_unsafe("alice");
+            let publisher_req = make_publisher_req(alice_handle.as_str(), &id_cert);
+            server.create_publisher(publisher_req).unwrap();
+
+            let rfc8181_uri = test::https("https://localhost/rfc8181/alice");
+            let response_id = || {
+                server
+                    .repository_response(rfc8181_uri.clone(), &alice_handle)
+                    .unwrap()
+                    .id_cert()
+                    .clone()
+            };
+
+            let list_reply = || {
+                let query = rfc8181::Message::list_query().into_bytes();
+                let msg = SignedMessageBuilder::create(&key, &signer, query)
+                    .unwrap()
+                    .as_bytes();
+                let reply = server.rfc8181(alice_handle.clone(), msg).unwrap();
+                SignedMessage::decode(reply, false).unwrap()
+            };
+
+            let old_id = response_id();
+            assert!(list_reply().validate(&old_id).is_ok());
+
+            match server.id_roll_activate() {
+                Err(Error::PublisherIdRollUnknown) => {}
+                _ => panic!("Expected no roll in progress"),
+            }
+
+            server.id_roll_init().unwrap();
+            match server.id_roll_init() {
+                Err(Error::PublisherIdRollInProgress) => {}
+                _ => panic!("Expected roll in progress"),
+            }
+
+            // The new id is advertised, but replies are still signed with the old key
+            let new_id = response_id();
+            assert_ne!(old_id.ski_hex(), new_id.ski_hex());
+            assert!(list_reply().validate(&old_id).is_ok());
+            assert!(list_reply().validate(&new_id).is_err());
+
+            server.id_roll_activate().unwrap();
+
+            assert_eq!(response_id().ski_hex(), new_id.ski_hex());
+            assert!(list_reply().validate(&new_id).is_ok());
+            assert!(list_reply().validate(&old_id).is_err());
+        });
+    }
+
+    #[test]
+    fn should_enforce_quota() {
+        test::test_under_tmp(|d| {
+            let server = make_server(&d);
+            let alice = publisher_alice(&d);
+
+            let alice_handle = Handle::from_str_unsafe("alice");
+            let publisher_req = make_publisher_req(alice_handle.as_str(), alice.id_cert());
+
+            server.create_publisher(publisher_req).unwrap();
+
+            let quota = PublisherQuota::new(Some(1), Some(24), Some(24));
+            server
+                .update_quota(alice_handle.clone(), quota.clone())
+                .unwrap();
+            let details = server.get_publisher_details(&alice_handle).unwrap();
+            assert_eq!(details.quota(), &quota);
+
+            let file1 = CurrentFile::new(
+                test::rsync("rsync://localhost/repo/alice/file.txt"),
+                &Bytes::from("example content"),
+            );
+            let file2 = CurrentFile::new(
+                test::rsync("rsync://localhost/repo/alice/file2.txt"),
+                &Bytes::from("example 2"),
+            );
+            let too_big = CurrentFile::new(
+                test::rsync("rsync://localhost/repo/alice/big.txt"),
+                &Bytes::from("example content which is too big"),
+            );
+
+            let mut builder = PublishDeltaBuilder::new();
+            builder.add_publish(too_big.as_publish());
+            match server.publish(alice_handle.clone(), builder.finish()) {
+                Err(Error::PublisherQuotaExceeded(_, QuotaExceeded::ObjectSize(uri, 24))) => {
+                    assert_eq!(&uri, too_big.uri())
+                }
+                _ => panic!("Expected error publishing object larger than quota"),
+            }
+
+            let mut builder = PublishDeltaBuilder::new();
+            builder.add_publish(file1.as_publish());
+            builder.add_publish(file2.as_publish());
+            match server.publish(alice_handle.clone(), builder.finish()) {
+                Err(Error::PublisherQuotaExceeded(_, QuotaExceeded::Objects(1))) => {}
+                _ => panic!("Expected error publishing more objects than quota"),
+            }
+
+            let mut builder = PublishDeltaBuilder::new();
+            builder.add_publish(file1.as_publish());
+            server
+                .publish(alice_handle.clone(), builder.finish())
+                .unwrap();
+
+            // Replacing an object only counts the new size
+            let mut builder = PublishDeltaBuilder::new();
+            builder.add_withdraw(file1.as_withdraw());
+            builder.add_publish(file2.as_publish());
+            server
+                .publish(alice_handle.clone(), builder.finish())
+                .unwrap();
+
+            // Lowering the quota keeps the objects, but only allows shrinking
+            let quota = PublisherQuota::new(Some(1), Some(5), None);
+            server.update_quota(alice_handle.clone(), quota).unwrap();
+
+            let file2_update = CurrentFile::new(
+                test::rsync("rsync://localhost/repo/alice/file2.txt"),
+                &Bytes::from("example 2 updated"),
+            );
+            let mut builder = PublishDeltaBuilder::new();
+            builder.add_update(file2_update.as_update(file2.hash()));
+            match server.publish(alice_handle.clone(), builder.finish()) {
+                Err(Error::PublisherQuotaExceeded(_, QuotaExceeded::Size(5))) => {}
+                _ => panic!("Expected error growing beyond size quota"),
+            }
+
+            let mut builder = PublishDeltaBuilder::new();
+            builder.add_withdraw(file2.as_withdraw());
+            server
+                .publish(alice_handle.clone(), builder.finish())
+                .unwrap();
+            assert!(server.list(&alice_handle).unwrap().elements().is_empty());
+        });
+    }
+
+    #[test]
+    fn should_report_expired_publishers() {
+        test::test_under_tmp(|d| {
+            let server = make_server(&d);
+            let alice = publisher_alice(&d);
+            let alice_handle = Handle::from_str_unsafe("alice");
+            let publisher_req = make_publisher_req(alice_handle.as_str(), alice.id_cert());
+            server.create_publisher(publisher_req).unwrap();
+
+            let mut signer = OpenSslSigner::build(&d).unwrap();
+            let in_an_hour = Time::now() + Duration::hours(1);
+            let mft = CurrentFile::new(
+                test::rsync("rsync://localhost/repo/alice/ca.mft"),
+                &test::manifest(&mut signer, &[], in_an_hour),
+            );
+
+            let mut builder = PublishDeltaBuilder::new();
+            builder.add_publish(mft.as_publish());
+            server
+                .publish(alice_handle.clone(), builder.finish())
+                .unwrap();
+
+            let stats = server.repo_stats().unwrap();
+            let alice_stats = stats.get_publishers().get(&alice_handle).unwrap();
+            let next_update = alice_stats.next_update().unwrap();
+            assert_eq!(next_update.timestamp(), in_an_hour.timestamp());
+            assert!(!alice_stats.is_expired());
+            assert!(stats.stale_publishers(3600).is_empty());
+
+            // A publisher with an expired CRL is stale, even if it has just
+            // published
+            let a_minute_ago = Time::now() - Duration::minutes(1);
+            let crl = CurrentFile::new(
+                test::rsync("rsync://localhost/repo/alice/ca.crl"),
+                &test::crl(&mut signer, a_minute_ago),
+            );
+
+            let mut builder = PublishDeltaBuilder::new();
+            builder.add_publish(crl.as_publish());
+            server
+                .publish(alice_handle.clone(), builder.finish())
+                .unwrap();
+
+            let stats = server.repo_stats().unwrap();
+            let alice_stats = stats.get_publishers().get(&alice_handle).unwrap();
+            assert!(alice_stats.is_expired());
+            assert_eq!(vec![alice_handle.clone()], stats.stale_publishers(3600));
+
+            // The expiry is known again when the server is built again
+            let server = make_server(&d);
+            let stats = server.repo_stats().unwrap();
+            let alice_stats = stats.get_publishers().get(&alice_handle).unwrap();
+            let next_update = alice_stats.next_update().unwrap();
+            assert_eq!(next_update.timestamp(), a_minute_ago.timestamp());
+            assert!(alice_stats.is_expired());
+
+            // Removing the objects removes the expiry
+            let mut builder = PublishDeltaBuilder::new();
+            builder.add_withdraw(mft.as_withdraw());
+            builder.add_withdraw(crl.as_withdraw());
+            server
+                .publish(alice_handle.clone(), builder.finish())
+                .unwrap();
+
+            let stats = server.repo_stats().unwrap();
+            let alice_stats = stats.get_publishers().get(&alice_handle).unwrap();
+            assert!(!alice_stats.is_expired());
+            assert!(alice_stats.next_update().is_none());
+        });
+    }
 }
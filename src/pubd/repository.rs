@@ -1,25 +1,32 @@
 use std::collections::HashMap;
+use std::fmt;
 use std::fs;
 use std::path::PathBuf;
 use std::str::{from_utf8_unchecked, FromStr};
 
+use chrono::Duration;
+
 use rpki::crypto::KeyIdentifier;
 use rpki::uri;
 use rpki::x509::Time;
 
 use crate::commons::api::rrdp::{
-    CurrentObjects, Delta, DeltaElements, DeltaRef, FileRef, Notification, RrdpSession, Snapshot,
-    SnapshotRef,
+    CurrentObjects, Delta, DeltaElements, DeltaRef, FileRef, Notification, RrdpRetention,
+    RrdpSession, Snapshot, SnapshotRef,
+};
+use crate::commons::api::{
+    Handle, HexEncodedHash, PublishDelta, PublisherHandle, PublisherInvitation, PublisherQuota,
+    QuotaExceeded, RepoInfo, TokenHash,
 };
-use crate::commons::api::{Handle, HexEncodedHash, PublishDelta, PublisherHandle, RepoInfo};
 use crate::commons::error::Error;
 use crate::commons::eventsourcing::Aggregate;
 use crate::commons::remote::id::IdCert;
 use crate::commons::remote::rfc8183;
 use crate::commons::util::file;
 use crate::commons::KrillResult;
-use crate::constants::{REPOSITORY_RRDP_DIR, REPOSITORY_RSYNC_DIR};
+use crate::constants::{REPOSITORY_RRDP_DIR, REPOSITORY_RSYNC_DIR, RRDP_RETIRED_MARKER_FILE};
 use crate::pubd::publishers::Publisher;
+use crate::pubd::validation::ObjectValidator;
 use crate::pubd::{Cmd, CmdDet, Evt, EvtDet, Ini, RrdpUpdate};
 
 //------------ RsyncdStore ---------------------------------------------------
@@ -46,16 +53,20 @@ impl RsyncdStore {
             rsync_dir,
         }
     }
+
+    fn rsync_dir(&self) -> &PathBuf {
+        &self.rsync_dir
+    }
 }
 
 /// # Publishing
 ///
 impl RsyncdStore {
-    /// Write all the files to disk for rsync to a tmp-dir, then switch
-    /// things over in an effort to minimise the chance of people getting
-    /// inconsistent syncs..
-    pub fn write(&self, snapshot: &Snapshot) -> KrillResult<()> {
-        let mut new_dir = self.rsync_dir.clone();
+    /// Write all the files to disk for rsync to a tmp-dir under the rsync
+    /// dir, then switch things over in an effort to minimise the chance of
+    /// people getting inconsistent syncs..
+    pub fn write(&self, rsync_dir: &PathBuf, snapshot: &Snapshot) -> KrillResult<()> {
+        let mut new_dir = rsync_dir.clone();
         new_dir.push(&format!("tmp-{}", snapshot.serial()));
         fs::create_dir_all(&new_dir)?;
 
@@ -75,10 +86,10 @@ impl RsyncdStore {
             file::save(&publish.base64().to_bytes(), &path)?;
         }
 
-        let mut current_dir = self.rsync_dir.clone();
+        let mut current_dir = rsync_dir.clone();
         current_dir.push("current");
 
-        let mut old_dir = self.rsync_dir.clone();
+        let mut old_dir = rsync_dir.clone();
         old_dir.push("old");
 
         if current_dir.exists() {
@@ -148,7 +159,11 @@ impl RrdpServer {
     /// the delta has already been checked against the jail and current
     /// objects of the publisher. Also note that this only becomes effective
     /// after the corresponding events have been applied.
-    fn publish(&self, elements: DeltaElements) -> Result<RrdpUpdate, Error> {
+    fn publish(
+        &self,
+        elements: DeltaElements,
+        retention: &RrdpRetention,
+    ) -> Result<RrdpUpdate, Error> {
         let next = self.serial + 1;
 
         let delta = Delta::new(self.session, next, elements);
@@ -162,26 +177,7 @@ impl RrdpServer {
         let snapshot_hash = HexEncodedHash::from_content(snapshot_xml.as_slice());
         let snapshot_ref = SnapshotRef::new(snapshot_uri, snapshot_path, snapshot_hash);
 
-        // keep at least 5 deltas if available, but beyond that no
-        // more then the combined size of which would exceed the
-        // size of the snapshot.
-        let snapshot_size = next_snapshot.size();
-        let mut deltas_size = delta.elements().size();
-
-        let mut deltas = vec![&delta];
-
-        for delta in &self.deltas {
-            if deltas.len() < 5 {
-                deltas.push(delta)
-            } else {
-                deltas_size += delta.elements().size();
-                if deltas_size < snapshot_size {
-                    deltas.push(delta)
-                } else {
-                    break;
-                }
-            }
-        }
+        let deltas = retention.retain(&delta, &self.deltas, next_snapshot.size());
 
         let refs: Vec<DeltaRef> = deltas
             .iter()
@@ -202,6 +198,33 @@ impl RrdpServer {
         Ok(RrdpUpdate::new(delta, notification))
     }
 
+    /// Creates the notification for a new session starting at serial 1, with
+    /// a snapshot of the current objects and no deltas. Note that this only
+    /// becomes effective after the corresponding event has been applied.
+    fn reset_session(&self) -> Notification {
+        let session = RrdpSession::new();
+        let serial = 1;
+
+        let snapshot = self.snapshot.for_new_session(session, serial);
+
+        let snapshot_uri = Self::new_snapshot_uri(&self.rrdp_base_uri, &session, serial);
+        let snapshot_path = Self::new_snapshot_path(&self.rrdp_base_dir, &session, serial);
+        let snapshot_hash = HexEncodedHash::from_content(snapshot.xml().as_slice());
+
+        let snapshot_ref = SnapshotRef::new(snapshot_uri, snapshot_path, snapshot_hash);
+
+        Notification::new(session, serial, snapshot_ref, vec![])
+    }
+
+    /// Start using the new session (as recorded in an event)
+    fn apply_session_reset(&mut self, notification: Notification) {
+        self.session = notification.session();
+        self.serial = notification.serial();
+        self.snapshot = self.snapshot.for_new_session(self.session, self.serial);
+        self.deltas = vec![];
+        self.notification = notification;
+    }
+
     /// Update the current RRDP state (as recorded in an event)
     pub fn apply_update(&mut self, update: RrdpUpdate) {
         let (delta, notification) = update.unpack();
@@ -219,13 +242,19 @@ impl RrdpServer {
         self.deltas.retain(|d| d.serial() >= last_delta);
     }
 
-    /// Write the (missing) RRDP files to disk, and remove the ones
-    /// no longer referenced in the notification file.
-    fn write(&self) -> Result<(), Error> {
+    /// Write the (missing) RRDP files to disk under the base dir, and remove
+    /// the ones no longer referenced in the notification file. Deltas and old
+    /// session dirs which are no longer referenced are retired when the
+    /// notification file is written, and removed by the first write after
+    /// the grace period for removal has passed.
+    ///
+    /// The notification file is written last, so that it never refers to
+    /// files which are not there yet.
+    fn write(&self, base_dir: &PathBuf, retention: &RrdpRetention) -> Result<(), Error> {
         let mut something_changed = false;
 
         // write snapshot if it's not there
-        let snapshot_path = self.snapshot_path(self.serial);
+        let snapshot_path = Self::new_snapshot_path(base_dir, &self.session, self.serial);
         if !snapshot_path.exists() {
             self.snapshot.write_xml(&snapshot_path)?;
             something_changed = true;
@@ -233,7 +262,7 @@ impl RrdpServer {
 
         // write deltas if they are not there
         for delta in &self.deltas {
-            let path = self.delta_path(delta.serial());
+            let path = Self::new_delta_path(base_dir, &self.session, delta.serial());
             if !path.exists() {
                 // assume that if the delta exists, it is correct
                 delta.write_xml(&path)?;
@@ -241,75 +270,119 @@ impl RrdpServer {
             }
         }
 
-        // if nothing changed then we're done
-        if !something_changed {
-            return Ok(());
-        }
+        if something_changed {
+            // something changes, update notification file
+            let notification_path = Self::new_notification_path(base_dir);
+            self.notification.write_xml(&notification_path)?;
 
-        // something changes, update notification file
-        let notification_path = self.notification_path();
-        self.notification.write_xml(&notification_path)?;
+            // relying parties can no longer find the dirs which are not
+            // referenced, so they are retired as of now
+            for dir in self.retired_dirs(base_dir)? {
+                Self::mark_retired(&dir)?;
+            }
 
-        // clean up under the base dir:
-        // - old session dirs
-        for entry in fs::read_dir(&self.rrdp_base_dir)? {
-            let entry = entry?;
-            if self.session.to_string() == entry.file_name().to_string_lossy() {
-                continue;
-            } else {
-                let path = entry.path();
-                if path.is_dir() {
-                    fs::remove_dir_all(path)?;
-                }
+            self.clean_up(base_dir)?;
+        }
+
+        for dir in self.retired_dirs(base_dir)? {
+            if Self::retired_longer_than(&dir, retention.removal_grace())? {
+                fs::remove_dir_all(dir)?;
             }
         }
 
-        // clean up under the current session
-        let mut session_dir = self.rrdp_base_dir.clone();
+        Ok(())
+    }
+
+    /// Removes the files and dirs under the current session dir which are
+    /// never referenced: anything which is not a serial, and snapshots for
+    /// serials other than the current serial.
+    fn clean_up(&self, base_dir: &PathBuf) -> Result<(), Error> {
+        let mut session_dir = base_dir.clone();
         session_dir.push(self.session.to_string());
 
         for entry in fs::read_dir(&session_dir)? {
             let entry = entry?;
             let path = entry.path();
 
-            // remove any dir or file that is:
-            // - not a number
-            // - a number that is higher than the current serial
-            // - a number that is lower than the last delta (if set)
             if let Ok(serial) = u64::from_str(entry.file_name().to_string_lossy().as_ref()) {
-                // Clean up old serial dirs
-                if let Some(last) = self.notification.last_delta() {
-                    if serial < last {
-                        if path.is_dir() {
-                            fs::remove_dir_all(path)?;
-                        } else {
-                            fs::remove_file(path)?;
-                        }
-
-                        continue;
-                    }
-                }
-
-                // Clean up snapshots in all dirs except the current
-                if serial != self.serial {
-                    let snapshot_path =
-                        Self::new_snapshot_path(&self.rrdp_base_dir, &self.session, serial);
+                if !path.is_dir() {
+                    fs::remove_file(path)?;
+                } else if serial != self.serial {
+                    let snapshot_path = Self::new_snapshot_path(base_dir, &self.session, serial);
                     if snapshot_path.exists() {
                         fs::remove_file(snapshot_path)?;
                     }
                 }
+            } else if path.is_dir() {
+                fs::remove_dir_all(path)?;
             } else {
-                // clean up dirs or files under the base dir which are not sessions
-                if path.is_dir() {
-                    fs::remove_dir_all(path)?;
-                } else {
-                    fs::remove_file(path)?;
+                fs::remove_file(path)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// # Retired deltas and sessions
+///
+impl RrdpServer {
+    /// Returns the dirs under the base dir which are no longer referenced in
+    /// the notification file: the dirs of old sessions, and the serial dirs
+    /// in the current session which are lower than the last delta, or higher
+    /// than the current serial.
+    fn retired_dirs(&self, base_dir: &PathBuf) -> Result<Vec<PathBuf>, Error> {
+        let mut res = vec![];
+
+        let session = self.session.to_string();
+        for entry in fs::read_dir(base_dir)? {
+            let path = entry?.path();
+            if path.is_dir() && path.file_name().unwrap().to_string_lossy() != session {
+                res.push(path);
+            }
+        }
+
+        let lowest = self.notification.last_delta().unwrap_or(self.serial);
+
+        let mut session_dir = base_dir.clone();
+        session_dir.push(session);
+        for entry in fs::read_dir(&session_dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if let Ok(serial) = u64::from_str(entry.file_name().to_string_lossy().as_ref()) {
+                if path.is_dir() && (serial < lowest || serial > self.serial) {
+                    res.push(path);
                 }
             }
         }
 
+        Ok(res)
+    }
+
+    /// Marks the serial or session dir as retired now, unless it was marked
+    /// before. Relying parties never ask for the marker file which records
+    /// this, so it is kept in the dir itself.
+    fn mark_retired(dir: &PathBuf) -> Result<(), Error> {
+        let marker = file::file_path(dir, RRDP_RETIRED_MARKER_FILE);
+        if !marker.exists() {
+            file::save_json(&Time::now(), &marker)?;
+        }
         Ok(())
     }
+
+    /// Returns whether the serial or session dir was retired longer ago than
+    /// the grace period. Dirs which were not marked before, e.g. because
+    /// they were retired by an older version, are marked as retired now.
+    fn retired_longer_than(dir: &PathBuf, grace: Duration) -> Result<bool, Error> {
+        let marker = file::file_path(dir, RRDP_RETIRED_MARKER_FILE);
+        if marker.exists() {
+            let retired: Time = file::load_json(&marker)?;
+            Ok(retired + grace <= Time::now())
+        } else {
+            Self::mark_retired(dir)?;
+            Ok(false)
+        }
+    }
 }
 
 /// rrdp paths and uris
@@ -323,8 +396,12 @@ impl RrdpServer {
         .unwrap() // Cannot fail. Config checked at startup.
     }
 
-    fn notification_path(&self) -> PathBuf {
-        let mut path = self.rrdp_base_dir.clone();
+    fn rrdp_base_dir(&self) -> &PathBuf {
+        &self.rrdp_base_dir
+    }
+
+    fn new_notification_path(base: &PathBuf) -> PathBuf {
+        let mut path = base.clone();
         path.push("notification.xml");
         path
     }
@@ -369,11 +446,52 @@ impl RrdpServer {
         .unwrap() // Cannot fail. Config checked at startup.
     }
 
-    fn delta_path(&self, serial: u64) -> PathBuf {
-        let mut path = self.rrdp_base_dir.clone();
-        path.push(Self::delta_rel(&self.session, serial));
+    fn new_delta_path(base: &PathBuf, session: &RrdpSession, serial: u64) -> PathBuf {
+        let mut path = base.clone();
+        path.push(Self::delta_rel(session, serial));
         path
     }
+
+    fn delta_path(&self, serial: u64) -> PathBuf {
+        Self::new_delta_path(&self.rrdp_base_dir, &self.session, serial)
+    }
+}
+
+//------------ RepositorySettings --------------------------------------------
+
+/// How the repository handles published objects. These follow the server
+/// configuration, and are updated through a command when that changes.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RepositorySettings {
+    validate_objects: bool,
+    rrdp_retention: RrdpRetention,
+}
+
+impl RepositorySettings {
+    pub fn new(validate_objects: bool, rrdp_retention: RrdpRetention) -> Self {
+        RepositorySettings {
+            validate_objects,
+            rrdp_retention,
+        }
+    }
+
+    pub fn validate_objects(&self) -> bool {
+        self.validate_objects
+    }
+
+    pub fn rrdp_retention(&self) -> &RrdpRetention {
+        &self.rrdp_retention
+    }
+}
+
+impl fmt::Display for RepositorySettings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "validate objects: {}, {}",
+            self.validate_objects, self.rrdp_retention
+        )
+    }
 }
 
 //------------ Repository --------------------------------------------------
@@ -389,8 +507,18 @@ pub struct Repository {
     id_cert: IdCert,
     key_id: KeyIdentifier, // convenience access to id_cert pub key id
 
+    // The new id cert while an ID key roll is in progress
+    #[serde(default)]
+    next_id_cert: Option<IdCert>,
+
     publishers: HashMap<PublisherHandle, Publisher>,
 
+    #[serde(default)]
+    invitations: HashMap<TokenHash, PublisherInvitation>,
+
+    #[serde(default)]
+    settings: RepositorySettings,
+
     rrdp: RrdpServer,
     rsync: RsyncdStore,
 
@@ -401,6 +529,21 @@ impl Repository {
     pub fn key_id(&self) -> &KeyIdentifier {
         &self.key_id
     }
+
+    /// Returns the id cert that is advertised to publishers. During an ID
+    /// key roll this is the new id cert, while responses are still signed
+    /// with the current key until the roll is activated.
+    pub fn advertised_id_cert(&self) -> &IdCert {
+        self.next_id_cert.as_ref().unwrap_or(&self.id_cert)
+    }
+
+    pub fn has_id_roll(&self) -> bool {
+        self.next_id_cert.is_some()
+    }
+
+    pub fn settings(&self) -> &RepositorySettings {
+        &self.settings
+    }
 }
 
 /// # Event Sourcing support
@@ -427,7 +570,10 @@ impl Aggregate for Repository {
             version: 1,
             id_cert,
             key_id,
+            next_id_cert: None,
             publishers: HashMap::new(),
+            invitations: HashMap::new(),
+            settings: RepositorySettings::default(),
             rrdp,
             rsync,
             stats,
@@ -451,6 +597,12 @@ impl Aggregate for Repository {
                 self.stats
                     .remove_publisher(&publisher_handle, &self.rrdp.notification);
             }
+            EvtDet::PublisherQuotaUpdated(publisher_handle, quota) => {
+                self.publishers
+                    .get_mut(&publisher_handle)
+                    .unwrap()
+                    .set_quota(quota);
+            }
             EvtDet::Published(publisher_handle, update) => {
                 // update content for publisher
                 self.update_publisher(&publisher_handle, &update);
@@ -468,6 +620,52 @@ impl Aggregate for Repository {
                 self.stats
                     .publish(&publisher_handle, publisher_stats, notification)
             }
+            EvtDet::BatchPublished(publishers, update) => {
+                let time = update.time();
+
+                let mut handles = vec![];
+                for (publisher_handle, elements) in publishers.into_iter() {
+                    self.publishers
+                        .get_mut(&publisher_handle)
+                        .unwrap()
+                        .apply_delta(elements, time);
+                    handles.push(publisher_handle);
+                }
+
+                self.rrdp.apply_update(update);
+
+                for publisher_handle in handles.iter() {
+                    let publisher = self.get_publisher(publisher_handle).unwrap();
+                    let publisher_stats = PublisherStats::new(publisher, time);
+                    self.stats
+                        .publish(publisher_handle, publisher_stats, &self.rrdp.notification)
+                }
+            }
+            EvtDet::RrdpSessionReset(notification) => {
+                self.rrdp.apply_session_reset(notification);
+                self.stats.session_reset(&self.rrdp.notification);
+            }
+            EvtDet::IdRollStarted(id_cert) => {
+                self.next_id_cert = Some(id_cert);
+            }
+            EvtDet::IdRollActivated => {
+                if let Some(id_cert) = self.next_id_cert.take() {
+                    self.key_id = id_cert.subject_public_key_info().key_identifier();
+                    self.id_cert = id_cert;
+                }
+            }
+            EvtDet::InvitationAdded(invitation) => {
+                self.invitations.insert(invitation.id().clone(), invitation);
+            }
+            EvtDet::InvitationUpdated(id, expires) => {
+                self.invitations.get_mut(&id).unwrap().set_expires(expires);
+            }
+            EvtDet::InvitationRemoved(id) => {
+                self.invitations.remove(&id);
+            }
+            EvtDet::SettingsUpdated(settings) => {
+                self.settings = settings;
+            }
         }
     }
 
@@ -480,7 +678,19 @@ impl Aggregate for Repository {
         match command.into_details() {
             CmdDet::AddPublisher(publisher_request) => self.add_publisher(publisher_request),
             CmdDet::RemovePublisher(publisher) => self.remove_publisher(publisher),
+            CmdDet::UpdateQuota(publisher, quota) => self.update_quota(publisher, quota),
             CmdDet::Publish(publisher_handle, delta) => self.publish(publisher_handle, delta),
+            CmdDet::PublishBatch(deltas) => self.publish_batch(deltas),
+            CmdDet::ResetRrdpSession => self.reset_rrdp_session(),
+            CmdDet::IdRollInit(id_cert) => self.id_roll_init(id_cert),
+            CmdDet::IdRollActivate => self.id_roll_activate(),
+            CmdDet::AddInvitation(invitation) => self.add_invitation(invitation),
+            CmdDet::UpdateInvitation(id, expires) => self.update_invitation(id, expires),
+            CmdDet::RevokeInvitation(id) => self.revoke_invitation(id),
+            CmdDet::AcceptInvitation(id, publisher_request) => {
+                self.accept_invitation(id, publisher_request)
+            }
+            CmdDet::UpdateSettings(settings) => self.update_settings(settings),
         }
     }
 }
@@ -493,20 +703,29 @@ impl Repository {
         publisher_request: rfc8183::PublisherRequest,
     ) -> Result<Vec<Evt>, Error> {
         let (_tag, handle, id_cert) = publisher_request.unpack();
+        let added = self.publisher_added(handle, id_cert, PublisherQuota::default())?;
+        Ok(vec![added])
+    }
 
+    fn publisher_added(
+        &self,
+        handle: PublisherHandle,
+        id_cert: IdCert,
+        quota: PublisherQuota,
+    ) -> Result<Evt, Error> {
         if self.publishers.contains_key(&handle) {
             Err(Error::PublisherDuplicate(handle))
         } else {
             let base_uri =
                 uri::Rsync::from_string(format!("{}{}/", self.rsync.base_uri, handle)).unwrap();
-            let publisher = Publisher::new(id_cert, base_uri, CurrentObjects::default());
+            let publisher = Publisher::new(id_cert, base_uri, CurrentObjects::default(), quota);
 
-            Ok(vec![EvtDet::publisher_added(
+            Ok(EvtDet::publisher_added(
                 &self.handle,
                 self.version,
                 handle,
                 publisher,
-            )])
+            ))
         }
     }
 
@@ -521,7 +740,9 @@ impl Repository {
             .map(|p| p.as_withdraw())
             .collect();
         let elements = DeltaElements::new(vec![], vec![], withdraws);
-        let update = self.rrdp.publish(elements)?;
+        let update = self
+            .rrdp
+            .publish(elements, self.settings.rrdp_retention())?;
 
         Ok(vec![EvtDet::publisher_removed(
             &self.handle,
@@ -531,6 +752,24 @@ impl Repository {
         )])
     }
 
+    /// Replaces the quota of a publisher. Lowering the quota below what the
+    /// publisher currently has does not remove any objects, but it will
+    /// prevent the publisher from adding more.
+    fn update_quota(
+        &self,
+        publisher_handle: PublisherHandle,
+        quota: PublisherQuota,
+    ) -> Result<Vec<Evt>, Error> {
+        self.get_publisher(&publisher_handle)?;
+
+        Ok(vec![EvtDet::publisher_quota_updated(
+            &self.handle,
+            self.version,
+            publisher_handle,
+            quota,
+        )])
+    }
+
     pub fn repo_info_for(&self, publisher: &PublisherHandle) -> RepoInfo {
         let publisher_rsync_base =
             uri::Rsync::from_str(&format!("{}{}/", self.rsync.base_uri, publisher)).unwrap();
@@ -552,7 +791,7 @@ impl Repository {
         Ok(rfc8183::RepositoryResponse::new(
             None,
             publisher_handle.clone(),
-            self.id_cert.clone(),
+            self.advertised_id_cert().clone(),
             service_uri,
             repo_info,
         ))
@@ -572,11 +811,132 @@ impl Repository {
         self.publishers.keys().cloned().collect()
     }
 
+    pub fn invitations(&self) -> Vec<PublisherInvitation> {
+        self.invitations.values().cloned().collect()
+    }
+
+    fn get_invitation(&self, id: &TokenHash) -> Result<&PublisherInvitation, Error> {
+        self.invitations
+            .get(id)
+            .ok_or_else(|| Error::PublisherInvitationUnknown)
+    }
+
+    /// Returns the handle that a publisher which asked for the requested
+    /// handle gets when it accepts the invitation.
+    pub fn invited_publisher_handle(
+        &self,
+        id: &TokenHash,
+        requested: &PublisherHandle,
+    ) -> Result<PublisherHandle, Error> {
+        self.get_invitation(id)?
+            .publisher_handle(requested)
+            .map_err(|_| Error::PublisherInvitationHandle(requested.clone()))
+    }
+
     fn update_publisher(&mut self, publisher: &PublisherHandle, update: &RrdpUpdate) {
         self.publishers
             .get_mut(publisher)
             .unwrap()
-            .apply_delta(update.elements().clone())
+            .apply_delta(update.elements().clone(), update.time())
+    }
+}
+
+/// # Invite publishers
+///
+impl Repository {
+    /// Adds the invitation, and removes invitations which have expired.
+    fn add_invitation(&self, invitation: PublisherInvitation) -> Result<Vec<Evt>, Error> {
+        let now = Time::now();
+        if invitation.is_expired(now) {
+            Err(Error::PublisherInvitationExpired)
+        } else if !invitation.handle_prefix_valid() {
+            let prefix = invitation.handle_prefix().unwrap().clone();
+            Err(Error::PublisherInvitationPrefix(prefix))
+        } else {
+            let mut version = self.version;
+            let mut res = vec![];
+
+            for expired in self.invitations.values().filter(|i| i.is_expired(now)) {
+                let id = expired.id().clone();
+                res.push(EvtDet::invitation_removed(&self.handle, version, id));
+                version += 1;
+            }
+
+            res.push(EvtDet::invitation_added(&self.handle, version, invitation));
+
+            Ok(res)
+        }
+    }
+
+    fn update_invitation(&self, id: TokenHash, expires: Time) -> Result<Vec<Evt>, Error> {
+        self.get_invitation(&id)?;
+        if expires <= Time::now() {
+            Err(Error::PublisherInvitationExpired)
+        } else {
+            Ok(vec![EvtDet::invitation_updated(
+                &self.handle,
+                self.version,
+                id,
+                expires,
+            )])
+        }
+    }
+
+    fn revoke_invitation(&self, id: TokenHash) -> Result<Vec<Evt>, Error> {
+        self.get_invitation(&id)?;
+        Ok(vec![EvtDet::invitation_removed(
+            &self.handle,
+            self.version,
+            id,
+        )])
+    }
+
+    /// Adds the publisher with the handle prefix and quota of the invitation,
+    /// and removes the invitation so that it cannot be used again.
+    fn accept_invitation(
+        &self,
+        id: TokenHash,
+        publisher_request: rfc8183::PublisherRequest,
+    ) -> Result<Vec<Evt>, Error> {
+        let invitation = self.get_invitation(&id)?;
+        if invitation.is_expired(Time::now()) {
+            return Err(Error::PublisherInvitationExpired);
+        }
+
+        let handle = self.invited_publisher_handle(&id, publisher_request.publisher_handle())?;
+        let (_tag, _requested, id_cert) = publisher_request.unpack();
+
+        let added = self.publisher_added(handle, id_cert, invitation.quota().clone())?;
+        let removed = EvtDet::invitation_removed(&self.handle, self.version + 1, id);
+
+        Ok(vec![added, removed])
+    }
+}
+
+/// # ID key roll
+///
+impl Repository {
+    /// Starts an ID key roll. The new id cert is included in repository
+    /// responses from now on, so that publishers can update, but responses
+    /// are still signed with the current key until the roll is activated.
+    fn id_roll_init(&self, id_cert: IdCert) -> Result<Vec<Evt>, Error> {
+        if self.has_id_roll() {
+            return Err(Error::PublisherIdRollInProgress);
+        }
+        Ok(vec![EvtDet::id_roll_started(
+            &self.handle,
+            self.version,
+            id_cert,
+        )])
+    }
+
+    /// Activates the new ID key, it will be used to sign all responses
+    /// from now on.
+    fn id_roll_activate(&self) -> Result<Vec<Evt>, Error> {
+        if !self.has_id_roll() {
+            return Err(Error::PublisherIdRollUnknown);
+        }
+        Ok(vec![EvtDet::id_roll_activated(&self.handle, self.version)])
     }
 }
 
@@ -588,10 +948,12 @@ impl Repository {
         publisher_handle: PublisherHandle,
         delta: PublishDelta,
     ) -> Result<Vec<Evt>, Error> {
-        let publisher = self.get_publisher(&publisher_handle)?;
         let delta_elements = DeltaElements::from(delta);
-        publisher.verify_delta(&delta_elements)?;
-        let rrdp_update = self.rrdp.publish(delta_elements)?;
+        self.verify_publish(&publisher_handle, &delta_elements)?;
+
+        let rrdp_update = self
+            .rrdp
+            .publish(delta_elements, self.settings.rrdp_retention())?;
 
         Ok(vec![EvtDet::published(
             &self.handle,
@@ -601,16 +963,217 @@ impl Repository {
         )])
     }
 
-    /// Update the RRPD and Rsync files on disk.
-    pub fn write(&self) -> Result<(), Error> {
-        // update RRDP
-        self.rrdp.write()?;
+    /// Publishes the deltas of several publishers in a single RRDP delta, so
+    /// that they result in one new serial. Each publisher may only occur
+    /// once, and if the delta of any publisher is rejected then nothing is
+    /// published.
+    fn publish_batch(
+        &self,
+        deltas: Vec<(PublisherHandle, PublishDelta)>,
+    ) -> Result<Vec<Evt>, Error> {
+        let mut publishers: Vec<(PublisherHandle, DeltaElements)> = vec![];
+        let mut all_elements = DeltaElements::new(vec![], vec![], vec![]);
+
+        for (publisher_handle, delta) in deltas.into_iter() {
+            if publishers
+                .iter()
+                .any(|(handle, _)| handle == &publisher_handle)
+            {
+                return Err(Error::custom(format!(
+                    "Publisher '{}' occurs more than once in batch",
+                    publisher_handle
+                )));
+            }
 
-        // re-sync RRDP snapshot to rsync files
-        let snapshot = self.rrdp.snapshot();
-        self.rsync.write(snapshot)?;
+            let delta_elements = DeltaElements::from(delta);
+            self.verify_publish(&publisher_handle, &delta_elements)?;
 
-        Ok(())
+            all_elements.append(delta_elements.clone());
+            publishers.push((publisher_handle, delta_elements));
+        }
+
+        let rrdp_update = self
+            .rrdp
+            .publish(all_elements, self.settings.rrdp_retention())?;
+
+        Ok(vec![EvtDet::batch_published(
+            &self.handle,
+            self.version,
+            publishers,
+            rrdp_update,
+        )])
+    }
+
+    /// Verifies that the delta may be published by the publisher: all
+    /// objects are within its jail and match its current objects, it stays
+    /// within its quota, and, if configured, the objects are valid.
+    pub fn verify_publish(
+        &self,
+        publisher_handle: &PublisherHandle,
+        delta_elements: &DeltaElements,
+    ) -> Result<(), Error> {
+        let publisher = self.get_publisher(publisher_handle)?;
+        publisher.verify_delta(delta_elements)?;
+
+        if self.settings.validate_objects() {
+            ObjectValidator::validate_delta(publisher.current_objects(), delta_elements)
+                .map_err(|e| Error::PublisherObjectInvalid(publisher_handle.clone(), e))?;
+        }
+
+        let publisher_stats = self
+            .stats
+            .get_publishers()
+            .get(publisher_handle)
+            .cloned()
+            .unwrap_or_default();
+        publisher_stats
+            .verify_quota(publisher, delta_elements)
+            .map_err(|e| Error::PublisherQuotaExceeded(publisher_handle.clone(), e))
+    }
+
+    /// Starts a new RRDP session at serial 1, with a fresh snapshot of the
+    /// current objects of all publishers.
+    fn reset_rrdp_session(&self) -> Result<Vec<Evt>, Error> {
+        let notification = self.rrdp.reset_session();
+
+        Ok(vec![EvtDet::rrdp_session_reset(
+            &self.handle,
+            self.version,
+            notification,
+        )])
+    }
+
+    /// Updates the settings to follow the server configuration. This is a
+    /// no-op if the settings did not change.
+    fn update_settings(&self, settings: RepositorySettings) -> Result<Vec<Evt>, Error> {
+        if settings == self.settings {
+            Ok(vec![])
+        } else {
+            Ok(vec![EvtDet::settings_updated(
+                &self.handle,
+                self.version,
+                settings,
+            )])
+        }
+    }
+
+    /// The RRDP and rsync dirs under the repository dir, which are always
+    /// written to.
+    pub fn default_output_targets(&self) -> Vec<OutputTarget> {
+        vec![
+            OutputTarget::new(OutputKind::Rrdp, self.rrdp.rrdp_base_dir().clone()),
+            OutputTarget::new(OutputKind::Rsync, self.rsync.rsync_dir().clone()),
+        ]
+    }
+
+    /// Update the RRDP or Rsync files on disk in the dir of the target.
+    pub fn write_target(&self, target: &OutputTarget) -> Result<(), Error> {
+        match target.kind() {
+            OutputKind::Rrdp => self
+                .rrdp
+                .write(target.dir(), self.settings.rrdp_retention()),
+            OutputKind::Rsync => {
+                // re-sync RRDP snapshot to rsync files
+                let snapshot = self.rrdp.snapshot();
+                self.rsync.write(target.dir(), snapshot)
+            }
+        }
+    }
+}
+
+//------------ OutputTarget --------------------------------------------------
+
+/// The kind of files which are written to an output target.
+#[derive(Clone, Copy, Debug, Deserialize, Display, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputKind {
+    #[display(fmt = "RRDP")]
+    Rrdp,
+
+    #[display(fmt = "rsync")]
+    Rsync,
+}
+
+/// A directory to which either the RRDP or the rsync files of the repository
+/// are written.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct OutputTarget {
+    kind: OutputKind,
+    dir: PathBuf,
+}
+
+impl OutputTarget {
+    pub fn new(kind: OutputKind, dir: PathBuf) -> Self {
+        OutputTarget { kind, dir }
+    }
+
+    pub fn kind(&self) -> OutputKind {
+        self.kind
+    }
+
+    pub fn dir(&self) -> &PathBuf {
+        &self.dir
+    }
+}
+
+impl fmt::Display for OutputTarget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} dir '{}'", self.kind, self.dir.to_string_lossy())
+    }
+}
+
+/// The outcome of the last writes to an output target.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct OutputTargetStatus {
+    target: OutputTarget,
+    last_attempt: Option<Time>,
+    last_success: Option<Time>,
+    error: Option<String>,
+}
+
+impl OutputTargetStatus {
+    pub fn new(target: OutputTarget) -> Self {
+        OutputTargetStatus {
+            target,
+            last_attempt: None,
+            last_success: None,
+            error: None,
+        }
+    }
+
+    pub fn succeeded(&mut self) {
+        let now = Time::now();
+        self.last_attempt = Some(now);
+        self.last_success = Some(now);
+        self.error = None;
+    }
+
+    pub fn failed(&mut self, error: &Error) {
+        self.last_attempt = Some(Time::now());
+        self.error = Some(error.to_string());
+    }
+
+    pub fn target(&self) -> &OutputTarget {
+        &self.target
+    }
+
+    pub fn last_attempt(&self) -> Option<Time> {
+        self.last_attempt
+    }
+
+    pub fn last_success(&self) -> Option<Time> {
+        self.last_success
+    }
+
+    /// The error of the last attempt, if it failed.
+    pub fn error(&self) -> Option<&String> {
+        self.error.as_ref()
+    }
+
+    /// Whether the last attempt succeeded. Targets which were never written
+    /// to are not up to date.
+    pub fn is_up_to_date(&self) -> bool {
+        self.last_success.is_some() && self.error.is_none()
     }
 }
 
@@ -622,6 +1185,11 @@ pub struct RepoStats {
     session: RrdpSession,
     serial: u64,
     last_update: Option<Time>,
+
+    // The status of writing to the output targets is not part of the state
+    // of the repository, it is filled in by the PubServer when asked.
+    #[serde(default)]
+    targets: Vec<OutputTargetStatus>,
 }
 
 impl RepoStats {
@@ -631,6 +1199,22 @@ impl RepoStats {
             session,
             serial: 0,
             last_update: None,
+            targets: vec![],
+        }
+    }
+
+    pub fn set_targets(&mut self, targets: Vec<OutputTargetStatus>) {
+        self.targets = targets;
+    }
+
+    pub fn set_expiry(
+        &mut self,
+        publisher: &PublisherHandle,
+        next_update: Option<Time>,
+        not_after: Option<Time>,
+    ) {
+        if let Some(stats) = self.publishers.get_mut(publisher) {
+            stats.set_expiry(next_update, not_after);
         }
     }
 
@@ -645,6 +1229,12 @@ impl RepoStats {
         self.last_update = Some(notification.time());
     }
 
+    pub fn session_reset(&mut self, notification: &Notification) {
+        self.session = notification.session();
+        self.serial = notification.serial();
+        self.last_update = Some(notification.time());
+    }
+
     pub fn new_publisher(&mut self, publisher: &PublisherHandle) {
         self.publishers
             .insert(publisher.clone(), PublisherStats::default());
@@ -660,10 +1250,14 @@ impl RepoStats {
         &self.publishers
     }
 
+    /// Returns the publishers which have not updated for the given number
+    /// of seconds, or which have an expired manifest or CRL.
     pub fn stale_publishers(&self, seconds: i64) -> Vec<PublisherHandle> {
         let mut res = vec![];
         for (publisher, stats) in self.publishers.iter() {
-            if let Some(update_time) = stats.last_update {
+            if stats.is_expired() {
+                res.push(publisher.clone())
+            } else if let Some(update_time) = stats.last_update {
                 if Time::now().timestamp() - update_time.timestamp() >= seconds {
                     res.push(publisher.clone())
                 }
@@ -685,6 +1279,10 @@ impl RepoStats {
     pub fn session(&self) -> RrdpSession {
         self.session
     }
+
+    pub fn targets(&self) -> &Vec<OutputTargetStatus> {
+        &self.targets
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -692,6 +1290,16 @@ pub struct PublisherStats {
     objects: usize,
     size: usize,
     last_update: Option<Time>,
+
+    // The earliest nextUpdate of the manifests and CRLs, and notAfter of
+    // the manifest EE certificates of the publisher are not part of the
+    // state of the repository, they are filled in by the PubServer when
+    // asked.
+    #[serde(default)]
+    next_update: Option<Time>,
+
+    #[serde(default)]
+    not_after: Option<Time>,
 }
 
 impl PublisherStats {
@@ -702,9 +1310,16 @@ impl PublisherStats {
             objects,
             size,
             last_update: Some(last_update),
+            next_update: None,
+            not_after: None,
         }
     }
 
+    pub fn set_expiry(&mut self, next_update: Option<Time>, not_after: Option<Time>) {
+        self.next_update = next_update;
+        self.not_after = not_after;
+    }
+
     pub fn objects(&self) -> usize {
         self.objects
     }
@@ -716,6 +1331,77 @@ impl PublisherStats {
     pub fn last_update(&self) -> Option<Time> {
         self.last_update
     }
+
+    /// The earliest 'nextUpdate' of the manifests and CRLs of the publisher.
+    pub fn next_update(&self) -> Option<Time> {
+        self.next_update
+    }
+
+    /// The earliest 'notAfter' of the manifest EE certificates of the
+    /// publisher.
+    pub fn not_after(&self) -> Option<Time> {
+        self.not_after
+    }
+
+    /// Returns true if a manifest or CRL of the publisher has expired.
+    pub fn is_expired(&self) -> bool {
+        let now = Time::now();
+        self.next_update.map(|t| t <= now).unwrap_or(false)
+            || self.not_after.map(|t| t <= now).unwrap_or(false)
+    }
+
+    /// Verifies that the publisher stays within its quota when the delta is
+    /// applied. Assumes that the delta was verified against the current
+    /// objects of the publisher.
+    ///
+    /// A publisher which already exceeds its quota, because the quota was
+    /// lowered, may still publish deltas which do not make this worse.
+    pub fn verify_quota(
+        &self,
+        publisher: &Publisher,
+        delta: &DeltaElements,
+    ) -> Result<(), QuotaExceeded> {
+        let quota = publisher.quota();
+
+        if let Some(max) = quota.max_object_size() {
+            for p in delta.publishes() {
+                if p.size() > max {
+                    return Err(QuotaExceeded::ObjectSize(p.uri().clone(), max));
+                }
+            }
+            for u in delta.updates() {
+                if u.size() > max {
+                    return Err(QuotaExceeded::ObjectSize(u.uri().clone(), max));
+                }
+            }
+        }
+
+        if let Some(max) = quota.max_objects() {
+            let objects =
+                (self.objects + delta.publishes().len()).saturating_sub(delta.withdraws().len());
+            if objects > max && objects > self.objects {
+                return Err(QuotaExceeded::Objects(max));
+            }
+        }
+
+        if let Some(max) = quota.max_size() {
+            let current = publisher.current_objects();
+            let removed = delta
+                .updates()
+                .iter()
+                .map(|u| u.hash())
+                .chain(delta.withdraws().iter().map(|w| w.hash()))
+                .filter_map(|hash| current.get(hash))
+                .fold(0, |sum, p| sum + p.size());
+
+            let size = (self.size + delta.size()).saturating_sub(removed);
+            if size > max && size > self.size {
+                return Err(QuotaExceeded::Size(max));
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl Default for PublisherStats {
@@ -724,6 +1410,8 @@ impl Default for PublisherStats {
             objects: 0,
             size: 0,
             last_update: None,
+            next_update: None,
+            not_after: None,
         }
     }
 }
@@ -0,0 +1,296 @@
+//! Validation of the content of objects published by publishers.
+
+use std::collections::HashMap;
+
+use bcder::Oid;
+use bytes::Bytes;
+
+use rpki::cert::Cert;
+use rpki::crl::Crl;
+use rpki::manifest::Manifest;
+use rpki::roa::Roa;
+use rpki::sigobj::SignedObject;
+use rpki::uri;
+
+use crate::commons::api::rrdp::{CurrentObjects, DeltaElements};
+use crate::commons::api::{Base64, HexEncodedHash};
+
+//------------ ObjectType ----------------------------------------------------
+
+/// The RPKI object types which may be published, as identified by the
+/// extension of the file name.
+#[derive(Clone, Copy, Debug, Display, Eq, PartialEq)]
+pub enum ObjectType {
+    #[display(fmt = "certificate")]
+    Cert,
+
+    #[display(fmt = "manifest")]
+    Manifest,
+
+    #[display(fmt = "CRL")]
+    Crl,
+
+    #[display(fmt = "ROA")]
+    Roa,
+
+    #[display(fmt = "Ghostbusters record")]
+    Ghostbusters,
+
+    #[display(fmt = "ASPA object")]
+    Aspa,
+}
+
+impl ObjectType {
+    pub fn for_uri(uri: &uri::Rsync) -> Option<Self> {
+        let uri = uri.to_string();
+        let name = uri.rsplit('/').next().unwrap_or_default();
+        match name.rsplit('.').next() {
+            Some("cer") => Some(ObjectType::Cert),
+            Some("mft") => Some(ObjectType::Manifest),
+            Some("crl") => Some(ObjectType::Crl),
+            Some("roa") => Some(ObjectType::Roa),
+            Some("gbr") => Some(ObjectType::Ghostbusters),
+            Some("asa") => Some(ObjectType::Aspa),
+            _ => None,
+        }
+    }
+
+    fn parses(self, bytes: Bytes) -> bool {
+        match self {
+            ObjectType::Cert => Cert::decode(bytes).is_ok(),
+            ObjectType::Manifest => Manifest::decode(bytes, false).is_ok(),
+            ObjectType::Crl => Crl::decode(bytes).is_ok(),
+            ObjectType::Roa => Roa::decode(bytes, false).is_ok(),
+            ObjectType::Ghostbusters => Self::is_signed_object(bytes, &oid::CT_GHOSTBUSTERS),
+            ObjectType::Aspa => Self::is_signed_object(bytes, &oid::CT_ASPA),
+        }
+    }
+
+    /// The rpki library does not support these types yet, so we can only
+    /// check that they are signed objects with the right content type.
+    fn is_signed_object(bytes: Bytes, content_type: &Oid<&[u8]>) -> bool {
+        match SignedObject::decode(bytes, false) {
+            Ok(signed) => signed.content_type() == content_type,
+            Err(_) => false,
+        }
+    }
+}
+
+//------------ ObjectValidationError -----------------------------------------
+
+/// Issues with the content of published objects.
+#[derive(Clone, Debug, Display, Eq, PartialEq)]
+pub enum ObjectValidationError {
+    #[display(fmt = "Object '{}' does not have a known RPKI file extension", _0)]
+    UnknownType(uri::Rsync),
+
+    #[display(fmt = "Object '{}' is not a valid {}", _0, _1)]
+    Malformed(uri::Rsync, ObjectType),
+
+    #[display(
+        fmt = "Manifest '{}' lists '{}' which is not published with the same hash",
+        _0,
+        _1
+    )]
+    ManifestEntryUnknown(uri::Rsync, String),
+}
+
+//------------ ObjectValidator -----------------------------------------------
+
+/// Verifies that the objects in a delta parse as the type indicated by their
+/// file extension, and that the manifests in it only list objects which the
+/// publisher will have when the delta is applied.
+///
+/// Assumes that the delta was verified against the current objects of the
+/// publisher.
+pub struct ObjectValidator;
+
+impl ObjectValidator {
+    pub fn validate_delta(
+        current: &CurrentObjects,
+        delta: &DeltaElements,
+    ) -> Result<(), ObjectValidationError> {
+        let published = delta
+            .publishes()
+            .iter()
+            .map(|p| (p.uri(), p.base64()))
+            .chain(delta.updates().iter().map(|u| (u.uri(), u.base64())));
+
+        let mut manifests = vec![];
+        for (uri, base64) in published {
+            let object_type = ObjectType::for_uri(uri)
+                .ok_or_else(|| ObjectValidationError::UnknownType(uri.clone()))?;
+
+            if !object_type.parses(base64.to_bytes()) {
+                return Err(ObjectValidationError::Malformed(uri.clone(), object_type));
+            }
+
+            if object_type == ObjectType::Manifest {
+                manifests.push((uri, base64));
+            }
+        }
+
+        if manifests.is_empty() {
+            return Ok(());
+        }
+
+        let mut after = current.clone();
+        after.apply_delta(delta.clone());
+        let after: HashMap<String, HexEncodedHash> = after
+            .elements()
+            .into_iter()
+            .map(|p| (p.uri().to_string(), p.base64().to_encoded_hash()))
+            .collect();
+
+        for (uri, base64) in manifests {
+            Self::verify_manifest_entries(uri, base64, &after)?;
+        }
+
+        Ok(())
+    }
+
+    fn verify_manifest_entries(
+        uri: &uri::Rsync,
+        base64: &Base64,
+        after: &HashMap<String, HexEncodedHash>,
+    ) -> Result<(), ObjectValidationError> {
+        // Parsed before, so this cannot fail.
+        let mft = Manifest::decode(base64.to_bytes(), false).unwrap();
+
+        let uri_str = uri.to_string();
+        let dir = &uri_str[..=uri_str.rfind('/').unwrap()];
+
+        for entry in mft.content().iter() {
+            let name = String::from_utf8_lossy(entry.file()).into_owned();
+            let hash = HexEncodedHash::from(hex::encode(entry.hash()));
+
+            match after.get(&format!("{}{}", dir, name)) {
+                Some(found) if found == &hash => {}
+                _ => {
+                    return Err(ObjectValidationError::ManifestEntryUnknown(
+                        uri.clone(),
+                        name,
+                    ))
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+//------------ OIDs ----------------------------------------------------------
+
+mod oid {
+    use bcder::Oid;
+
+    // 1.2.840.113549.1.9.16.1.35
+    pub const CT_GHOSTBUSTERS: Oid<&[u8]> = Oid(&[42, 134, 72, 134, 247, 13, 1, 9, 16, 1, 35]);
+
+    // 1.2.840.113549.1.9.16.1.49
+    pub const CT_ASPA: Oid<&[u8]> = Oid(&[42, 134, 72, 134, 247, 13, 1, 9, 16, 1, 49]);
+}
+
+//------------ Tests ---------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use rpki::x509::Time;
+
+    use crate::commons::api::rrdp::PublishElement;
+    use crate::commons::util::softsigner::OpenSslSigner;
+    use crate::commons::util::test;
+
+    fn publish(uri: &str, content: &[u8]) -> DeltaElements {
+        publish_all(&[(uri, content)])
+    }
+
+    fn publish_all(objects: &[(&str, &[u8])]) -> DeltaElements {
+        let elements = objects
+            .iter()
+            .map(|(uri, content)| {
+                PublishElement::new(Base64::from_content(content), test::rsync(uri))
+            })
+            .collect();
+        DeltaElements::new(elements, vec![], vec![])
+    }
+
+    #[test]
+    fn should_validate_objects() {
+        let current = CurrentObjects::default();
+        let cert: &[u8] = include_bytes!("../../test-resources/ta.cer");
+
+        let delta = publish("rsync://localhost/repo/alice/ta.cer", cert);
+        assert!(ObjectValidator::validate_delta(&current, &delta).is_ok());
+
+        let delta = publish("rsync://localhost/repo/alice/ta.roa", cert);
+        assert_eq!(
+            ObjectValidator::validate_delta(&current, &delta),
+            Err(ObjectValidationError::Malformed(
+                test::rsync("rsync://localhost/repo/alice/ta.roa"),
+                ObjectType::Roa
+            ))
+        );
+
+        let delta = publish("rsync://localhost/repo/alice/ta.mft", b"not a manifest");
+        assert_eq!(
+            ObjectValidator::validate_delta(&current, &delta),
+            Err(ObjectValidationError::Malformed(
+                test::rsync("rsync://localhost/repo/alice/ta.mft"),
+                ObjectType::Manifest
+            ))
+        );
+
+        let delta = publish("rsync://localhost/repo/alice/file.txt", cert);
+        assert_eq!(
+            ObjectValidator::validate_delta(&current, &delta),
+            Err(ObjectValidationError::UnknownType(test::rsync(
+                "rsync://localhost/repo/alice/file.txt"
+            )))
+        );
+
+        test::test_under_tmp(|d| {
+            let mut signer = OpenSslSigner::build(&d).unwrap();
+            let next_update = Time::now();
+
+            let mft_uri = "rsync://localhost/repo/alice/ca.mft";
+            let cer_uri = "rsync://localhost/repo/alice/ta.cer";
+
+            // A manifest listing an object published in the same delta
+            let mft = test::manifest(&mut signer, &[("ta.cer", cert)], next_update);
+            let delta = publish_all(&[(cer_uri, cert), (mft_uri, mft.as_ref())]);
+            assert!(ObjectValidator::validate_delta(&current, &delta).is_ok());
+
+            // A manifest listing an object which was published before
+            let mut published = CurrentObjects::default();
+            published.apply_delta(publish(cer_uri, cert));
+            let delta = publish(mft_uri, mft.as_ref());
+            assert!(ObjectValidator::validate_delta(&published, &delta).is_ok());
+
+            // A manifest listing an object which is not published
+            let mft = test::manifest(&mut signer, &[("ta.roa", cert)], next_update);
+            let delta = publish_all(&[(cer_uri, cert), (mft_uri, mft.as_ref())]);
+            assert_eq!(
+                ObjectValidator::validate_delta(&current, &delta),
+                Err(ObjectValidationError::ManifestEntryUnknown(
+                    test::rsync(mft_uri),
+                    "ta.roa".to_string()
+                ))
+            );
+
+            // A manifest listing an object with a different hash
+            let mft = test::manifest(&mut signer, &[("ta.cer", b"other".as_ref())], next_update);
+            let delta = publish_all(&[(cer_uri, cert), (mft_uri, mft.as_ref())]);
+            assert_eq!(
+                ObjectValidator::validate_delta(&current, &delta),
+                Err(ObjectValidationError::ManifestEntryUnknown(
+                    test::rsync(mft_uri),
+                    "ta.cer".to_string()
+                ))
+            );
+        });
+    }
+}
@@ -5,8 +5,9 @@ use std::sync::Arc;
 use crate::commons::api::Handle;
 use crate::commons::api::{Publish, PublishDelta, RepositoryContact, Update, Withdraw};
 use crate::commons::error::Error;
+use crate::commons::eventsourcing::Aggregate;
 use crate::commons::util::softsigner::OpenSslSigner;
-use crate::daemon::ca::CaServer;
+use crate::daemon::ca::{CaServer, CertAuth};
 use crate::pubd::PubServer;
 
 //------------ CaPublisher ---------------------------------------------------
@@ -46,42 +47,9 @@ impl CaPublisher {
             Err(_) => return Ok(()),
         };
 
-        let list_reply = match &repo_contact {
-            RepositoryContact::Embedded(_) => self.get_embedded()?.list(ca_handle)?,
-            RepositoryContact::Rfc8181(repo) => self.caserver.send_rfc8181_list(ca_handle, repo)?,
-        };
-
-        let delta = {
-            let elements: HashMap<_, _> = list_reply
-                .into_elements()
-                .into_iter()
-                .map(|el| el.unpack())
-                .collect();
-
-            let mut all_objects: HashMap<_, _> =
-                ca.all_objects().into_iter().map(|el| el.unpack()).collect();
-
-            let mut withdraws = vec![];
-            let mut updates = vec![];
-            for (uri, hash) in elements.into_iter() {
-                match all_objects.remove(&uri) {
-                    Some(base64) => {
-                        if base64.to_encoded_hash() != hash {
-                            updates.push(Update::new(None, uri, base64, hash))
-                        }
-                    }
-                    None => withdraws.push(Withdraw::new(None, uri, hash)),
-                }
-            }
-            let publishes = all_objects
-                .into_iter()
-                .map(|(uri, base64)| Publish::new(None, uri, base64))
-                .collect();
-
-            PublishDelta::new(publishes, updates, withdraws)
-        };
+        let delta = self.delta(&ca, repo_contact)?;
 
-        match &repo_contact {
+        match repo_contact {
             RepositoryContact::Embedded(_) => {
                 self.get_embedded()?.publish(ca_handle.clone(), delta)?
             }
@@ -90,9 +58,114 @@ impl CaPublisher {
             }
         };
 
+        // All objects of this version of the CA are now published, so a
+        // migration to a new parent may be completed.
+        self.caserver
+            .ca_parent_migration_published(ca_handle, ca.version());
+
         Ok(())
     }
 
+    /// Publishes for several CAs, and logs any errors. The deltas of CAs
+    /// which use the embedded repository are submitted together, so that
+    /// they can be published in a single RRDP delta.
+    pub fn publish_all(&self, ca_handles: &[Handle]) {
+        let mut embedded = vec![];
+
+        for ca_handle in ca_handles {
+            let ca = match self.caserver.get_ca(ca_handle) {
+                Ok(ca) => ca,
+                Err(e) => {
+                    error!("Failed to publish for CA: {}, error: {}", ca_handle, e);
+                    continue;
+                }
+            };
+
+            let res = match ca.get_repository_contact() {
+                Ok(contact @ RepositoryContact::Embedded(_)) => self
+                    .delta(&ca, contact)
+                    .map(|delta| embedded.push((ca.clone(), delta))),
+                Ok(RepositoryContact::Rfc8181(_)) => self.publish(ca_handle),
+                Err(_) => Ok(()), // no repository configured yet
+            };
+
+            if let Err(e) = res {
+                error!("Failed to publish for CA: {}, error: {}", ca_handle, e);
+            }
+        }
+
+        if embedded.is_empty() {
+            return;
+        }
+
+        let pubserver = match self.get_embedded() {
+            Ok(pubserver) => pubserver,
+            Err(e) => {
+                error!("Failed to publish for CAs, error: {}", e);
+                return;
+            }
+        };
+
+        let (cas, deltas): (Vec<_>, Vec<_>) = embedded
+            .into_iter()
+            .map(|(ca, delta)| {
+                let handle = ca.handle().clone();
+                (ca, (handle, delta))
+            })
+            .unzip();
+
+        for (ca, res) in cas.iter().zip(pubserver.publish_all(deltas)) {
+            match res {
+                Ok(()) => self
+                    .caserver
+                    .ca_parent_migration_published(ca.handle(), ca.version()),
+                Err(e) => error!("Failed to publish for CA: {}, error: {}", ca.handle(), e),
+            }
+        }
+    }
+
+    /// Returns the delta needed to bring the objects of the CA in the
+    /// repository up to date.
+    fn delta(
+        &self,
+        ca: &CertAuth<OpenSslSigner>,
+        repo_contact: &RepositoryContact,
+    ) -> Result<PublishDelta, Error> {
+        let ca_handle = ca.handle();
+        let list_reply = match repo_contact {
+            RepositoryContact::Embedded(_) => self.get_embedded()?.list(ca_handle)?,
+            RepositoryContact::Rfc8181(repo) => self.caserver.send_rfc8181_list(ca_handle, repo)?,
+        };
+
+        let elements: HashMap<_, _> = list_reply
+            .into_elements()
+            .into_iter()
+            .map(|el| el.unpack())
+            .collect();
+
+        let mut all_objects: HashMap<_, _> =
+            ca.all_objects().into_iter().map(|el| el.unpack()).collect();
+
+        let mut withdraws = vec![];
+        let mut updates = vec![];
+        for (uri, hash) in elements.into_iter() {
+            match all_objects.remove(&uri) {
+                Some(base64) => {
+                    if base64.to_encoded_hash() != hash {
+                        updates.push(Update::new(None, uri, base64, hash))
+                    }
+                }
+                None => withdraws.push(Withdraw::new(None, uri, hash)),
+            }
+        }
+        let publishes = all_objects
+            .into_iter()
+            .map(|(uri, base64)| Publish::new(None, uri, base64))
+            .collect();
+
+        Ok(PublishDelta::new(publishes, updates, withdraws))
+    }
+
     pub fn clean_up(&self, ca_handle: &Handle) -> Result<(), Error> {
         let ca = self.caserver.get_ca(ca_handle)?;
 
@@ -0,0 +1,68 @@
+extern crate krill;
+
+use std::str::FromStr;
+
+use krill::commons::api::{Handle, ParentCaReq, ResourceSet, RoaDefinition, RoaDefinitionUpdates};
+use krill::daemon::ca::ta_handle;
+use krill::daemon::test::*;
+
+#[test]
+/// Test that a CA issues AS0 ROAs for its unused address space when enabled,
+/// and that these are updated when authorizations change, or removed when
+/// disabled again.
+fn ca_as0_roas() {
+    test_with_krill_server(|_d| {
+        let ta_handle = ta_handle();
+        let child = Handle::from_str_unsafe("child");
+        let child_resources = ResourceSet::from_strs("", "10.0.0.0/16", "2001:DB8::/32").unwrap();
+
+        // AS0 ROAs can be enabled before the CA has a repository or resources
+        init_ca(&child);
+        ca_route_authorizations_as0_update(&child, true);
+        assert!(ca_route_authorizations_as0_info(&child).enabled());
+
+        ca_repo_update_embedded(&child);
+        {
+            let parent = {
+                let parent_contact = add_child_to_ta_embedded(&child, child_resources.clone());
+                ParentCaReq::new(ta_handle.clone(), parent_contact)
+            };
+            add_parent_to_ca(&child, parent);
+            wait_for_current_resources(&child, &child_resources);
+        }
+
+        let crl_file = ".crl";
+        let mft_file = ".mft";
+        let roa_file = ".roa";
+
+        // All space is unused, so it is covered by AS0 ROAs
+        let as0_v4 = RoaDefinition::from_str("10.0.0.0/16 => 0").unwrap();
+        let as0_v6 = RoaDefinition::from_str("2001:DB8::/32 => 0").unwrap();
+        wait_for_published_objects(&child, &[crl_file, mft_file, roa_file, roa_file]);
+        assert!(roas_contain_route(&child, &as0_v4));
+        assert!(roas_contain_route(&child, &as0_v6));
+
+        // An authorization takes its space out of the AS0 ROAs
+        let route = RoaDefinition::from_str("10.0.0.0/17 => 64496").unwrap();
+        let as0_rest = RoaDefinition::from_str("10.0.128.0/17 => 0").unwrap();
+
+        let mut updates = RoaDefinitionUpdates::empty();
+        updates.add(route);
+        ca_route_authorizations_update(&child, updates);
+        wait_for_published_objects(&child, &[crl_file, mft_file, roa_file, roa_file, roa_file]);
+        assert!(roas_contain_route(&child, &route));
+        assert!(roas_contain_route(&child, &as0_rest));
+        assert!(roas_contain_route(&child, &as0_v6));
+        assert!(!roas_contain_route(&child, &as0_v4));
+
+        let info = ca_route_authorizations_as0_info(&child);
+        assert_eq!(2, info.roas().len());
+        assert!(info.roas().contains(&as0_rest));
+        assert!(info.roas().contains(&as0_v6));
+
+        // Disabling removes the AS0 ROAs, but keeps the authorization
+        ca_route_authorizations_as0_update(&child, false);
+        wait_for_published_objects(&child, &[crl_file, mft_file, roa_file]);
+        assert!(roas_contain_route(&child, &route));
+    });
+}
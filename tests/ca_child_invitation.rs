@@ -0,0 +1,71 @@
+extern crate krill;
+
+use krill::commons::api::{Handle, ParentCaReq, ResourceSet, Token};
+use krill::commons::util::httpclient;
+use krill::commons::util::test::files_containing;
+use krill::daemon::ca::ta_handle;
+use krill::daemon::test::*;
+
+#[test]
+/// Test that a child can add itself to a CA using an invitation, that an
+/// invitation can only be used once, and that its token is not kept.
+fn ca_child_invitation() {
+    test_with_krill_server(|d| {
+        let ta_handle = ta_handle();
+        let ca = Handle::from_str_unsafe("ca");
+        let child = Handle::from_str_unsafe("child");
+        let other = Handle::from_str_unsafe("other");
+
+        let ca_res = ResourceSet::from_strs("", "10.0.0.0/16", "").unwrap();
+        let child_res = ResourceSet::from_strs("", "10.0.0.0/24", "").unwrap();
+
+        init_child_with_embedded_repo(&ca);
+        let parent = {
+            let contact = add_child_to_ta_embedded(&ca, ca_res.clone());
+            ParentCaReq::new(ta_handle.clone(), contact)
+        };
+        add_parent_to_ca(&ca, parent);
+        wait_for_current_resources(&ca, &ca_res);
+
+        // Revoked invitations cannot be used
+        let revoked = invite_child(&ca, &child_res);
+        revoke_child_invitation(&ca, revoked.invitation().id());
+        init_child_with_embedded_repo(&child);
+        assert!(accept_child_invitation(&ca, revoked.token(), child_request(&child)).is_err());
+
+        // The child adds itself using the invitation
+        let invitation = invite_child(&ca, &child_res);
+        assert_eq!(
+            vec![invitation.invitation().clone()],
+            child_invitations(&ca)
+        );
+
+        let contact =
+            accept_child_invitation(&ca, invitation.token(), child_request(&child)).unwrap();
+        add_parent_to_ca(&child, ParentCaReq::new(ca.clone(), contact));
+        wait_for_current_resources(&child, &child_res);
+
+        // The invitation is used up
+        assert!(child_invitations(&ca).is_empty());
+        init_child_with_embedded_repo(&other);
+        assert!(accept_child_invitation(&ca, invitation.token(), child_request(&other)).is_err());
+
+        // Only the hashes of the tokens are kept, or shown in the history
+        for token in &[revoked.token(), invitation.token()] {
+            assert!(!ca_history(&ca).to_string().contains(token.as_ref()));
+            assert!(files_containing(&d, token.as_ref()).is_empty());
+        }
+
+        // Attempts to accept an invitation are rate limited
+        let guess = Token::from("guess");
+        let mut rate_limited = false;
+        for _ in 0..11 {
+            if let Err(httpclient::Error::ErrorWithBody(status, _)) =
+                accept_child_invitation(&ca, &guess, child_request(&other))
+            {
+                rate_limited |= status.as_u16() == 429;
+            }
+        }
+        assert!(rate_limited);
+    });
+}
@@ -0,0 +1,152 @@
+extern crate chrono;
+extern crate krill;
+extern crate rpki;
+
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use chrono::Duration;
+
+use rpki::x509::Time;
+
+use krill::commons::api::{
+    Handle, ObjectName, ParentCaReq, ResourceSet, RoaDefinition, RoaDefinitionUpdates, RoaSchedule,
+    ScheduledRoa,
+};
+use krill::daemon::ca::ta_handle;
+use krill::daemon::test::*;
+
+#[test]
+/// Test that prefixes, and the ROAs for them, can be moved from one child
+/// of a CA to another. A ROA for a less specific prefix is split: both
+/// children get a ROA for their part, as long as the max length of the ROA
+/// authorizes that part. ROAs are never widened. Scheduled ROAs are moved
+/// as schedules.
+///
+/// The transfer fails halfway when the receiving child has no repository,
+/// and can be retried once it has one.
+///
+///                   TA
+///                   |
+///                 PARENT
+///                /      \
+///             FROM  ->  TO
+///
+fn ca_child_transfer() {
+    test_with_krill_server(|_d| {
+        let ta_handle = ta_handle();
+        let parent = Handle::from_str_unsafe("parent");
+        let from = Handle::from_str_unsafe("from");
+        let to = Handle::from_str_unsafe("to");
+
+        let parent_res = ResourceSet::from_strs("", "10.0.0.0/16", "").unwrap();
+        let from_res = ResourceSet::from_strs("", "10.0.0.0/23", "").unwrap();
+        let to_res = ResourceSet::from_strs("", "10.0.2.0/24", "").unwrap();
+        let moved_res = ResourceSet::from_strs("", "10.0.1.0/24", "").unwrap();
+
+        init_child_with_embedded_repo(&parent);
+        let parent_req = {
+            let contact = add_child_to_ta_embedded(&parent, parent_res.clone());
+            ParentCaReq::new(ta_handle.clone(), contact)
+        };
+        add_parent_to_ca(&parent, parent_req);
+        wait_for_current_resources(&parent, &parent_res);
+
+        init_child_with_embedded_repo(&from);
+        let contact = add_child_rfc6492(&parent, &from, child_request(&from), from_res.clone());
+        add_parent_to_ca(&from, ParentCaReq::new(parent.clone(), contact));
+        wait_for_current_resources(&from, &from_res);
+
+        init_ca(&to);
+        let contact = add_child_rfc6492(&parent, &to, child_request(&to), to_res.clone());
+        add_parent_to_ca(&to, ParentCaReq::new(parent.clone(), contact));
+
+        let kept = RoaDefinition::from_str("10.0.0.0/24 => 64496").unwrap();
+        let moved = RoaDefinition::from_str("10.0.1.0/24 => 64497").unwrap();
+        let covering = RoaDefinition::from_str("10.0.0.0/23-24 => 64498").unwrap();
+        let covering_kept = RoaDefinition::from_str("10.0.0.0/24 => 64498").unwrap();
+        let covering_moved = RoaDefinition::from_str("10.0.1.0/24 => 64498").unwrap();
+        let exact = RoaDefinition::from_str("10.0.0.0/23 => 64499").unwrap();
+        let pending = RoaDefinition::from_str("10.0.1.0/24 => 64500").unwrap();
+        let kept_file = ObjectName::from(&kept).to_string();
+        let moved_file = ObjectName::from(&moved).to_string();
+        let covering_file = ObjectName::from(&covering).to_string();
+        let covering_kept_file = ObjectName::from(&covering_kept).to_string();
+        let covering_moved_file = ObjectName::from(&covering_moved).to_string();
+        let exact_file = ObjectName::from(&exact).to_string();
+
+        let mut updates = RoaDefinitionUpdates::empty();
+        updates.add(kept);
+        updates.add(moved);
+        updates.add(covering);
+        updates.add(exact);
+        ca_route_authorizations_update(&from, updates);
+        wait_for_published_objects(
+            &from,
+            &[
+                ".crl",
+                ".mft",
+                &kept_file,
+                &moved_file,
+                &covering_file,
+                &exact_file,
+            ],
+        );
+
+        let tomorrow = Time::now() + Duration::days(1);
+        let schedule = RoaSchedule::new(Some(tomorrow), None);
+        ca_route_authorizations_schedule(&from, vec![ScheduledRoa::new(pending, schedule)]);
+
+        // The receiving child cannot authorize the moved prefix without a
+        // repository. The sending child keeps its resources and ROAs.
+        transfer_child_resources_expect_error(&parent, &from, &to, &moved_res);
+        assert_eq!(
+            definitions(&[kept, moved, covering, exact, pending]),
+            route_definitions(&from)
+        );
+        assert_eq!(from_res, ca_current_resources(&from));
+
+        ca_repo_update_embedded(&to);
+        wait_for_current_resources(&to, &to_res.union(&moved_res));
+
+        transfer_child_resources(&parent, &from, &to, &moved_res);
+
+        // The ROAs for the moved prefix are now published by the receiving
+        // child, including the moved part of the less specific ROA which
+        // allows a /24. The ROA which only allows the /23 is not split.
+        wait_for_current_resources(&to, &to_res.union(&moved_res));
+        wait_for_published_objects(&to, &[".crl", ".mft", &moved_file, &covering_moved_file]);
+        assert_eq!(
+            definitions(&[moved, covering_moved, pending]),
+            route_definitions(&to)
+        );
+
+        // The pending ROA is still only scheduled
+        let now = Time::now();
+        let scheduled = ca_route_authorizations_list(&to)
+            .into_iter()
+            .find(|roa| roa.definition() == &pending)
+            .unwrap();
+        assert!(scheduled.schedule().is_pending(now));
+
+        // The sending child keeps the part of the less specific ROA for the
+        // prefix it still holds, and no longer has a ROA for the /23.
+        wait_for_current_resources(
+            &from,
+            &ResourceSet::from_strs("", "10.0.0.0/24", "").unwrap(),
+        );
+        wait_for_published_objects(&from, &[".crl", ".mft", &kept_file, &covering_kept_file]);
+        assert_eq!(definitions(&[kept, covering_kept]), route_definitions(&from));
+    });
+}
+
+fn definitions(defs: &[RoaDefinition]) -> HashSet<RoaDefinition> {
+    defs.iter().cloned().collect()
+}
+
+fn route_definitions(ca: &Handle) -> HashSet<RoaDefinition> {
+    ca_route_authorizations_list(ca)
+        .iter()
+        .map(|roa| *roa.definition())
+        .collect()
+}
@@ -0,0 +1,155 @@
+extern crate krill;
+
+use std::str::FromStr;
+
+use krill::commons::api::{
+    Handle, ObjectName, ParentCaReq, ParentMigrationPhase, ResourceSet, RoaDefinition,
+    RoaDefinitionUpdates,
+};
+use krill::daemon::ca::ta_handle;
+use krill::daemon::test::*;
+
+#[test]
+/// Test that a CA can be moved to a new parent without its ROAs being
+/// withdrawn in between.
+///
+///                   TA
+///                 /  |  \
+///             (old) NEW  SMALL
+///               |   /   /
+///                CA ---
+///
+/// A migration to a parent which does not give the CA the resources for
+/// all its ROAs cannot complete, but it can be cancelled. The migration to
+/// the new parent is held at each phase in turn, by suspending the CA under
+/// the new parent and by giving it only part of its resources at first, and
+/// the ROA must stay published throughout.
+///
+fn ca_parent_migration() {
+    test_with_krill_server(|_d| {
+        let ta_handle = ta_handle();
+        let new_parent = Handle::from_str_unsafe("new_parent");
+        let small_parent = Handle::from_str_unsafe("small_parent");
+        let ca = Handle::from_str_unsafe("ca");
+
+        let new_parent_res = ResourceSet::from_strs("", "10.0.0.0/16", "").unwrap();
+        let small_parent_res = ResourceSet::from_strs("", "10.0.1.0/24", "").unwrap();
+        let ca_res = ResourceSet::from_strs("", "10.0.0.0/24", "").unwrap();
+
+        // Set up the new parent under the TA
+        init_child_with_embedded_repo(&new_parent);
+        let parent = {
+            let contact = add_child_to_ta_embedded(&new_parent, new_parent_res.clone());
+            ParentCaReq::new(ta_handle.clone(), contact)
+        };
+        add_parent_to_ca(&new_parent, parent);
+        wait_for_current_resources(&new_parent, &new_parent_res);
+
+        // Set up a parent under the TA, which cannot cover the CA's ROA
+        init_child_with_embedded_repo(&small_parent);
+        let parent = {
+            let contact = add_child_to_ta_embedded(&small_parent, small_parent_res.clone());
+            ParentCaReq::new(ta_handle.clone(), contact)
+        };
+        add_parent_to_ca(&small_parent, parent);
+        wait_for_current_resources(&small_parent, &small_parent_res);
+
+        // Set up the CA under the TA, with a ROA
+        init_child_with_embedded_repo(&ca);
+        let parent = {
+            let contact = add_child_to_ta_embedded(&ca, ca_res.clone());
+            ParentCaReq::new(ta_handle.clone(), contact)
+        };
+        add_parent_to_ca(&ca, parent);
+        wait_for_current_resources(&ca, &ca_res);
+
+        let route = RoaDefinition::from_str("10.0.0.0/24 => 64496").unwrap();
+        let route_file = ObjectName::from(&route).to_string();
+        let route_file = route_file.as_str();
+
+        let mut updates = RoaDefinitionUpdates::empty();
+        updates.add(route);
+        ca_route_authorizations_update(&ca, updates);
+        wait_for_published_objects(&ca, &[".crl", ".mft", route_file]);
+
+        // The ROA must stay published at every step of a migration.
+        let assert_roa_published = || {
+            let details = publisher_details(&ca);
+            assert!(
+                details
+                    .current_files()
+                    .iter()
+                    .any(|file| file.uri().ends_with(route_file)),
+                "ROA for {} was withdrawn",
+                route
+            );
+        };
+
+        // Try to move the CA to the small parent, the ROA is not covered
+        let req = child_request(&ca);
+        let contact = add_child_rfc6492(&small_parent, &ca, req, small_parent_res.clone());
+        start_parent_migration(
+            &ca,
+            &ta_handle,
+            ParentCaReq::new(small_parent.clone(), contact),
+        );
+        wait_for(30, "Parent migration is not awaiting ROAs", || {
+            assert_roa_published();
+            parent_migration_phase(&ca) == ParentMigrationPhase::AwaitingRoas
+        });
+        let status = parent_migration_status(&ca);
+        assert_eq!(&vec![route], status.uncovered_roas());
+        assert_eq!(&vec![route], status.pending_roas());
+
+        // Cancel, the CA keeps its old parent and its ROA
+        cancel_parent_migration(&ca);
+        let parents = ca_details(&ca).parents().clone();
+        assert_eq!(1, parents.len());
+        assert_eq!(&ta_handle, parents[0].handle());
+        parent_migration_status_expect_error(&ca);
+        wait_for_published_objects(&ca, &[".crl", ".mft", route_file]);
+
+        // Move the CA to the new parent, which does not issue a certificate
+        // to the CA yet, because it is suspended there.
+        let req = child_request(&ca);
+        let ca_partial_res = ResourceSet::from_strs("", "10.0.0.0/25", "").unwrap();
+        let contact = add_child_rfc6492(&new_parent, &ca, req, ca_partial_res);
+        suspend_child(&new_parent, &ca);
+        start_parent_migration(
+            &ca,
+            &ta_handle,
+            ParentCaReq::new(new_parent.clone(), contact),
+        );
+        assert_eq!(
+            ParentMigrationPhase::AwaitingCertificates,
+            parent_migration_phase(&ca)
+        );
+        assert_roa_published();
+
+        // Resume the CA under the new parent, with resources which do not
+        // cover the ROA yet.
+        resume_child(&new_parent, &ca);
+        wait_for(30, "Parent migration is not awaiting ROAs", || {
+            assert_roa_published();
+            parent_migration_phase(&ca) == ParentMigrationPhase::AwaitingRoas
+        });
+        let status = parent_migration_status(&ca);
+        assert_eq!(&vec![route], status.uncovered_roas());
+        assert_roa_published();
+
+        // Give the CA all its resources under the new parent, so that the
+        // migration can complete.
+        update_child(&new_parent, &ca, &ca_res);
+        wait_for(30, "Parent migration did not complete", || {
+            assert_roa_published();
+            parent_migration_phase(&ca) == ParentMigrationPhase::Completed
+        });
+
+        // Only the new parent is left, and the ROA is still published
+        let parents = ca_details(&ca).parents().clone();
+        assert_eq!(1, parents.len());
+        assert_eq!(&new_parent, parents[0].handle());
+        wait_for_current_resources(&ca, &ca_res);
+        wait_for_published_objects(&ca, &[".crl", ".mft", route_file]);
+    });
+}
@@ -1,6 +1,8 @@
 extern crate krill;
 
+use krill::cli::Error;
 use krill::commons::api::{Handle, ParentCaReq, ResourceSet};
+use krill::commons::util::httpclient;
 use krill::daemon::ca::ta_handle;
 use krill::daemon::test::*;
 
@@ -44,8 +46,9 @@ fn ca_rfc6492() {
         wait_for_resource_class_to_disappear(&child);
         wait_for_ta_to_have_number_of_issued_certs(0);
 
-        // Update the ID of the parent, and therefore tell child as well
-        generate_new_id(&ta_handle);
+        // Roll the ID of the parent, and tell the child about the new ID. The
+        // parent keeps using its old ID key until the roll is activated.
+        id_roll_init(&ta_handle);
         let contact = parent_contact(&ta_handle, &child);
         update_parent_contact(&child, &ta_handle, contact);
 
@@ -54,16 +57,36 @@ fn ca_rfc6492() {
         update_child(&ta_handle, &child, &child_resources);
         wait_for_current_resources(&child, &child_resources);
 
-        // Update the ID of the child, and tell parent
-        generate_new_id(&child);
+        // Activate the new ID of the parent, the child should still get updates
+        id_roll_activate(&ta_handle);
+        let child_resources = ResourceSet::from_strs("", "10.0.0.0/16", "").unwrap();
+        update_child(&ta_handle, &child, &child_resources);
+        wait_for_current_resources(&child, &child_resources);
+
+        // Roll the ID of the child. The new ID cannot be activated until the
+        // parent has been given it.
+        id_roll_init(&child);
+        match id_roll_activate_expect_error(&child) {
+            Error::HttpClientError(httpclient::Error::ErrorWithJson(_, res)) => {
+                assert_eq!(res.label(), "ca-id-roll-not-accepted")
+            }
+            _ => panic!("Expected new ID of child not to be accepted by parent"),
+        }
         let req = child_request(&child);
         update_child_id(&ta_handle, &child, req);
 
-        // The child should be able to get updated resources again
+        // The child should be able to get updated resources again, the
+        // parent only accepts the new ID key of the child now
         let child_resources = ResourceSet::from_strs("AS65000", "10.0.0.0/16", "").unwrap();
         update_child(&ta_handle, &child, &child_resources);
         wait_for_current_resources(&child, &child_resources);
 
+        // Activate the new ID of the child, it should still get updates
+        id_roll_activate(&child);
+        let child_resources = ResourceSet::from_strs("AS65000", "10.0.0.0/20", "").unwrap();
+        update_child(&ta_handle, &child, &child_resources);
+        wait_for_current_resources(&child, &child_resources);
+
         // Remove parent
         delete_parent(&child, &ta_handle);
         wait_for_published_objects(&child, &[]); // should withdraw everything
@@ -1,9 +1,15 @@
+extern crate chrono;
 extern crate krill;
+extern crate rpki;
 
 use std::str::FromStr;
 
+use chrono::Duration;
+use rpki::x509::Time;
+
 use krill::commons::api::{
     Handle, ObjectName, ParentCaReq, ResourceSet, RoaDefinition, RoaDefinitionUpdates,
+    RoaDefinitionsFormat, RoaSchedule, ScheduledRoa,
 };
 use krill::daemon::ca::ta_handle;
 use krill::daemon::test::*;
@@ -88,5 +94,93 @@ fn ca_roas() {
         let mut updates = RoaDefinitionUpdates::empty();
         updates.add(route_invalid_length);
         ca_route_authorizations_update_expect_error(&child, updates);
+
+        // A loose max length is only reported back as a lint warning, even in
+        // strict mode, because all more-specifics may well be announced.
+        let route_loose = RoaDefinition::from_str("192.168.1.0/24-25 => 64496").unwrap();
+        let mut updates = RoaDefinitionUpdates::empty();
+        updates.add(route_loose);
+        let report = ca_route_authorizations_update_strict(&child, updates);
+        assert_eq!(1, report.warnings().len());
+
+        // But an AS0 authorization overlapping it is refused in strict mode.
+        let route_as0 = RoaDefinition::from_str("192.168.1.0/24 => 0").unwrap();
+        let mut updates = RoaDefinitionUpdates::empty();
+        updates.add(route_as0);
+        ca_route_authorizations_update_strict_expect_error(&child, updates);
+
+        // Imported authorizations are added with their comments, but an
+        // import which holds the same authorization twice is refused.
+        let csv = "prefix,max_length,asn,comment\n192.168.3.0/24,,64496,imported\n";
+        let roas = RoaDefinitionsFormat::Csv.parse(&child, csv).unwrap();
+        ca_route_authorizations_import(&child, roas);
+
+        let csv = "192.168.4.0/24,,64496,\n192.168.4.0/24,,64496,again\n";
+        let roas = RoaDefinitionsFormat::Csv.parse(&child, csv).unwrap();
+        ca_route_authorizations_import_expect_error(&child, roas);
+
+        // Exported authorizations can be imported again
+        let mut before = ca_route_authorizations_list(&child);
+        let exported = ca_route_authorizations_export(&child, RoaDefinitionsFormat::Csv);
+
+        let mut updates = RoaDefinitionUpdates::empty();
+        for roa in before.iter() {
+            updates.remove(*roa.definition());
+        }
+        ca_route_authorizations_update(&child, updates);
+        assert!(ca_route_authorizations_list(&child).is_empty());
+
+        let roas = RoaDefinitionsFormat::Csv.parse(&child, &exported).unwrap();
+        ca_route_authorizations_import(&child, roas);
+
+        let mut after = ca_route_authorizations_list(&child);
+        before.sort_by_key(|roa| roa.to_string());
+        after.sort_by_key(|roa| roa.to_string());
+        assert_eq!(before, after);
+
+        // Scheduled authorizations which are due are applied right away. If
+        // any of them cannot be applied, then nothing is scheduled.
+        let route_due = RoaDefinition::from_str("192.168.2.0/24 => 64496").unwrap();
+        let route_uncovered = RoaDefinition::from_str("10.0.1.0/24 => 64496").unwrap();
+        let due = RoaSchedule::new(Some(Time::now() - Duration::minutes(1)), None);
+        ca_route_authorizations_schedule_expect_error(
+            &child,
+            vec![
+                ScheduledRoa::new(route_due, due),
+                ScheduledRoa::new(route_uncovered, due),
+            ],
+        );
+
+        let configured = ca_route_authorizations_list(&child);
+        assert!(!configured.iter().any(|roa| roa.definition() == &route_due));
+        assert!(!configured
+            .iter()
+            .any(|roa| roa.definition() == &route_uncovered));
+
+        // Once applied, the schedule is dropped
+        ca_route_authorizations_schedule(&child, vec![ScheduledRoa::new(route_due, due)]);
+        let configured = ca_route_authorizations_list(&child);
+        let roa = configured
+            .iter()
+            .find(|roa| roa.definition() == &route_due)
+            .unwrap();
+        assert!(roa.schedule().is_empty());
+
+        // An expired authorization is removed, and its schedule is dropped
+        // so that it is not removed again when it is added by hand.
+        let expired = RoaSchedule::new(None, Some(Time::now() - Duration::minutes(1)));
+        ca_route_authorizations_schedule(&child, vec![ScheduledRoa::new(route_due, expired)]);
+        let configured = ca_route_authorizations_list(&child);
+        assert!(!configured.iter().any(|roa| roa.definition() == &route_due));
+
+        let mut updates = RoaDefinitionUpdates::empty();
+        updates.add(route_due);
+        ca_route_authorizations_update(&child, updates);
+        let configured = ca_route_authorizations_list(&child);
+        let roa = configured
+            .iter()
+            .find(|roa| roa.definition() == &route_due)
+            .unwrap();
+        assert!(roa.schedule().is_empty());
     });
 }
@@ -0,0 +1,56 @@
+extern crate chrono;
+extern crate krill;
+
+use chrono::Duration;
+
+use krill::cli::options::{CaCommand, Command};
+use krill::cli::report::ApiResponse;
+use krill::commons::api::{Handle, PublisherQuota};
+use krill::commons::remote::rfc8183;
+use krill::commons::util::test::files_containing;
+use krill::daemon::test::*;
+
+fn publisher_request(ca: &Handle) -> rfc8183::PublisherRequest {
+    let command = Command::CertAuth(CaCommand::RepoPublisherRequest(ca.clone()));
+    match krill_admin(command) {
+        ApiResponse::Rfc8183PublisherRequest(req) => req,
+        _ => panic!("Expected publisher request"),
+    }
+}
+
+#[test]
+/// Test that a publisher can add itself to the repository using an
+/// invitation, that an invitation can be extended, that it can only be
+/// used once, and that its token is not kept.
+fn publisher_invitation() {
+    test_with_krill_server(|d| {
+        let ca = Handle::from_str_unsafe("ca");
+        init_child_with_embedded_repo(&ca);
+        let req = publisher_request(&ca);
+
+        let prefix = Handle::from_str_unsafe("member-");
+        let invitation = invite_publisher(Some(prefix), PublisherQuota::default());
+        assert_eq!(
+            vec![invitation.invitation().clone()],
+            publisher_invitations()
+        );
+
+        // The invitation can be extended
+        let expires = invitation.invitation().expires() + Duration::days(1);
+        update_publisher_invitation(invitation.invitation().id(), expires);
+        assert_eq!(expires, publisher_invitations()[0].expires());
+
+        // The publisher is added with the prefix of the invitation
+        let response = accept_publisher_invitation(invitation.token(), req.clone()).unwrap();
+        let publisher = Handle::from_str_unsafe("member-ca");
+        assert_eq!(response.publisher_handle(), &publisher);
+        assert_eq!(publisher_details(&publisher).id_cert(), req.id_cert());
+
+        // The invitation is used up
+        assert!(publisher_invitations().is_empty());
+        assert!(accept_publisher_invitation(invitation.token(), req).is_err());
+
+        // Only the hash of the token is kept
+        assert!(files_containing(&d, invitation.token().as_ref()).is_empty());
+    });
+}
@@ -10,16 +10,19 @@ use rpki::roa::Roa;
 
 use krill::cli::options::{CaCommand, Command, PublishersCommand};
 use krill::cli::report::ApiResponse;
+use krill::cli::Error;
 use krill::commons::api::{
     CaRepoDetails, CurrentRepoState, Handle, ParentCaReq, PublisherDetails, PublisherHandle,
     RepositoryUpdate, ResourceSet, RoaDefinition, RoaDefinitionUpdates,
 };
 use krill::commons::remote::rfc8183;
+use krill::commons::util::httpclient;
 use krill::daemon::ca::ta_handle;
 use krill::daemon::test::{
     add_child_to_ta_embedded, add_parent_to_ca, ca_route_authorizations_update,
-    init_child_with_embedded_repo, krill_admin, krill_pubd_admin, start_krill_pubd_server,
-    test_with_krill_server, wait_for, wait_for_current_resources, PubdTestContext,
+    init_child_with_embedded_repo, krill_admin, krill_pubd_admin, krill_pubd_admin_expect_error,
+    start_krill_pubd_server, test_with_krill_server, wait_for, wait_for_current_resources,
+    PubdTestContext,
 };
 
 fn repository_response(
@@ -68,6 +71,14 @@ fn repo_update(ca: &Handle, update: RepositoryUpdate) {
     krill_admin(command);
 }
 
+fn expect_pubd_not_found(path: &str) {
+    let uri = format!("https://localhost:3001/{}", path);
+    match httpclient::get_text(&uri, None) {
+        Err(httpclient::Error::ErrorWithBody(status, _)) => assert_eq!(status.as_u16(), 404),
+        _ => panic!("Expected {} to be not found", path),
+    }
+}
+
 fn publisher_request(ca: &Handle) -> rfc8183::PublisherRequest {
     let command = Command::CertAuth(CaCommand::RepoPublisherRequest(ca.clone()));
     match krill_admin(command) {
@@ -84,6 +95,16 @@ fn remote_publication() {
     test_with_krill_server(|_d| {
         let pubd_dir = start_krill_pubd_server();
 
+        // The standalone publication server has no CA end-points
+        match krill_pubd_admin_expect_error(Command::CertAuth(CaCommand::List)) {
+            Error::HttpClientError(httpclient::Error::ErrorWithJson(_, res)) => {
+                assert_eq!(res.label(), "api-unknown-method")
+            }
+            _ => panic!("Expected CA end-points to be unknown"),
+        }
+        expect_pubd_not_found("stats/cas");
+        expect_pubd_not_found("ta/ta.tal");
+
         let ta_handle = ta_handle();
 
         let child = Handle::from_str_unsafe("child");
@@ -180,6 +201,37 @@ fn remote_publication() {
             details_at_main.current_files().is_empty()
         });
 
+        // Roll the ID key of the publication server. The child is given the
+        // new repository response straight away, and should keep publishing
+        // before and after the roll is activated.
+        let command = Command::Publishers(PublishersCommand::IdRollInit);
+        krill_pubd_admin(command, PubdTestContext::Secondary);
+
+        let response = repository_response(&child, PubdTestContext::Secondary);
+        repo_update(&child, RepositoryUpdate::Rfc8181(response));
+
+        let route_3 = RoaDefinition::from_str("10.0.4.0/24 => 64496").unwrap();
+        let mut updates = RoaDefinitionUpdates::empty();
+        updates.add(route_3);
+        ca_route_authorizations_update(&child, updates);
+
+        wait_for(30, "Should publish during repository ID roll", || {
+            let details = details_publisher(&child, PubdTestContext::Secondary);
+            details.current_files().len() == 5
+        });
+
+        let command = Command::Publishers(PublishersCommand::IdRollActivate);
+        krill_pubd_admin(command, PubdTestContext::Secondary);
+
+        let mut updates = RoaDefinitionUpdates::empty();
+        updates.remove(route_3);
+        ca_route_authorizations_update(&child, updates);
+
+        wait_for(30, "Should publish after repository ID roll", || {
+            let details = details_publisher(&child, PubdTestContext::Secondary);
+            details.current_files().len() == 4
+        });
+
         // Now let's migrate back, so that we see that works too.
 
         // Get a Repository Response for the child CA
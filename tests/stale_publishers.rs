@@ -0,0 +1,76 @@
+extern crate chrono;
+extern crate krill;
+extern crate rpki;
+
+use chrono::Duration;
+
+use rpki::crypto::{PublicKeyFormat, Signer};
+use rpki::x509::Time;
+
+use krill::cli::options::{Command, PublishersCommand};
+use krill::cli::report::ApiResponse;
+use krill::commons::api::{Handle, PublishDeltaBuilder, PublisherSummary};
+use krill::commons::remote::builder::{IdCertBuilder, SignedMessageBuilder};
+use krill::commons::remote::{rfc8181, rfc8183};
+use krill::commons::util::file::CurrentFile;
+use krill::commons::util::softsigner::OpenSslSigner;
+use krill::commons::util::{httpclient, test};
+use krill::daemon::test::*;
+
+fn stale_publishers(seconds: i64) -> Vec<PublisherSummary> {
+    let command = Command::Publishers(PublishersCommand::StalePublishers(seconds));
+    match krill_admin(command) {
+        ApiResponse::PublisherList(list) => list.publishers().clone(),
+        _ => panic!("Expected publisher list"),
+    }
+}
+
+#[test]
+/// Test that a remote publisher which has just published, but whose CRL
+/// has expired, is included in the list of stale publishers.
+fn stale_publishers_include_expired() {
+    test_with_krill_server(|d| {
+        let mut signer = OpenSslSigner::build(&test::sub_dir(&d)).unwrap();
+        let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let id_cert = IdCertBuilder::new_ta_id_cert(&key, &signer).unwrap();
+
+        let publisher = Handle::from_str_unsafe("expired");
+        let req = rfc8183::PublisherRequest::new(None, publisher.clone(), id_cert);
+        krill_admin(Command::Publishers(PublishersCommand::AddPublisher(req)));
+
+        let base_uri = publisher_details(&publisher).base_uri().clone();
+        let in_an_hour = Time::now() + Duration::hours(1);
+        let a_minute_ago = Time::now() - Duration::minutes(1);
+        let mft = CurrentFile::new(
+            test::rsync(&format!("{}ca.mft", base_uri)),
+            &test::manifest(&mut signer, &[], in_an_hour),
+        );
+        let crl = CurrentFile::new(
+            test::rsync(&format!("{}ca.crl", base_uri)),
+            &test::crl(&mut signer, a_minute_ago),
+        );
+
+        let mut builder = PublishDeltaBuilder::new();
+        builder.add_publish(mft.as_publish());
+        builder.add_publish(crl.as_publish());
+        let message = rfc8181::Message::publish_delta_query(builder.finish());
+        let signed = SignedMessageBuilder::create(&key, &signer, message.into_bytes()).unwrap();
+
+        let uri = format!("https://localhost:3000/rfc8181/{}", publisher);
+        httpclient::post_binary(&uri, &signed.as_bytes(), rfc8181::CONTENT_TYPE).unwrap();
+        assert_eq!(2, publisher_details(&publisher).current_files().len());
+
+        let stale = stale_publishers(3600);
+        let summary = stale
+            .iter()
+            .find(|summary| summary.handle() == &publisher)
+            .expect("Expected publisher with expired CRL to be stale");
+
+        // The test manifests are valid until a day after next update
+        let next_update = summary.next_update().unwrap();
+        let not_after = summary.not_after().unwrap();
+        let mft_not_after = in_an_hour + Duration::days(1);
+        assert_eq!(next_update.timestamp(), a_minute_ago.timestamp());
+        assert_eq!(not_after.timestamp(), mft_not_after.timestamp());
+    });
+}